@@ -0,0 +1,68 @@
+use std::time::Instant;
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler,
+    SystemHandler,
+    TaskWaitable,
+    Read,
+    Searchable
+};
+
+struct Position(f32);
+struct Velocity(f32);
+struct Health(u32);
+struct Player;
+
+/// Defines the number of entities created.
+const NUMBER_OF_ENTITIES: usize = 100_000;
+
+/// Defines the number of times each query is executed.
+const ITERATIONS: u32 = 100;
+
+fn main() {
+    let world = DefaultWorld::default();
+
+    world.register::<Position>();
+    world.register::<Velocity>();
+    world.register::<Health>();
+    world.register::<Player>();
+
+    // Most of the entities only move, just a few of them are players.
+    for i in 0..NUMBER_OF_ENTITIES {
+        if i % 100 == 0 {
+            world.add_entity((Position(0.0), Health(100), Player));
+        } else {
+            world.add_entity((Position(0.0), Velocity(1.0)));
+        }
+    }
+
+    // Query the entities directly from the world.
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let health: u32 = world.get::<Read<Health>>().iter().map(|h| h.read().0).sum();
+        std::hint::black_box((health, world.get::<Read<Player>>()));
+    }
+    println!(
+        "World query (players, health): {:?} per query",
+        start.elapsed() / ITERATIONS
+    );
+
+    // Run a system which needs two components.
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        (
+            world.run(|p: Read<Position>, v: Read<Velocity>| {
+                let moved: f32 = (p.iter(), v.iter()).query()
+                    .map(|(p, v)| p.read().0 + v.read().0)
+                    .sum();
+                std::hint::black_box(moved);
+            }),
+        ).wait();
+    }
+    println!(
+        "System query (position, velocity): {:?} per run",
+        start.elapsed() / ITERATIONS
+    );
+}
@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler,
+    Read,
+    Searchable
+};
+
+struct Position([f32; 3]);
+struct Color([f32; 3]);
+
+/// Defines the number of entities created.
+const NUMBER_OF_ENTITIES: usize = 100_000;
+
+/// Returns the time it takes to create the entities in a new world.
+///
+/// # Arguments
+///
+/// `spawn` - Creates the entities.
+fn measure<F: Fn(&DefaultWorld)>(spawn: F) -> Duration {
+    let world = DefaultWorld::default();
+    world.register::<Position>();
+    world.register::<Color>();
+
+    let start = Instant::now();
+    spawn(&world);
+    let elapsed = start.elapsed();
+
+    // Read the components back, outside of the measured time.
+    let positions = world.get::<Read<Position>>();
+    let colors = world.get::<Read<Color>>();
+    let total: f32 = (positions.iter(), colors.iter()).query()
+        .map(|(p, c)| p.read().0.iter().chain(c.read().0.iter()).sum::<f32>())
+        .sum();
+    std::hint::black_box(total);
+
+    elapsed
+}
+
+fn main() {
+    let looped = measure(|world| {
+        for i in 0..NUMBER_OF_ENTITIES {
+            world.add_entity((Position([i as f32, 0.0, 0.0]), Color([1.0, 1.0, 1.0])));
+        }
+    });
+    println!("add_entity loop: {:?}", looped);
+
+    let batched = measure(|world| {
+        world.add_entities_batch(NUMBER_OF_ENTITIES, |i| {
+            (Position([i as f32, 0.0, 0.0]), Color([1.0, 1.0, 1.0]))
+        });
+    });
+    println!("add_entities_batch: {:?}", batched);
+
+    println!("Speedup: {:.1}x", looped.as_secs_f64() / batched.as_secs_f64());
+}
@@ -5,9 +5,11 @@ use std::{
     marker::PhantomData,
 };
 
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
 use crate::{
     entity::Entity,
-    component::{ComponentBuffer, BufferBlockVec, UniqueComponent},
+    component::{Component, ComponentBuffer, ComponentBlock, UniqueComponent, NUM_OF_COMPONETS_PER_PAGE},
     storage::Storage
 };
 
@@ -51,12 +53,26 @@ impl<'a, T: 'static + Send + Sync> Reader<'a, T> {
 /// A handy type used to wrap the Lock which contains the storage.
 pub(crate) type SLock<R> = RwLock<Storage<R>>;
 
+/// Returns the slot of the entity in the blocks, None if it is outside them.
+///
+/// # Arguments
+///
+/// `blocks` - The snapshot of the buffer.
+/// `id` - The id of the entity.
+fn component_in_blocks(blocks: &[ComponentBlock], id: usize) -> Option<&RwLock<Component>> {
+    blocks
+        .get(id / NUM_OF_COMPONETS_PER_PAGE)
+        .and_then(|block| block[id % NUM_OF_COMPONETS_PER_PAGE].as_ref())
+}
+
 /// A nice iterator used to walk over the reads.
 pub struct ReadAccessIterator<'a, T: 'static + Send + Sync> {
     counter: usize,
-    reader: RwLockReadGuard<'a, BufferBlockVec>,
+    /// The blocks of the buffer when the iterator was created, the buffer
+    /// is not locked while the iterator is alive.
+    blocks: Vec<ComponentBlock>,
     entities: Arc<Vec<Entity>>,
-    _marker: PhantomData<T> 
+    _marker: PhantomData<&'a T> 
 }
 
 impl<
@@ -70,55 +86,72 @@ impl<
             return None;
         });
 
-        // Check if the item exits if not just return None, that will
-        // ends the iterator execution.
-        guard!(let Some(item) = self.reader.get(entity.id) else {
-            panic!(
-                "The entity {} does not contain the component {}",
-                123, type_name::<T>()
-            );
-        });
-
-        // Store a the read in order to keep a reference to it and 
-        // avoid borrow checker complains.
-        let component = item.read();
-
-        // Get read access over the item.
-        guard!(let Ok(c_read) = component else {
-            panic!(
-                "Error trying to get read access over item at index {}",
-                self.counter
-            );
-        });
-
-        // Get the item itself it it exits otherwise just panic,
-        // TODO(Angel): Double check if this can break if the item
-        // is deleted in other thread and after that this is read.
-        guard!(let Some(u_c_read) = c_read.deref() else {
-            panic!(
-                "Component {} for entity {} does not exist",
-                type_name::<T>(), 123
-            );
-        });
-
-        let u_c_read_clone = u_c_read.clone();
-
-        // Cast the AnyStorage to the correct type.
-        guard!(let Ok(s_ref) = u_c_read_clone.downcast::<SLock<T>>() else {
-            panic!(
-                "There was a problem trying to cast component to {}",
-                type_name::<T>()
-            );
-        });
-        // Loosing lock access?.
+        let reader = reader_in_blocks(&self.blocks, entity, self.counter);
 
         // Increate counter to go to the next entity.
         self.counter += 1;
 
-        Some(Reader::new(s_ref))
+        Some(reader)
     }
 }
 
+/// Returns the reader of the entity component, it panics if the entity does
+/// not contain the component.
+///
+/// # Arguments
+///
+/// `blocks` - The snapshot of the buffer.
+/// `entity` - The entity which owns the component.
+/// `index` - The position of the entity in the iteration, used by the errors.
+fn reader_in_blocks<'a, T: 'static + Send + Sync>(
+    blocks: &[ComponentBlock],
+    entity: &Entity,
+    index: usize) -> Reader<'a, T> {
+    // Check if the item exits if not just return None, that will
+    // ends the iterator execution.
+    guard!(let Some(item) = component_in_blocks(blocks, entity.id) else {
+        panic!(
+            "The entity {} does not contain the component {}",
+            entity.id, type_name::<T>()
+        );
+    });
+
+    // Store a the read in order to keep a reference to it and 
+    // avoid borrow checker complains.
+    let component = item.read();
+
+    // Get read access over the item.
+    guard!(let Ok(c_read) = component else {
+        panic!(
+            "Error trying to get read access over item at index {}",
+            index
+        );
+    });
+
+    // Get the item itself it it exits otherwise just panic,
+    // TODO(Angel): Double check if this can break if the item
+    // is deleted in other thread and after that this is read.
+    guard!(let Some(u_c_read) = c_read.deref() else {
+        panic!(
+            "Component {} for entity {} does not exist",
+            type_name::<T>(), entity.id
+        );
+    });
+
+    let u_c_read_clone = u_c_read.clone();
+
+    // Cast the AnyStorage to the correct type.
+    guard!(let Ok(s_ref) = u_c_read_clone.downcast::<SLock<T>>() else {
+        panic!(
+            "There was a problem trying to cast component to {}",
+            type_name::<T>()
+        );
+    });
+    // Loosing lock access?.
+
+    Reader::new(s_ref)
+}
+
 /// Provides a type used to read storages from the `World`.
 pub struct Read<T: 'static + Send + Sync> {
     buffer: ComponentBuffer,
@@ -153,19 +186,38 @@ impl<T: 'static + Send + Sync> Read<T> {
             <Self as Accessible>::Component: Send + Sync {
         ReadAccessIterator {
             counter: 0,
-            // Take a read access now to avoid multiples read access when
-            // the iterator loops
-            reader: self.buffer.read().unwrap(),
+            // The lock of the buffer is only held while the blocks are
+            // cloned, so the buffer can grow while the iterator loops.
+            blocks: self.buffer.read().unwrap().snapshot_blocks(),
             // Send the correct entities ids.
             entities: self.entities.clone(),
             _marker: PhantomData
         }
     } 
 
+    /// Returns a rayon parallel iterator over the components, the entities
+    /// are split across the rayon tasks.
+    ///
+    /// The buffer is only locked while its blocks are cloned, the components
+    /// are locked one by one when they are read.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = Reader<'_, T>> + '_ {
+        let blocks = self.buffer.read().unwrap().snapshot_blocks();
+        self.entities
+            .par_iter()
+            .enumerate()
+            .map(move |(index, entity)| reader_in_blocks(&blocks, entity, index))
+    }
+
     /// Retuns the nuber of entities which have the associated component T.
     pub fn len(&self) -> usize {
         self.entities.len()
     }
+
+    /// Returns the entities which are iterated, the order is the same one
+    /// used by `iter`.
+    pub fn entities(&self) -> &Vec<Entity> {
+        &self.entities
+    }
 }
 
 /// Write access
@@ -204,9 +256,11 @@ impl<'a, T: 'static + Send + Sync> Writter<'a, T> {
 /// A nice iterator used to wrape the lock which contains the storage.
 pub struct WriteAccessIterator<'a, T: 'static + Send + Sync> {
     counter: usize,
-    reader: RwLockReadGuard<'a, BufferBlockVec>,
+    /// The blocks of the buffer when the iterator was created, the buffer
+    /// is not locked while the iterator is alive.
+    blocks: Vec<ComponentBlock>,
     entities: Arc<Vec<Entity>>,
-    _marker: PhantomData<T>  
+    _marker: PhantomData<&'a T>  
 }
 
 impl<
@@ -222,7 +276,7 @@ impl<
 
         // Check if the item exits if not just return None, that will
         // ends the iterator execution.
-        guard!(let Some(item) = self.reader.get(entity.id) else {
+        guard!(let Some(item) = component_in_blocks(&self.blocks, entity.id) else {
             panic!(
                 "The entity {} does not contain the component {}",
                 123, type_name::<T>()
@@ -294,6 +348,10 @@ impl<T: 'static + Send + Sync> Accessible for Write<T> {
     fn is_unique() -> bool { false }
 }
 
+/// There is not a parallel iterator for `Write`, the components would be
+/// written from several threads at once. It would only be safe if the slot
+/// of each entity is locked independently and no entity is repeated, which
+/// the buffer does not guarantee yet.
 impl<T: 'static + Send + Sync> Write<T> { 
     /// Returns a new iterator for `Read`.
     pub fn iter(&self) -> WriteAccessIterator<T>
@@ -301,14 +359,20 @@ impl<T: 'static + Send + Sync> Write<T> {
             <Self as Accessible>::Component: Send + Sync {
         WriteAccessIterator {
             counter: 0,
-            // Take a read access now to avoid multiples read access when
-            // the iterator loops
-            reader: self.buffer.read().unwrap(),
+            // The lock of the buffer is only held while the blocks are
+            // cloned, so the buffer can grow while the iterator loops.
+            blocks: self.buffer.read().unwrap().snapshot_blocks(),
             // Send the correct entities ids.
             entities: self.entities.clone(),
             _marker: PhantomData
         }
     } 
+
+    /// Returns the entities which are iterated, the order is the same one
+    /// used by `iter`.
+    pub fn entities(&self) -> &Vec<Entity> {
+        &self.entities
+    }
 }
 
 /// Defines a data type which allows the user access a unique type in the 
@@ -377,4 +441,64 @@ impl<T: 'static + Send + Sync> UniqueWrite<T> {
     pub fn write(&self) -> RwLockWriteGuard<'_, Storage<T>> {
         self.unique.write().unwrap()
     }
-}
\ No newline at end of file
+
+    /// Replaces the unique with the provided value and returns the old one.
+    ///
+    /// # Arguments
+    ///
+    /// `value` - The new value of the unique.
+    pub fn replace(&self, value: T) -> T {
+        std::mem::replace(&mut **self.write(), value)
+    }
+
+    /// Replaces the unique with the provided value, the old one is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// `value` - The new value of the unique.
+    pub fn set(&self, value: T) {
+        **self.write() = value;
+    }
+}
+/// Defines a handle to a unique type in the `World`, it is returned when the
+/// unique is registered so it can be accessed without going through the
+/// system dispatch.
+pub struct UniqueHandle<T: 'static + Send + Sync> {
+    /// A container for the component ref.
+    unique: Arc<SLock<T>>
+}
+
+impl<T: 'static + Send + Sync> UniqueHandle<T> {
+    /// Creates and returns a new handle.
+    ///
+    /// # Arguments
+    ///
+    /// `unique` - The lock which contains the unique.
+    pub(crate) fn new(unique: Arc<SLock<T>>) -> Self {
+        Self { unique }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, Storage<T>> {
+        self.unique.read().unwrap()
+    }
+
+    pub fn write(&self) -> RwLockWriteGuard<'_, Storage<T>> {
+        self.unique.write().unwrap()
+    }
+
+    /// Returns a read access to the unique which can be passed to a system.
+    pub fn reader(&self) -> UniqueRead<T> {
+        UniqueRead::unique_new(self.unique.clone())
+    }
+
+    /// Returns a write access to the unique which can be passed to a system.
+    pub fn writer(&self) -> UniqueWrite<T> {
+        UniqueWrite::unique_new(self.unique.clone())
+    }
+}
+
+impl<T: 'static + Send + Sync> Clone for UniqueHandle<T> {
+    fn clone(&self) -> Self {
+        Self { unique: self.unique.clone() }
+    }
+}
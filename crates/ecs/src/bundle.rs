@@ -1,13 +1,11 @@
-use std::{
-    sync::Arc,
-    any::TypeId
-};
+use std::sync::Arc;
 
 use paste::paste;
 
 use crate::{
-    type_id::id_of,
-    component::ComponentsHandler,
+    type_id::{id_of, ComponentId},
+    component::{ComponentsHandler, BufferBlockVec, write_reserved},
+    error::SystemResult,
     entity::Entity,
     storage::Storage,
     consts::BitmaskType
@@ -15,15 +13,30 @@ use crate::{
 
 /// Provides an aftraction used to add components into a handler.
 pub trait ComponentBundler {
-    /// An aftraction used to add components into the handler.
+    /// An aftraction used to add components into the handler, it returns
+    /// the bitmask of the components.
     fn add_components<Z: ComponentsHandler + Send + Sync>(
         self,
         entity: Entity,
-        handler: Arc<Z>) -> BitmaskType;
+        handler: Arc<Z>) -> SystemResult<BitmaskType>;
 
     /// An aftraction used to return the number of component in 
     /// the bundle.
     fn len(&self) -> usize;
+
+    /// Returns true if the bundle does not contain components.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An aftraction used to return the ids of the components, in the same
+    /// order as the tuple.
+    fn ids() -> Vec<ComponentId> where Self: Sized;
+
+    /// An aftraction used to write the components into the buffers of the
+    /// types returned by `ids`, in the same order. The slot of the entity
+    /// must be already reserved, see `ComponentsHandler::reserve`.
+    fn write_components(self, entity: Entity, buffers: &[&BufferBlockVec]);
 }
 
 impl<T: 'static + Send + Sync> ComponentBundler for (T, ) {
@@ -36,22 +49,37 @@ impl<T: 'static + Send + Sync> ComponentBundler for (T, ) {
     fn add_components<Z: ComponentsHandler + Send + Sync>(
         self,
         entity: Entity,
-        handler: Arc<Z>) -> BitmaskType {
+        handler: Arc<Z>) -> SystemResult<BitmaskType> {
 
         // Get the type id of the first element in the tuple.
-        let a_id: TypeId = id_of::<T>();
+        let a_id: ComponentId = id_of::<T>();
         // Create a new storage and safe the data there.
         let a_storage = Storage::new(self.0);
 
         // Send the component to the handler.
-        handler.add_component(entity, (a_id, ), (a_storage, ));
-    
+        handler.add_component(entity, (a_id, ), (a_storage, ))?;
+
         // Generate the bitmask.
         handler.bitmask(a_id) 
     }
 
     /// Returns the number of components.
     fn len(&self) -> usize { 1 }
+
+    /// Returns the id of the component.
+    fn ids() -> Vec<ComponentId> {
+        vec![id_of::<T>()]
+    }
+
+    /// Writes the component into the reserved slot of the entity.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity which receives the component.
+    /// `buffers` - The buffer of the component.
+    fn write_components(self, entity: Entity, buffers: &[&BufferBlockVec]) {
+        write_reserved(buffers[0], &entity, Storage::new(self.0));
+    }
 }
 
 macro_rules! generate_bundle {
@@ -61,26 +89,36 @@ macro_rules! generate_bundle {
         > ComponentBundler for ($($type), +) {
             fn add_components<
                 Z: ComponentsHandler + Send + Sync
-            >(self, entity: Entity, handler: Arc<Z>) -> BitmaskType {
+            >(self, entity: Entity, handler: Arc<Z>) -> SystemResult<BitmaskType> {
                 paste! {
                     handler.[<add_component $name>](
                         entity,
                         ($(id_of::<$type>(),)+),
                         ($(Storage::new(self.$index),)+)
-                    )
+                    )?;
                 }
 
-                // In order to avoid the last & (not sure how to get ride of
-                // it) we use a constant with all 1 to avoid any change
+                // In order to avoid the last | (not sure how to get ride of
+                // it) we use a constant with all 0 to avoid any change
                 // over the real bitmask.
-                $(
-                    handler.bitmask(id_of::<$type>()) |
-                )+ 0x0
+                Ok($(
+                    handler.bitmask(id_of::<$type>())? |
+                )+ 0x0)
             }
 
             fn len(&self) -> usize {
                 $name
             }
+
+            fn ids() -> Vec<ComponentId> {
+                vec![$(id_of::<$type>(),)+]
+            }
+
+            fn write_components(self, entity: Entity, buffers: &[&BufferBlockVec]) {
+                $(
+                    write_reserved(buffers[$index], &entity, Storage::new(self.$index));
+                )+
+            }
         }
     };
 }
@@ -93,3 +131,10 @@ generate_bundle!(6; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5]);
 generate_bundle!(7; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6]);
 generate_bundle!(8; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7]);
 generate_bundle!(9; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8]);
+generate_bundle!(10; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9]);
+generate_bundle!(11; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9], [K, 10]);
+generate_bundle!(12; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9], [K, 10], [L, 11]);
+generate_bundle!(13; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9], [K, 10], [L, 11], [M, 12]);
+generate_bundle!(14; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9], [K, 10], [L, 11], [M, 12], [N, 13]);
+generate_bundle!(15; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9], [K, 10], [L, 11], [M, 12], [N, 13], [O, 14]);
+generate_bundle!(16; [A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8], [J, 9], [K, 10], [L, 11], [M, 12], [N, 13], [O, 14], [P, 15]);
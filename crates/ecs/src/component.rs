@@ -1,5 +1,5 @@
 use std::{
-    any::{type_name, Any, TypeId},
+    any::{Any, TypeId},
     fmt::{Debug, Formatter, Result},
     sync::{Arc, RwLock},
 };
@@ -10,11 +10,13 @@ use paste::paste;
 use utils::BlockVec;
 
 use crate::{
-    access::Accessible,
-    consts::BitmaskType,
+    access::{Accessible, SLock, UniqueRead, UniqueWrite, UniqueHandle},
+    consts::{BitmaskType, MAX_COMPONENT_TYPES},
     entity::Entity,
     storage::AnyStorage,
-    storage::Storage
+    storage::Storage,
+    error::{EcsError, SystemResult},
+    type_id::ComponentId
 };
 
 /// Defines the number of componets per page in the block vec.
@@ -29,7 +31,7 @@ macro_rules! generate_add_component_trait {
                 &self,
                 entity: Entity,
                 ids: ($($id,)+ ),
-                component: ($($type,)+ ));
+                component: ($($type,)+ )) -> SystemResult<()>;
         }
     };
 }
@@ -43,11 +45,16 @@ macro_rules! generate_add_component {
                 &self,
                 entity: Entity,
                 ids: ($($id,)+ ),
-                component: ($($type,)+ )) {
+                component: ($($type,)+ )) -> SystemResult<()> {
 
     // Determines if some of the buffers grow.
     let mut were_expansions: bool = false;
 
+    // The components which were never registered are registered on demand.
+    $(
+        self.register_if_needed(ids.$index)?;
+    )+
+
     // In order to avoid a deadlock we must drop first the
     // lock on the reader before sync the buffers (c_reader, b_writer).
     {
@@ -55,13 +62,9 @@ macro_rules! generate_add_component {
         let c_reader = self.components.read().unwrap();
 
         $(
-            // Check if the buffer exist if not just panic.
-            guard!(let Some(c_buffer) = c_reader.get(&ids.$index) else {
-                // The component does not exist, panic an error.
-                panic!(
-                    "The component {:?} is not registered",
-                    type_name::<$type>()
-                );
+            // Check if the buffer exist if not return an error.
+            guard!(let Some(c_buffer) = c_reader.get(&ids.$index.id) else {
+                return Err(EcsError::ComponentNotRegistered(ids.$index.name));
             });
 
             {
@@ -98,6 +101,7 @@ macro_rules! generate_add_component {
         self.sync_buffers();
     }
 
+    Ok(())
                 }
         }
     };
@@ -107,52 +111,106 @@ pub trait ComponentHandler {
     /// An aftraction used to register one component.
     fn register<C0: 'static>(&self);
 
-    /// An aftraction used to register unique components.
-    fn register_unique<C0: 'static + Send + Sync>(&self, c: C0);
+    /// An aftraction used to register all the components which derive
+    /// `Component`.
+    fn auto_register_from_inventory(&self);
+
+    /// An aftraction used to register unique components, it returns a
+    /// handle to the registered unique.
+    fn register_unique<C0: 'static + Send + Sync>(&self, c: C0) -> UniqueHandle<C0>;
 
     /// An aftraction used to regurn an specific unique.
     fn get<T: 'static + Accessible>(&self) -> T 
         where 
             <T as Accessible>::Component: Sync + Send;
+
+    /// An aftraction used to return a read access to an unique, it panics
+    /// if the unique is not registered.
+    fn get_unique<T: 'static + Send + Sync>(&self) -> UniqueRead<T>;
+
+    /// An aftraction used to return a write access to an unique, it panics
+    /// if the unique is not registered.
+    fn get_unique_mut<T: 'static + Send + Sync>(&self) -> UniqueWrite<T>;
+
+    /// An aftraction used to return a read access to an unique, if the
+    /// unique is not registered None is returned.
+    fn try_get_unique<T: 'static + Send + Sync>(&self) -> Option<UniqueRead<T>>;
+
+    /// An aftraction used to return a write access to an unique, if the
+    /// unique is not registered None is returned.
+    fn try_get_unique_mut<T: 'static + Send + Sync>(&self) -> Option<UniqueWrite<T>>;
+
+    /// An aftraction used to know if an unique is registered.
+    fn has_unique<T: 'static + Send + Sync>(&self) -> bool;
+
+    /// An aftraction used to replace an unique, it returns the old value or
+    /// an error if the unique is not registered.
+    fn replace_unique<T: 'static + Send + Sync>(&self, value: T) -> SystemResult<T>;
 }
 
 /// Provides an aftraction to handle components.
 pub trait ComponentsHandler {
-    /// An aftraction used to register components.
-    fn register(&self, c0: TypeId, bitmask_shift: u8);
+    /// An aftraction used to register components, it fails if the bitmask
+    /// does not have space for the component. Registering a component
+    /// twice does nothing.
+    fn register(&self, c0: ComponentId) -> SystemResult<()>;
+
+    /// An aftraction used to return the ids of the registered components,
+    /// in registration order.
+    fn registered(&self) -> Vec<ComponentId>;
 
     /// An aftraction used to register a unique component.
-    fn register_unique<C0: 'static + Send + Sync>(&self, id: TypeId, c: C0);
+    fn register_unique<C0: 'static + Send + Sync>(&self, id: ComponentId, c: C0) -> Arc<SLock<C0>>;
 
     /// An aftraction used to add a new component into the storage.
     fn add_component<A: 'static + AnyStorage + Send + Sync>(
         &self,
         entity: Entity,
-        ids: (TypeId,),
+        ids: (ComponentId,),
         component: (A,),
-    );
+    ) -> SystemResult<()>;
 
     /// An aftraction used to get the associated bitmask.
-    fn bitmask(&self, type_id: TypeId) -> BitmaskType;
+    fn bitmask(&self, id: ComponentId) -> SystemResult<BitmaskType>;
 
     /// An aftraction used to return the component buffer for a specific type.
-    fn component_buffer(&self, type_id: &TypeId) -> Option<ComponentBuffer>;
+    fn component_buffer(&self, id: &ComponentId) -> SystemResult<ComponentBuffer>;
 
     /// An aftraction used to return the component for the type id.
-    fn unique_component(&self, type_id: &TypeId) -> Option<UniqueComponent>;
+    fn unique_component(&self, id: &ComponentId) -> SystemResult<UniqueComponent>;
 
     /// An aftraction used to remove all the components associated with the
     /// provided entity.
     fn remove_components(&self, entity: &Entity);
 
-    generate_add_component_trait!(2; [A, TypeId], [B, TypeId]);
-    generate_add_component_trait!(3; [A, TypeId], [B, TypeId], [C, TypeId]);
-    generate_add_component_trait!(4; [A, TypeId], [B, TypeId], [C, TypeId], [D, TypeId]);
-    generate_add_component_trait!(5; [A, TypeId], [B, TypeId], [C, TypeId], [D, TypeId], [E, TypeId]);
-    generate_add_component_trait!(6; [A, TypeId], [B, TypeId], [C, TypeId], [D, TypeId], [E, TypeId], [F, TypeId]);
-    generate_add_component_trait!(7; [A, TypeId], [B, TypeId], [C, TypeId], [D, TypeId], [E, TypeId], [F, TypeId], [G, TypeId]);
-    generate_add_component_trait!(8; [A, TypeId], [B, TypeId], [C, TypeId], [D, TypeId], [E, TypeId], [F, TypeId], [G, TypeId], [H, TypeId]);
-    generate_add_component_trait!(9; [A, TypeId], [B, TypeId], [C, TypeId], [D, TypeId], [E, TypeId], [F, TypeId], [G, TypeId], [H, TypeId], [I, TypeId]);
+    /// An aftraction used to remove one component associated with the
+    /// provided entity.
+    fn remove_component(&self, entity: &Entity, id: &ComponentId) -> SystemResult<()>;
+
+    /// An aftraction used to grow the buffers of the components at once, the
+    /// entities with an id lower than the capacity can be written without
+    /// expanding them. The components are registered if needed.
+    fn reserve(&self, ids: &[ComponentId], capacity: usize) -> SystemResult<()>;
+
+    /// An aftraction used to remove all the components associated with the
+    /// provided entities, the locks are taken once.
+    fn remove_components_batch(&self, entities: &[Entity]);
+
+    generate_add_component_trait!(2; [A, ComponentId], [B, ComponentId]);
+    generate_add_component_trait!(3; [A, ComponentId], [B, ComponentId], [C, ComponentId]);
+    generate_add_component_trait!(4; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId]);
+    generate_add_component_trait!(5; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId]);
+    generate_add_component_trait!(6; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId]);
+    generate_add_component_trait!(7; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId]);
+    generate_add_component_trait!(8; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId]);
+    generate_add_component_trait!(9; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId]);
+    generate_add_component_trait!(10; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId]);
+    generate_add_component_trait!(11; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId], [K, ComponentId]);
+    generate_add_component_trait!(12; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId], [K, ComponentId], [L, ComponentId]);
+    generate_add_component_trait!(13; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId], [K, ComponentId], [L, ComponentId], [M, ComponentId]);
+    generate_add_component_trait!(14; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId], [K, ComponentId], [L, ComponentId], [M, ComponentId], [N, ComponentId]);
+    generate_add_component_trait!(15; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId], [K, ComponentId], [L, ComponentId], [M, ComponentId], [N, ComponentId], [O, ComponentId]);
+    generate_add_component_trait!(16; [A, ComponentId], [B, ComponentId], [C, ComponentId], [D, ComponentId], [E, ComponentId], [F, ComponentId], [G, ComponentId], [H, ComponentId], [I, ComponentId], [J, ComponentId], [K, ComponentId], [L, ComponentId], [M, ComponentId], [N, ComponentId], [O, ComponentId], [P, ComponentId]);
 }
 
 pub(crate) type Component = Option<Arc<dyn Any + Send + Sync>>;
@@ -160,6 +218,10 @@ pub(crate) type Component = Option<Arc<dyn Any + Send + Sync>>;
 /// Defines a data type that is a reference to the storage.
 type ComponentRef = RwLock<Component>;
 
+/// Defines a block of the buffer, it is shared with the snapshots taken by
+/// the iterators.
+pub(crate) type ComponentBlock = Arc<Vec<Option<ComponentRef>>>;
+
 /// Defines the buffer which contains the components.
 pub(crate) type BufferBlockVec = BlockVec<ComponentRef, NUM_OF_COMPONETS_PER_PAGE>;
 
@@ -184,6 +246,10 @@ pub struct ComponentsStorage {
     /// Contains all the bitmasks of the components.
     bitmasks: RwLock<FxHashMap<TypeId, u8>>,
 
+    /// Contains the id of each registered component, in registration
+    /// order, the position is the bitmask shift of the component.
+    ids: RwLock<Vec<ComponentId>>,
+
     /// Contains all the unique components in the storage.
     unique_components: RwLock<FxHashMap<TypeId, UniqueComponent>>,
 }
@@ -199,36 +265,84 @@ impl Default for ComponentsStorage {
         Self {
             components: RwLock::new(FxHashMap::default()),
             bitmasks: RwLock::new(FxHashMap::default()),
+            ids: RwLock::new(Vec::new()),
             unique_components: RwLock::new(FxHashMap::default()),
         }
     }
 }
 
 impl ComponentsHandler for ComponentsStorage {
-    /// Registers a component into the `Storage`.
-    fn register(&self, c0: TypeId, bitmask_shift: u8) {
+    /// Registers a component into the `Storage`, the next free bit of the
+    /// bitmask is assigned to it.
+    fn register(&self, c0: ComponentId) -> SystemResult<()> {
         {
             // Get exclusive access to the map.
             let mut c_write = self.components.write().unwrap();
             let mut bitmask_c_write = self.bitmasks.write().unwrap();
+            let mut ids_write = self.ids.write().unwrap();
+
+            // The component could be registered by other thread.
+            if c_write.contains_key(&c0.id) {
+                return Ok(());
+            }
+
+            // The shift must leave the bit inside the bitmask.
+            let bitmask_shift = ids_write.len();
+            if bitmask_shift >= MAX_COMPONENT_TYPES {
+                return Err(EcsError::BitmaskOverflow(c0.name));
+            }
             // At this point we need create a new component buffer
             // due it does not exist.
             let new_vec = BlockVec::<ComponentRef, NUM_OF_COMPONETS_PER_PAGE>::new();
             // Insert the new buffer associated with the correct id.
-            c_write.insert(c0, Arc::new(RwLock::new(new_vec)));
+            c_write.insert(c0.id, Arc::new(RwLock::new(new_vec)));
             // Insert the bitmask shift for the component.
-            bitmask_c_write.insert(c0, bitmask_shift);
+            bitmask_c_write.insert(c0.id, bitmask_shift as u8);
+            ids_write.push(c0);
         }
 
         // Sync buffers, this could happen if the component is added
         // after entities.
         self.sync_buffers();
+
+        Ok(())
+    }
+
+    /// Returns the ids of the registered components, in registration order.
+    fn registered(&self) -> Vec<ComponentId> {
+        self.ids.read().unwrap().clone()
     }
 
     /// Registers a new unique component into the `Storage`.
-    fn register_unique<C0: 'static + Send + Sync>(&self, id: TypeId, c: C0) {
+    fn register_unique<C0: 'static + Send + Sync>(&self, id: ComponentId, c: C0) -> Arc<SLock<C0>> {
+        let unique = Arc::new(RwLock::new(Storage::new(c)));
         let mut u_c_writer = self.unique_components.write().unwrap();
-        u_c_writer.insert(id, Arc::new(RwLock::new(Storage::new(c))));
+        u_c_writer.insert(id.id, unique.clone());
+        unique
+    }
+
+    /// Removes the component associated with the provided entity.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity which owns the component.
+    /// `id` - The id of the component to be removed.
+    fn remove_component(&self, entity: &Entity, id: &ComponentId) -> SystemResult<()> {
+        // Take a read lock over the components.
+        let c_reader = self.components.read().unwrap();
+
+        guard!(let Some(buffer) = c_reader.get(&id.id) else {
+            return Err(EcsError::ComponentNotRegistered(id.name));
+        });
+        let b_reader = buffer.read().unwrap();
+
+        // Check if there is data in the block.
+        if let Ok(Some(item_ref)) = b_reader.get_inbouds(entity.id) {
+            let mut ir_writer = item_ref.write().unwrap();
+            *ir_writer = None;
+        }
+
+        Ok(())
     }
 
     /// Removes all the components associated with the provided entity.
@@ -275,25 +389,24 @@ impl ComponentsHandler for ComponentsStorage {
     fn add_component<A: 'static + AnyStorage + Send + Sync>(
         &self,
         entity: Entity,
-        ids: (TypeId,),
+        ids: (ComponentId,),
         component: (A,),
-    ) {
+    ) -> SystemResult<()> {
         // Determines if some of the buffers grow.
         let mut were_expansions: bool = false;
 
+        // The component is registered on demand if it was never registered.
+        self.register_if_needed(ids.0)?;
+
         // In order to avoid a deadlock we must drop first the
         // lock on the reader before sync the buffers (c_reader, b_writer).
         {
             // Take a read lock and check if the component buffer exist.
             let c_reader = self.components.read().unwrap();
 
-            // Check if the buffer exist if not just panic.
-            guard!(let Some(c_buffer) = c_reader.get(&ids.0) else {
-                // The component does not exist, panic an error.
-                panic!(
-                    "The component {:?} is not registered",
-                    type_name::<A>()
-                );
+            // Check if the buffer exist if not return an error.
+            guard!(let Some(c_buffer) = c_reader.get(&ids.0.id) else {
+                return Err(EcsError::ComponentNotRegistered(ids.0.name));
             });
 
             // Get a reference and write lock to the buffer.
@@ -324,63 +437,147 @@ impl ComponentsHandler for ComponentsStorage {
         if were_expansions {
             self.sync_buffers();
         }
+
+        Ok(())
+    }
+
+    /// Grows the buffers of the components to host the capacity, all the
+    /// buffers are synced once at the end.
+    ///
+    /// # Arguments
+    ///
+    /// `ids` - The components to be grown.
+    /// `capacity` - The number of entity ids the buffers must host.
+    fn reserve(&self, ids: &[ComponentId], capacity: usize) -> SystemResult<()> {
+        for id in ids {
+            self.register_if_needed(*id)?;
+        }
+
+        let blocks_needed = capacity.div_ceil(NUM_OF_COMPONETS_PER_PAGE);
+        let mut were_expansions: bool = false;
+
+        // The reader is dropped before the buffers are synced.
+        {
+            let c_reader = self.components.read().unwrap();
+            for id in ids {
+                guard!(let Some(buffer) = c_reader.get(&id.id) else {
+                    return Err(EcsError::ComponentNotRegistered(id.name));
+                });
+
+                let mut b_writer = buffer.write().unwrap();
+                let blocks_len = b_writer.blocks_len();
+                if blocks_needed > blocks_len {
+                    b_writer.append_empty_blocks(blocks_needed - blocks_len);
+                    b_writer.fill_empty_slots(|| RwLock::new(None));
+                    were_expansions = true;
+                }
+            }
+        }
+
+        if were_expansions {
+            self.sync_buffers();
+        }
+
+        Ok(())
     }
 
-    /// Returns the associated bitmask for the `TypeId`.
+    /// Removes all the components of the entities, each buffer is locked
+    /// once.
     ///
     /// # Arguments
     ///
-    /// `type_id` - The id used to extract the bitmask.
-    fn bitmask(&self, type_id: TypeId) -> BitmaskType {
+    /// `entities` - The entities whose components are removed.
+    fn remove_components_batch(&self, entities: &[Entity]) {
+        let c_reader = self.components.read().unwrap();
+
+        for (_, buffer) in c_reader.iter() {
+            let b_reader = buffer.read().unwrap();
+            for entity in entities {
+                if let Some(item_ref) = b_reader.get(entity.id) {
+                    *item_ref.write().unwrap() = None;
+                }
+            }
+        }
+    }
+
+    /// Returns the associated bitmask for the component.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id used to extract the bitmask.
+    fn bitmask(&self, id: ComponentId) -> SystemResult<BitmaskType> {
         // Get read over the bitmasks.
         let b_reader = self.bitmasks.read().unwrap();
 
-        // Extract the bitmask if not presset just crash.
-        // This should never fail.
-        guard!(let Some(shift) = b_reader.get(&type_id) else {
-            panic!("The component with id {:?} does not have bitmask", type_id);
+        // Only the registered components have bitmask.
+        guard!(let Some(shift) = b_reader.get(&id.id) else {
+            return Err(EcsError::ComponentNotRegistered(id.name));
         });
 
         // Generate the bitmask shifting a binary 1 `shift` times.
-        0b1 << shift
+        Ok(0b1 << shift)
     }
 
     /// Returns a reference to the component buffer.
     ///
     /// # Arguments
     ///
-    /// `type_id` - The id of the type to be search.
-    fn component_buffer(&self, type_id: &TypeId) -> Option<ComponentBuffer> {
+    /// `id` - The id of the type to be search.
+    fn component_buffer(&self, id: &ComponentId) -> SystemResult<ComponentBuffer> {
         // Get a read lock to the components.
         let c_read = self.components.read().unwrap();
-        // Check idf it can get the buffer if not just return None.
-        guard!(let Some(buffer) = c_read.get(&type_id) else { return None; });
+        // Check idf it can get the buffer if not just return an error.
+        guard!(let Some(buffer) = c_read.get(&id.id) else {
+            return Err(EcsError::ComponentNotRegistered(id.name));
+        });
         // Returns a clone of the reference to the buffer.
-        Some(buffer.clone())
+        Ok(buffer.clone())
     }
 
     /// Returns a reference to the unique component associated with the id.
     ///
     /// # Arguments
     ///
-    /// `type_id`: The id of the component.
-    fn unique_component(&self, type_id: &TypeId) -> Option<UniqueComponent> {
+    /// `id`: The id of the component.
+    fn unique_component(&self, id: &ComponentId) -> SystemResult<UniqueComponent> {
         let c_u_read = self.unique_components.read().unwrap();
-        guard!(let Some(component) = c_u_read.get(&type_id) else { return None; });
-        Some(component.clone())
+        guard!(let Some(component) = c_u_read.get(&id.id) else {
+            return Err(EcsError::UniqueNotRegistered(id.name));
+        });
+        Ok(component.clone())
     }
 
-    generate_add_component!(2; [A, TypeId, 0], [B, TypeId, 1]);
-    generate_add_component!(3; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2]);
-    generate_add_component!(4; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2], [D, TypeId, 3]);
-    generate_add_component!(5; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2], [D, TypeId, 3], [E, TypeId, 4]);
-    generate_add_component!(6; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2], [D, TypeId, 3], [E, TypeId, 4], [F, TypeId, 5]);
-    generate_add_component!(7; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2], [D, TypeId, 3], [E, TypeId, 4], [F, TypeId, 5], [G, TypeId, 6]);
-    generate_add_component!(8; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2], [D, TypeId, 3], [E, TypeId, 4], [F, TypeId, 5], [G, TypeId, 6], [H, TypeId, 7]);
-    generate_add_component!(9; [A, TypeId, 0], [B, TypeId, 1], [C, TypeId, 2], [D, TypeId, 3], [E, TypeId, 4], [F, TypeId, 5], [G, TypeId, 6], [H, TypeId, 7], [I, TypeId, 8]);
+    generate_add_component!(2; [A, ComponentId, 0], [B, ComponentId, 1]);
+    generate_add_component!(3; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2]);
+    generate_add_component!(4; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3]);
+    generate_add_component!(5; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4]);
+    generate_add_component!(6; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5]);
+    generate_add_component!(7; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6]);
+    generate_add_component!(8; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7]);
+    generate_add_component!(9; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8]);
+    generate_add_component!(10; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9]);
+    generate_add_component!(11; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9], [K, ComponentId, 10]);
+    generate_add_component!(12; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9], [K, ComponentId, 10], [L, ComponentId, 11]);
+    generate_add_component!(13; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9], [K, ComponentId, 10], [L, ComponentId, 11], [M, ComponentId, 12]);
+    generate_add_component!(14; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9], [K, ComponentId, 10], [L, ComponentId, 11], [M, ComponentId, 12], [N, ComponentId, 13]);
+    generate_add_component!(15; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9], [K, ComponentId, 10], [L, ComponentId, 11], [M, ComponentId, 12], [N, ComponentId, 13], [O, ComponentId, 14]);
+    generate_add_component!(16; [A, ComponentId, 0], [B, ComponentId, 1], [C, ComponentId, 2], [D, ComponentId, 3], [E, ComponentId, 4], [F, ComponentId, 5], [G, ComponentId, 6], [H, ComponentId, 7], [I, ComponentId, 8], [J, ComponentId, 9], [K, ComponentId, 10], [L, ComponentId, 11], [M, ComponentId, 12], [N, ComponentId, 13], [O, ComponentId, 14], [P, ComponentId, 15]);
 }
 
 impl ComponentsStorage {
+    /// Registers the component only if it was not registered yet, the read
+    /// lock avoids blocking the rest of the threads on each addition.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id of the component.
+    fn register_if_needed(&self, id: ComponentId) -> SystemResult<()> {
+        if self.components.read().unwrap().contains_key(&id.id) {
+            return Ok(());
+        }
+        self.register(id)
+    }
+
     fn sync_buffers(&self) {
         // Get a writer over components in order to avoid
         // modifications in the buffers sizes in the middle of the
@@ -414,6 +611,8 @@ impl ComponentsStorage {
         for w in writers.iter_mut() {
             let len = w.blocks_len();
             w.append_empty_blocks(biggest - len);
+            // Every slot contains a lock, see `add_new_component`.
+            w.fill_empty_slots(|| RwLock::new(None));
         }
     }
 
@@ -431,14 +630,41 @@ impl ComponentsStorage {
         component: C,
     ) -> bool {
         let mut b_writer = buffer.write().unwrap();
-        // If there are not items in that position we have to create
-        // a new one.
 
-        // Replace the current component with a new one.
-        b_writer.set(
-            RwLock::new(Some(Arc::new(RwLock::new(component)))),
-            entity.id,
-        )
+        // The slots are never replaced because the iterators could be
+        // reading a snapshot of the blocks, the buffer is expanded and the
+        // new slots are filled with empty locks instead.
+        let blocks_needed = entity.id / NUM_OF_COMPONETS_PER_PAGE + 1;
+        let blocks_len = b_writer.blocks_len();
+        let expanded = blocks_needed > blocks_len;
+        if expanded {
+            b_writer.append_empty_blocks(blocks_needed - blocks_len);
+            b_writer.fill_empty_slots(|| RwLock::new(None));
+        }
+
+        if let Some(item_lock) = b_writer.get(entity.id) {
+            let mut i_writer = item_lock.write().unwrap();
+            *i_writer = Some(Arc::new(RwLock::new(component)));
+        }
+
+        expanded
+    }
+}
+
+/// Writes the component into the slot of the entity, the buffer must be
+/// already reserved so the slot contains a lock.
+///
+/// # Arguments
+///
+/// `buffer` - The buffer of the component type.
+/// `entity` - The owner of the component.
+/// `component` - The component itself.
+pub(crate) fn write_reserved<C: 'static + AnyStorage + Send + Sync>(
+    buffer: &BufferBlockVec,
+    entity: &Entity,
+    component: C) {
+    if let Some(item_lock) = buffer.get(entity.id) {
+        *item_lock.write().unwrap() = Some(Arc::new(RwLock::new(component)));
     }
 }
 
@@ -452,3 +678,18 @@ impl Debug for ComponentsStorage {
         )
     }
 }
+
+#[test]
+fn too_many_component_types_fails() {
+    use crate::type_id::id_of;
+
+    struct Overflow;
+
+    // Fill the bitmask, the next component does not fit.
+    let storage = ComponentsStorage::default();
+    *storage.ids.write().unwrap() = vec![id_of::<u8>(); MAX_COMPONENT_TYPES];
+
+    let error = storage.register(id_of::<Overflow>()).unwrap_err();
+    assert_eq!(error, EcsError::BitmaskOverflow(id_of::<Overflow>().name));
+    assert!(error.to_string().starts_with("Too many component types"));
+}
@@ -1 +1,6 @@
-pub type BitmaskType = u64;
+/// Defines the bitmask of the components, each registered component type
+/// takes one bit so it limits the number of component types.
+pub type BitmaskType = u128;
+
+/// Defines the maximum number of component types the `World` supports.
+pub const MAX_COMPONENT_TYPES: usize = BitmaskType::BITS as usize;
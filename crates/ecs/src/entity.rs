@@ -4,7 +4,8 @@ use utils::BlockVec;
 
 use crate::{
     bundle::ComponentBundler,
-    consts::BitmaskType
+    consts::BitmaskType,
+    index::EntityIndex
 };
 
 /// Pro and aftraction to handle the entitines in the storages.
@@ -18,14 +19,50 @@ pub trait EntitiesHandler {
     /// An aftraction to reset the mask of the entity.
     fn reset_bitmask(&self, entity: &Entity);
 
+    /// An aftraction used to add bits to the mask of a living entity, the
+    /// mask is read and written under the same lock.
+    fn insert_bits(&self, entity: &Entity, bits: &BitmaskType);
+
+    /// An aftraction used to remove bits from the mask of a living entity,
+    /// the mask is read and written under the same lock.
+    fn remove_bits(&self, entity: &Entity, bits: &BitmaskType);
+
     /// An aftraction used to search for all the entities which 
     fn query_by_bitmask(&self, bitmasks: BitmaskType) -> Vec<Entity>;
+
+    /// An aftraction used to know if the entity handle still points to a
+    /// living entity.
+    fn is_alive(&self, entity: &Entity) -> bool;
+
+    /// An aftraction used to invalidate all the handles of the entity, it
+    /// returns the entity with the new generation or None if the handle
+    /// was already stale.
+    fn bump_generation(&self, entity: &Entity) -> Option<Entity>;
+
+    /// An aftraction used to register the same bitmask for many entities,
+    /// the locks are taken once.
+    fn register_bitmasks(&self, entities: &[Entity], bit_mask: &BitmaskType);
+
+    /// An aftraction used to reset the masks of many entities.
+    fn reset_bitmasks(&self, entities: &[Entity]);
+
+    /// An aftraction used to invalidate the handles of many entities, it
+    /// returns the entities with the new generation, the stale handles are
+    /// skipped.
+    fn bump_generations(&self, entities: &[Entity]) -> Vec<Entity>;
 }
 
 /// Represents a storage which holds entities.
 pub struct EntitiesStorage<const N: usize> {
     /// Contains a list of all the masks.
-    bit_masks: RwLock<BlockVec<BitmaskType, N>>
+    bit_masks: RwLock<BlockVec<BitmaskType, N>>,
+
+    /// Contains the current generation of each entity slot.
+    generations: RwLock<BlockVec<EntityGeneration, N>>,
+
+    /// Contains the entities grouped by bitmask, used to answer the
+    /// queries.
+    index: RwLock<EntityIndex>
 }
 
 unsafe impl<const N: usize> Send for EntitiesStorage<N> {}
@@ -35,11 +72,42 @@ impl<const N: usize> Default for EntitiesStorage<N> {
     /// Creates and returns a new `EntitiesStorage`.
     fn default() -> Self {
         Self {
-            bit_masks: RwLock::new(BlockVec::new())
+            bit_masks: RwLock::new(BlockVec::new()),
+            generations: RwLock::new(BlockVec::new()),
+            index: RwLock::new(EntityIndex::default())
         }
     }
 }
 
+impl<const N: usize> EntitiesStorage<N> {
+    /// Replaces the mask of the entity with the one returned by `f`.
+    ///
+    /// The write lock of the masks is held while the mask is read and
+    /// written, so two concurrent updates of the same entity do not lose
+    /// bits. The generation is checked under that lock too, so the mask of
+    /// a removed entity is not written back.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity whose mask is updated.
+    /// `f` - Returns the new mask from the current one.
+    fn update_bitmask<F: FnOnce(BitmaskType) -> BitmaskType>(&self, entity: &Entity, f: F) {
+        // The locks are taken in the same order as `register_bitmask`.
+        let mut cm_writer = self.bit_masks.write().unwrap();
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        let current: BitmaskType = match cm_writer.get_inbouds(entity.id) {
+            Ok(Some(bitmask)) => *bitmask,
+            _ => 0
+        };
+        let bitmask = f(current);
+        cm_writer.set(bitmask, entity.id);
+        self.index.write().unwrap().insert(*entity, bitmask);
+    }
+}
+
 impl<const N: usize> EntitiesHandler for EntitiesStorage<N> {
     /// Registers the bit mask associated with the entity.
     ///
@@ -51,7 +119,11 @@ impl<const N: usize> EntitiesHandler for EntitiesStorage<N> {
         // Get a write lock of the bit masks.
         let mut cm_writer = self.bit_masks.write().unwrap();
         // Add or override the mask.
-        cm_writer.set(bit_mask.clone(), entity.id);
+        cm_writer.set(*bit_mask, entity.id);
+        // Stamp the generation of the new entity in the slot.
+        self.generations.write().unwrap().set(entity.generation, entity.id);
+        // Move the entity to the group of the new mask.
+        self.index.write().unwrap().insert(*entity, *bit_mask);
     }
 
     /// Returns the bit mask for the given entity.
@@ -60,6 +132,11 @@ impl<const N: usize> EntitiesHandler for EntitiesStorage<N> {
     ///
     /// `entity` - The entity used to return the bitmask.
     fn get_bitmask(&self, entity: &Entity) -> BitmaskType {
+        // A stale handle does not contain any component.
+        if !self.is_alive(entity) {
+            return 0;
+        }
+
         // Get a read lock of the bit masks.
         let cm_reader = self.bit_masks.read().unwrap();
         // Return the bit mask.
@@ -71,7 +148,7 @@ impl<const N: usize> EntitiesHandler for EntitiesStorage<N> {
             );
         });
 
-        bit_mask.clone()
+        *bit_mask
     }
 
     /// Rests the bitmask of the given entity.
@@ -87,42 +164,140 @@ impl<const N: usize> EntitiesHandler for EntitiesStorage<N> {
         let mut cm_writer = self.bit_masks.write().unwrap();
         // Clear the bitmask.
         cm_writer.set(0, entity.id);
+        // Remove the entity from the queries.
+        self.index.write().unwrap().remove(entity.id);
+    }
+
+    /// Adds the bits to the mask of the entity, the stale handles are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity whose mask is updated.
+    /// `bits` - The bits to be added.
+    fn insert_bits(&self, entity: &Entity, bits: &BitmaskType) {
+        self.update_bitmask(entity, |bitmask| bitmask | *bits);
+    }
+
+    /// Removes the bits from the mask of the entity, the stale handles are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity whose mask is updated.
+    /// `bits` - The bits to be removed.
+    fn remove_bits(&self, entity: &Entity, bits: &BitmaskType) {
+        self.update_bitmask(entity, |bitmask| bitmask & !*bits);
     }
 
     /// Returns a list of entities which cumpliments with the
     /// bitmask requirement.
+    ///
+    /// The entities are taken from the index, so only the groups of
+    /// entities are checked and not every single entity.
     /// 
     /// # Arguments
     /// 
     /// `bitmasks` - The bitmask filter.
-    /// 
-    /// TODO(Angel): Huge optimization here, it is not needed to
-    /// iterate over all the entire vec just till the last element
-    /// but that required `BlockVec` modifications.
     fn query_by_bitmask(&self, bitmasks: BitmaskType) -> Vec<Entity> {
-        // A list of filtered entities.
-        let mut f_entities: Vec<Entity> = Vec::new();
-        // The read access to the masks. 
-        let r_bitmasks = self.bit_masks.read().unwrap();
-        // Get the length of the vector.
-        let actual_len = r_bitmasks.actual_len();
-        
-        // As bitmask is setted to 0 when it is deleted the filter
-        // will ignore them.
-        for i in 0..actual_len {
-            // The entity bitmask.
-            if let Some(e_bitmask) = r_bitmasks.get(i) {
-                // Apply a logical "and" over the masks, if the result
-                // is equal to the mask provided then the entity 
-                // contains all the needed components.
-                if e_bitmask & bitmasks == bitmasks {
-                    f_entities.push(Entity::new(i));
-                }
-            }
-        } 
-        
-        f_entities
+        self.index.read().unwrap().query(bitmasks)
+    }
+
+    /// Returns true if the generation of the entity matches with the
+    /// generation of the slot.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity to check.
+    fn is_alive(&self, entity: &Entity) -> bool {
+        let r_generations = self.generations.read().unwrap();
+        match r_generations.get_inbouds(entity.id) {
+            Ok(Some(generation)) => *generation == entity.generation,
+            _ => false
+        }
+    }
+
+    /// Increases the generation of the slot, all the handles with the old
+    /// generation are not alive anymore.
+    ///
+    /// The generation is compared and increased under the same lock, so
+    /// when the same handle is invalidated concurrently only one of the
+    /// calls gets the new entity.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity to invalidate.
+    fn bump_generation(&self, entity: &Entity) -> Option<Entity> {
+        let mut g_writer = self.generations.write().unwrap();
+        bump_if_alive(&mut g_writer, entity)
     }
+
+    /// Registers the bitmask for all the entities.
+    ///
+    /// # Arguments
+    ///
+    /// `entities` - The entities to be registered.
+    /// `bit_mask` - The bitmask shared by all of them.
+    fn register_bitmasks(&self, entities: &[Entity], bit_mask: &BitmaskType) {
+        // The locks are taken in the same order as `register_bitmask`.
+        let mut cm_writer = self.bit_masks.write().unwrap();
+        let mut g_writer = self.generations.write().unwrap();
+        for entity in entities {
+            cm_writer.set(*bit_mask, entity.id);
+            g_writer.set(entity.generation, entity.id);
+        }
+        self.index.write().unwrap().insert_batch(entities, *bit_mask);
+    }
+
+    /// Resets the bitmasks of the entities.
+    ///
+    /// # Arguments
+    ///
+    /// `entities` - The entities whose masks are cleared.
+    fn reset_bitmasks(&self, entities: &[Entity]) {
+        let mut cm_writer = self.bit_masks.write().unwrap();
+        let mut i_writer = self.index.write().unwrap();
+
+        for entity in entities {
+            cm_writer.set(0, entity.id);
+            i_writer.remove(entity.id);
+        }
+    }
+
+    /// Increases the generation of the slots of the entities.
+    ///
+    /// # Arguments
+    ///
+    /// `entities` - The entities to invalidate.
+    fn bump_generations(&self, entities: &[Entity]) -> Vec<Entity> {
+        let mut g_writer = self.generations.write().unwrap();
+
+        // An entity listed twice is only invalidated once, the second
+        // handle is already stale.
+        entities.iter()
+            .filter_map(|entity| bump_if_alive(&mut g_writer, entity))
+            .collect()
+    }
+}
+
+/// Increases the generation of the slot if it still matches the generation
+/// of the entity, it returns the entity with the new generation.
+///
+/// # Arguments
+///
+/// `generations` - The generations of the slots, already locked.
+/// `entity` - The entity to invalidate.
+fn bump_if_alive<const N: usize>(
+    generations: &mut BlockVec<EntityGeneration, N>,
+    entity: &Entity) -> Option<Entity> {
+    match generations.get_inbouds(entity.id) {
+        Ok(Some(generation)) if *generation == entity.generation => {},
+        _ => return None
+    }
+
+    let next = entity.generation.wrapping_add(1);
+    generations.set(next, entity.id);
+    Some(Entity::with_generation(entity.id, next))
 }
 
 /// Provides an aftraction to handle entities.
@@ -134,16 +309,47 @@ pub trait EntityHandler {
  
     /// Defines an interface to delete entities.
     fn remove_entity(&self, entity: Entity);
+
+    /// Defines an interface to add many entities at once, `f` returns the
+    /// components of the entity at each position.
+    fn add_entities_batch<B: ComponentBundler, F: Fn(usize) -> B>(
+        &self,
+        count: usize,
+        f: F) -> Vec<Entity>;
+
+    /// Defines an interface to delete many entities at once.
+    fn remove_entities_batch(&self, entities: &[Entity]);
+
+    /// Defines an interface to know if the entity still exists.
+    fn is_alive(&self, entity: Entity) -> bool;
+
+    /// Defines an interface to add a component to an existing entity.
+    fn add_component<T: 'static + Send + Sync>(&self, entity: Entity, component: T);
+
+    /// Defines an interface to remove a component from an existing entity.
+    fn remove_component<T: 'static + Send + Sync>(&self, entity: Entity);
+
+    /// Defines an interface to list the components of an entity by name.
+    fn component_names(&self, entity: Entity) -> Vec<&'static str>;
 }
 
 /// Defines the size of the entities id.
 pub(crate) type EntityId = usize;
 
+/// Defines the size of the entities generation.
+pub(crate) type EntityGeneration = u32;
+
 /// Represents an Entity in the ECS.
-#[derive(Copy, Clone)]
+///
+/// The ids are reused when the entities are removed, the generation allows
+/// to detect handles which point to a removed entity.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Entity {
     /// Conatins the unique id of the entity.
-    pub(crate) id: EntityId
+    pub(crate) id: EntityId,
+
+    /// Contains the generation of the id when the entity was created.
+    pub(crate) generation: EntityGeneration
 }
 
 impl Entity {
@@ -153,8 +359,29 @@ impl Entity {
     /// 
     /// `id` - The id for the entity.
     pub fn new(id: EntityId) -> Self {
+        Self::with_generation(id, 0)
+    }
+
+    /// Creates and returns a new entity with the provided generation.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id for the entity.
+    /// `generation` - The generation of the id.
+    pub(crate) fn with_generation(id: EntityId, generation: EntityGeneration) -> Self {
         Self {
-            id
+            id,
+            generation
         }
     }
+
+    /// Returns the id of the entity.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the generation of the entity.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
 }
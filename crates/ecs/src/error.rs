@@ -0,0 +1,50 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::{consts::MAX_COMPONENT_TYPES, entity::Entity};
+
+/// Represents the errors that can happen when the components of the ECS
+/// are accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcsError {
+    /// The component with the contained type name is not registered.
+    ComponentNotRegistered(&'static str),
+
+    /// The unique component with the contained type name is not registered.
+    UniqueNotRegistered(&'static str),
+
+    /// The contained entity was removed or never existed.
+    EntityNotFound(Entity),
+
+    /// The stored component could not be casted to the contained type name.
+    CastFailed(&'static str),
+
+    /// The component with the contained type name does not fit in the
+    /// bitmask, there are too many components registered.
+    BitmaskOverflow(&'static str)
+}
+
+/// The result returned by the systems and the components handlers.
+pub type SystemResult<T, E = EcsError> = Result<T, E>;
+
+impl Display for EcsError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            EcsError::ComponentNotRegistered(name) =>
+                write!(formatter, "The component {} is not registered", name),
+            EcsError::UniqueNotRegistered(name) =>
+                write!(formatter, "The unique component {} does not exist", name),
+            EcsError::EntityNotFound(entity) =>
+                write!(formatter, "The entity {} does not exist", entity.id),
+            EcsError::CastFailed(name) =>
+                write!(formatter, "The component could not be casted to {}", name),
+            EcsError::BitmaskOverflow(name) => write!(
+                formatter,
+                "Too many component types (max {}), {} can not be registered",
+                MAX_COMPONENT_TYPES,
+                name
+            )
+        }
+    }
+}
+
+impl std::error::Error for EcsError {}
@@ -0,0 +1,225 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, Mutex}
+};
+
+use crate::{
+    access::{Accessible, SLock},
+    component::{ComponentBuffer, ComponentsHandler},
+    entity::Entity,
+    type_id::id_of
+};
+
+/// Contains the events of type T sent during the current and the previous
+/// frame, the older events are dropped.
+pub struct Events<T> {
+    /// The events sent during the previous frame.
+    previous: Vec<T>,
+
+    /// The events sent during the current frame.
+    current: Vec<T>,
+
+    /// The id of the first event in `previous`.
+    start: usize,
+
+    /// The number of events sent, it is the id of the next event.
+    count: usize,
+
+    /// The id of the next event to read by each reader.
+    cursors: Mutex<HashMap<TypeId, usize>>
+}
+
+impl<T> Default for Events<T> {
+    /// Creates and returns a new `Events` without events.
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            start: 0,
+            count: 0,
+            cursors: Mutex::new(HashMap::new())
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Adds an event to the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The event to be sent.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+        self.count += 1;
+    }
+
+    /// Swaps the buffers, the events of the previous frame are dropped.
+    pub fn update(&mut self) {
+        self.start += self.previous.len();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    /// Returns the events which were not read using the cursor and moves
+    /// the cursor after the last event.
+    ///
+    /// # Arguments
+    ///
+    /// `cursor` - The id of the next event to read.
+    pub fn read_from(&self, cursor: &mut usize) -> impl Iterator<Item = &T> {
+        // The events before the start were already dropped.
+        let skip = (*cursor).max(self.start) - self.start;
+        *cursor = self.count;
+        self.previous.iter().chain(self.current.iter()).skip(skip)
+    }
+
+    /// Returns the number of events stored.
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns true if there are not events stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Allows a system to read the events of type T.
+///
+/// Each reader type keeps its own cursor, so every reader sees each event
+/// once. The readers which need their own cursor use a different marker
+/// type R.
+pub struct EventReader<T: 'static + Send + Sync, R: 'static = ()> {
+    events: Arc<SLock<Events<T>>>,
+    _marker: PhantomData<fn() -> R>
+}
+
+impl<T: 'static + Send + Sync, R: 'static> Accessible for EventReader<T, R> {
+    type Component = Events<T>;
+
+    fn new(_buffer: ComponentBuffer, _entities: Arc<Vec<Entity>>) -> Self {
+        panic!("new is not available for EventReader try with unique_new");
+    }
+
+    fn unique_new(component: Arc<SLock<Events<T>>>) -> Self {
+        Self {
+            events: component,
+            _marker: PhantomData
+        }
+    }
+
+    fn is_unique() -> bool { true }
+}
+
+impl<T: 'static + Send + Sync, R: 'static> EventReader<T, R> {
+    /// Returns a copy of the events this reader did not read yet.
+    pub fn read(&self) -> Vec<T> where T: Clone {
+        let events = self.events.read().unwrap();
+        let mut cursors = events.cursors.lock().unwrap();
+        let cursor = cursors.entry(TypeId::of::<R>()).or_insert(0);
+        events.read_from(cursor).cloned().collect()
+    }
+}
+
+/// Allows a system to send events of type T.
+pub struct EventWriter<T: 'static + Send + Sync> {
+    events: Arc<SLock<Events<T>>>
+}
+
+impl<T: 'static + Send + Sync> Accessible for EventWriter<T> {
+    type Component = Events<T>;
+
+    fn new(_buffer: ComponentBuffer, _entities: Arc<Vec<Entity>>) -> Self {
+        panic!("new is not available for EventWriter try with unique_new");
+    }
+
+    fn unique_new(component: Arc<SLock<Events<T>>>) -> Self {
+        Self { events: component }
+    }
+
+    fn is_unique() -> bool { true }
+}
+
+impl<T: 'static + Send + Sync> EventWriter<T> {
+    /// Sends the event, the readers see it until the end of the next frame.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The event to be sent.
+    pub fn send(&self, event: T) {
+        self.events.write().unwrap().send(event);
+    }
+}
+
+/// Provides the functions used to handle the events.
+pub trait EventHandler {
+    /// An aftraction used to register the events of type T, after this the
+    /// systems can use `EventReader<T>` and `EventWriter<T>`.
+    fn register_event<T: 'static + Send + Sync>(&self);
+
+    /// An aftraction used to send an event from outside a system.
+    fn send_event<T: 'static + Send + Sync>(&self, event: T);
+
+    /// An aftraction used to swap the buffers of all the registered events,
+    /// it must be called once per frame.
+    fn update_events(&self);
+}
+
+/// Swaps the buffers of the events of type T.
+///
+/// # Arguments
+///
+/// `components_handler` - The handler which contains the events.
+pub(crate) fn update_events_of<T: 'static + Send + Sync, H: ComponentsHandler>(
+    components_handler: &H
+) {
+    guard!(let Ok(c) = components_handler.unique_component(&id_of::<Events<T>>()) else {
+        return;
+    });
+    guard!(let Ok(events) = c.downcast::<SLock<Events<T>>>() else {
+        panic!("Error casting the events {}", type_name::<T>());
+    });
+    events.write().unwrap().update();
+}
+
+#[cfg(test)]
+struct OtherReader;
+
+#[test]
+fn multiple_readers_see_every_event() {
+    let events = Arc::new(SLock::new(crate::storage::Storage::new(Events::<u32>::default())));
+    let writer = EventWriter::<u32>::unique_new(events.clone());
+    let reader = EventReader::<u32>::unique_new(events.clone());
+    let other = EventReader::<u32, OtherReader>::unique_new(events.clone());
+
+    writer.send(1);
+    writer.send(2);
+    assert_eq!(reader.read(), vec![1, 2]);
+    assert!(reader.read().is_empty());
+
+    events.write().unwrap().update();
+    writer.send(3);
+    assert_eq!(reader.read(), vec![3]);
+    assert_eq!(other.read(), vec![1, 2, 3]);
+}
+
+#[test]
+fn events_expire_after_two_frames() {
+    let mut events = Events::<u32>::default();
+    let mut cursor = 0;
+
+    events.send(1);
+    events.update();
+    events.send(2);
+    assert_eq!(events.len(), 2);
+
+    // The first event is dropped, it was sent two frames ago.
+    events.update();
+    assert_eq!(events.read_from(&mut cursor).copied().collect::<Vec<_>>(), vec![2]);
+
+    events.update();
+    assert!(events.is_empty());
+    assert_eq!(events.read_from(&mut cursor).count(), 0);
+}
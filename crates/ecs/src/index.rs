@@ -0,0 +1,131 @@
+use fxhash::FxHashMap;
+
+use crate::{
+    consts::BitmaskType,
+    entity::{Entity, EntityId}
+};
+
+/// Groups the entities by their bitmask, this allows to answer the queries
+/// without walking over all the entity slots.
+///
+/// All the entities inside a group contain exactly the same components, a
+/// query only needs to check the groups and not the entities.
+#[derive(Default)]
+pub(crate) struct EntityIndex {
+    /// Contains the entities grouped by bitmask.
+    groups: FxHashMap<BitmaskType, Vec<Entity>>,
+
+    /// Contains the bitmask and the position inside the group of each
+    /// entity, it is used to remove the entities without searching.
+    locations: FxHashMap<EntityId, (BitmaskType, usize)>
+}
+
+impl EntityIndex {
+    /// Adds the entity into the group of the provided bitmask, if the
+    /// entity was in other group it is moved.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity to be added.
+    /// `bitmask` - The bitmask of the entity.
+    pub(crate) fn insert(&mut self, entity: Entity, bitmask: BitmaskType) {
+        self.remove(entity.id);
+
+        // The entities without components are not part of any query.
+        if bitmask == 0 {
+            return;
+        }
+
+        let group = self.groups.entry(bitmask).or_default();
+        self.locations.insert(entity.id, (bitmask, group.len()));
+        group.push(entity);
+    }
+
+    /// Adds the entities into the group of the provided bitmask, the memory
+    /// of the group is reserved once.
+    ///
+    /// # Arguments
+    ///
+    /// `entities` - The entities to be added.
+    /// `bitmask` - The bitmask shared by all the entities.
+    pub(crate) fn insert_batch(&mut self, entities: &[Entity], bitmask: BitmaskType) {
+        for entity in entities {
+            self.remove(entity.id);
+        }
+
+        if bitmask == 0 {
+            return;
+        }
+
+        self.locations.reserve(entities.len());
+        let group = self.groups.entry(bitmask).or_default();
+        group.reserve(entities.len());
+        for entity in entities {
+            self.locations.insert(entity.id, (bitmask, group.len()));
+            group.push(*entity);
+        }
+    }
+
+    /// Removes the entity from its group.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id of the entity to be removed.
+    pub(crate) fn remove(&mut self, id: EntityId) {
+        guard!(let Some((bitmask, position)) = self.locations.remove(&id) else {
+            return;
+        });
+        guard!(let Some(group) = self.groups.get_mut(&bitmask) else {
+            return;
+        });
+
+        // Move the last entity into the empty position.
+        group.swap_remove(position);
+        if let Some(moved) = group.get(position) {
+            self.locations.insert(moved.id, (bitmask, position));
+        }
+
+        if group.is_empty() {
+            self.groups.remove(&bitmask);
+        }
+    }
+
+    /// Returns all the entities which contain at least the components of
+    /// the bitmask.
+    ///
+    /// # Arguments
+    ///
+    /// `bitmask` - The bitmask filter.
+    pub(crate) fn query(&self, bitmask: BitmaskType) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = Vec::new();
+        for (group_bitmask, group) in self.groups.iter() {
+            if group_bitmask & bitmask == bitmask {
+                entities.extend_from_slice(group);
+            }
+        }
+        entities
+    }
+}
+
+#[test]
+fn query_by_groups() {
+    let mut index = EntityIndex::default();
+    index.insert(Entity::new(0), 0b011);
+    index.insert(Entity::new(1), 0b001);
+    index.insert(Entity::new(2), 0b111);
+
+    let mut entities = index.query(0b001);
+    entities.sort_by_key(|e| e.id);
+    assert_eq!(entities, vec![Entity::new(0), Entity::new(1), Entity::new(2)]);
+
+    // Moving an entity to other group.
+    index.insert(Entity::new(2), 0b100);
+    assert_eq!(index.query(0b010), vec![Entity::new(0)]);
+
+    // Removing an entity keeps the rest of the group.
+    index.insert(Entity::new(3), 0b001);
+    index.remove(1);
+    assert_eq!(index.query(0b001).len(), 2);
+    index.remove(0);
+    assert_eq!(index.query(0b001), vec![Entity::new(3)]);
+}
@@ -1,30 +1,52 @@
 mod type_id;
-mod bundle;
 mod consts;
+mod index;
+
+mod bundle;
+pub use bundle::ComponentBundler;
 
 mod query;
 pub use query::*;
 
 mod sync;
-pub use sync::{TaskSync, TaskWaitable};
+pub use sync::{TaskSync, TaskWaitable, TaskResult};
 
 mod component;
 pub use component::ComponentHandler;
 
+mod registry;
+pub use registry::{Component, ComponentRegistrar};
+pub use ecs_macros::Component;
+// Used by `#[derive(Component)]` to submit the components.
+#[doc(hidden)]
+pub use inventory;
+
 mod world;
-pub use world::{World, DefaultWorld};
+pub use world::{World, DefaultWorld, WorldWorkers};
 
 mod entity;
 pub use entity::{Entity, EntityHandler};
 
 mod system;
-pub use system::{System, SystemHandler};
+pub use system::{System, SystemWithData, SystemHandler};
+
+mod error;
+pub use error::{EcsError, SystemResult};
 
 mod access;
-pub use access::{Read, Write, UniqueRead, UniqueWrite, Accessible};
+pub use access::{Read, Write, Reader, UniqueRead, UniqueWrite, UniqueHandle, Accessible};
+// The parallel iterators returned by `Read::par_iter` need the rayon traits.
+pub use rayon::iter::{ParallelIterator, IndexedParallelIterator};
+
+mod event;
+pub use event::{Events, EventReader, EventWriter, EventHandler};
 
 mod storage;
 pub use storage::Storage;
 
+pub use tasks::{TaskPriority, CancellationToken, WorkersDescriptor};
+
+// Allows `#[derive(Component)]` to be used inside this crate.
+extern crate self as ecs;
 extern crate fxhash;
 #[macro_use] extern crate guard;
@@ -1,33 +1,199 @@
+use std::{
+    iter::Filter,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+        Condvar,
+        Mutex
+    }
+};
+
 use paste::paste;
+use tasks::{Dispatcher, Task, Workers};
+
+/// Provides a way to filter the items of a query while iterating, it avoids
+/// collecting the items in order to filter them later.
+pub trait QueryFilter: Iterator + Sized {
+    /// Returns an iterator which only yields the items that match the
+    /// predicate.
+    ///
+    /// # Arguments
+    ///
+    /// `predicate` - Returns true if the item must be yielded.
+    fn with_filter<F: FnMut(&Self::Item) -> bool>(self, predicate: F) -> Filter<Self, F> {
+        self.filter(predicate)
+    }
+}
 
-pub struct TupleAccessIterator<A: Iterator, B: Iterator>(A, B);
+/// Provides a way to iterate the items of a query in parallel using the
+/// workers of the `World`.
+pub trait ParallelQuery: Iterator + Sized where Self::Item: Send {
+    /// Calls the function with each item, the items are split in chunks
+    /// which are processed by the workers and the calling thread. It
+    /// returns once all the items were visited.
+    ///
+    /// The calling thread takes chunks too and only waits for the workers
+    /// which already picked up a chunk, so it never waits for a worker
+    /// which is busy running the caller itself. If the function panics the panic is
+    /// propagated once all the chunks finished.
+    ///
+    /// # Arguments
+    ///
+    /// `world_workers` - The workers which process the chunks.
+    /// `chunk_size` - The number of items of each chunk.
+    /// `f` - The function called with each item.
+    fn par_for_each<F: Fn(Self::Item) + Send + Sync>(
+        self,
+        world_workers: &Workers,
+        chunk_size: usize,
+        f: F) {
+        let chunk_size = chunk_size.max(1);
+        let mut chunks: Vec<Mutex<Option<Vec<Self::Item>>>> = Vec::new();
+        let mut chunk: Vec<Self::Item> = Vec::with_capacity(chunk_size);
+        for item in self {
+            chunk.push(item);
+            if chunk.len() == chunk_size {
+                chunks.push(Mutex::new(Some(chunk)));
+                chunk = Vec::with_capacity(chunk_size);
+            }
+        }
+        if !chunk.is_empty() {
+            chunks.push(Mutex::new(Some(chunk)));
+        }
+
+        // The calling thread takes one of the chunks, the rest are shared
+        // with the workers.
+        let helpers: usize = (chunks.len() - 1).min(world_workers.thread_count());
+        if helpers == 0 {
+            chunks.into_iter()
+                .filter_map(|c| c.into_inner().unwrap())
+                .flatten()
+                .for_each(f);
+            return;
+        }
 
-impl<
-    A: Iterator, B: Iterator
-> Iterator for TupleAccessIterator<A, B> {
-    type Item = (<A as Iterator>::Item, <B as Iterator>::Item);
+        let state = ParallelChunks {
+            chunks,
+            next: AtomicUsize::new(0),
+            panicked: AtomicBool::new(false),
+            f
+        };
+        let helpers_state = Arc::new(ParallelHelpers::default());
 
-    fn next(&mut self) -> Option<Self::Item> {
-        guard!(let Some(a) = self.0.next() else { return None; });
-        guard!(let Some(b) = self.1.next() else { return None; });
-        Some((a, b))
+        let tasks: Vec<Task> = (0..helpers).map(|_| {
+            let helpers_state = helpers_state.clone();
+            let state_ref: &ParallelChunks<Self::Item, F> = &state;
+            let task: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+                // The helpers which start once the call returned must not
+                // touch the state, it could be already dropped.
+                if !helpers_state.join() {
+                    return;
+                }
+                if catch_unwind(AssertUnwindSafe(|| state_ref.run())).is_err() {
+                    state_ref.panicked.store(true, Ordering::SeqCst);
+                }
+                helpers_state.leave();
+            });
+            // SAFETY: The tasks only use the state after joining, the
+            // joins are closed and the joined helpers are waited below
+            // before the state is dropped, even if `f` panics in the
+            // calling thread.
+            unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + '_>, Task>(task) }
+        }).collect();
+        world_workers.execute_batch(tasks);
+
+        // The calling thread takes chunks until all of them are taken, so
+        // it only waits for the helpers which are processing a chunk, never
+        // for the ones still queued behind it.
+        let result = catch_unwind(AssertUnwindSafe(|| state.run()));
+        helpers_state.close_and_wait();
+
+        if let Err(panic) = result {
+            resume_unwind(panic);
+        }
+        if state.panicked.load(Ordering::SeqCst) {
+            panic!("A chunk of the parallel query panicked");
+        }
     }
 }
 
-pub trait Searchable {
-    type Iter: Iterator;
+/// Tracks the helpers of a parallel query which are taking chunks, the
+/// helpers which start after the query is closed do nothing.
+#[derive(Default)]
+struct ParallelHelpers {
+    /// True once the calling thread finished, and the number of helpers
+    /// which are still taking chunks.
+    state: Mutex<(bool, usize)>,
 
-    fn query(self) -> Self::Iter;
+    /// Used to notify the calling thread when a helper leaves.
+    left_signal: Condvar
+}
+
+impl ParallelHelpers {
+    /// Registers a helper, it returns false if the query is already
+    /// closed.
+    fn join(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.0 {
+            return false;
+        }
+        state.1 += 1;
+        true
+    }
+
+    /// Unregisters a helper and wakes up the calling thread.
+    fn leave(&self) {
+        self.state.lock().unwrap().1 -= 1;
+        self.left_signal.notify_all();
+    }
+
+    /// Closes the query and blocks until all the joined helpers left.
+    fn close_and_wait(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.0 = true;
+        while state.1 > 0 {
+            state = self.left_signal.wait(state).unwrap();
+        }
+    }
 }
 
-impl<A: Iterator, B: Iterator> Searchable for (A, B) {
-    type Iter = TupleAccessIterator<A, B>;
+impl<I: Iterator> ParallelQuery for I where I::Item: Send {}
+
+/// Contains the chunks of a parallel query, each chunk is taken only once.
+struct ParallelChunks<T, F: Fn(T)> {
+    /// The chunks which are still not taken.
+    chunks: Vec<Mutex<Option<Vec<T>>>>,
+
+    /// The index of the next chunk to be taken.
+    next: AtomicUsize,
 
-    fn query(self) -> Self::Iter {
-        TupleAccessIterator(self.0, self.1)
+    /// True if the function panicked in a worker.
+    panicked: AtomicBool,
+
+    /// The function called with each item.
+    f: F
+}
+
+impl<T, F: Fn(T)> ParallelChunks<T, F> {
+    /// Takes chunks until there are no more left.
+    fn run(&self) {
+        loop {
+            let index = self.next.fetch_add(1, Ordering::SeqCst);
+            guard!(let Some(chunk) = self.chunks.get(index) else { return; });
+            if let Some(items) = chunk.lock().unwrap().take() {
+                items.into_iter().for_each(&self.f);
+            }
+        }
     }
 }
 
+pub trait Searchable {
+    type Iter: Iterator;
+
+    fn query(self) -> Self::Iter;
+}
+
 macro_rules! generate_query {
     ($([$type: ident, $id: tt]), +) => {
 
@@ -41,7 +207,7 @@ paste! {
     impl<
         $($type: Iterator),+
     > Iterator for [<TupleAccessIterator $($type)+>]<$($type),+> {
-        type Item = ($(<$type as Iterator>::Item),+);
+        type Item = ($(<$type as Iterator>::Item,)+);
 
         fn next(&mut self) -> Option<Self::Item> {
             $(
@@ -52,7 +218,7 @@ paste! {
             )+
             
             Some((
-                $(paste! { [<$type _p>] }),+
+                $(paste! { [<$type _p>] },)+
             ))
         }
     }
@@ -61,7 +227,13 @@ paste! {
 paste! {
     impl<
         $($type: Iterator),+
-    > Searchable for ($($type),+) {
+    > QueryFilter for [<TupleAccessIterator $($type)+>]<$($type),+> {}
+}
+
+paste! {
+    impl<
+        $($type: Iterator),+
+    > Searchable for ($($type,)+) {
         type Iter = [<TupleAccessIterator $($type)+>]<$($type),+>;
 
         fn query(self) -> Self::Iter {
@@ -73,10 +245,111 @@ paste! {
     };
 }
 
+generate_query!([A, 0]);
+generate_query!([A, 0], [B, 1]);
 generate_query!([A, 0], [B, 1], [C, 2]);
 generate_query!([A, 0], [B, 1], [C, 2], [D, 3]);
 generate_query!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4]);
 generate_query!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5]);
 generate_query!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6]);
 generate_query!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7]);
-generate_query!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8]);
\ No newline at end of file
+generate_query!([A, 0], [B, 1], [C, 2], [D, 3], [E, 4], [F, 5], [G, 6], [H, 7], [I, 8]);
+#[test]
+fn parallel_query_visits_each_item_once() {
+    use crate::{
+        access::{Read, UniqueRead},
+        component::ComponentHandler,
+        entity::EntityHandler,
+        sync::TaskWaitable,
+        system::SystemHandler,
+        world::{DefaultWorld, WorldWorkers}
+    };
+    use tasks::WorkersDescriptor;
+
+    struct Id(usize);
+    struct Tag;
+
+    const COUNT: usize = 10_000;
+
+    let world = DefaultWorld::with_workers(WorkersDescriptor {
+        amount: 4,
+        ..WorkersDescriptor::default()
+    });
+    world.register::<Id>();
+    world.register::<Tag>();
+    for i in 0..COUNT {
+        // Only the even entities match the query.
+        if i % 2 == 0 {
+            world.add_entity((Id(i), Tag));
+        } else {
+            world.add_entity((Id(i),));
+        }
+    }
+
+    let visits: Arc<Vec<AtomicUsize>> = Arc::new((0..COUNT).map(|_| AtomicUsize::new(0)).collect());
+    let serial: Arc<Mutex<Vec<usize>>> = Arc::new(Mutex::new(Vec::new()));
+    let (c_visits, c_serial) = (visits.clone(), serial.clone());
+    (world.run(move |workers: UniqueRead<WorldWorkers>, ids: Read<Id>, tags: Read<Tag>| {
+        *c_serial.lock().unwrap() = (ids.iter(), tags.iter()).query()
+            .map(|(id, _)| id.read().0)
+            .collect();
+
+        (ids.iter(), tags.iter()).query().par_for_each(&workers.read(), 64, |(id, _)| {
+            c_visits[id.read().0].fetch_add(1, Ordering::SeqCst);
+        });
+    }),).wait();
+
+    let serial = serial.lock().unwrap();
+    assert_eq!(serial.len(), COUNT / 2);
+    for (i, visit) in visits.iter().enumerate() {
+        let expected = if serial.binary_search(&i).is_ok() { 1 } else { 0 };
+        assert_eq!(visit.load(Ordering::SeqCst), expected);
+    }
+
+    // The single component queries use the 1-tuple.
+    let total: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let c_total = total.clone();
+    (world.run(move |workers: UniqueRead<WorldWorkers>, ids: Read<Id>| {
+        (ids.iter(),).query().par_for_each(&workers.read(), 1000, |(_,)| {
+            c_total.fetch_add(1, Ordering::SeqCst);
+        });
+    }),).wait();
+    assert_eq!(total.load(Ordering::SeqCst), COUNT);
+}
+
+#[test]
+fn parallel_query_with_one_worker() {
+    use crate::{
+        access::{Read, UniqueRead},
+        entity::EntityHandler,
+        sync::TaskWaitable,
+        system::SystemHandler,
+        world::{DefaultWorld, WorldWorkers}
+    };
+    use tasks::WorkersDescriptor;
+
+    struct Id(usize);
+
+    const COUNT: usize = 1_000;
+
+    // The system runs in the only worker, so the helper tasks are queued
+    // behind it and must not be waited.
+    let world = DefaultWorld::with_workers(WorkersDescriptor {
+        amount: 1,
+        ..WorkersDescriptor::default()
+    });
+    for i in 0..COUNT {
+        world.add_entity((Id(i),));
+    }
+
+    let total: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let c_total = total.clone();
+    let result = (world.run(move |workers: UniqueRead<WorldWorkers>, ids: Read<Id>| {
+        (ids.iter(),).query().par_for_each(&workers.read(), 10, |(id,)| {
+            c_total.fetch_add(id.read().0, Ordering::SeqCst);
+        });
+    }),).wait_timeout(std::time::Duration::from_secs(5));
+
+    assert!(result.finished);
+    assert_eq!(total.load(Ordering::SeqCst), (0..COUNT).sum::<usize>());
+}
@@ -0,0 +1,32 @@
+use crate::type_id::{ComponentId, id_of};
+
+/// Implemented by the components which are registered at compile time, it
+/// is generated by `#[derive(Component)]`.
+///
+/// Any `'static + Send + Sync` type can still be used as a component without
+/// implementing it.
+pub trait Component: 'static + Send + Sync {}
+
+/// Contains the id of a component submitted to the inventory by
+/// `#[derive(Component)]`, all the entries are registered when a world is
+/// created.
+pub struct ComponentRegistrar {
+    /// Returns the id of the component.
+    id: fn() -> ComponentId
+}
+
+impl ComponentRegistrar {
+    /// Creates and returns the registrar of the component T.
+    pub const fn of<T: Component>() -> Self {
+        Self { id: id_of::<T> }
+    }
+}
+
+inventory::collect!(ComponentRegistrar);
+
+/// Returns the ids of all the components submitted to the inventory.
+pub(crate) fn inventory_components() -> impl Iterator<Item = ComponentId> {
+    inventory::iter::<ComponentRegistrar>
+        .into_iter()
+        .map(|registrar| (registrar.id)())
+}
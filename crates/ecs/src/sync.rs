@@ -1,78 +1,220 @@
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc
+        Arc,
+        Mutex,
+        Condvar
     },
-    time::Duration,
-    thread
+    time::{Duration, Instant}
 };
 
+use log::error;
+
+use crate::error::EcsError;
+
 /// A type that allows know when a task finished, in a thread safe
-/// way using atomics (no locks).
-pub struct TaskSync {
+/// way.
+///
+/// The waiters are blocked using a condition variable, they are notified
+/// as soon as the task finishes.
+///
+/// If the task fails the error is kept, by default the tasks are systems
+/// so the error is an `EcsError`.
+pub struct TaskSync<E = EcsError> {
+    /// The name of the task, it is used to report the tasks which did
+    /// not finish in time.
+    name: &'static str,
+
     /// Contains a flag which determines if the task was finished or
     /// not.
-    finish: AtomicBool,
+    finish: Mutex<bool>,
+
+    /// Used to notify the waiters when the task finishes.
+    finished_signal: Condvar,
+
+    /// Contains a flag which determines if the task failed.
+    failed: AtomicBool,
+
+    /// Contains the error which made the task fail.
+    error: Mutex<Option<E>>,
+
+    /// Contains a flag which determines if the task was cancelled.
+    cancelled: AtomicBool,
 }
 
-impl Default for TaskSync {
+/// Contains the result of waiting for one or more tasks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskResult {
+    /// True if all the tasks finished.
+    pub finished: bool,
+
+    /// True if at least one task was cancelled.
+    pub cancelled: bool
+}
+
+impl<E> Default for TaskSync<E> {
     /// Creates and returns a new `TaskSync` instance with default,
     /// configuration.
     fn default() -> Self {
+        Self::named("unnamed task")
+    }
+}
+
+impl<E> TaskSync<E> {
+    /// Creates and returns a new `TaskSync` for the task with the
+    /// provided name.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the task.
+    pub fn named(name: &'static str) -> Self {
         Self {
-            finish: AtomicBool::new(false)
+            name,
+            finish: Mutex::new(false),
+            finished_signal: Condvar::new(),
+            failed: AtomicBool::new(false),
+            error: Mutex::new(None),
+            cancelled: AtomicBool::new(false)
         }
     }
-}
 
-impl TaskSync {
-    /// Marks the task sync as finished.
+    /// Returns the name of the task.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Marks the task sync as finished and wakes up the waiters.
     pub fn mark_as_finish(&self) {
-        self.finish.swap(true, Ordering::Relaxed);
+        *self.finish.lock().unwrap() = true;
+        self.finished_signal.notify_all();
     }
-}
 
-pub trait TaskWaitable {
-    fn wait(self);
-}
+    /// Returns true if the task finished.
+    pub fn is_finished(&self) -> bool {
+        *self.finish.lock().unwrap()
+    }
 
-impl TaskWaitable for (Arc<TaskSync>, ) {
-    /// Locks the current thread until the TaskSyncs passed by 
-    /// parameters are finished.
-    fn wait(self) {
-        // Infinite loop that locks the thread and check if all the 
-        // flags are true.
-        loop {
-            let mut did_finish: bool = true;
-            did_finish &= self.0.finish.load(Ordering::SeqCst);
-            // If the flags are all true it means it finishes.
-            if did_finish {
-                return;
+    /// Blocks the current thread until the task finishes or the deadline
+    /// is reached, it returns true if the task finished.
+    ///
+    /// # Arguments
+    ///
+    /// `deadline` - The maximum instant to wait, if it is not present it
+    /// waits forever.
+    fn wait_until(&self, deadline: Option<Instant>) -> bool {
+        let mut finished = self.finish.lock().unwrap();
+        while !*finished {
+            match deadline {
+                Some(d) => {
+                    let now = Instant::now();
+                    if now >= d {
+                        return false;
+                    }
+                    finished = self.finished_signal
+                        .wait_timeout(finished, d - now)
+                        .unwrap()
+                        .0;
+                },
+                None => finished = self.finished_signal.wait(finished).unwrap()
             }
-            // Wait 1 millisecond to not flood the thread.
-            thread::sleep(Duration::from_millis(1));
         }
+        true
+    }
+
+    /// Marks the task sync as failed, the task must still be marked as
+    /// finished.
+    ///
+    /// # Arguments
+    ///
+    /// `error` - The reason of the failure.
+    pub fn mark_as_failed(&self, error: E) {
+        *self.error.lock().unwrap() = Some(error);
+        self.failed.swap(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the task failed.
+    pub fn did_fail(&self) -> bool {
+        self.failed.load(Ordering::SeqCst)
+    }
+
+    /// Marks the task sync as cancelled, the task must still be marked as
+    /// finished.
+    pub fn mark_as_cancelled(&self) {
+        self.cancelled.swap(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the task was cancelled.
+    pub fn was_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl<E: Clone> TaskSync<E> {
+    /// Returns the error which made the task fail, if the task did not
+    /// fail None is returned.
+    pub fn error(&self) -> Option<E> {
+        self.error.lock().unwrap().clone()
+    }
+}
+
+/// Waits for all the tasks and returns the result, if the timeout is
+/// reached the names of the unfinished tasks are logged.
+///
+/// # Arguments
+///
+/// `syncs` - The tasks to wait for.
+/// `timeout` - The maximum time to wait, if it is not present it waits
+/// forever.
+fn wait_all(syncs: &[&TaskSync], timeout: Option<Duration>) -> TaskResult {
+    let deadline: Option<Instant> = timeout.map(|t| Instant::now() + t);
+
+    let mut finished: bool = true;
+    for sync in syncs {
+        finished &= sync.wait_until(deadline);
+    }
+
+    if !finished {
+        let unfinished: Vec<&str> = syncs
+            .iter()
+            .filter(|s| !s.is_finished())
+            .map(|s| s.name())
+            .collect();
+        error(&format!(
+            "The tasks did not finish in {:?}: {}",
+            timeout.unwrap_or_default(),
+            unfinished.join(", ")
+        ));
+    }
+
+    TaskResult {
+        finished,
+        cancelled: syncs.iter().any(|s| s.was_cancelled())
     }
 }
 
+pub trait TaskWaitable {
+    /// Locks the current thread until all the tasks are finished.
+    fn wait(self) -> TaskResult;
+
+    /// Locks the current thread until all the tasks are finished or the
+    /// timeout is reached, the result is not finished if any task is still
+    /// running.
+    ///
+    /// # Arguments
+    ///
+    /// `timeout` - The maximum time to wait.
+    fn wait_timeout(&self, timeout: Duration) -> TaskResult;
+}
+
 macro_rules! generate_task_waitable {
     ($([$type: ident, $index: tt]), +) => {
 impl TaskWaitable for ($($type,)+ ) {
-    fn wait(self) {
-        // Infinite loop that locks the thread and check if all the 
-        // flags are true.
-        loop {
-            let mut did_finish: bool = true;
-            $(
-                did_finish &= self.$index.finish.load(Ordering::SeqCst);
-            )+            
-            // If the flags are all true it means it finishes.
-            if did_finish {
-                return;
-            }
-            // Wait 1 millisecond to not flood the thread.
-            thread::sleep(Duration::from_millis(1));
-        }
+    fn wait(self) -> TaskResult {
+        wait_all(&[$(&self.$index,)+], None)
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> TaskResult {
+        wait_all(&[$(&self.$index,)+], Some(timeout))
     }
 }       
     };
@@ -80,6 +222,7 @@ impl TaskWaitable for ($($type,)+ ) {
 
 type RefTaskSync = Arc<TaskSync>;
 
+generate_task_waitable!([RefTaskSync, 0]);
 generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1]);
 generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2]);
 generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [RefTaskSync, 3]);
@@ -88,4 +231,17 @@ generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [R
 generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [RefTaskSync, 3], [RefTaskSync, 4], [RefTaskSync, 5], [RefTaskSync, 6]);
 generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [RefTaskSync, 3], [RefTaskSync, 4], [RefTaskSync, 5], [RefTaskSync, 6], [RefTaskSync, 7]);
 generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [RefTaskSync, 3], [RefTaskSync, 4], [RefTaskSync, 5], [RefTaskSync, 6], [RefTaskSync, 7], [RefTaskSync, 8]);
-generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [RefTaskSync, 3], [RefTaskSync, 4], [RefTaskSync, 5], [RefTaskSync, 6], [RefTaskSync, 7], [RefTaskSync, 8], [RefTaskSync, 9]);
\ No newline at end of file
+generate_task_waitable!([RefTaskSync, 0], [RefTaskSync, 1], [RefTaskSync, 2], [RefTaskSync, 3], [RefTaskSync, 4], [RefTaskSync, 5], [RefTaskSync, 6], [RefTaskSync, 7], [RefTaskSync, 8], [RefTaskSync, 9]);
+
+/// A variable number of tasks, for example the tasks dispatched in a loop.
+impl TaskWaitable for Vec<RefTaskSync> {
+    fn wait(self) -> TaskResult {
+        let syncs: Vec<&TaskSync> = self.iter().map(|s| s.as_ref()).collect();
+        wait_all(&syncs, None)
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> TaskResult {
+        let syncs: Vec<&TaskSync> = self.iter().map(|s| s.as_ref()).collect();
+        wait_all(&syncs, Some(timeout))
+    }
+}
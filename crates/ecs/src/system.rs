@@ -5,26 +5,134 @@ use std::{
 
 use paste::paste;
 
+use tasks::{TaskPriority, CancellationToken};
+
 use crate::{
     bundle::ComponentBundler,
     component::ComponentsHandler,
     access::{Accessible, SLock},
-    entity::EntitiesHandler,
+    consts::BitmaskType,
+    entity::{EntitiesHandler, Entity},
+    error::{EcsError, SystemResult},
     sync::TaskSync,
     type_id::id_of
 };
 
 pub trait SystemHandler {
+    /// Runs the system using the normal priority.
     fn run<
         B: ComponentBundler, S: System<B> + 'static + Send + Sync
-    >(&self, system: S) -> Arc<TaskSync>;
+    >(&self, system: S) -> Arc<TaskSync<EcsError>>;
+
+    /// Runs the system using the provided priority.
+    fn run_priority<
+        B: ComponentBundler, S: System<B> + 'static + Send + Sync
+    >(&self, system: S, priority: TaskPriority) -> Arc<TaskSync<EcsError>>;
+
+    /// Runs the system using the normal priority, if the token is cancelled
+    /// before the system starts it is not executed and the `TaskSync` is
+    /// marked as cancelled.
+    fn run_cancellable<
+        B: ComponentBundler, S: System<B> + 'static + Send + Sync
+    >(&self, system: S, token: CancellationToken) -> Arc<TaskSync<EcsError>>;
+
+    /// Runs the system in the calling thread without using the workers, it
+    /// returns the result of the system.
+    fn run_sync<
+        B: ComponentBundler, S: System<B>
+    >(&self, system: S) -> SystemResult<(), EcsError>;
+
+    /// Runs the system using the normal priority, the data is sent to the
+    /// system as its first parameter.
+    fn run_with_data<
+        D: 'static + Send, B: ComponentBundler, S: SystemWithData<D, B> + 'static + Send + Sync
+    >(&self, system: S, data: D) -> Arc<TaskSync<EcsError>>;
+
+    /// Runs the system in the calling thread, the data is sent to the
+    /// system as its first parameter. The data does not need to be `Send`
+    /// so it can borrow from the caller.
+    fn run_sync_with_data<
+        'a, D: 'a, B: ComponentBundler, S: SystemWithData<D, B>
+    >(&self, system: S, data: D) -> SystemResult<(), EcsError>;
 }
 
 pub trait System<B: ComponentBundler> {
     fn run<
         C: ComponentsHandler + Send + Sync,
         E: EntitiesHandler + Send + Sync
-    >(self, components_handler: Arc<C>, entities_handler: Arc<E>);
+    >(self, components_handler: Arc<C>, entities_handler: Arc<E>)
+        -> SystemResult<(), EcsError>;
+}
+
+/// Provides an aftraction for the systems which receive data from the caller
+/// as the first parameter, followed by the accesses.
+pub trait SystemWithData<D, B: ComponentBundler> {
+    fn run_with_data<
+        C: ComponentsHandler + Send + Sync,
+        E: EntitiesHandler + Send + Sync
+    >(self, data: D, components_handler: Arc<C>, entities_handler: Arc<E>)
+        -> SystemResult<(), EcsError>;
+}
+
+/// Returns the bitmask of the parameter T, the unique parameters do not
+/// have bitmask so 0 is returned.
+///
+/// # Arguments
+///
+/// `components_handler` - The handler which contains the components.
+fn bitmask_of<T: 'static + Accessible, C: ComponentsHandler>(components_handler: &Arc<C>)
+    -> SystemResult<BitmaskType> {
+    if T::is_unique() {
+        return Ok(0);
+    }
+
+    components_handler.bitmask(id_of::<T::Component>())
+}
+
+/// Returns the entities which contain all the components of the bitmask,
+/// if there are not components (only uniques) no entity is returned.
+///
+/// # Arguments
+///
+/// `entities_handler` - The handler which contains the entities.
+/// `bitmasks` - The bitmask used to filter the entities.
+fn filter_entities<E: EntitiesHandler>(
+    entities_handler: &Arc<E>,
+    bitmasks: BitmaskType) -> Arc<Vec<Entity>> {
+    if bitmasks == 0 {
+        return Arc::new(Vec::new());
+    }
+    Arc::new(entities_handler.query_by_bitmask(bitmasks))
+}
+
+/// Creates and returns the access for the parameter T.
+///
+/// # Arguments
+///
+/// `components_handler` - The handler which contains the components.
+/// `filtered_entities` - The entities which match with the system.
+fn access_of<
+    T: 'static + Accessible,
+    C: ComponentsHandler
+>(
+    components_handler: &Arc<C>,
+    filtered_entities: &Arc<Vec<Entity>>) -> SystemResult<T>
+    where
+        <T as Accessible>::Component: Sync + Send {
+    let t_typeid = id_of::<T::Component>();
+
+    if T::is_unique() {
+        let c = components_handler.unique_component(&t_typeid)?;
+        guard!(let Ok(c_downcasted) = c.downcast::<SLock<T::Component>>() else {
+            return Err(EcsError::CastFailed(type_name::<T::Component>()));
+        });
+        Ok(T::unique_new(c_downcasted))
+    } else {
+        // Get the component buffer of T.
+        let t_b = components_handler.component_buffer(&t_typeid)?;
+
+        Ok(T::new(t_b, filtered_entities.clone()))
+    }
 }
 
 impl<F, A> System<(A,)> for F
@@ -35,47 +143,17 @@ where
 {
     fn run<
         C: ComponentsHandler, E: EntitiesHandler
-    >(self, components_handler: Arc<C>, entities_handler: Arc<E>) {
-        let a_typeid = id_of::<A::Component>();
-
-        let a: A;
-
-        // TODO: Check if we could avoid this using the compiler.
-        if A::is_unique() {
-            guard!(let Some(c) = components_handler.unique_component(&a_typeid) else {
-                panic!(
-                    "The component {} does not exist",
-                    type_name::<A::Component>()
-                );
-            });
-            guard!(let Ok(c_downcasted) = c.downcast::<SLock<A::Component>>() else {
-                panic!("Error casting Arc pointer");
-            });
-            a = A::unique_new(c_downcasted);
-        } else {
-            // Extract the id of A, in order to get the bitmask.
-            let a_bitmask = components_handler.bitmask(a_typeid); 
-            
-            // Generate a new buffer with all the entities that matches
-            // with this requirement.
-            let filtered_entities = Arc::new(
-                entities_handler.query_by_bitmask(a_bitmask)
-            );
-
-            // Get the component buffer of a.
-            guard!(let Some(a_b) = components_handler.component_buffer(&a_typeid) else {
-                panic!(
-                    "The component {} does not exist",
-                    type_name::<A::Component>()
-                );
-            });
-
-            a = A::new(a_b, filtered_entities);
-        }
+    >(self, components_handler: Arc<C>, entities_handler: Arc<E>)
+        -> SystemResult<(), EcsError> {
+        let bitmasks = bitmask_of::<A, C>(&components_handler)?;
+        let filtered_entities = filter_entities(&entities_handler, bitmasks);
+        let a: A = access_of::<A, C>(&components_handler, &filtered_entities)?;
 
         // Create a new instance of Read or Write and and set inside it the
         // reference to the array and send the reference to the block vec.
         (self)(a);
+
+        Ok(())
     }
 }
 
@@ -92,80 +170,36 @@ where
     fn run<
         C: ComponentsHandler + Send + Sync,
         E: EntitiesHandler + Send + Sync
-    >(self, components_handler: Arc<C>, entities_handler: Arc<E>) {
-        $(
-            paste! {
-                let [<$type _typeid>] = id_of::<$type::Component>();
-                let [<$type _var>]: $type;
-            }
-        )+
-
-        
+    >(self, components_handler: Arc<C>, entities_handler: Arc<E>)
+        -> SystemResult<(), EcsError> {
         let mut bitmasks = 0x00;
 
         $(
-            if !$type::is_unique() {
-                paste! {
-                    bitmasks |= components_handler.bitmask([<$type _typeid>]);
-                }
-            }
+            bitmasks |= bitmask_of::<$type, C>(&components_handler)?;
         )+
-        
-
-        $(
-            if $type::is_unique() {
-                paste! {
-                    guard!(let Some(c) = components_handler.unique_component(&[<$type _typeid>]) else {
-                        panic!(
-                            "The component {} does not exist",
-                            type_name::<A::Component>()
-                        );
-                    });
-                }
-                
-                paste! {
-                    guard!(let Ok(c_downcasted) = c.downcast::<SLock<$type::Component>>() else {
-                        panic!("Error casting Arc pointer");
-                    });
-                    [<$type _var>] = $type::unique_new(c_downcasted);
-                }
-            } else {
-                paste! {
-                    // Extract the id of A, in order to get the bitmask.
-                    let a_bitmask = components_handler.bitmask([<$type _typeid>]); 
-                
-                }
-               
-                // Generate a new buffer with all the entities that matches
-                // with this requirement.
-                let filtered_entities = Arc::new(
-                    entities_handler.query_by_bitmask(bitmasks)
-                );
 
-                paste! {
-                   // Get the component buffer of a.
-                    guard!(let Some(a_b) = components_handler.component_buffer(&[<$type _typeid>]) else {
-                        panic!(
-                            "The component {} does not exist",
-                            type_name::<A::Component>()
-                        );
-                    }); 
-                }
-                
+        // All the parameters share the same entities, so the query is
+        // performed only once.
+        let filtered_entities = filter_entities(&entities_handler, bitmasks);
 
-                paste! {
-                    [<$type _var>] = $type::new(a_b, filtered_entities);
-                }
+        $(
+            paste! {
+                let [<$type:lower _var>]: $type = access_of::<$type, C>(
+                    &components_handler,
+                    &filtered_entities
+                )?;
             }
         )+
 
         (self)(
             $(
                 paste! {
-                    [<$type _var>]
+                    [<$type:lower _var>]
                 }
             ),+
         );
+
+        Ok(())
     }
 }
 
@@ -179,4 +213,75 @@ generate_system!(A, B, C1, D, E1);
 generate_system!(A, B, C1, D, E1, F1);
 generate_system!(A, B, C1, D, E1, F1, G);
 generate_system!(A, B, C1, D, E1, F1, G, H);
-generate_system!(A, B, C1, D, E1, F1, G, H, I);
\ No newline at end of file
+generate_system!(A, B, C1, D, E1, F1, G, H, I);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J, K);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J, K, L);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J, K, L, M);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J, K, L, M, N);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J, K, L, M, N, O);
+generate_system!(A, B, C1, D, E1, F1, G, H, I, J, K, L, M, N, O, P);
+
+macro_rules! generate_system_with_data {
+    ($($type: ident), +) => {
+
+impl<F, D, $($type,)+> SystemWithData<D, ($($type,)+)> for F
+where 
+    F: FnOnce(D, $($type,)+) -> (),
+    $(
+        $type: 'static + Accessible,
+        <$type as Accessible>::Component: Sync + Send,)+
+{
+    fn run_with_data<
+        C: ComponentsHandler + Send + Sync,
+        E: EntitiesHandler + Send + Sync
+    >(self, data: D, components_handler: Arc<C>, entities_handler: Arc<E>)
+        -> SystemResult<(), EcsError> {
+        // The data is captured so the regular system does the rest.
+        paste! {
+            let system = move |$([<$type:lower _var>]: $type),+| {
+                (self)(data, $([<$type:lower _var>]),+)
+            };
+        }
+
+        System::<($($type,)+)>::run(system, components_handler, entities_handler)
+    }
+}
+
+    };
+}
+
+generate_system_with_data!(A);
+generate_system_with_data!(A, B);
+generate_system_with_data!(A, B, C1);
+generate_system_with_data!(A, B, C1, D1);
+generate_system_with_data!(A, B, C1, D1, E1);
+generate_system_with_data!(A, B, C1, D1, E1, F1);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G, H);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G, H, I);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G, H, I, J);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G, H, I, J, K);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G, H, I, J, K, L);
+generate_system_with_data!(A, B, C1, D1, E1, F1, G, H, I, J, K, L, M);
+
+#[cfg(test)]
+use crate::{
+    access::UniqueRead,
+    component::ComponentsStorage,
+    entity::EntitiesStorage
+};
+
+#[test]
+fn missing_unique_reports_type() {
+    let components = Arc::new(ComponentsStorage::default());
+    let entities = Arc::new(EntitiesStorage::<32>::default());
+    components.register_unique(id_of::<u32>(), 0u32);
+
+    // Only the second parameter is missing.
+    let system = |_a: UniqueRead<u32>, _b: UniqueRead<String>| {};
+    let result = system.run(components, entities);
+
+    assert_eq!(result, Err(EcsError::UniqueNotRegistered(type_name::<String>())));
+    assert!(result.unwrap_err().to_string().contains("String"));
+}
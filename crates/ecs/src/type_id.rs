@@ -1,6 +1,37 @@
-use std::any::TypeId;
+use std::{
+    any::{type_name, TypeId},
+    hash::{Hash, Hasher}
+};
+
+/// Identifies a component type, the name is kept in order to report the
+/// errors of the components which are not registered.
+#[derive(Clone, Copy, Debug)]
+pub struct ComponentId {
+    /// The runtime representation of the type.
+    pub id: TypeId,
+
+    /// The name of the type.
+    pub name: &'static str
+}
+
+impl PartialEq for ComponentId {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for ComponentId {}
+
+impl Hash for ComponentId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
 
 /// Creates and returns an id representation of the provided type.
-pub(crate) fn id_of<T: ?Sized + 'static>() -> TypeId {
-    TypeId::of::<T>()
-}
\ No newline at end of file
+pub(crate) fn id_of<T: ?Sized + 'static>() -> ComponentId {
+    ComponentId {
+        id: TypeId::of::<T>(),
+        name: type_name::<T>()
+    }
+}
@@ -3,27 +3,43 @@ use crossbeam_queue::SegQueue;
 use std::{
     fmt::{Debug, Formatter, Result},
     any::type_name,
+    ops::Deref,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
+        RwLock,
+        RwLockReadGuard,
     },
 };
 
-use tasks::{Dispatcher, Workers};
+use tasks::{
+    Dispatcher,
+    Workers,
+    WorkersDescriptor,
+    Task,
+    TaskPriority,
+    CancellationToken
+};
 use log::error;
 
 use crate::{
     bundle::ComponentBundler,
-    access::{Accessible, SLock},
+    access::{Accessible, SLock, UniqueRead, UniqueWrite, UniqueHandle},
     component::{
         ComponentHandler,
         ComponentsHandler,
         ComponentsStorage,
+        ComponentBuffer,
+        BufferBlockVec,
         NUM_OF_COMPONETS_PER_PAGE,
     },
     entity::{EntitiesHandler, EntitiesStorage, Entity, EntityHandler},
+    event::{Events, EventHandler, update_events_of},
     sync::TaskSync,
-    system::{System, SystemHandler},
+    storage::Storage,
+    system::{System, SystemWithData, SystemHandler},
+    error::{EcsError, SystemResult},
+    registry::inventory_components,
     type_id::id_of,
 };
 
@@ -52,14 +68,16 @@ pub struct World<
     /// Contains a counter of the amount of ids in the `World`.
     number_of_entities: AtomicUsize,
 
-    /// Contains a counter of the ampunt of components in the `World`.
-    number_of_components: AtomicUsize,
-
     /// Contains a queue of free entities to be used.
     free_entities: SegQueue<Entity>,
 
-    /// Contains the workers pool.
-    workers: Workers,
+    /// Contains the workers pool, it is shared with the systems using the
+    /// `WorldWorkers` unique.
+    workers: Arc<Workers>,
+
+    /// Contains the function which swaps the buffers of each registered
+    /// event type.
+    event_updaters: RwLock<Vec<fn(&H)>>,
 }
 
 /// Mark `World` as thread safe.
@@ -75,25 +93,68 @@ unsafe impl<
     for World<H, E>
 {}
 
+impl<
+    H: ComponentsHandler + Send + Sync,
+    E: EntitiesHandler + Send + Sync
+> Drop
+    for World<H, E>
+{
+    /// Stops the workers before the storages are released, in this way
+    /// no system is running when the data is dropped.
+    fn drop(&mut self) {
+        self.workers.set_thread_count(0);
+    }
+}
+
+/// Contains the workers of the `World`, it is registered as a unique so the
+/// systems can split their work using `ParallelQuery::par_for_each`.
+pub struct WorldWorkers(Arc<Workers>);
+
+impl Deref for WorldWorkers {
+    type Target = Workers;
+
+    fn deref(&self) -> &Workers {
+        &self.0
+    }
+}
+
 impl Default for DefaultWorld {
     /// Creates and returns a new `World` which contains a default
     /// configuration.
     fn default() -> Self {
+        Self::with_workers(WorkersDescriptor::default())
+    }
+}
+
+impl DefaultWorld {
+    /// Creates and returns a new `World` which uses a pool of workers
+    /// with the provided configuration.
+    ///
+    /// # Arguments
+    ///
+    /// `descriptor` - The configuration of the workers.
+    pub fn with_workers(descriptor: WorkersDescriptor) -> Self {
         // Start workers.
-        let mut workers = Workers::default();
+        let mut workers = Workers::new(descriptor);
         workers.start();
+        let workers = Arc::new(workers);
 
         let c_storage = Arc::new(DefaultComponentsStorage::default());
         let e_storage = Arc::new(DefaultEntitiesStorage::default());
+        c_storage.register_unique(id_of::<WorldWorkers>(), WorldWorkers(workers.clone()));
 
-        Self {
+        let world = Self {
             components_storage: c_storage,
             entities_storage: e_storage,
             number_of_entities: AtomicUsize::new(0),
-            number_of_components: AtomicUsize::new(0),
             free_entities: SegQueue::new(),
             workers: workers,
-        }
+            event_updaters: RwLock::new(Vec::new()),
+        };
+
+        // The derived components are registered ahead of time.
+        world.auto_register_from_inventory();
+        world
     }
 }
 
@@ -110,17 +171,15 @@ impl<
     ///
     /// `components` - All the components that the entity contains.
     fn add_entity<B: ComponentBundler>(&self, components: B) -> Entity {
-        // Increate the number of components.
-        self.number_of_components
-            .fetch_add(components.len(), Ordering::SeqCst);
-
         // Generate a new entity. For now we are not reusing entities
         // so as soon as this thing is finished we have to do a pool
         // of not used entities.
         let entity: Entity = self.generate_entity();
 
         // Add all the components to the entity.
-        let bitmask = components.add_components(entity, self.components_storage.clone());
+        let bitmask = or_panic(
+            components.add_components(entity, self.components_storage.clone())
+        );
 
         // Register the bitmask for the given entity.
         self.entities_storage.register_bitmask(&entity, &bitmask);
@@ -134,11 +193,170 @@ impl<
     ///
     /// `entity` - The entity to be deleted.
     fn remove_entity(&self, entity: Entity) {
+        // Invalidate all the handles first, a stale handle or a concurrent
+        // removal of the same entity returns here, so the id is added to
+        // the pool only once.
+        guard!(let Some(next_entity) = self.entities_storage.bump_generation(&entity) else {
+            return;
+        });
+
         self.entities_storage.reset_bitmask(&entity);
         self.components_storage.remove_components(&entity);
 
-        // Add move entity to the pool.
-        self.free_entities.push(entity);
+        // The next entity using the id will have the new generation.
+        self.free_entities.push(next_entity);
+    }
+
+    /// Adds `count` entities into the `World`, the components of each one
+    /// are returned by `f` which receives the position in the batch.
+    ///
+    /// The ids are reserved with a single atomic add and the buffers are
+    /// grown once, so the components are written without taking the locks
+    /// of the storage per entity. The removed ids are not reused, the batch
+    /// always uses a new range.
+    ///
+    /// # Arguments
+    ///
+    /// `count` - The number of entities to be added.
+    /// `f` - Returns the components of the entity at the provided position.
+    fn add_entities_batch<B: ComponentBundler, F: Fn(usize) -> B>(
+        &self,
+        count: usize,
+        f: F) -> Vec<Entity> {
+        if count == 0 {
+            return Vec::new();
+        }
+
+        // The components are created before any lock is taken, `f` could
+        // use the world.
+        let bundles: Vec<B> = (0..count).map(f).collect();
+
+        let first = self.number_of_entities.fetch_add(count, Ordering::SeqCst);
+        let entities: Vec<Entity> = (first..first + count).map(Entity::new).collect();
+
+        let ids = B::ids();
+        or_panic(self.components_storage.reserve(&ids, first + count));
+        let bitmask = ids.iter().fold(0, |mask, id| {
+            mask | or_panic(self.components_storage.bitmask(*id))
+        });
+
+        {
+            let buffers: Vec<ComponentBuffer> = ids.iter()
+                .map(|id| or_panic(self.components_storage.component_buffer(id)))
+                .collect();
+            let readers: Vec<RwLockReadGuard<BufferBlockVec>> = buffers.iter()
+                .map(|buffer| buffer.read().unwrap())
+                .collect();
+            let readers: Vec<&BufferBlockVec> = readers.iter().map(|r| &**r).collect();
+
+            for (entity, bundle) in entities.iter().zip(bundles) {
+                bundle.write_components(*entity, &readers);
+            }
+        }
+
+        self.entities_storage.register_bitmasks(&entities, &bitmask);
+
+        entities
+    }
+
+    /// Removes the entities from the `World`, the stale handles are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// `entities` - The entities to be deleted.
+    fn remove_entities_batch(&self, entities: &[Entity]) {
+        // Only the handles which are still alive are invalidated, an entity
+        // listed twice or removed concurrently is only removed once. The
+        // new entities keep the ids of the removed ones.
+        let next_entities: Vec<Entity> = self.entities_storage.bump_generations(entities);
+
+        self.entities_storage.reset_bitmasks(&next_entities);
+        self.components_storage.remove_components_batch(&next_entities);
+
+        for next_entity in next_entities {
+            self.free_entities.push(next_entity);
+        }
+    }
+
+    /// Returns true if the entity was not removed.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity to check.
+    fn is_alive(&self, entity: Entity) -> bool {
+        self.entities_storage.is_alive(&entity)
+    }
+
+    /// Adds a component to the entity, if the entity already contains a
+    /// component of the same type it is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity which will own the component.
+    /// `component` - The component to be added.
+    fn add_component<T: 'static + Send + Sync>(&self, entity: Entity, component: T) {
+        let t_typeid = id_of::<T>();
+
+        // Ignore the stale handles.
+        if !self.entities_storage.is_alive(&entity) {
+            return;
+        }
+
+        // The component is registered if it is the first one of its type.
+        or_panic(self.components_storage.add_component(
+            entity,
+            (t_typeid,),
+            (Storage::new(component),)
+        ));
+        let t_bitmask = or_panic(self.components_storage.bitmask(t_typeid));
+
+        // Add the bit of the component to the entity mask.
+        self.entities_storage.insert_bits(&entity, &t_bitmask);
+    }
+
+    /// Removes a component from the entity.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity which owns the component.
+    fn remove_component<T: 'static + Send + Sync>(&self, entity: Entity) {
+        // Ignore the stale handles.
+        if !self.entities_storage.is_alive(&entity) {
+            return;
+        }
+
+        let t_typeid = id_of::<T>();
+        let t_bitmask = or_panic(self.components_storage.bitmask(t_typeid));
+
+        // Remove the bit of the component from the entity mask.
+        self.entities_storage.remove_bits(&entity, &t_bitmask);
+
+        or_panic(self.components_storage.remove_component(&entity, &t_typeid));
+    }
+
+    /// Returns the names of the components the entity contains, sorted
+    /// alphabetically.
+    ///
+    /// # Arguments
+    ///
+    /// `entity` - The entity to be inspected.
+    fn component_names(&self, entity: Entity) -> Vec<&'static str> {
+        if !self.entities_storage.is_alive(&entity) {
+            return Vec::new();
+        }
+
+        let bitmask = self.entities_storage.get_bitmask(&entity);
+        let mut names: Vec<&'static str> = self.components_storage
+            .registered()
+            .iter()
+            .filter(|id| self.components_storage
+                .bitmask(**id)
+                .is_ok_and(|b| bitmask & b != 0))
+            .map(|id| id.name)
+            .collect();
+        names.sort_unstable();
+        names
     }
 }
 
@@ -149,24 +367,38 @@ impl<
     for World<H, E>
 {
     /// Registers a new component into the system.
+    ///
+    /// The components are registered when they are added for the first
+    /// time, registering them before only avoids syncing the buffers later.
+    /// Registering a component twice does nothing.
     fn register<C0: 'static>(&self) {
         // Generate an unique id for the component.
         let id = id_of::<C0>();
-        let bm_shift = self.number_of_components.fetch_add(1, Ordering::SeqCst);
-        // Register the component.
-        self.components_storage.register(id, bm_shift as u8);
+        // Register the component, it only fails if there are more
+        // components than bits in the bitmask.
+        or_panic(self.components_storage.register(id));
     }
 
-    /// Registers a new unique component.
+    /// Registers all the components which derive `Component`, it is called
+    /// when a `DefaultWorld` is created so the manual registration is not
+    /// needed.
+    fn auto_register_from_inventory(&self) {
+        for id in inventory_components() {
+            or_panic(self.components_storage.register(id));
+        }
+    }
+
+    /// Registers a new unique component and returns a handle to it, the
+    /// handle allows accessing the unique outside the systems.
     ///
     /// # Arguments
     ///
     /// - `c`: The component to be registered.
-    fn register_unique<C0: 'static + Send + Sync>(&self, c: C0) {
+    fn register_unique<C0: 'static + Send + Sync>(&self, c: C0) -> UniqueHandle<C0> {
         // Generate an unique id for the component.
         let id = id_of::<C0>();
         // Register the component.
-        self.components_storage.register_unique(id, c);
+        UniqueHandle::new(self.components_storage.register_unique(id, c))
     }
 
     /// Borrows a reference to the component.
@@ -178,24 +410,15 @@ impl<
         let t: T;
 
         if T::is_unique() {
-            guard!(let Some(c) = self.components_storage.unique_component(&t_typeid) else {
-                error(
-                    &format!(
-                        "The component {} does not exist",
-                        type_name::<T::Component>()
-                    ).to_string()
-                );
-                panic!();
-            });
+            let c = or_panic(self.components_storage.unique_component(&t_typeid));
             guard!(let Ok(c_downcasted) = c.downcast::<SLock<T::Component>>() else {
-                error("Error casting Arc pointer");
-                panic!();
+                panic!("{}", EcsError::CastFailed(type_name::<T::Component>()));
             });
             t = T::unique_new(c_downcasted);
         } else {
             // Extract the id of A, in order to get the bitmask.
-            let a_bitmask = self.components_storage.bitmask(t_typeid); 
-            
+            let a_bitmask = or_panic(self.components_storage.bitmask(t_typeid));
+
             // Generate a new buffer with all the entities that matches
             // with this requirement.
             let filtered_entities = Arc::new(
@@ -203,21 +426,94 @@ impl<
             );
 
             // Get the component buffer of a.
-            guard!(let Some(a_b) = self.components_storage.component_buffer(&t_typeid) else {
-                error(
-                    &format!(
-                        "The component {} does not exist",
-                    type_name::<T::Component>()
-                    ).to_string()
-                );
-                panic!();
-            });
+            let a_b = or_panic(self.components_storage.component_buffer(&t_typeid));
 
             t = T::new(a_b, filtered_entities);
         }
 
         t
     }
+
+    /// Returns a read access to the unique.
+    ///
+    /// It panics if the unique was not registered.
+    fn get_unique<T: 'static + Send + Sync>(&self) -> UniqueRead<T> {
+        guard!(let Some(unique) = self.try_get_unique::<T>() else {
+            panic!("The unique component {} does not exist", type_name::<T>());
+        });
+        unique
+    }
+
+    /// Returns a write access to the unique.
+    ///
+    /// It panics if the unique was not registered.
+    fn get_unique_mut<T: 'static + Send + Sync>(&self) -> UniqueWrite<T> {
+        guard!(let Some(unique) = self.try_get_unique_mut::<T>() else {
+            panic!("The unique component {} does not exist", type_name::<T>());
+        });
+        unique
+    }
+
+    /// Returns a read access to the unique or None if the unique was not
+    /// registered.
+    fn try_get_unique<T: 'static + Send + Sync>(&self) -> Option<UniqueRead<T>> {
+        self.unique_lock::<T>().ok().map(UniqueRead::unique_new)
+    }
+
+    /// Returns a write access to the unique or None if the unique was not
+    /// registered.
+    fn try_get_unique_mut<T: 'static + Send + Sync>(&self) -> Option<UniqueWrite<T>> {
+        self.unique_lock::<T>().ok().map(UniqueWrite::unique_new)
+    }
+
+    /// Returns true if the unique was registered.
+    fn has_unique<T: 'static + Send + Sync>(&self) -> bool {
+        self.components_storage.unique_component(&id_of::<T>()).is_ok()
+    }
+
+    /// Replaces the unique and returns the old value.
+    ///
+    /// # Arguments
+    ///
+    /// `value` - The new value of the unique.
+    fn replace_unique<T: 'static + Send + Sync>(&self, value: T) -> SystemResult<T> {
+        let unique = self.unique_lock::<T>().map(UniqueWrite::unique_new)?;
+        Ok(unique.replace(value))
+    }
+}
+
+impl<
+    H: ComponentsHandler + Send + Sync,
+    E: EntitiesHandler + Send + Sync
+> EventHandler
+    for World<H, E>
+{
+    /// Registers the events of type T, registering the same type twice
+    /// does not have effect.
+    fn register_event<T: 'static + Send + Sync>(&self) {
+        if self.has_unique::<Events<T>>() {
+            return;
+        }
+
+        self.register_unique(Events::<T>::default());
+        self.event_updaters.write().unwrap().push(update_events_of::<T, H>);
+    }
+
+    /// Sends an event, it panics if the event type was not registered.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The event to be sent.
+    fn send_event<T: 'static + Send + Sync>(&self, event: T) {
+        self.get_unique_mut::<Events<T>>().write().send(event);
+    }
+
+    /// Swaps the buffers of all the registered events.
+    fn update_events(&self) {
+        for update in self.event_updaters.read().unwrap().iter() {
+            update(&self.components_storage);
+        }
+    }
 }
 
 /// Provide handy functions.
@@ -235,6 +531,16 @@ impl<
 
         Entity::new(self.number_of_entities.fetch_add(1, Ordering::SeqCst))
     }
+
+    /// Returns the lock which contains the unique or an error if the unique
+    /// was not registered.
+    fn unique_lock<T: 'static + Send + Sync>(&self) -> SystemResult<Arc<SLock<T>>> {
+        let c = self.components_storage.unique_component(&id_of::<T>())?;
+        guard!(let Ok(c_downcasted) = c.downcast::<SLock<T>>() else {
+            return Err(EcsError::CastFailed(type_name::<T>()));
+        });
+        Ok(c_downcasted)
+    }
 }
 
 /// Provides handy functions to handle the systems.
@@ -246,21 +552,138 @@ impl<
     fn run<B: ComponentBundler, Sys: System<B> + 'static + Send + Sync>(
         &self,
         system: Sys,
-    ) -> Arc<TaskSync> {
+    ) -> Arc<TaskSync<EcsError>> {
+        self.run_priority(system, TaskPriority::Normal)
+    }
+
+    fn run_priority<B: ComponentBundler, Sys: System<B> + 'static + Send + Sync>(
+        &self,
+        system: Sys,
+        priority: TaskPriority
+    ) -> Arc<TaskSync<EcsError>> {
+        self.dispatch_system(system, priority, None)
+    }
+
+    fn run_cancellable<B: ComponentBundler, Sys: System<B> + 'static + Send + Sync>(
+        &self,
+        system: Sys,
+        token: CancellationToken
+    ) -> Arc<TaskSync<EcsError>> {
+        self.dispatch_system(system, TaskPriority::Normal, Some(token))
+    }
+
+    fn run_sync<B: ComponentBundler, Sys: System<B>>(&self, system: Sys) -> SystemResult<()> {
+        system.run(self.components_storage.clone(), self.entities_storage.clone())
+    }
+
+    fn run_with_data<
+        D: 'static + Send, B: ComponentBundler, Sys: SystemWithData<D, B> + 'static + Send + Sync
+    >(
+        &self,
+        system: Sys,
+        data: D
+    ) -> Arc<TaskSync<EcsError>> {
+        self.dispatch(
+            type_name::<Sys>(),
+            move |c, e| system.run_with_data(data, c, e),
+            TaskPriority::Normal,
+            None
+        )
+    }
+
+    fn run_sync_with_data<'a, D: 'a, B: ComponentBundler, Sys: SystemWithData<D, B>>(
+        &self,
+        system: Sys,
+        data: D
+    ) -> SystemResult<()> {
+        system.run_with_data(
+            data,
+            self.components_storage.clone(),
+            self.entities_storage.clone()
+        )
+    }
+}
+
+impl<
+    H: ComponentsHandler + Send + Sync + 'static,
+    E: EntitiesHandler + Send + Sync + 'static>
+    World<H, E>
+{
+    /// Sends the system to the workers and returns the `TaskSync` used to
+    /// know when it finishes.
+    ///
+    /// # Arguments
+    ///
+    /// `system` - The system to be executed.
+    /// `priority` - The priority of the system.
+    /// `token` - The token used to cancel the system.
+    fn dispatch_system<B: ComponentBundler, Sys: System<B> + 'static + Send + Sync>(
+        &self,
+        system: Sys,
+        priority: TaskPriority,
+        token: Option<CancellationToken>
+    ) -> Arc<TaskSync<EcsError>> {
+        self.dispatch(
+            type_name::<Sys>(),
+            move |c, e| system.run(c, e),
+            priority,
+            token
+        )
+    }
+
+    /// Sends the function which runs a system to the workers and returns
+    /// the `TaskSync` used to know when it finishes.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the system, used by the errors.
+    /// `run` - The function which runs the system.
+    /// `priority` - The priority of the system.
+    /// `token` - The token used to cancel the system.
+    fn dispatch<R: FnOnce(Arc<H>, Arc<E>) -> SystemResult<()> + 'static + Send>(
+        &self,
+        name: &'static str,
+        run: R,
+        priority: TaskPriority,
+        token: Option<CancellationToken>
+    ) -> Arc<TaskSync<EcsError>> {
         // Get a clone of the storages in order to send them to the
         // queue.
         let c_s_copy = self.components_storage.clone();
         let e_s_copy = self.entities_storage.clone();
 
         // Generate a signal in order to know when the task finish.
-        let task_sync = Arc::new(TaskSync::default());
+        let task_sync = Arc::new(TaskSync::named(name));
         let task_sync_copy = task_sync.clone();
 
-        // This must by run in a worker thread.
-        self.workers.execute_dyn(Box::new(move || {
-            system.run(c_s_copy, e_s_copy);
+        // The regular systems never wait for a saturated pool, they run
+        // in the calling thread instead.
+        let blocking: bool = priority == TaskPriority::Normal && token.is_none();
+
+        // This must by run in a worker thread. The token is checked here
+        // instead of the workers due the task sync must be always marked
+        // as finished.
+        let task: Task = Box::new(move || {
+            if token.is_some_and(|t| t.is_cancelled()) {
+                task_sync_copy.mark_as_cancelled();
+                task_sync_copy.mark_as_finish();
+                return;
+            }
+
+            // Report the failure using the task sync, panicking here would
+            // kill the worker and the task would never finish.
+            if let Err(e) = run(c_s_copy, e_s_copy) {
+                error(&format!("The system {} could not run: {}", name, e));
+                task_sync_copy.mark_as_failed(e);
+            }
             task_sync_copy.mark_as_finish();
-        }));
+        });
+
+        if blocking {
+            self.workers.execute_blocking(task);
+        } else {
+            self.workers.execute_dyn(task, priority, None);
+        }
 
         task_sync
     }
@@ -279,3 +702,520 @@ impl<
         )
     }
 }
+
+
+#[test]
+fn unique_accessors() {
+    let world = DefaultWorld::default();
+    world.register_unique(10u32);
+
+    assert!(world.has_unique::<u32>());
+    assert!(!world.has_unique::<u64>());
+
+    **world.get_unique_mut::<u32>().write() += 5;
+    assert_eq!(**world.get_unique::<u32>().read(), 15);
+
+    assert!(world.try_get_unique::<u32>().is_some());
+    assert!(world.try_get_unique::<u64>().is_none());
+    assert!(world.try_get_unique_mut::<u64>().is_none());
+}
+
+#[test]
+fn registered_unique_handle() {
+    let world = DefaultWorld::default();
+    let handle = world.register_unique(10u32);
+
+    **handle.write() += 1;
+    assert_eq!(**world.get_unique::<u32>().read(), 11);
+
+    **world.get_unique_mut::<u32>().write() += 1;
+    assert_eq!(**handle.read(), 12);
+    assert_eq!(**handle.reader().read(), 12);
+}
+
+#[test]
+#[should_panic(expected = "does not exist")]
+fn missing_unique_panics() {
+    let world = DefaultWorld::default();
+    world.get_unique::<u64>();
+}
+
+/// Returns the value of the result, the convenience functions of the
+/// `World` panic with the error message instead of returning the error.
+///
+/// # Arguments
+///
+/// `result` - The result returned by the components handler.
+fn or_panic<T>(result: SystemResult<T>) -> T {
+    result.unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[cfg(test)]
+use crate::{access::Read, sync::TaskWaitable, query::{Searchable, QueryFilter}, consts::BitmaskType, ParallelIterator};
+
+#[test]
+fn replace_unique_between_systems() {
+    struct ActiveCamera(u32);
+
+    let world = DefaultWorld::default();
+    world.register_unique(ActiveCamera(0));
+
+    let observed = Arc::new(AtomicUsize::new(0));
+    let observed_copy = observed.clone();
+
+    // The first system swaps the camera and the second one reads it.
+    (
+        world.run(|camera: UniqueWrite<ActiveCamera>| {
+            assert_eq!(camera.replace(ActiveCamera(1)).0, 0);
+        }),
+    ).wait();
+    (
+        world.run(move |camera: UniqueRead<ActiveCamera>| {
+            observed_copy.store(camera.read().0 as usize, Ordering::SeqCst);
+        }),
+    ).wait();
+    assert_eq!(observed.load(Ordering::SeqCst), 1);
+
+    assert_eq!(world.replace_unique(ActiveCamera(2)).unwrap().0, 1);
+    assert!(world.replace_unique(0u64).is_err());
+}
+
+#[test]
+fn events_between_systems() {
+    use crate::event::{EventReader, EventWriter};
+
+    #[derive(Clone)]
+    struct Clicked(u32);
+
+    let world = DefaultWorld::default();
+    world.register_event::<Clicked>();
+    world.register_event::<Clicked>();
+
+    let observed = Arc::new(AtomicUsize::new(0));
+
+    for frame in 0..3 {
+        world.update_events();
+        (
+            world.run(move |clicks: EventWriter<Clicked>| clicks.send(Clicked(frame))),
+        ).wait();
+
+        let observed_copy = observed.clone();
+        (
+            world.run(move |clicks: EventReader<Clicked>| {
+                let read = clicks.read();
+                assert_eq!(read.len(), 1);
+                observed_copy.fetch_add(read[0].0 as usize, Ordering::SeqCst);
+            }),
+        ).wait();
+    }
+
+    // Each event was read exactly once.
+    assert_eq!(observed.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn stale_entity_handle() {
+    struct Health(u32);
+
+    let world = DefaultWorld::default();
+    world.register::<Health>();
+    world.register::<u8>();
+
+    let old = world.add_entity((Health(1), 0u8));
+    world.remove_entity(old);
+    assert!(!world.is_alive(old));
+
+    // The new entity reuses the slot.
+    let new = world.add_entity((Health(2), 0u8));
+    assert!(world.is_alive(new));
+    assert_ne!(old, new);
+
+    // Removing the old handle does not affect the new entity.
+    world.remove_entity(old);
+    assert!(world.is_alive(new));
+
+    let healths = world.get::<Read<Health>>();
+    assert_eq!(healths.entities(), &vec![new]);
+    assert_eq!(healths.iter().next().unwrap().read().0, 2);
+}
+
+#[test]
+fn cancelled_system_does_not_run() {
+    let world = DefaultWorld::default();
+    world.register_unique(0u32);
+
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let result = (
+        world.run_cancellable(|value: UniqueWrite<u32>| {
+            value.set(1);
+        }, token),
+    ).wait();
+
+    assert!(result.finished);
+    assert!(result.cancelled);
+    assert_eq!(**world.get_unique::<u32>().read(), 0);
+}
+
+#[test]
+fn add_and_remove_component() {
+    struct Voxel;
+    struct Selected;
+
+    let world = DefaultWorld::default();
+    world.register::<Voxel>();
+    world.register::<u8>();
+    world.register::<Selected>();
+
+    let entity = world.add_entity((Voxel, 0u8));
+    assert!(world.get::<Read<Selected>>().entities().is_empty());
+
+    world.add_component(entity, Selected);
+    assert_eq!(world.get::<Read<Selected>>().entities(), &vec![entity]);
+    assert_eq!(world.get::<Read<Voxel>>().entities(), &vec![entity]);
+
+    world.remove_component::<Selected>(entity);
+    assert!(world.get::<Read<Selected>>().entities().is_empty());
+    assert_eq!(world.get::<Read<Voxel>>().entities(), &vec![entity]);
+}
+
+#[test]
+fn add_unregistered_component() {
+    struct Unknown;
+
+    let world = DefaultWorld::default();
+    world.register::<u8>();
+
+    let entity = world.add_entity((0u8, 0u16));
+    world.add_component(entity, Unknown);
+    assert_eq!(world.get::<Read<u16>>().entities(), &vec![entity]);
+    assert_eq!(world.get::<Read<Unknown>>().entities(), &vec![entity]);
+
+    // The component keeps its bit after registering it again.
+    world.register::<u16>();
+    assert_eq!(world.get::<Read<u16>>().entities(), &vec![entity]);
+    assert_eq!(world.component_names(entity).len(), 3);
+}
+
+#[cfg(test)]
+fn run_counter_workload(world: &DefaultWorld) -> u32 {
+    world.register_unique(0u32);
+
+    let increment = |value: UniqueWrite<u32>| {
+        **value.write() += 1;
+    };
+
+    (
+        world.run(increment),
+        world.run(increment),
+        world.run(increment),
+        world.run(increment)
+    ).wait();
+
+    **world.get_unique::<u32>().read()
+}
+
+#[test]
+fn workload_with_one_worker() {
+    let world = DefaultWorld::with_workers(WorkersDescriptor {
+        amount: 1,
+        ..WorkersDescriptor::default()
+    });
+    assert_eq!(run_counter_workload(&world), 4);
+}
+
+#[test]
+fn workload_with_many_workers() {
+    let world = DefaultWorld::with_workers(WorkersDescriptor {
+        amount: 8,
+        ..WorkersDescriptor::default()
+    });
+    assert_eq!(run_counter_workload(&world), 4);
+}
+
+#[test]
+fn slow_system_times_out() {
+    let world = DefaultWorld::default();
+    world.register_unique(0u32);
+
+    let tasks = (
+        world.run(|_: UniqueRead<u32>| {
+            std::thread::sleep(std::time::Duration::from_millis(200))
+        }),
+    );
+
+    let result = tasks.wait_timeout(std::time::Duration::from_millis(20));
+    assert!(!result.finished);
+
+    // The system keeps running and finishes later.
+    assert!(tasks.wait().finished);
+}
+
+#[test]
+fn add_entities_while_iterating() {
+    struct Health(u32);
+
+    let world = DefaultWorld::default();
+    world.register::<Health>();
+    world.add_entity((Health(1),));
+
+    let health = world.get::<Read<Health>>();
+    let mut iter = health.iter();
+
+    // The buffer grows while the iterator is alive, it must not deadlock.
+    for i in 0..1000 {
+        world.add_entity((Health(i),));
+    }
+
+    assert_eq!(iter.next().map(|h| h.read().0), Some(1));
+    assert!(iter.next().is_none());
+    assert_eq!(world.get::<Read<Health>>().iter().count(), 1001);
+}
+
+#[test]
+fn filter_query() {
+    struct Health(u32);
+    struct Armor(u32);
+
+    let world = DefaultWorld::default();
+    for i in 0..10 {
+        world.add_entity((Health(i), Armor(i * 2)));
+    }
+
+    let health = world.get::<Read<Health>>();
+    let armor = world.get::<Read<Armor>>();
+    let armors: Vec<u32> = (health.iter(), armor.iter())
+        .query()
+        .with_filter(|(h, _)| h.read().0 % 2 == 0)
+        .map(|(_, a)| a.read().0)
+        .collect();
+
+    assert_eq!(armors, vec![0, 4, 8, 12, 16]);
+}
+
+#[test]
+fn many_component_types_stay_disjoint() {
+    struct Tag<const N: usize>;
+
+    let world = DefaultWorld::default();
+
+    macro_rules! check_tags {
+        ($($n: literal)+) => {
+            let entities = vec![$(world.add_entity((Tag::<$n>,)),)+];
+            let mut index = 0;
+            $(
+                assert_eq!(world.get::<Read<Tag<$n>>>().entities(), &vec![entities[index]]);
+                index += 1;
+            )+
+            assert_eq!(index, 70);
+        };
+    }
+
+    check_tags!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26
+        27 28 29 30 31 32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50
+        51 52 53 54 55 56 57 58 59 60 61 62 63 64 65 66 67 68 69
+    );
+}
+
+#[test]
+fn parallel_read() {
+    struct Health(u32);
+
+    let world = DefaultWorld::default();
+    for i in 0..1000 {
+        world.add_entity((Health(i),));
+    }
+
+    let total = AtomicUsize::new(0);
+    world.get::<Read<Health>>().par_iter().for_each(|health| {
+        total.fetch_add(health.read().0 as usize, Ordering::SeqCst);
+    });
+
+    assert_eq!(total.load(Ordering::SeqCst), (0..1000).sum::<usize>());
+}
+
+#[test]
+fn systems_with_data() {
+    use std::cell::Cell;
+
+    let world = DefaultWorld::default();
+    world.register_unique(1u32);
+
+    let add = |amount: u32, value: UniqueWrite<u32>| {
+        **value.write() += amount;
+    };
+    assert!((world.run_with_data(add, 2),).wait().finished);
+    assert_eq!(**world.get_unique::<u32>().read(), 3);
+
+    let double = |value: UniqueWrite<u32>| {
+        **value.write() *= 2;
+    };
+    assert!(world.run_sync(double).is_ok());
+    assert_eq!(**world.get_unique::<u32>().read(), 6);
+    world.replace_unique(3u32).unwrap();
+
+    // The cell is `Send` but not `Sync`, it is borrowed from this thread.
+    let cell = Cell::new(0u32);
+    let copy = |cell: &Cell<u32>, value: UniqueRead<u32>| {
+        cell.set(**value.read());
+    };
+    assert!(world.run_sync_with_data(copy, &cell).is_ok());
+    assert_eq!(cell.get(), 3);
+
+    // The missing uniques are reported to the caller.
+    let missing = |_: (), _: UniqueRead<u64>| {};
+    assert!(world.run_sync_with_data(missing, ()).is_err());
+}
+
+#[test]
+fn component_names_of_entity() {
+    struct Voxel;
+    struct Selected;
+
+    let world = DefaultWorld::default();
+    world.register::<Voxel>();
+    world.register::<Selected>();
+    world.register::<u8>();
+
+    let entity = world.add_entity((Voxel, 0u8));
+    let names = world.component_names(entity);
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&"u8"));
+    assert!(names.iter().any(|n| n.ends_with("Voxel")));
+
+    world.remove_entity(entity);
+    assert!(world.component_names(entity).is_empty());
+}
+
+#[test]
+fn failed_system_keeps_error() {
+    struct Missing;
+
+    let world = DefaultWorld::default();
+    let sync = world.run(|_m: Read<Missing>| {});
+    (sync.clone(),).wait();
+
+    assert!(sync.did_fail());
+    assert_eq!(sync.error(), Some(EcsError::ComponentNotRegistered(type_name::<Missing>())));
+}
+
+#[test]
+fn batch_matches_one_by_one() {
+    struct Position(usize);
+    struct Velocity(usize);
+
+    let looped = DefaultWorld::default();
+    let batched = DefaultWorld::default();
+
+    // The batch spans several pages of the buffers.
+    let count = NUM_OF_COMPONETS_PER_PAGE * 2 + 10;
+    let looped_entities: Vec<Entity> = (0..count)
+        .map(|i| looped.add_entity((Position(i), Velocity(i * 2))))
+        .collect();
+    let batched_entities = batched.add_entities_batch(count, |i| (Position(i), Velocity(i * 2)));
+    assert_eq!(looped_entities, batched_entities);
+
+    let snapshot = |world: &DefaultWorld| {
+        let masks: Vec<BitmaskType> = (0..count)
+            .map(|i| world.entities_storage.get_bitmask(&Entity::new(i)))
+            .collect();
+        let positions = world.get::<Read<Position>>();
+        let velocities = world.get::<Read<Velocity>>();
+        let mut values: Vec<(usize, usize, usize)> = positions.entities().iter()
+            .zip((positions.iter(), velocities.iter()).query())
+            .map(|(e, (p, v))| (e.id, p.read().0, v.read().0))
+            .collect();
+        values.sort_unstable();
+        (masks, values)
+    };
+    assert_eq!(snapshot(&looped), snapshot(&batched));
+
+    // Remove every other entity with both paths.
+    let removed: Vec<Entity> = looped_entities.iter().copied().step_by(2).collect();
+    removed.iter().for_each(|e| looped.remove_entity(*e));
+    batched.remove_entities_batch(&removed);
+    assert_eq!(snapshot(&looped), snapshot(&batched));
+    assert!(!batched.is_alive(removed[0]));
+    assert!(batched.is_alive(batched_entities[1]));
+}
+
+#[test]
+fn derived_components_are_registered() {
+    #[derive(crate::Component)]
+    struct Velocity(u32);
+
+    let world = DefaultWorld::default();
+    assert!(world.components_storage.registered().contains(&id_of::<Velocity>()));
+
+    world.add_entity((Velocity(3),));
+    assert_eq!(world.get::<Read<Velocity>>().iter().next().unwrap().read().0, 3);
+}
+
+#[test]
+fn concurrent_removals_free_the_id_once() {
+    struct Position;
+
+    let world = Arc::new(DefaultWorld::default());
+    let entity = world.add_entity((Position,));
+
+    let removers: Vec<std::thread::JoinHandle<()>> = (0..8)
+        .map(|_| {
+            let world = world.clone();
+            std::thread::spawn(move || world.remove_entity(entity))
+        })
+        .collect();
+    removers.into_iter().for_each(|r| r.join().unwrap());
+
+    assert!(!world.is_alive(entity));
+    assert_eq!(world.free_entities.len(), 1);
+
+    // The same handle listed twice is also removed once.
+    let entity = world.add_entity((Position,));
+    world.remove_entities_batch(&[entity, entity]);
+    assert_eq!(world.free_entities.len(), 1);
+}
+
+#[test]
+fn stale_remove_component_does_not_panic() {
+    struct Position;
+    struct NeverAdded;
+
+    let world = DefaultWorld::default();
+    let entity = world.add_entity((Position,));
+    world.remove_entity(entity);
+
+    // The component is not registered, the stale handle returns first.
+    world.remove_component::<NeverAdded>(entity);
+}
+
+#[test]
+fn concurrent_component_changes_keep_the_bits() {
+    struct A;
+    struct B;
+    struct C;
+    struct D;
+
+    let world = Arc::new(DefaultWorld::default());
+    world.register::<A>();
+    world.register::<B>();
+    world.register::<C>();
+    world.register::<D>();
+
+    for _ in 0..50 {
+        let entity = world.add_entity((C, D));
+        let writers: Vec<std::thread::JoinHandle<()>> = vec![
+            { let w = world.clone(); std::thread::spawn(move || w.add_component(entity, A)) },
+            { let w = world.clone(); std::thread::spawn(move || w.add_component(entity, B)) },
+            { let w = world.clone(); std::thread::spawn(move || w.remove_component::<C>(entity)) },
+            { let w = world.clone(); std::thread::spawn(move || w.remove_component::<D>(entity)) },
+        ];
+        writers.into_iter().for_each(|w| w.join().unwrap());
+
+        assert_eq!(world.component_names(entity).len(), 2);
+        assert_eq!(world.entities_storage.get_bitmask(&entity),
+            or_panic(world.components_storage.bitmask(id_of::<A>())) |
+            or_panic(world.components_storage.bitmask(id_of::<B>())));
+    }
+}
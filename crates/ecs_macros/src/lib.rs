@@ -0,0 +1,36 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derives `ecs::Component` for the type.
+///
+/// The types without generics are also submitted to the component inventory,
+/// so they are registered when a `DefaultWorld` is created. The generic types
+/// can not be submitted due the inventory needs a concrete type, they must
+/// still be registered manually.
+#[proc_macro_derive(Component)]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let registration = if input.generics.params.is_empty() {
+        quote! {
+            ::ecs::inventory::submit! {
+                ::ecs::ComponentRegistrar::of::<#name>()
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let output = quote! {
+        const _: () = {
+            impl #impl_generics ::ecs::Component for #name #type_generics #where_clause {}
+
+            #registration
+        };
+    };
+
+    output.into()
+}
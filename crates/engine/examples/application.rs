@@ -1,15 +1,32 @@
-use cgmath::{Vector3, Quaternion};
+use cgmath::Vector3;
+use winit::event::{VirtualKeyCode, MouseButton};
 
 use engine::{
-    scene::components::{Voxel, Transform},
+    scene::{
+        components::{Voxel, LocalTransform},
+        input::Input,
+        camera::Camera,
+        picking::{PickResult, screen_ray, select_under_cursor}
+    },
     run_program,
+    lock_cursor_system,
+    ScheduleHandler,
+    Workloads,
+    FrameHistory,
+    request_exit,
+    DebugDraw,
+    Window,
     InitialConfig
 };
 
 use ecs::{
     DefaultWorld,
     ComponentHandler,
-    EntityHandler
+    EntityHandler,
+    SystemHandler,
+    TaskWaitable,
+    UniqueRead,
+    UniqueWrite
 };
 
 use log::info;
@@ -34,6 +51,10 @@ impl Default for FlyCamera {
     }
 }
 
+/// Determines if the picking ray is drawn, it is toggled with R.
+#[derive(Default)]
+pub struct ShowPickingRay(pub bool);
+
 /// Configures the application.
 ///
 /// # Arguments
@@ -42,21 +63,23 @@ impl Default for FlyCamera {
 fn configure_application(world: &DefaultWorld) {
     // Adds the fly camera information.
     world.register_unique(FlyCamera::default());
+    world.register_unique(ShowPickingRay(true));
+
+    // The picking ray is drawn in parallel with the engine systems.
+    world.add_system(Workloads::Synchronize, picking_ray_system);
+
+    // Keep the cursor inside the window while the camera is controlled.
+    (
+        world.run(lock_cursor_system),
+    ).wait();
 
     for i in 1..10 {
         for j in 1..10 {
-            let transform = Transform {
-                position: Vector3 {
-                    x: 2.0 * (i as f32),
-                    y: 0.0,
-                    z: 2.0 * (j as f32)
-                },
-                scale: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
-                rotation: Quaternion { 
-                    v: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
-                    s: 0.0
-                }
-            };
+            let transform = LocalTransform::from_position(Vector3 {
+                x: 2.0 * (i as f32),
+                y: 0.0,
+                z: 2.0 * (j as f32)
+            });
             world.add_entity((
                 Voxel::rand_color(),
                 transform
@@ -70,17 +93,98 @@ fn configure_application(world: &DefaultWorld) {
 /// # Arguments
 ///
 /// `world` - The world used to store and handle data.
-fn tick(_world: &DefaultWorld) {
+fn tick(world: &DefaultWorld) {
     info("Tick");
+
+    // Report the frame times of the last frames to find the hitches.
+    if world.get_unique::<Input>().read().is_key_just_pressed(VirtualKeyCode::F) {
+        let history = world.get_unique::<FrameHistory>();
+        let history_read = history.read();
+        if let (Some(average), Some(p99), Some(worst)) =
+            (history_read.average(), history_read.percentile(99.0), history_read.worst()) {
+            info(&format!(
+                "Frames: {} | Average: {:.2} ms | 99%: {:.2} ms | Worst: {:.2} ms",
+                history_read.len(), average, p99, worst
+            ));
+        }
+    }
+
+    // Finish the application cleanly instead of calling process::exit.
+    if world.get_unique::<Input>().read().is_key_down(VirtualKeyCode::Escape) {
+        request_exit(world);
+    }
+
+    // Outline the voxel in the center of the screen.
+    let clicked = world.get_unique::<Input>().read().was_clicked(MouseButton::Left);
+    if clicked {
+        if let Some(entity) = select_under_cursor(world) {
+            info(&format!("Selected voxel {:?}", entity));
+        }
+    }
+}
+
+/// Toggles the picking ray with R and draws the ray used to pick the voxels
+/// and the normal of the face under the cursor.
+fn picking_ray_system(
+    input: UniqueRead<Input>,
+    window: UniqueRead<Window>,
+    camera: UniqueRead<Camera>,
+    pick_result: UniqueRead<PickResult>,
+    show: UniqueWrite<ShowPickingRay>,
+    debug_draw: UniqueWrite<DebugDraw>) {
+    // A single toggle per key press, holding the key does not flicker.
+    let mut show_write = show.write();
+    if input.read().is_key_just_pressed(VirtualKeyCode::R) {
+        show_write.0 = !show_write.0;
+    }
+    if !show_write.0 {
+        return;
+    }
+
+    let size = window.read().physical_size;
+    let center = (size.width as f64 / 2.0, size.height as f64 / 2.0);
+    let ray = match screen_ray(&camera.read(), center, size) {
+        Some(r) => r,
+        None => return
+    };
+
+    let mut debug_draw_write = debug_draw.write();
+    debug_draw_write.ray(ray.origin, ray.direction, 100.0, [1.0, 0.0, 0.0, 1.0]);
+
+    if let Some((_, point, normal)) = pick_result.read().0 {
+        debug_draw_write.ray(point, normal, 2.0, [0.0, 1.0, 0.0, 1.0]);
+    }
+}
+
+/// Called when the application finishes, before the world is dropped.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn on_exit(world: &DefaultWorld) {
+    let camera = world.get_unique::<FlyCamera>();
+    info(&format!("Exiting, camera yaw: {}", camera.read().yaw));
 }
 
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
 /// Application entry point.
 fn main() {
     // Trigger application main loop.
     match run_program(
         configure_application,
         tick,
-        InitialConfig::default()
+        fixed_tick,
+        InitialConfig {
+            on_exit: Some(on_exit),
+            enable_dev_gui: true,
+            ..InitialConfig::default()
+        }
     ) {
         Ok(_) => return,
         Err(e) => println!("{}", e) 
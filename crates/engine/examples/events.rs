@@ -0,0 +1,132 @@
+use cgmath::{Point3, Vector3};
+use winit::event::MouseButton;
+
+use engine::{
+    scene::{
+        camera::Camera,
+        components::{Voxel, LocalTransform},
+        input::Input,
+        picking::PickResult,
+        text_label::TextLabel
+    },
+    run_program,
+    InitialConfig
+};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler,
+    EventHandler,
+    EventReader,
+    EventWriter,
+    Entity,
+    SystemHandler,
+    TaskWaitable,
+    UniqueRead,
+    Write
+};
+
+use log::info;
+
+/// Defines the number of voxels per row and column.
+const COLUMNS: u32 = 10;
+const ROWS: u32 = 10;
+
+/// Sent when the user clicks a voxel.
+#[derive(Clone, Copy)]
+struct VoxelClicked {
+    entity: Entity,
+    point: Point3<f32>
+}
+
+/// Configures the application.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn configure_application(world: &DefaultWorld) {
+    world.register_event::<VoxelClicked>();
+
+    for i in 0..COLUMNS {
+        for j in 0..ROWS {
+            let transform = LocalTransform::from_position(Vector3 {
+                x: 4.0 * (i as f32),
+                y: 0.0,
+                z: 4.0 * (j as f32)
+            });
+            world.add_entity((Voxel::rand_color(), transform, TextLabel::new("0")));
+        }
+    }
+
+    world
+        .get_unique_mut::<Camera>()
+        .write()
+        .look_at((-10.0, 25.0, -10.0).into(), (20.0, 0.0, 20.0).into());
+
+    info("Click the voxels to count the clicks");
+}
+
+/// Sends a `VoxelClicked` when the voxel under the cursor is clicked, it
+/// does not know who is interested in the clicks.
+fn collision_system(
+    input: UniqueRead<Input>,
+    pick_result: UniqueRead<PickResult>,
+    clicks: EventWriter<VoxelClicked>) {
+    if !input.read().was_clicked(MouseButton::Left) {
+        return;
+    }
+
+    if let Some((entity, point, _)) = pick_result.read().0 {
+        clicks.send(VoxelClicked { entity, point });
+    }
+}
+
+/// Shows the number of clicks of each voxel in its label.
+fn ui_system(clicks: EventReader<VoxelClicked>, labels: Write<TextLabel>) {
+    let clicked = clicks.read();
+    if clicked.is_empty() {
+        return;
+    }
+
+    labels.entities().iter()
+        .zip(labels.iter())
+        .for_each(|(entity, label)| {
+            for click in clicked.iter().filter(|c| c.entity == *entity) {
+                let mut label_write = label.write();
+                let count: u32 = label_write.text.parse().unwrap_or(0) + 1;
+                label_write.text = count.to_string();
+                info(&format!("Voxel clicked at {:?}", click.point));
+            }
+        });
+}
+
+/// Executes the application logic.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn tick(world: &DefaultWorld) {
+    (world.run(collision_system),).wait();
+    (world.run(ui_system),).wait();
+}
+
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
+/// Application entry point.
+fn main() {
+    // Trigger application main loop.
+    if let Err(e) = run_program(
+        configure_application,
+        tick,
+        fixed_tick,
+        InitialConfig::default()
+    ) {
+        println!("{}", e);
+    }
+}
@@ -0,0 +1,101 @@
+use std::{fs, path::Path};
+
+use engine::{
+    scene::{
+        camera::Camera,
+        components::LocalTransform,
+        file_drop::FileDropEvents,
+        mesh::load_mesh
+    },
+    run_program,
+    InitialConfig
+};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler
+};
+
+use log::{info, warning};
+
+/// Configures the application.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn configure_application(world: &DefaultWorld) {
+    world
+        .get_unique_mut::<Camera>()
+        .write()
+        .look_at((-10.0, 15.0, -10.0).into(), (0.0, 0.0, 0.0).into());
+
+    info("Drop an .obj model or an image into the window");
+}
+
+/// Loads the dropped file, the models are placed in the origin.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+/// `path` - The path of the dropped file.
+fn load_file(world: &DefaultWorld, path: &Path) {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    match extension.as_deref() {
+        Some("obj") => match load_mesh(world, path) {
+            Ok(handle) => {
+                info(&format!("Loaded the model {:?}", path));
+                world.add_entity((handle, LocalTransform::default()));
+            },
+            Err(e) => warning(&format!("The model could not be loaded: {}", e))
+        },
+        Some("png") | Some("jpg") | Some("jpeg") => match fs::read(path) {
+            Ok(bytes) => info(&format!("Loaded image {:?} ({} bytes)", path, bytes.len())),
+            Err(e) => warning(&format!("The image could not be read: {}", e))
+        },
+        _ => warning(&format!("The file {:?} is not supported", path))
+    }
+}
+
+/// Executes the application logic.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn tick(world: &DefaultWorld) {
+    // Copy the paths, the events can not be read while adding entities.
+    let paths: Vec<_> = world
+        .get_unique::<FileDropEvents>()
+        .read()
+        .dropped()
+        .map(|p| p.to_path_buf())
+        .collect();
+
+    for path in paths {
+        load_file(world, &path);
+    }
+}
+
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
+/// Application entry point.
+fn main() {
+    // Trigger application main loop.
+    if let Err(e) = run_program(
+        configure_application,
+        tick,
+        fixed_tick,
+        InitialConfig::default()
+    ) {
+        println!("{}", e);
+    }
+}
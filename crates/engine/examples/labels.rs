@@ -0,0 +1,75 @@
+use cgmath::Vector3;
+
+use engine::{
+    scene::{
+        camera::Camera,
+        components::{Voxel, LocalTransform},
+        text_label::TextLabel
+    },
+    run_program,
+    InitialConfig
+};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler
+};
+
+/// Defines the number of voxels per row and column.
+const COLUMNS: u32 = 40;
+const ROWS: u32 = 25;
+
+/// Configures the application.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn configure_application(world: &DefaultWorld) {
+    for i in 0..COLUMNS {
+        for j in 0..ROWS {
+            let transform = LocalTransform::from_position(Vector3 {
+                x: 4.0 * (i as f32),
+                y: 0.0,
+                z: 4.0 * (j as f32)
+            });
+            world.add_entity((
+                Voxel::rand_color(),
+                transform,
+                TextLabel::new(&format!("{}", i * ROWS + j))
+            ));
+        }
+    }
+
+    world
+        .get_unique_mut::<Camera>()
+        .write()
+        .look_at((-20.0, 30.0, -20.0).into(), (80.0, 0.0, 50.0).into());
+}
+
+/// Executes the application logic.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn tick(_world: &DefaultWorld) {}
+
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
+/// Application entry point.
+fn main() {
+    // Trigger application main loop.
+    if let Err(e) = run_program(
+        configure_application,
+        tick,
+        fixed_tick,
+        InitialConfig::default()
+    ) {
+        println!("{}", e);
+    }
+}
@@ -0,0 +1,87 @@
+use cgmath::Vector3;
+
+use engine::{
+    scene::{
+        camera::{Camera, Projection, create_render_target},
+        components::{Voxel, LocalTransform}
+    },
+    run_program,
+    InitialConfig
+};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler
+};
+
+use types::Size;
+
+/// Defines the size of the minimap texture.
+const MINIMAP_SIZE: u32 = 256;
+
+/// Configures the application.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn configure_application(world: &DefaultWorld) {
+    for i in 0..20 {
+        for j in 0..20 {
+            let transform = LocalTransform::from_position(Vector3 {
+                x: 2.0 * (i as f32),
+                y: 0.0,
+                z: 2.0 * (j as f32)
+            });
+            world.add_entity((
+                Voxel::rand_color(),
+                transform
+            ));
+        }
+    }
+
+    // The main camera renders into the swap chain.
+    world
+        .get_unique_mut::<Camera>()
+        .write()
+        .look_at((-10.0, 15.0, -10.0).into(), (20.0, 0.0, 20.0).into());
+
+    // The minimap camera looks straight down using an orthographic
+    // projection and renders into its own texture.
+    let mut minimap = Camera {
+        projection: Projection::Orthographic { height: 45.0 },
+        up: Vector3::unit_z(),
+        ..Camera::default()
+    };
+    minimap.look_at((20.0, 30.0, 20.0).into(), (20.0, 0.0, 20.0).into());
+
+    let target = create_render_target(world, Size::new(MINIMAP_SIZE, MINIMAP_SIZE));
+    world.add_entity((minimap, target));
+}
+
+/// Executes the application logic.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn tick(_world: &DefaultWorld) {}
+
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
+/// Application entry point.
+fn main() {
+    // Trigger application main loop.
+    if let Err(e) = run_program(
+        configure_application,
+        tick,
+        fixed_tick,
+        InitialConfig::default()
+    ) {
+        println!("{}", e);
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Instant;
+
+use engine::{
+    scene::{
+        camera::Camera,
+        terrain::{TerrainDescriptor, generate_heightmap_terrain}
+    },
+    run_program,
+    InitialConfig
+};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler
+};
+use log::info;
+
+/// Configures the application.
+///
+/// The 256x256 island with one layer beneath the surface creates around
+/// 131000 voxels, the noise takes a few milliseconds and most of the load
+/// time is spent adding the entities to the world.
+///
+/// # Arguments
+///
+/// `world` - The world used to store and handle data.
+fn configure_application(world: &DefaultWorld) {
+    let start = Instant::now();
+    let count = generate_heightmap_terrain(world, TerrainDescriptor {
+        width: 256,
+        depth: 256,
+        seed: 42,
+        amplitude: 32.0,
+        scale: 96.0,
+        octaves: 5,
+        layers: 1,
+        island: true,
+        ..TerrainDescriptor::default()
+    });
+    info(&format!("Terrain with {} voxels generated in {:?}", count, start.elapsed()));
+
+    let camera = world.get_unique_mut::<Camera>();
+    let mut camera_write = camera.write();
+    camera_write.zfar = 1000.0;
+    camera_write.look_at((0.0, 250.0, -450.0).into(), (0.0, 0.0, 0.0).into());
+}
+
+/// Executes the application logic.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn tick(_world: &DefaultWorld) {}
+
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
+/// Application entry point.
+fn main() {
+    // Trigger application main loop.
+    if let Err(e) = run_program(
+        configure_application,
+        tick,
+        fixed_tick,
+        InitialConfig::default()
+    ) {
+        println!("{}", e);
+    }
+}
@@ -48,12 +48,20 @@ fn tick(_world: &DefaultWorld) {
     warning("Tick");
 }
 
+/// Executes the logic which needs a fixed timestep.
+///
+/// # Arguments
+///
+/// `_world` - The world used to store and handle data.
+fn fixed_tick(_world: &DefaultWorld) {}
+
 /// Application entry point.
 fn main() {
     // Trigger application main loop.
     match run_program(
         configure_application,
         tick,
+        fixed_tick,
         InitialConfig::default()
     ) {
         Ok(_) => return,
@@ -0,0 +1,186 @@
+use ecs::DefaultWorld;
+
+use crate::InitialConfig;
+
+/// Defines when a system added to the `App` is executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Once per frame, after the start workload.
+    Tick,
+
+    /// Every fixed timestep, it could run several times per frame or none.
+    FixedTick
+}
+
+/// Groups the resources, components and systems of a feature, in this way
+/// the feature is added to the application with a single call.
+pub trait Plugin {
+    /// Registers the plugin into the world, it is called once the engine
+    /// resources are initialized.
+    ///
+    /// # Arguments
+    ///
+    /// `world` - The world of the application.
+    fn build(&self, world: &DefaultWorld);
+}
+
+/// A system added to the `App`, unlike `TickFn` it can capture state.
+type AppSystem = Box<dyn FnMut(&DefaultWorld)>;
+
+/// A system added to the `App` which is executed only once.
+type SetupSystem = Box<dyn FnOnce(&DefaultWorld)>;
+
+/// Builds and runs an application.
+///
+/// The plugins are built in the order they were added, after them the
+/// setup systems run once and the tick systems run every frame.
+///
+/// ```ignore
+/// App::new()
+///     .with_config(InitialConfig::default())
+///     .add_plugin(VoxelPlugin)
+///     .add_system(Stage::Tick, my_system)
+///     .run()
+/// ```
+#[derive(Default)]
+pub struct App {
+    /// The configuration used to create the window and the world.
+    pub(crate) config: InitialConfig,
+
+    /// The plugins, in the order they are built.
+    plugins: Vec<Box<dyn Plugin>>,
+
+    /// The systems executed once, after the plugins.
+    setup_systems: Vec<SetupSystem>,
+
+    /// The systems executed every frame.
+    tick_systems: Vec<AppSystem>,
+
+    /// The systems executed every fixed timestep.
+    fixed_tick_systems: Vec<AppSystem>
+}
+
+impl App {
+    /// Creates and returns a new `App` with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the configuration of the application.
+    ///
+    /// # Arguments
+    ///
+    /// `config` - The configuration used to create the window and the world.
+    pub fn with_config(mut self, config: InitialConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Adds a plugin, it is built after the world is initialized.
+    ///
+    /// # Arguments
+    ///
+    /// `plugin` - The plugin to be added.
+    pub fn add_plugin<P: Plugin + 'static>(mut self, plugin: P) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Adds a system executed once, after all the plugins are built.
+    ///
+    /// # Arguments
+    ///
+    /// `system` - The system to be executed.
+    pub fn add_setup_system<F: FnOnce(&DefaultWorld) + 'static>(mut self, system: F) -> Self {
+        self.setup_systems.push(Box::new(system));
+        self
+    }
+
+    /// Adds a system executed in the provided stage, the systems of the same
+    /// stage run in the order they were added.
+    ///
+    /// # Arguments
+    ///
+    /// `stage` - When the system is executed.
+    /// `system` - The system to be executed.
+    pub fn add_system<F: FnMut(&DefaultWorld) + 'static>(mut self, stage: Stage, system: F) -> Self {
+        match stage {
+            Stage::Tick => self.tick_systems.push(Box::new(system)),
+            Stage::FixedTick => self.fixed_tick_systems.push(Box::new(system))
+        }
+        self
+    }
+
+    /// Creates the window and the world and runs the main loop, it only
+    /// returns if the initialization fails.
+    pub fn run(self) -> Result<(), String> {
+        crate::run_app(self)
+    }
+
+    /// Builds the plugins and runs the setup systems.
+    ///
+    /// # Arguments
+    ///
+    /// `world` - The initialized world.
+    pub(crate) fn build(&mut self, world: &DefaultWorld) {
+        for plugin in &self.plugins {
+            plugin.build(world);
+        }
+
+        for system in self.setup_systems.drain(..) {
+            system(world);
+        }
+    }
+
+    /// Runs the systems of the stage.
+    ///
+    /// # Arguments
+    ///
+    /// `stage` - The stage to be executed.
+    /// `world` - The world of the application.
+    pub(crate) fn run_stage(&mut self, stage: Stage, world: &DefaultWorld) {
+        let systems = match stage {
+            Stage::Tick => &mut self.tick_systems,
+            Stage::FixedTick => &mut self.fixed_tick_systems
+        };
+
+        for system in systems.iter_mut() {
+            system(world);
+        }
+    }
+}
+
+#[cfg(test)]
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(test)]
+struct CounterPlugin;
+
+#[cfg(test)]
+impl Plugin for CounterPlugin {
+    fn build(&self, world: &DefaultWorld) {
+        use ecs::ComponentHandler;
+        world.register_unique(0u32);
+    }
+}
+
+#[test]
+fn plugins_build_before_systems() {
+    use ecs::ComponentHandler;
+
+    let ticks = Rc::new(RefCell::new(0));
+    let captured = ticks.clone();
+    let mut app = App::new()
+        .add_plugin(CounterPlugin)
+        .add_setup_system(|world| **world.get_unique_mut::<u32>().write() += 1)
+        .add_system(Stage::Tick, move |_| *captured.borrow_mut() += 1);
+
+    let world = DefaultWorld::default();
+    app.build(&world);
+    app.run_stage(Stage::Tick, &world);
+    app.run_stage(Stage::Tick, &world);
+    app.run_stage(Stage::FixedTick, &world);
+
+    assert_eq!(**world.get_unique::<u32>().read(), 1);
+    assert_eq!(*ticks.borrow(), 2);
+}
@@ -0,0 +1,749 @@
+use std::collections::HashSet;
+
+use bytemuck::{Pod, Zeroable};
+use winit::event::MouseButton;
+
+use ecs::{UniqueRead, UniqueWrite};
+
+use crate::{
+    basics::window::GuiScreen,
+    graphics::{
+        render_stats::RenderStats,
+        font::{
+            glyph_rect,
+            solid_rect,
+            CELL_WIDTH,
+            CELL_HEIGHT,
+            GLYPH_WIDTH,
+            GLYPH_HEIGHT
+        }
+    },
+    scene::input::{InputEvent, MousePosition, GuiInputCapture}
+};
+
+/// The size of a font pixel in logical pixels.
+pub const GUI_FONT_SCALE: f32 = 2.0;
+
+/// The maximum number of characters per line, the longer lines are wrapped.
+pub const GUI_MAX_COLUMNS: usize = 72;
+
+/// The color used by the text which does not need a special color.
+pub const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+
+/// The height of a line of text in font pixels.
+const LINE_HEIGHT: f32 = (CELL_HEIGHT + 2) as f32;
+
+/// The space between the windows and around their content in font pixels.
+const PADDING: f32 = 3.0;
+
+/// The minimum width of the plots and the bars in font pixels.
+const PLOT_WIDTH: f32 = (48 * CELL_WIDTH) as f32;
+
+/// The height of the plots in font pixels.
+const PLOT_HEIGHT: f32 = LINE_HEIGHT * 4.0;
+
+/// The colors of the window parts.
+const WINDOW_COLOR: [f32; 4] = [0.05, 0.05, 0.08, 0.85];
+const TITLE_COLOR: [f32; 4] = [0.15, 0.2, 0.35, 0.95];
+const BUTTON_COLOR: [f32; 4] = [0.25, 0.25, 0.3, 1.0];
+const SELECTED_BUTTON_COLOR: [f32; 4] = [0.2, 0.45, 0.8, 1.0];
+const PLOT_COLOR: [f32; 4] = [0.12, 0.12, 0.15, 1.0];
+
+/// Represents a quad of the dev GUI, it is a glyph or a solid rectangle
+/// when it uses the solid cell of the font atlas.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GuiQuad {
+    /// The top left corner in physical pixels.
+    pub position: [f32; 2],
+
+    /// The size in physical pixels.
+    pub size: [f32; 2],
+
+    /// The area of the font atlas.
+    pub rect: [f32; 4],
+
+    pub color: [f32; 4]
+}
+
+unsafe impl Pod for GuiQuad {}
+unsafe impl Zeroable for GuiQuad {}
+
+/// Defines where a window is placed, the windows with the same anchor are
+/// stacked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GuiAnchor {
+    /// A bar which uses the full width of the screen, at the top.
+    Top,
+
+    /// A column at the left, below the top bars.
+    Left,
+
+    /// A column at the right, below the top bars.
+    Right,
+
+    /// A column at the left, from the bottom of the screen up.
+    Bottom
+}
+
+/// Represents an element of a window.
+#[derive(Clone, Debug, PartialEq)]
+enum GuiWidget {
+    /// A text, the new lines start a new row.
+    Text(String, [f32; 4]),
+
+    /// A row of buttons, the label and if it is selected.
+    Buttons(Vec<(String, bool)>),
+
+    /// The values as bars and the horizontal lines drawn on top.
+    Plot(Vec<f32>, Vec<(f32, [f32; 4])>, [f32; 4]),
+
+    /// A bar split in segments proportional to their values.
+    StackedBar(Vec<(f32, [f32; 4])>)
+}
+
+/// Contains the content of a window of the dev GUI, it is created with
+/// `DevGui::window` and added with `DevGui::show`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuiWindow {
+    /// The title of the window, the windows without title do not have a
+    /// title bar.
+    title: String,
+
+    /// Where the window is placed.
+    anchor: GuiAnchor,
+
+    /// The content from the top to the bottom.
+    widgets: Vec<GuiWidget>,
+
+    /// The labels of the buttons clicked since the last frame.
+    clicked: Vec<String>
+}
+
+impl GuiWindow {
+    /// Adds a text, the lines longer than `GUI_MAX_COLUMNS` are wrapped.
+    ///
+    /// # Arguments
+    ///
+    /// `text` - The text, it could contain many lines.
+    /// `color` - The color of the text.
+    pub fn text(&mut self, text: &str, color: [f32; 4]) {
+        self.widgets.push(GuiWidget::Text(text.to_string(), color));
+    }
+
+    /// Adds a button and returns true if it was clicked during the last
+    /// frame. Consecutive buttons are placed in the same row.
+    ///
+    /// # Arguments
+    ///
+    /// `label` - The text of the button, it identifies the button inside
+    /// the window.
+    pub fn button(&mut self, label: &str) -> bool {
+        self.selectable(label, false)
+    }
+
+    /// Adds a button which is highlighted when it is selected and returns
+    /// true if it was clicked during the last frame.
+    ///
+    /// # Arguments
+    ///
+    /// `label` - The text of the button.
+    /// `selected` - Defines if the button is highlighted.
+    pub fn selectable(&mut self, label: &str, selected: bool) -> bool {
+        match self.widgets.last_mut() {
+            Some(GuiWidget::Buttons(row)) => row.push((label.to_string(), selected)),
+            _ => self.widgets.push(GuiWidget::Buttons(vec![(label.to_string(), selected)]))
+        }
+        self.clicked.iter().any(|c| c == label)
+    }
+
+    /// Adds a plot which draws each value as a bar, the lines are drawn on
+    /// top, for example to show a limit.
+    ///
+    /// # Arguments
+    ///
+    /// `values` - The values from the oldest to the newest.
+    /// `lines` - The value and the color of each horizontal line.
+    /// `color` - The color of the bars.
+    pub fn plot(&mut self, values: Vec<f32>, lines: Vec<(f32, [f32; 4])>, color: [f32; 4]) {
+        self.widgets.push(GuiWidget::Plot(values, lines, color));
+    }
+
+    /// Adds a bar split in segments, the width of each segment is
+    /// proportional to its value.
+    ///
+    /// # Arguments
+    ///
+    /// `segments` - The value and the color of each segment.
+    pub fn stacked_bar(&mut self, segments: Vec<(f32, [f32; 4])>) {
+        self.widgets.push(GuiWidget::StackedBar(segments));
+    }
+
+    /// Returns true if the window does not contain anything to draw.
+    pub fn is_empty(&self) -> bool {
+        self.widgets.is_empty()
+    }
+}
+
+/// Contains the state of the debug GUI, it is only registered when
+/// `InitialConfig::enable_dev_gui` is set.
+///
+/// The systems add their windows during the frame, at the end of the frame
+/// they are placed and converted into quads, which are drawn by the next
+/// render. The clicks are checked against the buttons of the last layout.
+#[derive(Default)]
+pub struct DevGui {
+    /// The windows added since the last layout.
+    windows: Vec<GuiWindow>,
+
+    /// The quads of the last layout, from the back to the front.
+    quads: Vec<GuiQuad>,
+
+    /// The area of each window of the last layout, x, y, width and height
+    /// in physical pixels.
+    areas: Vec<[f32; 4]>,
+
+    /// The id and the area of each button of the last layout.
+    buttons: Vec<(String, [f32; 4])>,
+
+    /// The ids of the buttons clicked since the last layout.
+    clicked: HashSet<String>,
+
+    /// The panels which are toggled from the top bar and their visibility,
+    /// in the order they were added.
+    panels: Vec<(&'static str, bool)>
+}
+
+impl DevGui {
+    /// Adds a panel which is toggled from the top bar, the panels start
+    /// hidden.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the panel, it is the label of its button.
+    pub fn add_panel(&mut self, name: &'static str) {
+        if !self.panels.iter().any(|(n, _)| *n == name) {
+            self.panels.push((name, false));
+        }
+    }
+
+    /// Returns the panels and their visibility.
+    pub fn panels(&self) -> Vec<(&'static str, bool)> {
+        self.panels.clone()
+    }
+
+    /// Returns true if the panel is visible, the panels which were not
+    /// added are never visible.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the panel.
+    pub fn is_visible(&self, name: &str) -> bool {
+        self.panels.iter().any(|(n, visible)| *n == name && *visible)
+    }
+
+    /// Shows the panel if it is hidden and hides it otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the panel.
+    pub fn toggle(&mut self, name: &str) {
+        if let Some((_, visible)) = self.panels.iter_mut().find(|(n, _)| *n == name) {
+            *visible = !*visible;
+        }
+    }
+
+    /// Creates and returns an empty window, it must be added with `show`.
+    ///
+    /// # Arguments
+    ///
+    /// `title` - The title of the window, it also identifies its buttons.
+    /// `anchor` - Where the window is placed.
+    pub fn window(&self, title: &str, anchor: GuiAnchor) -> GuiWindow {
+        let prefix = format!("{}/", title);
+        GuiWindow {
+            title: title.to_string(),
+            anchor,
+            widgets: Vec::new(),
+            clicked: self.clicked
+                .iter()
+                .filter_map(|id| id.strip_prefix(&prefix))
+                .map(|label| label.to_string())
+                .collect()
+        }
+    }
+
+    /// Adds the window to the current frame, the empty windows are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// `window` - The window to be drawn.
+    pub fn show(&mut self, window: GuiWindow) {
+        if !window.is_empty() {
+            self.windows.push(window);
+        }
+    }
+
+    /// Returns the quads of the last layout, from the back to the front.
+    pub fn quads(&self) -> &[GuiQuad] {
+        &self.quads
+    }
+
+    /// Returns true if the point is inside a window of the last layout.
+    ///
+    /// # Arguments
+    ///
+    /// `point` - The point in physical pixels.
+    pub fn is_over(&self, point: (f64, f64)) -> bool {
+        let point = [point.0 as f32, point.1 as f32];
+        self.areas.iter().any(|area| contains(area, point))
+    }
+
+    /// Stores the clicks on the buttons of the last layout, it must receive
+    /// the events before they are claimed by `GuiInputCapture`.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The input event.
+    /// `cursor` - The position of the cursor in physical pixels.
+    pub fn process_event(&mut self, event: &InputEvent, cursor: (f64, f64)) {
+        if *event != InputEvent::MouseDown(MouseButton::Left) {
+            return;
+        }
+
+        let cursor = [cursor.0 as f32, cursor.1 as f32];
+        if let Some((id, _)) = self.buttons.iter().find(|(_, area)| contains(area, cursor)) {
+            self.clicked.insert(id.clone());
+        }
+    }
+
+    /// Places the windows added since the last layout and converts them
+    /// into quads, the windows and the clicks are cleared.
+    ///
+    /// # Arguments
+    ///
+    /// `screen` - The screen where the windows are drawn.
+    pub fn layout(&mut self, screen: &GuiScreen) {
+        let scale = GUI_FONT_SCALE * screen.scale_factor as f32;
+        let screen_width = screen.physical_size.width as f32 / scale;
+        let screen_height = screen.physical_size.height as f32 / scale;
+
+        // The systems add the windows in any order, sorting them keeps the
+        // place of each window between frames.
+        let mut windows = std::mem::take(&mut self.windows);
+        windows.sort_by(|a, b| (a.anchor, &a.title).cmp(&(b.anchor, &b.title)));
+
+        self.quads.clear();
+        self.areas.clear();
+        self.buttons.clear();
+        self.clicked.clear();
+
+        let mut painter = GuiPainter { scale, quads: &mut self.quads, buttons: &mut self.buttons };
+        let mut top: f32 = 0.0;
+        let mut left: f32 = PADDING;
+        let mut right: f32 = PADDING;
+        let mut bottom: f32 = screen_height - PADDING;
+
+        for window in &windows {
+            // The top bars use the full width and are not padded.
+            let full_width = screen_width - PADDING * 2.0;
+            let width = match window.anchor {
+                GuiAnchor::Top => full_width,
+                _ => content_width(window).min(full_width)
+            };
+            let height = window_height(window, width);
+
+            let (x, y) = match window.anchor {
+                GuiAnchor::Top => {
+                    top += height;
+                    left = top + PADDING;
+                    right = top + PADDING;
+                    (0.0, top - height)
+                },
+                GuiAnchor::Left => {
+                    left += height + PADDING;
+                    (PADDING, left - height - PADDING)
+                },
+                GuiAnchor::Right => {
+                    right += height + PADDING;
+                    (screen_width - width - PADDING * 3.0, right - height - PADDING)
+                },
+                GuiAnchor::Bottom => {
+                    bottom -= height + PADDING;
+                    (PADDING, bottom + PADDING)
+                }
+            };
+
+            let area_width = width + PADDING * 2.0;
+            painter.window(window, x, y, width, height);
+            self.areas.push([x * scale, y * scale, area_width * scale, height * scale]);
+        }
+    }
+}
+
+/// Converts the windows into quads, the positions are in font pixels and
+/// the quads in physical pixels.
+struct GuiPainter<'a> {
+    /// The physical pixels per font pixel.
+    scale: f32,
+
+    /// Where the quads are added.
+    quads: &'a mut Vec<GuiQuad>,
+
+    /// Where the buttons are added.
+    buttons: &'a mut Vec<(String, [f32; 4])>
+}
+
+impl<'a> GuiPainter<'a> {
+    /// Adds a solid rectangle.
+    ///
+    /// # Arguments
+    ///
+    /// `x`, `y` - The top left corner.
+    /// `width`, `height` - The size.
+    /// `color` - The color of the rectangle.
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: [f32; 4]) {
+        self.quads.push(GuiQuad {
+            position: [x * self.scale, y * self.scale],
+            size: [width * self.scale, height * self.scale],
+            rect: solid_rect(),
+            color
+        });
+    }
+
+    /// Adds the glyphs of a line of text, the spaces only advance.
+    ///
+    /// # Arguments
+    ///
+    /// `x`, `y` - The top left corner of the line.
+    /// `line` - The text, it must not contain new lines.
+    /// `color` - The color of the text.
+    fn line(&mut self, x: f32, y: f32, line: &str, color: [f32; 4]) {
+        for (index, c) in line.chars().enumerate() {
+            if c == ' ' {
+                continue;
+            }
+            self.quads.push(GuiQuad {
+                position: [
+                    (x + (index as u32 * CELL_WIDTH) as f32) * self.scale,
+                    (y + 1.0) * self.scale
+                ],
+                size: [GLYPH_WIDTH as f32 * self.scale, GLYPH_HEIGHT as f32 * self.scale],
+                rect: glyph_rect(c),
+                color
+            });
+        }
+    }
+
+    /// Adds the window and its content.
+    ///
+    /// # Arguments
+    ///
+    /// `window` - The window.
+    /// `x`, `y` - The top left corner.
+    /// `width` - The width of the content.
+    /// `height` - The height of the window, including the title.
+    fn window(&mut self, window: &GuiWindow, x: f32, y: f32, width: f32, height: f32) {
+        self.rect(x, y, width + PADDING * 2.0, height, WINDOW_COLOR);
+
+        let mut y = y + PADDING;
+        if !window.title.is_empty() {
+            self.rect(x, y - PADDING, width + PADDING * 2.0, LINE_HEIGHT + PADDING, TITLE_COLOR);
+            self.line(x + PADDING, y, &window.title, TEXT_COLOR);
+            y += LINE_HEIGHT + PADDING;
+        }
+
+        let x = x + PADDING;
+        for widget in &window.widgets {
+            y += self.widget(&window.title, widget, x, y, width);
+        }
+    }
+
+    /// Adds the widget and returns its height.
+    ///
+    /// # Arguments
+    ///
+    /// `title` - The title of the window, it identifies the buttons.
+    /// `widget` - The widget.
+    /// `x`, `y` - The top left corner.
+    /// `width` - The width of the content.
+    fn widget(&mut self, title: &str, widget: &GuiWidget, x: f32, y: f32, width: f32) -> f32 {
+        match widget {
+            GuiWidget::Text(text, color) => {
+                let lines = wrap(text, columns(width));
+                for (index, line) in lines.iter().enumerate() {
+                    self.line(x, y + index as f32 * LINE_HEIGHT, line, *color);
+                }
+                lines.len() as f32 * LINE_HEIGHT
+            },
+            GuiWidget::Buttons(row) => {
+                let mut button_x = x;
+                for (label, selected) in row {
+                    let button_width = button_width(label);
+                    let color = if *selected { SELECTED_BUTTON_COLOR } else { BUTTON_COLOR };
+                    self.rect(button_x, y, button_width, LINE_HEIGHT, color);
+                    self.line(button_x + PADDING, y, label, TEXT_COLOR);
+                    self.buttons.push((
+                        format!("{}/{}", title, label),
+                        [
+                            button_x * self.scale,
+                            y * self.scale,
+                            button_width * self.scale,
+                            LINE_HEIGHT * self.scale
+                        ]
+                    ));
+                    button_x += button_width + PADDING;
+                }
+                LINE_HEIGHT + PADDING
+            },
+            GuiWidget::Plot(values, lines, color) => {
+                self.rect(x, y, width, PLOT_HEIGHT, PLOT_COLOR);
+
+                // The plot leaves some room over the highest value.
+                let max = values
+                    .iter()
+                    .chain(lines.iter().map(|(value, _)| value))
+                    .fold(0.0f32, |a, b| a.max(*b)) * 1.1;
+                if max > 0.0 && !values.is_empty() {
+                    let bar_width = width / values.len() as f32;
+                    for (index, value) in values.iter().enumerate() {
+                        let bar_height = (value / max).clamp(0.0, 1.0) * PLOT_HEIGHT;
+                        let bar_x = x + index as f32 * bar_width;
+                        self.rect(bar_x, y + PLOT_HEIGHT - bar_height, bar_width, bar_height, *color);
+                    }
+                    for (value, line_color) in lines {
+                        let line_y = y + PLOT_HEIGHT - (value / max).clamp(0.0, 1.0) * PLOT_HEIGHT;
+                        self.rect(x, line_y, width, 1.0, *line_color);
+                    }
+                }
+                PLOT_HEIGHT + PADDING
+            },
+            GuiWidget::StackedBar(segments) => {
+                self.rect(x, y, width, LINE_HEIGHT, PLOT_COLOR);
+
+                let total: f32 = segments.iter().map(|(value, _)| value.max(0.0)).sum();
+                if total > 0.0 {
+                    let mut segment_x = x;
+                    for (value, color) in segments {
+                        let segment_width = value.max(0.0) / total * width;
+                        self.rect(segment_x, y, segment_width, LINE_HEIGHT, *color);
+                        segment_x += segment_width;
+                    }
+                }
+                LINE_HEIGHT + PADDING
+            }
+        }
+    }
+}
+
+/// Returns the width of the content of the window in font pixels, the long
+/// lines are wrapped so the width is limited by `GUI_MAX_COLUMNS`.
+///
+/// # Arguments
+///
+/// `window` - The window to measure.
+fn content_width(window: &GuiWindow) -> f32 {
+    let title = (window.title.chars().count() as u32 * CELL_WIDTH) as f32;
+    let max = (GUI_MAX_COLUMNS as u32 * CELL_WIDTH) as f32;
+
+    window.widgets
+        .iter()
+        .map(|widget| match widget {
+            GuiWidget::Text(text, _) => {
+                let longest = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+                (longest as u32 * CELL_WIDTH) as f32
+            },
+            GuiWidget::Buttons(row) => {
+                row.iter().map(|(label, _)| button_width(label) + PADDING).sum::<f32>() - PADDING
+            },
+            GuiWidget::Plot(..) | GuiWidget::StackedBar(_) => PLOT_WIDTH
+        })
+        .fold(title, f32::max)
+        .min(max)
+}
+
+/// Returns the height of the window in font pixels, including the title.
+///
+/// # Arguments
+///
+/// `window` - The window to measure.
+/// `width` - The width of the content.
+fn window_height(window: &GuiWindow, width: f32) -> f32 {
+    let title = match window.title.is_empty() {
+        true => 0.0,
+        false => LINE_HEIGHT + PADDING
+    };
+
+    let content: f32 = window.widgets
+        .iter()
+        .map(|widget| match widget {
+            GuiWidget::Text(text, _) => wrap(text, columns(width)).len() as f32 * LINE_HEIGHT,
+            GuiWidget::Buttons(_) | GuiWidget::StackedBar(_) => LINE_HEIGHT + PADDING,
+            GuiWidget::Plot(..) => PLOT_HEIGHT + PADDING
+        })
+        .sum();
+
+    title + content + PADDING * 2.0
+}
+
+/// Returns the width of the button in font pixels.
+///
+/// # Arguments
+///
+/// `label` - The text of the button.
+fn button_width(label: &str) -> f32 {
+    (label.chars().count() as u32 * CELL_WIDTH) as f32 + PADDING * 2.0
+}
+
+/// Returns the number of characters which fit in the width.
+///
+/// # Arguments
+///
+/// `width` - The width in font pixels.
+fn columns(width: f32) -> usize {
+    ((width / CELL_WIDTH as f32) as usize).max(1)
+}
+
+/// Splits the text in lines of at most `columns` characters, the new lines
+/// always start a new line.
+///
+/// # Arguments
+///
+/// `text` - The text to be wrapped.
+/// `columns` - The maximum number of characters per line.
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        for chunk in chars.chunks(columns) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines
+}
+
+/// Returns true if the point is inside the area.
+///
+/// # Arguments
+///
+/// `area` - The area, x, y, width and height.
+/// `point` - The point.
+fn contains(area: &[f32; 4], point: [f32; 2]) -> bool {
+    point[0] >= area[0] && point[0] < area[0] + area[2] &&
+    point[1] >= area[1] && point[1] < area[1] + area[3]
+}
+
+/// Draws the top bar, it contains a button per panel which shows or hides
+/// it and the render stats of the frame.
+pub fn top_bar_renderer_system(
+    dev_gui: UniqueWrite<DevGui>,
+    stats: UniqueRead<RenderStats>) {
+    let mut dev_gui_write = dev_gui.write();
+    let mut bar = dev_gui_write.window("", GuiAnchor::Top);
+
+    for (name, visible) in dev_gui_write.panels() {
+        if bar.selectable(name, visible) {
+            dev_gui_write.toggle(name);
+        }
+    }
+    bar.text(&stats.read().overlay_text(), TEXT_COLOR);
+
+    dev_gui_write.show(bar);
+}
+
+/// Places the windows added during the frame, they are drawn by the next
+/// render. The mouse is claimed while the cursor is over a window, so the
+/// clicks on the GUI do not reach the game.
+pub fn layout_dev_gui_system(
+    dev_gui: UniqueWrite<DevGui>,
+    screen: UniqueRead<GuiScreen>,
+    mouse: UniqueRead<MousePosition>,
+    capture: UniqueWrite<GuiInputCapture>) {
+    let mut dev_gui_write = dev_gui.write();
+    dev_gui_write.layout(&screen.read());
+    capture.write().pointer = dev_gui_write.is_over(mouse.read().physical);
+}
+
+#[test]
+fn dev_gui_layout_is_stable() {
+    use types::Size;
+
+    let screen = GuiScreen { physical_size: Size::new(1000, 800), scale_factor: 1.0 };
+    let mut first = DevGui::default();
+    let mut second = DevGui::default();
+
+    let windows: Vec<GuiWindow> = ["B", "A"]
+        .iter()
+        .map(|title| {
+            let mut window = first.window(title, GuiAnchor::Left);
+            window.text("Hello\nWorld", TEXT_COLOR);
+            window
+        })
+        .collect();
+    let mut bar = first.window("", GuiAnchor::Top);
+    bar.text("Bar", TEXT_COLOR);
+
+    // The order the windows are added does not change the layout.
+    first.show(bar.clone());
+    windows.iter().for_each(|w| first.show(w.clone()));
+    windows.iter().rev().for_each(|w| second.show(w.clone()));
+    second.show(bar);
+    first.layout(&screen);
+    second.layout(&screen);
+    assert_eq!(first.quads(), second.quads());
+
+    // The bar uses the full width and the columns start below it.
+    assert_eq!(first.areas[0][2], 1000.0);
+    assert_eq!(first.areas[1][1], first.areas[0][3] + PADDING * GUI_FONT_SCALE);
+    assert!(first.areas[2][1] > first.areas[1][1]);
+
+    // The windows are cleared after the layout.
+    first.layout(&screen);
+    assert!(first.quads().is_empty());
+}
+
+#[test]
+fn dev_gui_buttons_are_clicked() {
+    use types::Size;
+
+    let screen = GuiScreen { physical_size: Size::new(800, 600), scale_factor: 1.0 };
+    let mut dev_gui = DevGui::default();
+    dev_gui.add_panel("Console");
+
+    let mut window = dev_gui.window("Tools", GuiAnchor::Left);
+    assert!(!window.button("Clear"));
+    dev_gui.show(window);
+    dev_gui.layout(&screen);
+
+    let (_, area) = dev_gui.buttons[0].clone();
+    let inside = ((area[0] + 1.0) as f64, (area[1] + 1.0) as f64);
+    assert!(dev_gui.is_over(inside));
+    assert!(!dev_gui.is_over((790.0, 590.0)));
+
+    // Only the left button clicks the buttons.
+    dev_gui.process_event(&InputEvent::MouseDown(MouseButton::Right), inside);
+    assert!(!dev_gui.window("Tools", GuiAnchor::Left).button("Clear"));
+    dev_gui.process_event(&InputEvent::MouseDown(MouseButton::Left), inside);
+    assert!(dev_gui.window("Tools", GuiAnchor::Left).button("Clear"));
+    assert!(!dev_gui.window("Other", GuiAnchor::Left).button("Clear"));
+
+    // The panels are toggled by name.
+    assert!(!dev_gui.is_visible("Console"));
+    dev_gui.toggle("Console");
+    assert!(dev_gui.is_visible("Console"));
+}
+
+#[test]
+fn dev_gui_wraps_long_lines() {
+    assert_eq!(wrap("abcde\n\nfg", 2), vec!["ab", "cd", "e", "", "fg"]);
+
+    let mut window = DevGui::default().window("Long", GuiAnchor::Left);
+    window.text(&"x".repeat(GUI_MAX_COLUMNS * 2), TEXT_COLOR);
+    let width = content_width(&window);
+    assert_eq!(width, (GUI_MAX_COLUMNS as u32 * CELL_WIDTH) as f32);
+    assert_eq!(window_height(&window, width), LINE_HEIGHT * 3.0 + PADDING * 3.0);
+}
@@ -0,0 +1,24 @@
+use ecs::{DefaultWorld, ComponentHandler, UniqueWrite};
+
+/// Contains a flag defining if the application must finish, it is checked
+/// by the main loop at the end of each frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExitRequested(pub bool);
+
+/// Asks the engine to finish the application.
+///
+/// The current frame is completed, then the exit callback is called and the
+/// world is dropped (stopping the worker threads) before the process ends.
+/// It must be used instead of `std::process::exit` which skips all that.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the exit flag.
+pub fn request_exit(world: &DefaultWorld) {
+    request_exit_system(world.get_unique_mut::<ExitRequested>());
+}
+
+/// Asks the engine to finish the application, see `request_exit`.
+pub fn request_exit_system(exit: UniqueWrite<ExitRequested>) {
+    exit.write().0 = true;
+}
@@ -0,0 +1,228 @@
+use arboard::Clipboard;
+
+use ecs::{UniqueRead, UniqueWrite};
+use log::warning;
+
+use crate::{
+    basics::dev_gui::{DevGui, GuiAnchor, TEXT_COLOR},
+    graphics::render_stats::RenderStats,
+    scene::time::Time
+};
+
+/// The name of the frame history panel in the dev GUI.
+pub const FRAME_HISTORY_PANEL: &str = "Frames";
+
+/// The number of frames kept in the history.
+pub const FRAME_HISTORY_LENGTH: usize = 240;
+
+/// The colors of the charts and the percentile lines.
+const CPU_COLOR: [f32; 4] = [0.4, 0.8, 0.4, 1.0];
+const GPU_COLOR: [f32; 4] = [0.8, 0.5, 0.9, 1.0];
+const P95_COLOR: [f32; 4] = [1.0, 0.8, 0.0, 1.0];
+const P99_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+
+/// Contains the information recorded for a single frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameSample {
+    /// The CPU time of the frame in milliseconds.
+    pub cpu_ms: f32,
+
+    /// The GPU time of the profiled passes in milliseconds, None if the GPU
+    /// profiling is disabled. The GPU times are read back late so they
+    /// usually belong to the previous frame.
+    pub gpu_ms: Option<f32>,
+
+    /// The number of instances drawn.
+    pub instances: u32
+}
+
+/// Contains the last `FRAME_HISTORY_LENGTH` frames, it is used to find the
+/// hitches that the instantaneous stats hide.
+///
+/// The frames are stored in a fixed ring buffer, nothing is allocated when
+/// a frame is added.
+pub struct FrameHistory {
+    /// The frames, `next` points to the oldest one once the buffer is full.
+    samples: [FrameSample; FRAME_HISTORY_LENGTH],
+
+    /// The position where the next frame is stored.
+    next: usize,
+
+    /// The number of frames stored.
+    len: usize
+}
+
+impl Default for FrameHistory {
+    /// Creates and returns an empty `FrameHistory`.
+    fn default() -> Self {
+        Self {
+            samples: [FrameSample::default(); FRAME_HISTORY_LENGTH],
+            next: 0,
+            len: 0
+        }
+    }
+}
+
+impl FrameHistory {
+    /// Adds a frame, the oldest one is replaced when the history is full.
+    ///
+    /// # Arguments
+    ///
+    /// `sample` - The information of the frame.
+    pub fn push(&mut self, sample: FrameSample) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % FRAME_HISTORY_LENGTH;
+        self.len = (self.len + 1).min(FRAME_HISTORY_LENGTH);
+    }
+
+    /// Returns the number of frames stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no frame was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the frames from the oldest to the newest.
+    pub fn samples(&self) -> impl Iterator<Item = &FrameSample> {
+        let start = (self.next + FRAME_HISTORY_LENGTH - self.len) % FRAME_HISTORY_LENGTH;
+        (0..self.len).map(move |i| &self.samples[(start + i) % FRAME_HISTORY_LENGTH])
+    }
+
+    /// Returns the average CPU time in milliseconds, None if no frame was
+    /// recorded.
+    pub fn average(&self) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.samples().map(|s| s.cpu_ms).sum::<f32>() / self.len as f32)
+    }
+
+    /// Returns the CPU time in milliseconds which is not exceeded by the
+    /// provided percentage of the frames, None if no frame was recorded.
+    ///
+    /// # Arguments
+    ///
+    /// `p` - The percentile, in the range [0, 100].
+    pub fn percentile(&self, p: f32) -> Option<f32> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // The times are sorted in a copy on the stack.
+        let mut times = [0.0f32; FRAME_HISTORY_LENGTH];
+        for (time, sample) in times.iter_mut().zip(self.samples()) {
+            *time = sample.cpu_ms;
+        }
+        let times = &mut times[..self.len];
+        times.sort_unstable_by(|a, b| a.total_cmp(b));
+
+        // Nearest rank, the 0 percentile is the fastest frame.
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * self.len as f32).ceil() as usize;
+        Some(times[rank.max(1) - 1])
+    }
+
+    /// Returns the slowest CPU time in milliseconds, None if no frame was
+    /// recorded.
+    pub fn worst(&self) -> Option<f32> {
+        self.samples().map(|s| s.cpu_ms).reduce(f32::max)
+    }
+
+    /// Returns the frames as CSV from the oldest to the newest, the GPU
+    /// column is empty when the GPU time is not available.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("cpu_ms,gpu_ms,instances\n");
+        for sample in self.samples() {
+            let gpu = sample.gpu_ms.map(|t| t.to_string()).unwrap_or_default();
+            csv.push_str(&format!("{},{},{}\n", sample.cpu_ms, gpu, sample.instances));
+        }
+        csv
+    }
+}
+
+/// Adds the current frame to the history, it must run once per frame after
+/// the renderers.
+pub fn update_frame_history_system(
+    time: UniqueRead<Time>,
+    stats: UniqueRead<RenderStats>,
+    history: UniqueWrite<FrameHistory>) {
+    let stats_read = stats.read();
+    history.write().push(FrameSample {
+        cpu_ms: time.read().delta_seconds * 1000.0,
+        gpu_ms: stats_read.gpu_time(),
+        instances: stats_read.instances()
+    });
+}
+
+/// Draws the CPU and GPU times of the last frames with the 95th and 99th
+/// percentiles as lines, the window is only drawn when the frames panel is
+/// visible. The history can be copied to the clipboard as CSV.
+pub fn frame_history_overlay_system(
+    dev_gui: UniqueWrite<DevGui>,
+    history: UniqueRead<FrameHistory>) {
+    let mut dev_gui_write = dev_gui.write();
+    if !dev_gui_write.is_visible(FRAME_HISTORY_PANEL) {
+        return;
+    }
+
+    let history_read = history.read();
+    let mut window = dev_gui_write.window(FRAME_HISTORY_PANEL, GuiAnchor::Left);
+
+    if window.button("Copy CSV") {
+        let copied = Clipboard::new().and_then(|mut c| c.set_text(history_read.to_csv()));
+        if let Err(e) = copied {
+            warning(&format!("{{FrameHistory}} The CSV could not be copied: {}", e));
+        }
+    }
+
+    let p95 = history_read.percentile(95.0).unwrap_or(0.0);
+    let p99 = history_read.percentile(99.0).unwrap_or(0.0);
+    window.text(
+        &format!(
+            "CPU avg {:.2} | p95 {:.2} | p99 {:.2} | worst {:.2} ms",
+            history_read.average().unwrap_or(0.0),
+            p95,
+            p99,
+            history_read.worst().unwrap_or(0.0)
+        ),
+        TEXT_COLOR
+    );
+    window.plot(
+        history_read.samples().map(|s| s.cpu_ms).collect(),
+        vec![(p95, P95_COLOR), (p99, P99_COLOR)],
+        CPU_COLOR
+    );
+
+    // The GPU chart is only drawn when the GPU profiling is enabled.
+    let gpu: Vec<f32> = history_read.samples().filter_map(|s| s.gpu_ms).collect();
+    if let Some(last) = gpu.last() {
+        window.text(&format!("GPU {:.2} ms", last), TEXT_COLOR);
+        window.plot(gpu, Vec::new(), GPU_COLOR);
+    }
+
+    dev_gui_write.show(window);
+}
+
+#[test]
+fn frame_history_statistics() {
+    let mut history = FrameHistory::default();
+    assert_eq!(history.average(), None);
+    assert_eq!(history.percentile(99.0), None);
+
+    // Two full laps, only the last 240 frames (61..=300) are kept.
+    for i in 1..=300 {
+        history.push(FrameSample { cpu_ms: i as f32, gpu_ms: None, instances: 0 });
+    }
+
+    assert_eq!(history.len(), FRAME_HISTORY_LENGTH);
+    assert_eq!(history.samples().next().map(|s| s.cpu_ms), Some(61.0));
+    assert_eq!(history.worst(), Some(300.0));
+    assert_eq!(history.average(), Some(180.5));
+    assert_eq!(history.percentile(0.0), Some(61.0));
+    assert_eq!(history.percentile(50.0), Some(180.0));
+    assert_eq!(history.percentile(95.0), Some(288.0));
+    assert_eq!(history.percentile(100.0), Some(300.0));
+    assert!(history.to_csv().starts_with("cpu_ms,gpu_ms,instances\n61,,0\n"));
+}
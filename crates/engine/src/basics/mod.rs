@@ -1 +1,5 @@
-pub mod window;
\ No newline at end of file
+pub mod dev_gui;
+pub mod exit;
+pub mod frame_history;
+pub mod profiler;
+pub mod window;
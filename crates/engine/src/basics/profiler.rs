@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::Duration
+};
+
+use ecs::{UniqueRead, UniqueWrite};
+
+use crate::basics::dev_gui::{DevGui, GuiAnchor, TEXT_COLOR};
+
+/// The name of the profiler panel in the dev GUI.
+pub const PROFILER_PANEL: &str = "Profiler";
+
+/// The number of frames kept for each scope.
+pub const PROFILER_HISTORY: usize = 64;
+
+/// The scopes recorded for each workload, in the order they run.
+pub const WORKLOAD_SCOPES: [&str; 5] = ["Start", "Synchronize", "Render", "Commit", "End"];
+
+/// The color of each workload in the frame breakdown.
+const WORKLOAD_COLORS: [[f32; 4]; 5] = [
+    [0.3, 0.7, 0.3, 1.0],
+    [0.3, 0.5, 0.9, 1.0],
+    [0.9, 0.5, 0.2, 1.0],
+    [0.8, 0.3, 0.8, 1.0],
+    [0.8, 0.8, 0.3, 1.0]
+];
+
+/// The color of the charts of the scopes.
+const SCOPE_COLOR: [f32; 4] = [0.4, 0.7, 0.9, 1.0];
+
+/// Measures the time taken by a block and records it in the profiler, it
+/// returns the value of the block.
+///
+/// # Arguments
+///
+/// `profiler` - The `Profiler`, it is evaluated after the block.
+/// `name` - The name of the scope.
+/// `body` - The block to be measured.
+#[macro_export]
+macro_rules! profile_scope {
+    ($profiler:expr, $name:expr, $body:block) => {{
+        let start = std::time::Instant::now();
+        let result = $body;
+        $profiler.record($name, start.elapsed());
+        result
+    }};
+}
+
+/// Contains the time taken by the profiled scopes during the last frames,
+/// the workloads are always profiled and the systems can add their own
+/// scopes using `profile_scope!`.
+#[derive(Default)]
+pub struct Profiler {
+    /// The times of each scope in milliseconds, from the oldest to the
+    /// newest.
+    timings: Mutex<HashMap<&'static str, Vec<f32>>>
+}
+
+impl Profiler {
+    /// Adds a time to the scope, the oldest time is discarded when the
+    /// scope already contains `PROFILER_HISTORY` times.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the scope.
+    /// `duration` - The time taken by the scope.
+    pub fn record(&self, name: &'static str, duration: Duration) {
+        let mut timings = self.timings.lock().unwrap();
+        let times = timings.entry(name).or_default();
+        if times.len() == PROFILER_HISTORY {
+            times.remove(0);
+        }
+        times.push(duration.as_secs_f32() * 1000.0);
+    }
+
+    /// Returns the times of the scope in milliseconds, from the oldest to
+    /// the newest.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the scope.
+    pub fn times(&self, name: &str) -> Vec<f32> {
+        self.timings.lock().unwrap().get(name).cloned().unwrap_or_default()
+    }
+
+    /// Returns the average time of the scope in milliseconds, None if it
+    /// was never recorded.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the scope.
+    pub fn average(&self, name: &str) -> Option<f32> {
+        let times = self.times(name);
+        if times.is_empty() {
+            return None;
+        }
+        Some(times.iter().sum::<f32>() / times.len() as f32)
+    }
+
+    /// Returns the names of all the recorded scopes sorted alphabetically.
+    pub fn scopes(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self.timings.lock().unwrap().keys().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the time taken by each workload during the last frame, in the
+    /// order they run, it is used to draw the frame breakdown.
+    pub fn workload_breakdown(&self) -> Vec<(&'static str, f32)> {
+        let timings = self.timings.lock().unwrap();
+        WORKLOAD_SCOPES
+            .iter()
+            .map(|name| {
+                let last = timings.get(name).and_then(|t| t.last()).copied();
+                (*name, last.unwrap_or(0.0))
+            })
+            .collect()
+    }
+}
+
+/// Draws the workloads of the last frame as a stacked bar and a chart per
+/// scope, the window is only drawn when the profiler panel is visible.
+pub fn profiler_panel_system(
+    dev_gui: UniqueWrite<DevGui>,
+    profiler: UniqueRead<Profiler>) {
+    let mut dev_gui_write = dev_gui.write();
+    if !dev_gui_write.is_visible(PROFILER_PANEL) {
+        return;
+    }
+
+    let profiler_read = profiler.read();
+    let mut window = dev_gui_write.window(PROFILER_PANEL, GuiAnchor::Left);
+
+    let breakdown = profiler_read.workload_breakdown();
+    window.stacked_bar(
+        breakdown
+            .iter()
+            .zip(WORKLOAD_COLORS.iter())
+            .map(|((_, time), color)| (*time, *color))
+            .collect()
+    );
+    for ((name, time), color) in breakdown.iter().zip(WORKLOAD_COLORS.iter()) {
+        window.text(&format!("{}: {:.2} ms", name, time), *color);
+    }
+
+    for name in profiler_read.scopes() {
+        let average = profiler_read.average(name).unwrap_or(0.0);
+        window.text(&format!("{} (avg {:.2} ms)", name, average), TEXT_COLOR);
+        window.plot(profiler_read.times(name), Vec::new(), SCOPE_COLOR);
+    }
+
+    dev_gui_write.show(window);
+}
+
+#[test]
+fn profiler_keeps_last_frames() {
+    let profiler = Profiler::default();
+    for i in 0..(PROFILER_HISTORY + 4) {
+        profiler.record("Render", Duration::from_millis(i as u64));
+    }
+
+    let times = profiler.times("Render");
+    assert_eq!(times.len(), PROFILER_HISTORY);
+    assert_eq!(times[0], 4.0);
+
+    let value = profile_scope!(profiler, "Start", { 1 + 1 });
+    assert_eq!(value, 2);
+    assert_eq!(profiler.scopes(), vec!["Render", "Start"]);
+    assert_eq!(profiler.workload_breakdown()[2], ("Render", (PROFILER_HISTORY + 3) as f32));
+}
@@ -1,24 +1,110 @@
+use std::time::{Duration, Instant};
 
 use winit::{
+    dpi::{LogicalSize, PhysicalSize},
     event_loop::{EventLoop},
-    window::WindowBuilder
+    window::{WindowBuilder, Fullscreen}
 };
 
 use types::Size;
+use ecs::{UniqueRead, UniqueWrite};
+use log::{info, error};
 
 use crate::{
-    helpers::errors::InitError
+    helpers::errors::InitError,
+    graphics::{
+        gpu::Gpu,
+        texture::{TextureGenerator, DepthTexture, HdrTexture},
+        pipelines::{
+            tone_mapping_pipeline::{ToneMapping, ToneMappingPipeline},
+            pipeline_cache::PipelineCache
+        }
+    },
+    scene::camera::Camera
 };
 
 pub enum CustomEvent {
     RequestRedraw
 }
 
+/// Represents the ways the window can be presented.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FullscreenMode {
+    /// The window takes the monitor changing its video mode.
+    Exclusive,
+
+    /// The window covers the monitor without changing its video mode.
+    Borderless,
+
+    /// The window is a regular window.
+    Windowed
+}
+
+/// Contains a flag defining if the cursor is locked inside the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CursorLocked(pub bool);
+
+/// Contains the state of the window reported by the platform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindowState {
+    /// Defines if the window has the focus.
+    pub focused: bool,
+
+    /// Defines if the application is suspended, on mobile platforms the
+    /// surface could be invalid while it is suspended.
+    pub suspended: bool
+}
+
+impl Default for WindowState {
+    /// Creates and returns a new `WindowState`, the window starts with the
+    /// focus and running.
+    fn default() -> Self {
+        Self {
+            focused: true,
+            suspended: false
+        }
+    }
+}
+
+impl WindowState {
+    /// Returns the moment when the next frame should be rendered, None if
+    /// no frame should be rendered.
+    ///
+    /// # Arguments
+    ///
+    /// `background_fps` - The frame rate used while the window does not
+    /// have the focus, if it is not present the rate is not limited.
+    /// `last_frame` - The moment when the last frame was rendered.
+    pub fn next_frame_time(&self,
+                           background_fps: Option<u32>,
+                           last_frame: Instant) -> Option<Instant> {
+        if self.suspended {
+            return None;
+        }
+
+        match background_fps {
+            Some(fps) if !self.focused && fps > 0 => {
+                Some(last_frame + Duration::from_secs_f64(1.0 / fps as f64))
+            },
+            _ => Some(last_frame)
+        }
+    }
+}
+
 /// Represents the window which contains the necessary information to render
 /// over the user's screen.
 pub struct Window {
-    /// The size of the screen in user screen space.
-    pub size: Size<u32>,
+    /// The size of the window in logical pixels, it is used by the UI
+    /// layout.
+    pub logical_size: Size<u32>,
+
+    /// The size of the window in physical pixels, it is used by all the
+    /// gpu resources (swap chain, depth texture...).
+    pub physical_size: Size<u32>,
+
+    /// The number of physical pixels per logical pixel, it changes when the
+    /// window is moved to a monitor with a different density.
+    pub scale_factor: f64,
 
     /// The window provided by winit.
     pub native_window: winit::window::Window,
@@ -30,7 +116,7 @@ impl Window {
     /// # Arguments
     ///
     /// `title` - The window title.
-    /// `size` - The window initial size.
+    /// `size` - The window initial size in logical pixels.
     pub fn new(title: &str, size: Size<u32>) 
         -> Result<(Self, EventLoop<CustomEvent>), InitError> {
         // Create the event loop.
@@ -39,12 +125,325 @@ impl Window {
         // Create the new window.
         let native_window = match WindowBuilder::new()
             .with_title(title)
+            .with_inner_size(LogicalSize::new(size.width, size.height))
             .build(&event_loop) {
             Ok(w) => w,
             Err(_) => return Err(InitError::Window)
         };
 
+        let mut window = Window {
+            logical_size: size,
+            physical_size: size,
+            scale_factor: 1.0,
+            native_window
+        };
+        // The real size depends on the monitor scale factor.
+        window.refresh_size();
+
         // Return the result window.
-        Ok((Window { size, native_window }, event_loop))
+        Ok((window, event_loop))
+    }
+
+    /// Updates the sizes using the current size and scale factor of the
+    /// native window.
+    pub fn refresh_size(&mut self) {
+        let physical: PhysicalSize<u32> = self.native_window.inner_size();
+        let scale_factor: f64 = self.native_window.scale_factor();
+        self.update_size(physical, scale_factor);
+    }
+
+    /// Updates the logical and physical sizes, it must be called when the
+    /// window is resized or the scale factor changes.
+    ///
+    /// # Arguments
+    ///
+    /// `physical` - The new size in physical pixels.
+    /// `scale_factor` - The new number of physical pixels per logical pixel.
+    pub fn update_size(&mut self, physical: PhysicalSize<u32>, scale_factor: f64) {
+        let logical: LogicalSize<u32> = physical.to_logical(scale_factor);
+
+        self.physical_size = Size::new(physical.width, physical.height);
+        self.logical_size = Size::new(logical.width, logical.height);
+        self.scale_factor = scale_factor;
+    }
+
+    /// Returns the aspect of the window using the physical size.
+    pub fn aspect(&self) -> f32 {
+        aspect_ratio(&self.physical_size)
+    }
+
+    /// Changes the way the window is presented.
+    ///
+    /// If the exclusive mode is requested and the monitor does not provide
+    /// any video mode the borderless mode is used.
+    ///
+    /// # Arguments
+    ///
+    /// `mode` - The new mode.
+    pub fn set_fullscreen(&self, mode: FullscreenMode) {
+        let fullscreen: Option<Fullscreen> = match mode {
+            FullscreenMode::Exclusive => {
+                // Use the biggest video mode of the current monitor.
+                let video_mode = self.native_window
+                    .current_monitor()
+                    .and_then(|m| m.video_modes().max_by_key(|v| {
+                        (v.size().width * v.size().height, v.refresh_rate())
+                    }));
+                match video_mode {
+                    Some(v) => Some(Fullscreen::Exclusive(v)),
+                    None => Some(Fullscreen::Borderless(None))
+                }
+            },
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(None)),
+            FullscreenMode::Windowed => None
+        };
+
+        self.native_window.set_fullscreen(fullscreen);
+    }
+
+    /// Confines and hides the cursor inside the window, it returns true if
+    /// the cursor was locked.
+    ///
+    /// The mouse motion is still received using the device events.
+    pub fn lock_cursor(&self) -> bool {
+        if let Err(e) = self.native_window.set_cursor_grab(true) {
+            error(&format!("The cursor could not be locked: {}", e));
+            return false;
+        }
+
+        self.native_window.set_cursor_visible(false);
+        true
+    }
+
+    /// Releases and shows the cursor.
+    pub fn unlock_cursor(&self) {
+        if let Err(e) = self.native_window.set_cursor_grab(false) {
+            error(&format!("The cursor could not be unlocked: {}", e));
+        }
+
+        self.native_window.set_cursor_visible(true);
+    }
+
+    /// Returns the current mode of the window.
+    pub fn fullscreen_mode(&self) -> FullscreenMode {
+        match self.native_window.fullscreen() {
+            Some(Fullscreen::Exclusive(_)) => FullscreenMode::Exclusive,
+            Some(Fullscreen::Borderless(_)) => FullscreenMode::Borderless,
+            None => FullscreenMode::Windowed
+        }
+    }
+}
+
+/// Contains the last size received from the resize events.
+///
+/// Dragging the corner of the window generates a lot of resize events, so
+/// the size is stored here and the gpu resources are recreated once per
+/// frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PendingResize(pub Option<Size<u32>>);
+
+impl PendingResize {
+    /// Stores the new size, it replaces any previous pending size.
+    ///
+    /// # Arguments
+    ///
+    /// `size` - The new size in physical pixels.
+    pub fn request(&mut self, size: Size<u32>) {
+        self.0 = Some(size);
+    }
+
+    /// Removes and returns the pending size if it is different from the
+    /// current one.
+    ///
+    /// # Arguments
+    ///
+    /// `current` - The size of the current gpu resources.
+    pub fn take_changed(&mut self, current: Size<u32>) -> Option<Size<u32>> {
+        match self.0.take() {
+            Some(size) if size != current => Some(size),
+            _ => None
+        }
+    }
+}
+
+/// Contains the screen used by the dev GUI, the windows are placed using the
+/// physical size and scaled by the scale factor so both must be updated when
+/// the window is resized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GuiScreen {
+    /// The size of the swap chain in physical pixels.
+    pub physical_size: Size<u32>,
+
+    /// The number of physical pixels per logical pixel.
+    pub scale_factor: f64
+}
+
+/// Returns the width divided by the height, a zero height (minimized window)
+/// returns 1.
+///
+/// # Arguments
+///
+/// `size` - The size used to calculate the aspect.
+fn aspect_ratio(size: &Size<u32>) -> f32 {
+    if size.height == 0 {
+        return 1.0;
     }
-}
\ No newline at end of file
+
+    size.width as f32 / size.height as f32
+}
+
+/// Recreates all the resources which depend on the size of the window, the
+/// swap chain, the depth texture and the HDR texture, and updates the
+/// aspect of the camera.
+///
+/// A minimized window has a zero size, in that case nothing is recreated.
+pub fn update_window_with_new_size_system(
+    window: UniqueRead<Window>,
+    gpu: UniqueWrite<Gpu>,
+    depth_texture: UniqueWrite<DepthTexture>,
+    hdr_texture: UniqueWrite<HdrTexture>,
+    tone_mapping: UniqueWrite<ToneMapping>,
+    pipeline_cache: UniqueRead<PipelineCache>,
+    camera: UniqueWrite<Camera>) {
+    let window_read = window.read();
+    let size = &window_read.physical_size;
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+
+    info(&format!(
+        "Resizing to {}x{} (scale factor {})",
+        size.width,
+        size.height,
+        window_read.scale_factor
+    ));
+
+    let mut gpu_write = gpu.write();
+    gpu_write.resize(Size::new(size.width, size.height));
+
+    depth_texture.write().0 = gpu_write.create_depth_texture();
+
+    // The tone mapping samples the HDR texture, so it must be recreated
+    // along with it.
+    let mut hdr_write = hdr_texture.write();
+    if hdr_write.0.is_some() {
+        let texture = gpu_write.create_hdr_texture();
+        tone_mapping.write().0 = Some(ToneMappingPipeline::new(
+            &gpu_write,
+            &pipeline_cache.read(),
+            &texture
+        ));
+        hdr_write.0 = Some(texture);
+    }
+
+    camera.write().aspect = window_read.aspect();
+}
+
+/// Updates the screen used by the GUI with the new size of the window, it
+/// runs along with `update_window_with_new_size_system`.
+///
+/// # Arguments
+///
+/// `physical_size` - The new size in physical pixels.
+/// `scale_factor` - The scale factor of the window.
+/// `screen` - The screen used by the GUI renderer.
+pub fn update_gui_with_new_size_system(
+    physical_size: Size<u32>,
+    scale_factor: f64,
+    screen: UniqueWrite<GuiScreen>) {
+    screen.set(GuiScreen { physical_size, scale_factor });
+}
+
+/// Switches the window between the borderless and the windowed modes.
+pub fn toggle_fullscreen_system(window: UniqueRead<Window>) {
+    let window = window.read();
+    match window.fullscreen_mode() {
+        FullscreenMode::Windowed => window.set_fullscreen(FullscreenMode::Borderless),
+        _ => window.set_fullscreen(FullscreenMode::Windowed)
+    }
+}
+
+/// Locks the cursor inside the window and updates the cursor state.
+pub fn lock_cursor_system(
+    window: UniqueRead<Window>,
+    locked: UniqueWrite<CursorLocked>) {
+    locked.write().0 = window.read().lock_cursor();
+}
+
+/// Unlocks the cursor and updates the cursor state.
+pub fn unlock_cursor_system(
+    window: UniqueRead<Window>,
+    locked: UniqueWrite<CursorLocked>) {
+    window.read().unlock_cursor();
+    locked.write().0 = false;
+}
+
+#[test]
+fn resize_burst_is_coalesced() {
+    let current = Size::new(800, 600);
+    let mut pending = PendingResize::default();
+    let mut rebuilds = 0;
+
+    // A burst of events during a single frame.
+    for width in 801..900 {
+        pending.request(Size::new(width, 600));
+    }
+    if pending.take_changed(current).is_some() {
+        rebuilds += 1;
+    }
+    // Nothing else is pending in the next frame.
+    if pending.take_changed(current).is_some() {
+        rebuilds += 1;
+    }
+    assert_eq!(rebuilds, 1);
+
+    // The duplicated events do not rebuild anything.
+    pending.request(current);
+    assert_eq!(pending.take_changed(current), None);
+}
+
+#[test]
+fn aspect_ratio_of_minimized_window() {
+    assert_eq!(aspect_ratio(&Size::new(1600, 800)), 2.0);
+    assert_eq!(aspect_ratio(&Size::new(1600, 0)), 1.0);
+}
+
+#[test]
+fn next_frame_time() {
+    let last_frame = Instant::now();
+    let mut state = WindowState::default();
+
+    // The focused window renders as fast as possible.
+    assert_eq!(state.next_frame_time(Some(10), last_frame), Some(last_frame));
+
+    state.focused = false;
+    assert_eq!(
+        state.next_frame_time(Some(10), last_frame),
+        Some(last_frame + Duration::from_millis(100))
+    );
+    assert_eq!(state.next_frame_time(None, last_frame), Some(last_frame));
+
+    state.suspended = true;
+    assert_eq!(state.next_frame_time(Some(10), last_frame), None);
+}
+
+#[test]
+fn resize_updates_gui_screen() {
+    use ecs::{DefaultWorld, ComponentHandler};
+
+    let world = DefaultWorld::default();
+    world.register_unique(GuiScreen {
+        physical_size: Size::new(800, 600),
+        scale_factor: 1.0
+    });
+
+    update_gui_with_new_size_system(
+        Size::new(2560, 1440),
+        2.0,
+        world.get_unique_mut::<GuiScreen>()
+    );
+
+    let screen = **world.get_unique::<GuiScreen>().read();
+    assert_eq!(screen.physical_size, Size::new(2560, 1440));
+    assert_eq!(screen.scale_factor, 2.0);
+}
@@ -1,5 +1,7 @@
+use std::sync::Arc;
+
 use bytemuck::{Pod, Zeroable};
-use wgpu::Buffer;
+use wgpu::{Buffer, BufferUsage};
 
 use types::Bytes;
 
@@ -30,6 +32,10 @@ pub trait BufferCreator {
     /// This should only receive and hold data, could not be used as source.
     fn create_vertex_with_size(&self, size: u64) -> Buffer;
 
+    /// Should create a new storage buffer with the size provided, the extra
+    /// usages allow to use it also as vertex or indirect buffer.
+    fn create_storage_with_size(&self, size: u64, usage: BufferUsage) -> Buffer;
+
     /// Should create and return the created index buffer.
     fn create_index<T: RawBufferRepresentable>(&self, data: T) -> Buffer;
     
@@ -39,6 +45,7 @@ pub trait BufferCreator {
 
 /// Represents an instance that could manipulate GPU buffers.
 pub trait BufferManipulator {
-    /// Should copy the data to the buffer provided.
-    fn copy_to_buffer(&self, buffer: &Buffer, data: &[u8]);
+    /// Should copy the data to the buffer provided, the buffer is shared
+    /// because the copy could happen after the call returns.
+    fn copy_to_buffer(&self, buffer: &Arc<Buffer>, data: &[u8]);
 }
@@ -0,0 +1,212 @@
+/// The width of a glyph in pixels.
+pub const GLYPH_WIDTH: u32 = 5;
+
+/// The height of a glyph in pixels.
+pub const GLYPH_HEIGHT: u32 = 7;
+
+/// The size of the cell used by each glyph in the atlas, it leaves one pixel
+/// of padding in order to avoid sampling the neighbour glyphs.
+pub const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+pub const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 1;
+
+/// The number of glyphs per row in the atlas.
+const ATLAS_COLUMNS: u32 = 16;
+
+/// The first and last characters stored in the atlas, the printable ASCII.
+const FIRST_CHAR: u32 = 32;
+const LAST_CHAR: u32 = 126;
+
+/// The position of the solid cell in the atlas, it is the first cell after
+/// the printable characters.
+const SOLID_CELL: u32 = LAST_CHAR - FIRST_CHAR + 1;
+
+/// The size of the atlas in pixels.
+pub const ATLAS_WIDTH: u32 = ATLAS_COLUMNS * CELL_WIDTH;
+pub const ATLAS_HEIGHT: u32 = ((LAST_CHAR - FIRST_CHAR) / ATLAS_COLUMNS + 1) * CELL_HEIGHT;
+
+/// Returns the rows of the glyph, the most significant of the 5 bits is the
+/// leftmost pixel.
+///
+/// The lowercase letters use the uppercase glyphs and the characters which
+/// are not supported use the question mark.
+///
+/// # Arguments
+///
+/// `c` - The character.
+fn glyph_rows(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '"' => [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000],
+        '#' => [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010],
+        '$' => [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '&' => [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101],
+        '\'' => [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        '*' => [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        ';' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000],
+        '<' => [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '>' => [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000],
+        '@' => [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110],
+        'A' => [0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '[' => [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110],
+        '\\' => [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000],
+        ']' => [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110],
+        '^' => [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '`' => [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000],
+        '{' => [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010],
+        '|' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        '}' => [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000],
+        '~' => [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]
+    }
+}
+
+/// Returns the position of the character cell in the atlas.
+///
+/// # Arguments
+///
+/// `c` - The character.
+fn cell(c: char) -> (u32, u32) {
+    let code = c as u32;
+    let index = match code {
+        FIRST_CHAR..=LAST_CHAR => code - FIRST_CHAR,
+        _ => '?' as u32 - FIRST_CHAR
+    };
+
+    (
+        (index % ATLAS_COLUMNS) * CELL_WIDTH,
+        (index / ATLAS_COLUMNS) * CELL_HEIGHT
+    )
+}
+
+/// Returns the area of the glyph in the atlas, x, y, width and height in
+/// normalized coordinates.
+///
+/// # Arguments
+///
+/// `c` - The character.
+pub fn glyph_rect(c: char) -> [f32; 4] {
+    let (x, y) = cell(c);
+    [
+        x as f32 / ATLAS_WIDTH as f32,
+        y as f32 / ATLAS_HEIGHT as f32,
+        GLYPH_WIDTH as f32 / ATLAS_WIDTH as f32,
+        GLYPH_HEIGHT as f32 / ATLAS_HEIGHT as f32
+    ]
+}
+
+/// Returns the area of the solid cell in the atlas, it has no size so the
+/// quads which use it sample a single opaque pixel and are drawn as solid
+/// rectangles.
+pub fn solid_rect() -> [f32; 4] {
+    let x = (SOLID_CELL % ATLAS_COLUMNS) * CELL_WIDTH;
+    let y = (SOLID_CELL / ATLAS_COLUMNS) * CELL_HEIGHT;
+    [
+        (x as f32 + CELL_WIDTH as f32 / 2.0) / ATLAS_WIDTH as f32,
+        (y as f32 + CELL_HEIGHT as f32 / 2.0) / ATLAS_HEIGHT as f32,
+        0.0,
+        0.0
+    ]
+}
+
+/// Creates and returns the pixels of the font atlas, one byte per pixel
+/// where 255 is an opaque pixel.
+pub fn build_atlas() -> Vec<u8> {
+    let mut pixels = vec![0u8; (ATLAS_WIDTH * ATLAS_HEIGHT) as usize];
+
+    for code in FIRST_CHAR..=LAST_CHAR {
+        let c = std::char::from_u32(code).unwrap_or('?');
+        let (cell_x, cell_y) = cell(c);
+
+        for (row, bits) in glyph_rows(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    let x = cell_x + column;
+                    let y = cell_y + row as u32;
+                    pixels[(y * ATLAS_WIDTH + x) as usize] = 255;
+                }
+            }
+        }
+    }
+
+    // The solid cell is used to draw rectangles with the glyphs.
+    let cell_x = (SOLID_CELL % ATLAS_COLUMNS) * CELL_WIDTH;
+    let cell_y = (SOLID_CELL / ATLAS_COLUMNS) * CELL_HEIGHT;
+    for y in cell_y..cell_y + CELL_HEIGHT {
+        for x in cell_x..cell_x + CELL_WIDTH {
+            pixels[(y * ATLAS_WIDTH + x) as usize] = 255;
+        }
+    }
+
+    pixels
+}
+
+#[test]
+fn font_atlas_cells() {
+    let atlas = build_atlas();
+    assert_eq!(atlas.len(), (ATLAS_WIDTH * ATLAS_HEIGHT) as usize);
+
+    // The lowercase letters share the uppercase glyphs.
+    assert_eq!(glyph_rows('a'), glyph_rows('A'));
+
+    // The top row of 'T' is filled.
+    let (x, y) = cell('T');
+    let start = (y * ATLAS_WIDTH + x) as usize;
+    assert!(atlas[start..start + GLYPH_WIDTH as usize].iter().all(|p| *p == 255));
+
+    // Unknown characters use the question mark.
+    assert_eq!(glyph_rect('é'), glyph_rect('?'));
+
+    // The solid cell fits in the atlas and its center is opaque.
+    let [u, v, _, _] = solid_rect();
+    let x = (u * ATLAS_WIDTH as f32) as u32;
+    let y = (v * ATLAS_HEIGHT as f32) as u32;
+    assert!(x < ATLAS_WIDTH && y < ATLAS_HEIGHT);
+    assert_eq!(atlas[(y * ATLAS_WIDTH + x) as usize], 255);
+}
@@ -1,4 +1,9 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    fs,
+    path::Path,
+    sync::Arc
+};
 
 use wgpu::{
     Device,
@@ -7,6 +12,7 @@ use wgpu::{
     SwapChainDescriptor,
     Instance,
     Surface,
+    Backend,
     BackendBit,
     RequestAdapterOptions,
     PowerPreference,
@@ -21,6 +27,9 @@ use wgpu::{
     TextureFormat,
     RenderPipeline,
     RenderPipelineDescriptor,
+    PipelineLayout,
+    PipelineLayoutDescriptor,
+    PushConstantRange,
     BindGroup,
     BindGroupLayoutDescriptor,
     BindGroupLayout,
@@ -34,11 +43,15 @@ use wgpu::{
     TextureViewDescriptor,
     Sampler,
     SamplerDescriptor,
+    TextureCopyView,
+    TextureDataLayout,
+    Origin3d,
     COPY_BUFFER_ALIGNMENT,
 
     util::{DeviceExt, BufferInitDescriptor}
 };
 
+use types::Size;
 use log::{info, error};
 
 use crate::{
@@ -48,7 +61,19 @@ use crate::{
         shaders::{ShaderGenerator, ShaderProvider},
         buffer::{BufferCreator, RawBufferRepresentable, BufferManipulator},
         pipelines::bind_groups::BindGroupGenerator,
-        texture::{Texture, TextureGenerator, DepthTexture, DEPTH_FORMAT},
+        uploader::GpuUploader,
+        gpu_info::{GpuInfo, available_adapters},
+        gpu_profiler::GpuProfiler,
+        push_constants::MAX_PUSH_CONSTANT_SIZE,
+        texture::{
+            Texture,
+            TextureGenerator,
+            TextureError,
+            Image,
+            decode_image,
+            select_depth_format,
+            HDR_FORMAT
+        },
     },
 };
 
@@ -66,7 +91,24 @@ pub struct GpuOptions {
     ///
     /// If the platform / device contains only one GPU no matter wich option
     /// is setted that will be used.
-    pub use_low_end_graphics_card: bool
+    pub use_low_end_graphics_card: bool,
+
+    /// Forces a specific graphics API, it is used to work around driver
+    /// bugs. When it is present `use_alternative_backend` is ignored.
+    pub force_backend: Option<Backend>,
+
+    /// Contains a flag to indicate if the scene should be rendered in HDR,
+    /// the scene is rendered into a float texture and tone mapped into the
+    /// swap chain.
+    pub hdr: bool,
+
+    /// The depth format requested by the application, if it is not a depth
+    /// format `DEPTH_FORMAT` is used instead.
+    pub preferred_depth_format: Option<TextureFormat>,
+
+    /// Contains a flag to indicate if the render passes are timed in the
+    /// GPU, it only has effect if the adapter supports timestamp queries.
+    pub enable_gpu_profiling: bool
 }
 
 impl Default for GpuOptions {
@@ -77,7 +119,11 @@ impl Default for GpuOptions {
     fn default() -> GpuOptions {
         GpuOptions {
             use_alternative_backend: false,
-            use_low_end_graphics_card: false
+            use_low_end_graphics_card: false,
+            force_backend: None,
+            hdr: false,
+            preferred_depth_format: None,
+            enable_gpu_profiling: false
         }
     }
 }
@@ -103,7 +149,24 @@ pub struct Gpu {
     pub swap_chain: SwapChain,
 
     /// Contains the swap chain description.
-    pub swap_chain_descriptor: SwapChainDescriptor
+    pub swap_chain_descriptor: SwapChainDescriptor,
+
+    /// Contains a flag defining if the scene is rendered in HDR.
+    pub hdr: bool,
+
+    /// Batches the buffer uploads of the frame.
+    pub uploader: GpuUploader,
+
+    /// Times the render passes when the GPU profiling is enabled.
+    pub profiler: GpuProfiler,
+
+    /// The format of the depth textures, the pipelines must use it in
+    /// their depth stencil state.
+    pub depth_format: TextureFormat,
+
+    /// The adapter and device description, it is also registered as a
+    /// unique resource.
+    pub info: GpuInfo
 }
 
 impl Gpu {
@@ -113,8 +176,20 @@ impl Gpu {
     ///
     /// * `window` - the window used to extract the surface target.
     pub async fn default(window: &Window) -> Result<Self, InitError> {
+        Gpu::with_options(window, GpuOptions::default()).await
+    }
+
+    /// Creates and returns a new instance of a Gpu representation using the
+    /// provided options.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - the window used to extract the surface target.
+    /// * `options` - The options used to create the Gpu.
+    pub async fn with_options(window: &Window, options: GpuOptions)
+        -> Result<Self, InitError> {
         info("Generating GPU");
-        let gpu = Gpu::new(window, GpuOptions::default()).await;
+        let gpu = Gpu::new(window, options).await;
         info("GPU generated successfully");
         gpu
     }
@@ -124,15 +199,19 @@ impl Gpu {
     /// # Arguments
     ///
     /// * `window` - The window used to extract the surface target.
-    /// * `use_alternative_backend` - Indicates if it should use OpenGL or Dx11
-    /// instead of the main one
+    /// * `options` - The configuration of the Gpu, if the forced backend is
+    ///   not available it returns `InitError::Gpu` with the available adapters.
     pub async fn new(window: &Window, options: GpuOptions) 
         -> Result<Self, InitError> {
         
         // Defines which backend should be used.
-        let backend: BackendBit = match options.use_alternative_backend {
-            true => BackendBit::SECONDARY,
-            false => BackendBit::PRIMARY
+        let backend: BackendBit = match (options.force_backend, options.use_alternative_backend) {
+            (Some(b), _) => {
+                info(&format!("Forcing the {:?} backend", b));
+                BackendBit::from(b)
+            },
+            (None, true) => BackendBit::SECONDARY,
+            (None, false) => BackendBit::PRIMARY
         };
 
         // Creates a new WGPU instance. 
@@ -168,15 +247,38 @@ impl Gpu {
         let adapter = match instance.request_adapter(&adapter_options).await {
             Some(a) => a,
             None => {
-                error("Error getting the adapter.");
-                return Err(InitError::Gpu);
+                let message = format!(
+                    "No adapter found for {:?}, available adapters: {}",
+                    backend,
+                    available_adapters()
+                );
+                error(&message);
+                return Err(InitError::Gpu(message));
             }
         };
 
+        // The timestamp queries are only requested if the adapter supports
+        // them, otherwise the profiling is disabled.
+        let mut features = match options.enable_gpu_profiling &&
+            adapter.features().contains(Features::TIMESTAMP_QUERY) {
+            true => Features::TIMESTAMP_QUERY,
+            false => Features::empty()
+        };
+
+        // The push constants are requested when the adapter supports them,
+        // otherwise the pipelines must use uniform buffers.
+        let mut limits = Limits::default();
+        if adapter.features().contains(Features::PUSH_CONSTANTS) {
+            features |= Features::PUSH_CONSTANTS;
+            limits.max_push_constant_size = adapter.limits()
+                .max_push_constant_size
+                .min(MAX_PUSH_CONSTANT_SIZE);
+        }
+
         let device_descriptor = wgpu::DeviceDescriptor {
             label: None,
-            features: Features::empty(),
-            limits: Limits::default(),
+            features,
+            limits,
         };
 
         let (device, queue) 
@@ -188,35 +290,164 @@ impl Gpu {
             }
         };
 
+        // The adapter is logged to diagnose the rendering bugs.
+        let info = GpuInfo::new(&adapter, &device);
+        info.log();
+
         // Define the format of the image to write to.
         let swap_chain_descriptor = SwapChainDescriptor {
             usage: TextureUsage::RENDER_ATTACHMENT,
             format: TextureFormat::Bgra8UnormSrgb,
-            width: window.size.width,
-            height: window.size.height,
+            width: window.physical_size.width,
+            height: window.physical_size.height,
             present_mode: wgpu::PresentMode::Fifo,
         };
 
         let swap_chain = device.create_swap_chain(&native_surface,
                                                   &swap_chain_descriptor);
 
+        // The passes are only timed if the device supports timestamp
+        // queries.
+        let profiler = GpuProfiler::new(&adapter, &device);
+
         Ok(Self {
             surface: native_surface,
             adapter,
             device,
             queue,
             swap_chain,
-            swap_chain_descriptor
+            swap_chain_descriptor,
+            hdr: options.hdr,
+            uploader: GpuUploader::default(),
+            profiler,
+            depth_format: select_depth_format(options.preferred_depth_format),
+            info
         })
     }
 }
 
 impl Gpu {
+    /// Creates and returns a buffer of the size provided filled with zeros,
+    /// the size is padded to the copy alignment.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The size of the buffer in bytes.
+    /// * `usage` - The usages of the buffer.
+    fn create_zeroed_buffer(&self, size: u64, usage: BufferUsage) -> Buffer {
+        // Convert the size from the provided one into one that WGPU handles.
+        let unpadded_size: BufferAddress = size as BufferAddress;
+        // Make sure the size is 4 bytes aligned.
+        let padding: BufferAddress = 
+            COPY_BUFFER_ALIGNMENT -
+            unpadded_size %
+            COPY_BUFFER_ALIGNMENT; 
+        
+        // Final padding, the size now is memory aligned.
+        let padded_size: BufferAddress = unpadded_size + padding;
+
+        // Define the descriptor that contains all the information neeeded
+        // to allocate the buffer.
+        let descriptor: BufferDescriptor = BufferDescriptor {
+            label: None,
+            size: padded_size,
+            usage,
+            mapped_at_creation: true
+        };
+
+        let buffer: Buffer = self.device.create_buffer(&descriptor);
+
+        {
+            let mut slice = buffer.slice(..).get_mapped_range_mut();
+            for i in 0..padded_size {
+                slice[i as usize] = 0;
+            }
+        }
+
+        buffer.unmap();
+        buffer
+    }
+
     /// Returns the swap chain preferred format.
     pub fn swap_chain_format(&self) -> TextureFormat {
         self.adapter.get_swap_chain_preferred_format(&self.surface)
     }
 
+    /// Returns the format where the scene is rendered, when HDR is enabled
+    /// the scene is rendered into a float texture instead of the swap chain.
+    pub fn scene_format(&self) -> TextureFormat {
+        match self.hdr {
+            true => HDR_FORMAT,
+            false => self.swap_chain_format()
+        }
+    }
+
+    /// Recreates the swap chain using the provided size.
+    ///
+    /// # Arguments
+    ///
+    /// `size` - The new size in physical pixels.
+    pub fn resize(&mut self, size: Size<u32>) {
+        self.swap_chain_descriptor.width = size.width;
+        self.swap_chain_descriptor.height = size.height;
+        self.swap_chain = self.device.create_swap_chain(
+            &self.surface,
+            &self.swap_chain_descriptor
+        );
+    }
+
+    /// Creates and returns a new color texture which can be rendered and
+    /// sampled.
+    ///
+    /// # Arguments
+    ///
+    /// `label` - The label of the texture used for debugging.
+    /// `width` - The width of the texture.
+    /// `height` - The height of the texture.
+    /// `format` - The format of the texture.
+    fn create_color_texture(&self,
+                            label: &str,
+                            width: u32,
+                            height: u32,
+                            format: TextureFormat) -> Texture {
+        let descriptor: TextureDescriptor = TextureDescriptor {
+            label: Some(label),
+            size: Extent3d { width, height, depth: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            // The texture is rendered and sampled by whoever wants to
+            // display it.
+            usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::SAMPLED
+        };
+
+        let raw_texture: wgpu::Texture = self.device.create_texture(&descriptor);
+
+        let view: TextureView = raw_texture.create_view(
+            &TextureViewDescriptor::default()
+        );
+
+        let sampler: Sampler = self.device.create_sampler(
+            &SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }
+        );
+
+        Texture {
+            raw_texture,
+            view,
+            sampler,
+            size: Size::new(width, height)
+        }
+    }
+
     /// Creates and returns ¡a new render pipeline.
     ///
     /// # Arguments
@@ -226,6 +457,37 @@ impl Gpu {
         descriptor: &RenderPipelineDescriptor) -> RenderPipeline {
         self.device.create_render_pipeline(descriptor) 
     }
+
+    /// Returns true if the device supports push constants.
+    pub fn supports_push_constants(&self) -> bool {
+        self.device.features().contains(Features::PUSH_CONSTANTS)
+    }
+
+    /// Creates and returns a new pipeline layout, the push constant ranges
+    /// are only used if the device supports them so the pipelines must
+    /// check `supports_push_constants` and fall back to uniform buffers.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The label of the layout.
+    /// * `bind_group_layouts` - The bind group layouts in the order they are bound.
+    /// * `push_constants` - The push constant ranges, see `push_constant_range`.
+    pub fn create_pipeline_layout(
+        &self,
+        label: Option<&str>,
+        bind_group_layouts: &[&BindGroupLayout],
+        push_constants: Option<&[PushConstantRange]>) -> PipelineLayout {
+        let push_constant_ranges = match push_constants {
+            Some(ranges) if self.supports_push_constants() => ranges,
+            _ => &[]
+        };
+
+        self.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts,
+            push_constant_ranges
+        })
+    }
 }
 
 /// Provides to the Gpu aftraction the hability to handle bing groups.
@@ -262,37 +524,17 @@ impl BufferCreator for Gpu {
 
     /// Creates and returns a buffer of the specific size provided.
     fn create_vertex_with_size(&self, size: u64) -> Buffer {
-        // Convert the size from the provided one into one that WGPU handles.
-        let unpadded_size: BufferAddress = size as BufferAddress;
-        // Make sure the size is 4 bytes aligned.
-        let padding: BufferAddress = 
-            COPY_BUFFER_ALIGNMENT -
-            unpadded_size %
-            COPY_BUFFER_ALIGNMENT; 
-        
-        // Final padding, the size now is memory aligned.
-        let padded_size: BufferAddress = unpadded_size + padding;
-
-        // Define the descriptor that contains all the information neeeded
-        // to allocate the buffer.
-        let descriptor: BufferDescriptor = BufferDescriptor {
-            label: None,
-            size: padded_size,
-            usage: BufferUsage::VERTEX | BufferUsage::COPY_DST,
-            mapped_at_creation: true
-        };
-
-        let buffer: Buffer = self.device.create_buffer(&descriptor);
-
-        {
-            let mut slice = buffer.slice(..).get_mapped_range_mut();
-            for i in 0..padded_size {
-                slice[i as usize] = 0;
-            }
-        }
+        self.create_zeroed_buffer(size, BufferUsage::VERTEX | BufferUsage::COPY_DST)
+    }
 
-        buffer.unmap();
-        buffer
+    /// Creates and returns a storage buffer of the specific size provided.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The size of the buffer in bytes.
+    /// * `usage` - The usages needed besides the storage.
+    fn create_storage_with_size(&self, size: u64, usage: BufferUsage) -> Buffer {
+        self.create_zeroed_buffer(size, BufferUsage::STORAGE | BufferUsage::COPY_DST | usage)
     }
 
     /// Creates and returns a new index buffer after submit to GPU.
@@ -346,7 +588,8 @@ impl ShaderGenerator for Gpu {
 
 /// Provides to the Gpu the aftraction to manipulate Gpu buffers.
 impl BufferManipulator for Gpu {
-    /// Copy the provided data into the GPU buffer.
+    /// Copy the provided data into the GPU buffer, the copy is batched with
+    /// the rest of the uploads of the frame, see `GpuUploader`.
     ///
     /// # Arguments
     ///
@@ -355,8 +598,8 @@ impl BufferManipulator for Gpu {
     ///
     /// TODO(Angel): Create a more complex data structure to write an specific
     /// chunk of the buffer.
-    fn copy_to_buffer(&self, buffer: &Buffer, data: &[u8]) {
-        self.queue.write_buffer(buffer, 0, data);
+    fn copy_to_buffer(&self, buffer: &Arc<Buffer>, data: &[u8]) {
+        self.uploader.write(buffer, 0, data);
     }
 }
 
@@ -364,11 +607,25 @@ impl BufferManipulator for Gpu {
 impl TextureGenerator for Gpu {
     /// Creates and returns a new depth texture.
     fn create_depth_texture(&self) -> Texture {
-        // Defines the size of the depth texture, in this case it should be
-        // of the size of the entire screen or the swap chain.
+        // The depth texture should be of the size of the entire screen or
+        // the swap chain.
+        self.create_depth_texture_with_size(
+            self.swap_chain_descriptor.width,
+            self.swap_chain_descriptor.height
+        )
+    }
+
+    /// Creates and returns a new depth texture of the provided size.
+    ///
+    /// # Arguments
+    ///
+    /// `width` - The width of the texture.
+    /// `height` - The height of the texture.
+    fn create_depth_texture_with_size(&self, width: u32, height: u32) -> Texture {
+        // Defines the size of the depth texture.
         let size: Extent3d = Extent3d {
-            width: self.swap_chain_descriptor.width,
-            height: self.swap_chain_descriptor.height,
+            width,
+            height,
             depth: 1
         };
 
@@ -385,7 +642,7 @@ impl TextureGenerator for Gpu {
             // The texture is 2D.
             dimension: TextureDimension::D2,
             // We want a depth format.
-            format: DEPTH_FORMAT,
+            format: self.depth_format,
             // We need render to the texture so RENDER_ATTACHMEN comes in 
             // place, sampled due the data could be extracted using a sampler.
             usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::SAMPLED
@@ -417,7 +674,130 @@ impl TextureGenerator for Gpu {
         Texture {
             raw_texture,
             view,
-            sampler
+            sampler,
+            size: Size::new(width, height)
         }
     }
-}
\ No newline at end of file
+
+    /// Creates and returns a new texture which can be used as render target.
+    ///
+    /// The texture uses the scene format so the pipelines that render the
+    /// scene can render into it too.
+    ///
+    /// # Arguments
+    ///
+    /// `width` - The width of the texture.
+    /// `height` - The height of the texture.
+    fn create_render_target_texture(&self, width: u32, height: u32) -> Texture {
+        self.create_color_texture("Render target", width, height, self.scene_format())
+    }
+
+    /// Creates and returns a new HDR texture of the size of the swap chain.
+    fn create_hdr_texture(&self) -> Texture {
+        self.create_color_texture(
+            "HDR target",
+            self.swap_chain_descriptor.width,
+            self.swap_chain_descriptor.height,
+            HDR_FORMAT
+        )
+    }
+
+    /// Creates and returns a new texture which contains the provided pixels.
+    ///
+    /// # Arguments
+    ///
+    /// `label` - The label of the texture.
+    /// `size` - The size of the texture in pixels.
+    /// `format` - The format of the pixels.
+    /// `bytes_per_pixel` - The number of bytes of each pixel.
+    /// `data` - The pixels, row by row from the top.
+    fn create_texture_with_data(&self,
+                                label: &str,
+                                size: Size<u32>,
+                                format: TextureFormat,
+                                bytes_per_pixel: u32,
+                                data: &[u8]) -> Texture {
+        let extent = Extent3d { width: size.width, height: size.height, depth: 1 };
+
+        let raw_texture: wgpu::Texture = self.device.create_texture(&TextureDescriptor {
+            label: Some(label),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST
+        });
+
+        self.queue.write_texture(
+            TextureCopyView {
+                texture: &raw_texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO
+            },
+            data,
+            TextureDataLayout {
+                offset: 0,
+                bytes_per_row: size.width * bytes_per_pixel,
+                rows_per_image: size.height
+            },
+            extent
+        );
+
+        let view: TextureView = raw_texture.create_view(
+            &TextureViewDescriptor::default()
+        );
+
+        // The data textures are usually pixel art, so they are not filtered.
+        let sampler: Sampler = self.device.create_sampler(
+            &SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Nearest,
+                min_filter: wgpu::FilterMode::Nearest,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }
+        );
+
+        Texture {
+            raw_texture,
+            view,
+            sampler,
+            size
+        }
+    }
+
+    /// Loads a PNG, JPEG or BMP file into a new RGBA texture.
+    ///
+    /// The device and the queue can be used from any thread, so this can be
+    /// called from the workers in order to not stall the frame.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the image file.
+    fn load_texture_from_file(&self, path: &Path) -> Result<Texture, TextureError> {
+        let bytes = fs::read(path).map_err(|e| TextureError::IoError(e.to_string()))?;
+        let image = decode_image(&bytes)?;
+
+        Ok(self.create_texture_from_image(&path.to_string_lossy(), &image))
+    }
+
+    /// Creates and returns a new RGBA texture which contains the decoded
+    /// image.
+    ///
+    /// # Arguments
+    ///
+    /// `label` - The label of the texture.
+    /// `image` - The decoded image.
+    fn create_texture_from_image(&self, label: &str, image: &Image) -> Texture {
+        self.create_texture_with_data(
+            label,
+            image.size,
+            TextureFormat::Rgba8UnormSrgb,
+            4,
+            &image.pixels
+        )
+    }
+}
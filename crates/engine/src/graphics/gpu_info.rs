@@ -0,0 +1,123 @@
+use wgpu::{
+    Adapter,
+    Backend,
+    BackendBit,
+    Device,
+    DeviceType,
+    Features,
+    Instance,
+    Limits
+};
+
+use ecs::{UniqueRead, UniqueWrite};
+use log::info;
+
+use crate::basics::dev_gui::{DevGui, GuiAnchor, TEXT_COLOR};
+
+/// The name of the GPU information panel in the dev GUI.
+pub const GPU_INFO_PANEL: &str = "GPU";
+
+/// Contains the description of the adapter picked by the engine and the
+/// limits and features of the device, it is useful to diagnose rendering
+/// bugs.
+#[derive(Clone, Debug)]
+pub struct GpuInfo {
+    /// The name of the adapter.
+    pub name: String,
+
+    /// The PCI id of the vendor.
+    pub vendor: usize,
+
+    /// The PCI id of the device.
+    pub device: usize,
+
+    /// Defines if the adapter is integrated, discrete, virtual or a CPU.
+    pub device_type: DeviceType,
+
+    /// The graphics API used by the adapter.
+    pub backend: Backend,
+
+    /// The limits of the device.
+    pub limits: Limits,
+
+    /// The features enabled in the device.
+    pub features: Features
+}
+
+impl GpuInfo {
+    /// Creates and returns the information of the adapter and its device.
+    ///
+    /// # Arguments
+    ///
+    /// `adapter` - The adapter picked by the engine.
+    /// `device` - The device created from the adapter.
+    pub fn new(adapter: &Adapter, device: &Device) -> Self {
+        let adapter_info = adapter.get_info();
+        Self {
+            name: adapter_info.name,
+            vendor: adapter_info.vendor,
+            device: adapter_info.device,
+            device_type: adapter_info.device_type,
+            backend: adapter_info.backend,
+            limits: device.limits(),
+            features: device.features()
+        }
+    }
+
+    /// Returns the information as lines of text, they are used by the log
+    /// and the diagnostics windows.
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("Adapter: {}", self.name),
+            format!("Vendor: {:#06x}, device: {:#06x}", self.vendor, self.device),
+            format!("Type: {:?}, backend: {:?}", self.device_type, self.backend),
+            format!("Features: {:?}", self.features),
+            format!("Limits: {:?}", self.limits)
+        ]
+    }
+
+    /// Writes the information into the log.
+    pub fn log(&self) {
+        for line in self.lines() {
+            info(&format!("{{Gpu}} {}", line));
+        }
+    }
+}
+
+/// Draws the information of the adapter, the long lines are wrapped by the
+/// window. It is only drawn when the GPU panel is visible.
+pub fn gpu_info_panel_system(
+    dev_gui: UniqueWrite<DevGui>,
+    gpu_info: UniqueRead<GpuInfo>) {
+    let mut dev_gui_write = dev_gui.write();
+    if !dev_gui_write.is_visible(GPU_INFO_PANEL) {
+        return;
+    }
+
+    let mut window = dev_gui_write.window(GPU_INFO_PANEL, GuiAnchor::Right);
+    for line in gpu_info.read().lines() {
+        window.text(&line, TEXT_COLOR);
+    }
+
+    dev_gui_write.show(window);
+}
+
+/// Returns the name and the backend of all the adapters in the system
+/// separated by commas, it is used when the requested backend is not
+/// available.
+pub fn available_adapters() -> String {
+    // The instance of the engine only contains the requested backends.
+    let instance = Instance::new(BackendBit::all());
+    let adapters: Vec<String> = instance
+        .enumerate_adapters(BackendBit::all())
+        .map(|adapter| {
+            let adapter_info = adapter.get_info();
+            format!("{} ({:?})", adapter_info.name, adapter_info.backend)
+        })
+        .collect();
+
+    match adapters.is_empty() {
+        true => "none".to_string(),
+        false => adapters.join(", ")
+    }
+}
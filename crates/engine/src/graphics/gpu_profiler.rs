@@ -0,0 +1,386 @@
+use std::{
+    convert::TryInto,
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex
+    },
+    task::{Context, Poll}
+};
+
+use futures::task::noop_waker_ref;
+
+use wgpu::{
+    Adapter,
+    Buffer,
+    BufferAddress,
+    BufferAsyncError,
+    BufferDescriptor,
+    BufferUsage,
+    CommandBuffer,
+    CommandEncoder,
+    CommandEncoderDescriptor,
+    Device,
+    Features,
+    Maintain,
+    MapMode,
+    QuerySet,
+    QuerySetDescriptor,
+    QueryType
+};
+
+use log::{info, warning};
+
+use crate::graphics::{
+    OrderedCommandBuffer,
+    renderers::RenderOrder
+};
+
+/// The maximum number of passes profiled per frame, the passes after it
+/// are not timed.
+pub const MAX_PROFILED_PASSES: u32 = 32;
+
+/// The size in bytes of a timestamp.
+const TIMESTAMP_SIZE: BufferAddress = 8;
+
+/// The size in bytes of the timestamps of all the passes.
+const TIMESTAMPS_BUFFER_SIZE: BufferAddress =
+    MAX_PROFILED_PASSES as BufferAddress * 2 * TIMESTAMP_SIZE;
+
+/// Defines the future returned when the read back buffer is mapped.
+type MapFuture = Pin<Box<dyn Future<Output = Result<(), BufferAsyncError>> + Send>>;
+
+/// Contains the GPU objects used to time the passes, they only exist if the
+/// device supports timestamp queries.
+struct TimestampQueries {
+    /// Contains the begin and end timestamps of each pass.
+    query_set: QuerySet,
+
+    /// The queries are resolved into this buffer.
+    resolve_buffer: Buffer,
+
+    /// The resolved queries are copied into this buffer to be read by the
+    /// CPU.
+    read_buffer: Buffer,
+
+    /// The number of nanoseconds per timestamp tick.
+    period: f32
+}
+
+/// Contains the state of the read back protected by its lock.
+#[derive(Default)]
+struct ReadbackState {
+    /// The label and the pass index of the passes resolved into the read
+    /// buffer.
+    passes: Vec<(String, u32)>,
+
+    /// The mapping of the read buffer, it is present while the GPU did not
+    /// finish the frame which resolved the queries.
+    map: Option<MapFuture>,
+
+    /// True if the queries of the current frame were resolved, the read
+    /// buffer is mapped after the frame is submitted.
+    resolved: bool
+}
+
+/// Times each render pass in the GPU using timestamp queries, it is owned
+/// by the `Gpu`.
+///
+/// The renderers write a timestamp at the beginning and at the end of their
+/// command buffer using `ProfiledEncoder`, `submit_commnads_system`
+/// resolves the queries and reads them back once the GPU finishes. The
+/// results are always at least a frame late, the GPU did not execute the
+/// passes when the frame is submitted.
+///
+/// If the profiling is disabled or the adapter does not support
+/// `Features::TIMESTAMP_QUERY` all the functions are no-ops.
+pub struct GpuProfiler {
+    /// The queries, None if the profiling is not available.
+    queries: Option<TimestampQueries>,
+
+    /// The index of the next pass profiled in the current frame.
+    next_pass: AtomicU32,
+
+    /// The state of the read back.
+    readback: Mutex<ReadbackState>
+}
+
+impl GpuProfiler {
+    /// Creates and returns a new `GpuProfiler`, the queries are only created
+    /// if the device was created with timestamp queries.
+    ///
+    /// # Arguments
+    ///
+    /// `adapter` - The adapter which provides the timestamp period.
+    /// `device` - The device used to create the queries.
+    pub fn new(adapter: &Adapter, device: &Device) -> Self {
+        // wgpu 0.7 exposes the period in the adapter, it is zero when the
+        // timestamps are not supported.
+        let period = adapter.get_timestamp_period();
+        let enabled = device.features().contains(Features::TIMESTAMP_QUERY) && period > 0.0;
+        let queries = match enabled {
+            true => {
+                info("{GpuProfiler} Timestamp queries enabled");
+                Some(TimestampQueries {
+                    query_set: device.create_query_set(&QuerySetDescriptor {
+                        ty: QueryType::Timestamp,
+                        count: MAX_PROFILED_PASSES * 2
+                    }),
+                    resolve_buffer: device.create_buffer(&BufferDescriptor {
+                        label: Some("Timestamps resolve buffer"),
+                        size: TIMESTAMPS_BUFFER_SIZE,
+                        usage: BufferUsage::COPY_SRC | BufferUsage::COPY_DST,
+                        mapped_at_creation: false
+                    }),
+                    read_buffer: device.create_buffer(&BufferDescriptor {
+                        label: Some("Timestamps read buffer"),
+                        size: TIMESTAMPS_BUFFER_SIZE,
+                        usage: BufferUsage::COPY_DST | BufferUsage::MAP_READ,
+                        mapped_at_creation: false
+                    }),
+                    period
+                })
+            },
+            false => None
+        };
+
+        Self {
+            queries,
+            next_pass: AtomicU32::new(0),
+            readback: Mutex::new(ReadbackState::default())
+        }
+    }
+
+    /// Returns true if the passes are timed.
+    pub fn is_enabled(&self) -> bool {
+        self.queries.is_some()
+    }
+
+    /// Writes the begin timestamp of a new pass and returns its index, None
+    /// is returned if the profiling is disabled or there are too many
+    /// passes.
+    ///
+    /// # Arguments
+    ///
+    /// `encoder` - The encoder of the pass.
+    fn begin(&self, encoder: &mut CommandEncoder) -> Option<u32> {
+        let queries = self.queries.as_ref()?;
+        let pass = self.next_pass.fetch_add(1, Ordering::Relaxed);
+        if pass >= MAX_PROFILED_PASSES {
+            return None;
+        }
+
+        encoder.write_timestamp(&queries.query_set, pass * 2);
+        Some(pass)
+    }
+
+    /// Writes the end timestamp of the pass.
+    ///
+    /// # Arguments
+    ///
+    /// `encoder` - The encoder of the pass.
+    /// `pass` - The index returned by `begin`.
+    fn end(&self, encoder: &mut CommandEncoder, pass: u32) {
+        if let Some(queries) = &self.queries {
+            encoder.write_timestamp(&queries.query_set, pass * 2 + 1);
+        }
+    }
+
+    /// Returns the times of the last frame read back, None if the GPU did
+    /// not finish it yet or there is nothing to read.
+    ///
+    /// # Arguments
+    ///
+    /// `device` - The device polled to finish the mapping.
+    pub fn collect(&self, device: &Device) -> Option<Vec<(String, f32)>> {
+        let queries = self.queries.as_ref()?;
+        let mut readback = self.readback.lock().unwrap();
+        let map = readback.map.as_mut()?;
+
+        device.poll(Maintain::Poll);
+        let mut context = Context::from_waker(noop_waker_ref());
+        let mapped = match map.as_mut().poll(&mut context) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return None
+        };
+        readback.map = None;
+
+        if mapped.is_err() {
+            warning("{GpuProfiler} The timestamps could not be read");
+            return None;
+        }
+
+        let times = {
+            let view = queries.read_buffer.slice(..).get_mapped_range();
+            let timestamps: Vec<u64> = view
+                .chunks_exact(TIMESTAMP_SIZE as usize)
+                .map(|t| u64::from_le_bytes(t.try_into().unwrap()))
+                .collect();
+            pass_times(&readback.passes, &timestamps, queries.period)
+        };
+        queries.read_buffer.unmap();
+
+        Some(times)
+    }
+
+    /// Returns the command buffer which resolves the queries of the frame,
+    /// None if the profiling is disabled, there are no passes or the
+    /// previous read back did not finish, in that case the frame is not
+    /// profiled.
+    ///
+    /// The pass counter is reset so it must be called once all the passes of
+    /// the frame are recorded.
+    ///
+    /// # Arguments
+    ///
+    /// `device` - The device used to create the encoder.
+    /// `commands` - The command buffers of the frame.
+    pub fn resolve(&self, device: &Device, commands: &[OrderedCommandBuffer]) -> Option<CommandBuffer> {
+        let queries = self.queries.as_ref()?;
+        let count = self.next_pass.swap(0, Ordering::Relaxed).min(MAX_PROFILED_PASSES);
+
+        let mut readback = self.readback.lock().unwrap();
+        if count == 0 || readback.map.is_some() {
+            return None;
+        }
+
+        // The label of the command buffer identifies the pass.
+        readback.passes = commands
+            .iter()
+            .filter_map(|c| Some((c.label.clone()?, c.timestamps?)))
+            .collect();
+        readback.resolved = true;
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Timestamps resolve encoder")
+        });
+        encoder.resolve_query_set(&queries.query_set, 0..count * 2, &queries.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &queries.resolve_buffer,
+            0,
+            &queries.read_buffer,
+            0,
+            count as BufferAddress * 2 * TIMESTAMP_SIZE
+        );
+        Some(encoder.finish())
+    }
+
+    /// Starts reading back the queries resolved in the frame, it must be
+    /// called after the frame is submitted.
+    pub fn start_readback(&self) {
+        let queries = match &self.queries {
+            Some(q) => q,
+            None => return
+        };
+
+        let mut readback = self.readback.lock().unwrap();
+        if !readback.resolved {
+            return;
+        }
+        readback.resolved = false;
+        readback.map = Some(Box::pin(queries.read_buffer.slice(..).map_async(MapMode::Read)));
+    }
+}
+
+/// Wraps a command encoder and writes a timestamp at the beginning and at
+/// the end of it, in this way the GPU time of the pass is measured.
+///
+/// The encoder is used as a regular `CommandEncoder` and it is finished into
+/// an `OrderedCommandBuffer`, its label identifies the pass in the stats.
+pub struct ProfiledEncoder<'a> {
+    /// The wrapped encoder.
+    encoder: CommandEncoder,
+
+    /// The profiler which owns the queries.
+    profiler: &'a GpuProfiler,
+
+    /// The label of the pass.
+    label: &'static str,
+
+    /// The index of the pass, None if it is not profiled.
+    pass: Option<u32>
+}
+
+impl<'a> ProfiledEncoder<'a> {
+    /// Creates and returns a new encoder and writes the begin timestamp.
+    ///
+    /// # Arguments
+    ///
+    /// `device` - The device used to create the encoder.
+    /// `profiler` - The profiler which times the pass.
+    /// `label` - The label of the pass.
+    pub fn new(device: &Device, profiler: &'a GpuProfiler, label: &'static str) -> Self {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some(label)
+        });
+        let pass = profiler.begin(&mut encoder);
+
+        Self { encoder, profiler, label, pass }
+    }
+
+    /// Writes the end timestamp and returns the command buffer ready to be
+    /// queued.
+    ///
+    /// # Arguments
+    ///
+    /// `order` - The position of the command buffer in the frame.
+    pub fn finish(mut self, order: RenderOrder) -> OrderedCommandBuffer {
+        if let Some(pass) = self.pass {
+            self.profiler.end(&mut self.encoder, pass);
+        }
+
+        OrderedCommandBuffer {
+            label: Some(self.label.to_string()),
+            order: order.as_index(),
+            command: self.encoder.finish(),
+            timestamps: self.pass
+        }
+    }
+}
+
+impl<'a> Deref for ProfiledEncoder<'a> {
+    type Target = CommandEncoder;
+
+    fn deref(&self) -> &CommandEncoder {
+        &self.encoder
+    }
+}
+
+impl<'a> DerefMut for ProfiledEncoder<'a> {
+    fn deref_mut(&mut self) -> &mut CommandEncoder {
+        &mut self.encoder
+    }
+}
+
+/// Returns the label and the time in milliseconds of each pass.
+///
+/// # Arguments
+///
+/// `passes` - The label and the index of each pass.
+/// `timestamps` - The begin and end timestamps of the passes.
+/// `period` - The number of nanoseconds per timestamp tick.
+fn pass_times(passes: &[(String, u32)], timestamps: &[u64], period: f32) -> Vec<(String, f32)> {
+    passes
+        .iter()
+        .filter_map(|(label, pass)| {
+            let begin = *timestamps.get(*pass as usize * 2)?;
+            let end = *timestamps.get(*pass as usize * 2 + 1)?;
+            let nanoseconds = end.saturating_sub(begin) as f32 * period;
+            Some((label.clone(), nanoseconds / 1_000_000.0))
+        })
+        .collect()
+}
+
+#[test]
+fn pass_times_use_timestamp_period() {
+    let passes = vec![("Sky_System".to_string(), 1), ("Voxel_System".to_string(), 0)];
+    let timestamps = [1_000, 3_000, 500, 1_000];
+
+    let times = pass_times(&passes, &timestamps, 1000.0);
+    assert_eq!(times, vec![("Sky_System".to_string(), 0.5), ("Voxel_System".to_string(), 2.0)]);
+
+    // A pass without timestamps is not reported.
+    assert!(pass_times(&[("Text_System".to_string(), 5)], &timestamps, 1.0).is_empty());
+}
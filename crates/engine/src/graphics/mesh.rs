@@ -0,0 +1,182 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::Path
+};
+
+use cgmath::{Vector3, InnerSpace, Zero};
+
+use crate::graphics::{
+    gpu::Gpu,
+    vertex::Vertex
+};
+
+/// Represents a triangle mesh loaded in memory.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    /// The vertices of the mesh, they use the same layout as the voxels.
+    pub vertices: Vec<Vertex>,
+
+    /// Three indices per triangle.
+    pub indices: Vec<u32>,
+
+    /// The color of the mesh, the diffuse color of the first material.
+    pub color: Vector3<f32>
+}
+
+impl Default for Mesh {
+    /// Creates and returns an empty white mesh.
+    fn default() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            color: Vector3 { x: 1.0, y: 1.0, z: 1.0 }
+        }
+    }
+}
+
+impl Mesh {
+    /// Appends a triangulated model to the mesh, if the model does not
+    /// contain normals they are calculated from the faces.
+    ///
+    /// # Arguments
+    ///
+    /// `positions` - Three floats per vertex.
+    /// `normals` - Three floats per vertex or empty.
+    /// `indices` - Three indices per triangle, relative to the model.
+    pub fn append(&mut self, positions: &[f32], normals: &[f32], indices: &[u32]) {
+        let offset = self.vertices.len() as u32;
+        let vertex_count = positions.len() / 3;
+
+        let normals: Vec<Vector3<f32>> = match normals.len() == positions.len() {
+            true => normals.chunks(3).map(|n| Vector3 { x: n[0], y: n[1], z: n[2] }).collect(),
+            false => face_normals(positions, indices)
+        };
+
+        for i in 0..vertex_count {
+            let position = Vector3 {
+                x: positions[i * 3],
+                y: positions[i * 3 + 1],
+                z: positions[i * 3 + 2]
+            };
+            self.vertices.push(Vertex::new(position, normals[i], [0.0, 0.0]));
+        }
+
+        self.indices.extend(indices.iter().map(|i| i + offset));
+    }
+}
+
+/// Returns the normal of each vertex, the average of the normals of the
+/// faces which contain it.
+///
+/// # Arguments
+///
+/// `positions` - Three floats per vertex.
+/// `indices` - Three indices per triangle.
+fn face_normals(positions: &[f32], indices: &[u32]) -> Vec<Vector3<f32>> {
+    let position = |i: u32| Vector3 {
+        x: positions[i as usize * 3],
+        y: positions[i as usize * 3 + 1],
+        z: positions[i as usize * 3 + 2]
+    };
+
+    let mut normals = vec![Vector3::zero(); positions.len() / 3];
+    for triangle in indices.chunks(3) {
+        if let [a, b, c] = *triangle {
+            // Not normalized, in this way the big faces weigh more.
+            let normal = (position(b) - position(a)).cross(position(c) - position(a));
+            normals[a as usize] += normal;
+            normals[b as usize] += normal;
+            normals[c as usize] += normal;
+        }
+    }
+
+    normals
+        .into_iter()
+        .map(|n| if n.magnitude2() > 0.0 { n.normalize() } else { Vector3::unit_y() })
+        .collect()
+}
+
+/// Represents the errors that can happen when a mesh is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    /// The file could not be read.
+    IoError(String),
+
+    /// The content of the file is not a valid model.
+    ParseError(String),
+
+    /// The file does not contain any triangle.
+    Empty
+}
+
+impl Display for MeshError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            MeshError::IoError(e) => write!(formatter, "Mesh file error: {}", e),
+            MeshError::ParseError(e) => write!(formatter, "Invalid mesh: {}", e),
+            MeshError::Empty => write!(formatter, "The mesh does not contain triangles")
+        }
+    }
+}
+
+/// Provides the needed symbols used to load meshes.
+pub trait MeshLoader {
+    /// Should load the OBJ file into a new mesh.
+    fn load_obj(&self, path: &Path) -> Result<Mesh, MeshError>;
+}
+
+impl MeshLoader for Gpu {
+    /// Loads all the models of the OBJ file into a single triangulated mesh.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the OBJ file.
+    fn load_obj(&self, path: &Path) -> Result<Mesh, MeshError> {
+        let options = tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        };
+
+        let (models, materials) = tobj::load_obj(path, &options).map_err(|e| match e {
+            tobj::LoadError::OpenFileFailed | tobj::LoadError::ReadError =>
+                MeshError::IoError(e.to_string()),
+            _ => MeshError::ParseError(e.to_string())
+        })?;
+
+        let mut mesh = Mesh::default();
+        for model in models.iter() {
+            mesh.append(&model.mesh.positions, &model.mesh.normals, &model.mesh.indices);
+        }
+
+        if mesh.indices.is_empty() {
+            return Err(MeshError::Empty);
+        }
+
+        // The materials are optional, the mesh is white without them.
+        let material = models
+            .iter()
+            .find_map(|m| m.mesh.material_id)
+            .and_then(|id| materials.as_ref().ok()?.get(id));
+        if let Some(m) = material {
+            mesh.color = Vector3 { x: m.diffuse[0], y: m.diffuse[1], z: m.diffuse[2] };
+        }
+
+        Ok(mesh)
+    }
+}
+
+#[test]
+fn mesh_normals_and_offsets() {
+    let positions = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+    let mut mesh = Mesh::default();
+    mesh.append(&positions, &[], &[0, 1, 2]);
+    mesh.append(&positions, &[], &[0, 2, 1]);
+
+    assert_eq!(mesh.vertices.len(), 6);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 3, 5, 4]);
+
+    // The first triangle is counter clockwise when seen from +z.
+    assert_eq!(mesh.vertices[0].normal.z, 1.0);
+    assert_eq!(mesh.vertices[3].normal.z, -1.0);
+}
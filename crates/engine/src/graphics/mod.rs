@@ -1,9 +1,16 @@
 pub mod buffer;
+pub mod font;
 pub mod gpu;
+pub mod gpu_info;
+pub mod gpu_profiler;
+pub mod mesh;
 pub mod pipelines;
+pub mod push_constants;
+pub mod render_stats;
 pub mod renderers;
 pub mod shaders;
 pub mod texture;
+pub mod uploader;
 pub mod vertex;
 
 extern crate crossbeam_queue;
@@ -21,7 +28,8 @@ pub const MAX_NUMBER_OF_COMMANDS_PER_CALL: usize = 50;
 /// to know which position should take in the commander buffer submition 
 /// process.
 pub struct OrderedCommandBuffer {
-    /// Contains a handy label used for debugging. 
+    /// Contains a handy label used for debugging, the GPU times of the
+    /// profiled passes are published with it.
     label: Option<String>,
 
     /// Contains the position in which the command buffer will be 
@@ -32,7 +40,11 @@ pub struct OrderedCommandBuffer {
     order: usize,
 
     /// The command to send to the GPU.
-    command: CommandBuffer
+    command: CommandBuffer,
+
+    /// The index of the pass in the GPU profiler, None if the command
+    /// buffer is not timed.
+    timestamps: Option<u32>
 }
 
 /// A type alias of a thread shafe queue.
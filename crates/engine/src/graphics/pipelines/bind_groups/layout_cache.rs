@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex}
+};
+
+use wgpu::{
+    ShaderStage,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingType,
+    BufferBindingType,
+    TextureSampleType,
+    TextureViewDimension
+};
+
+use ecs::{DefaultWorld, ComponentHandler};
+
+use crate::graphics::{
+    gpu::Gpu,
+    pipelines::bind_groups::{
+        BindGroupGenerator,
+        locals_bind_group::create_locals_bind_group_layout
+    }
+};
+
+/// Identifies the bind group layouts shared by the pipelines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindGroupLayoutKey {
+    /// The camera locals and the point lights, see `locals_bind_group`.
+    Locals,
+
+    /// A single uniform buffer read by the fragment stage, it is used by
+    /// the sky and the outline settings.
+    FragmentUniform,
+
+    /// A 2D texture and its sampler read by the fragment stage.
+    Texture
+}
+
+/// Contains the bind group layouts created so far, in this way the
+/// pipelines which use the same bind group share the layout instead of
+/// creating their own.
+///
+/// It is registered as a unique resource, the layouts are created the first
+/// time they are requested.
+#[derive(Default)]
+pub struct BindGroupLayoutCache {
+    layouts: Mutex<HashMap<BindGroupLayoutKey, Arc<BindGroupLayout>>>
+}
+
+impl BindGroupLayoutCache {
+    /// Returns the layout associated with the key, it is created the first
+    /// time it is requested.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the layout.
+    /// `key` - The layout to be returned.
+    pub fn get_or_create(&self, gpu: &Gpu, key: BindGroupLayoutKey) -> Arc<BindGroupLayout> {
+        get_or_insert_with(&self.layouts, key, || create_layout(gpu, key))
+    }
+}
+
+/// Returns the layout associated with the key, if it does not exist it is
+/// created using the provided function.
+///
+/// # Arguments
+///
+/// `layouts` - The cached layouts.
+/// `key` - The layout to be returned.
+/// `create` - Creates the layout if it is not in the cache.
+fn get_or_insert_with<L, F: FnOnce() -> L>(
+    layouts: &Mutex<HashMap<BindGroupLayoutKey, Arc<L>>>,
+    key: BindGroupLayoutKey,
+    create: F) -> Arc<L> {
+    layouts
+        .lock()
+        .unwrap()
+        .entry(key)
+        .or_insert_with(|| Arc::new(create()))
+        .clone()
+}
+
+/// Returns the layout associated with the key using the cache registered in
+/// the world, it is used by the pipeline constructors.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the layout.
+/// `world` - The world which contains the `BindGroupLayoutCache`.
+/// `key` - The layout to be returned.
+pub fn bind_group_layout(gpu: &Gpu, world: &DefaultWorld, key: BindGroupLayoutKey) -> Arc<BindGroupLayout> {
+    world.get_unique::<BindGroupLayoutCache>().read().get_or_create(gpu, key)
+}
+
+/// Creates and returns the layout described by the key.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the layout.
+/// `key` - The layout to be created.
+fn create_layout(gpu: &Gpu, key: BindGroupLayoutKey) -> BindGroupLayout {
+    match key {
+        BindGroupLayoutKey::Locals => create_locals_bind_group_layout(gpu),
+        BindGroupLayoutKey::FragmentUniform => gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Fragment uniform bind group layout")
+        }),
+        BindGroupLayoutKey::Texture => gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2
+                    },
+                    count: None
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Sampler {
+                        filtering: true,
+                        comparison: false
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Texture bind group layout")
+        })
+    }
+}
+
+#[test]
+fn same_key_shares_layout() {
+    let layouts: Mutex<HashMap<BindGroupLayoutKey, Arc<u32>>> = Mutex::new(HashMap::new());
+    let mut created = 0;
+
+    // Two pipelines asking for the locals layout.
+    let first = get_or_insert_with(&layouts, BindGroupLayoutKey::Locals, || { created += 1; 1 });
+    let second = get_or_insert_with(&layouts, BindGroupLayoutKey::Locals, || { created += 1; 2 });
+    let texture = get_or_insert_with(&layouts, BindGroupLayoutKey::Texture, || { created += 1; 3 });
+
+    assert!(Arc::ptr_eq(&first, &second));
+    assert!(!Arc::ptr_eq(&first, &texture));
+    assert_eq!(created, 2);
+}
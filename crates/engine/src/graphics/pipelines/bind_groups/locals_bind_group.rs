@@ -1,4 +1,9 @@
-use cgmath::Matrix4;
+use std::{
+    collections::HashMap,
+    sync::Arc
+};
+
+use cgmath::{Matrix4, Vector3, Vector4};
 use bytemuck::{Pod, Zeroable};
 
 use wgpu::{
@@ -15,34 +20,67 @@ use wgpu::{
     Buffer
 };
 
-use ecs::{DefaultWorld, ComponentHandler};
+use ecs::{DefaultWorld, ComponentHandler, Entity};
 use types::Bytes;
 
 use crate::{
     graphics::{
         gpu::Gpu,
         buffer::{BufferCreator, RawBufferRepresentable},
-        pipelines::bind_groups::BindGroupGenerator,
+        pipelines::bind_groups::{
+            BindGroupGenerator,
+            layout_cache::{BindGroupLayoutKey, bind_group_layout}
+        },
+        texture::Texture
     },
-    scene::camera::Camera
+    scene::{
+        camera::Camera,
+        light::{DirectionalLight, PointLight}
+    }
 };
 
 /// Define where the locals with be placed in the shader.
 const LOCAL_BINDING_POSITION: u32 = 0;
 
+/// Define where the point lights will be placed in the shader.
+const POINT_LIGHTS_BINDING_POSITION: u32 = 2;
+
+/// The maximum number of point lights sent to the shader, the rest are
+/// ignored.
+pub const MAX_POINT_LIGHTS: usize = 32;
+
 /// Represetns all the locals uniforms to be sent to the GPU.
+///
+/// The vectors use 4 components to match the uniform alignment in WGSL.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Locals {
     // The project of the camera.
-    view_proj: Matrix4<f32>
+    view_proj: Matrix4<f32>,
+
+    // The RGB ambient light, w is not used.
+    ambient_light: Vector4<f32>,
+
+    // The normalized direction of the directional light, w is not used.
+    directional_light_dir: Vector4<f32>,
+
+    // The RGB color of the directional light, w is not used.
+    directional_light_color: Vector4<f32>
 }
 
 impl Locals {
-    /// Creates and returns a new `Locals` using the default camera projection.
-    pub fn new() -> Self {
+    /// Creates and returns a new `Locals`.
+    ///
+    /// # Arguments
+    ///
+    /// `camera` - The camera used to generate the view projection.
+    /// `light` - The light of the scene.
+    pub fn new(camera: &Camera, light: &DirectionalLight) -> Self {
         Locals {
-            view_proj: Camera::default().view_projection()
+            view_proj: camera.view_projection(),
+            ambient_light: light.ambient.extend(0.0),
+            directional_light_dir: light.normalized_direction().extend(0.0),
+            directional_light_color: light.color.extend(0.0)
         }
     }
 }
@@ -59,17 +97,107 @@ impl RawBufferRepresentable for Locals {
 unsafe impl Pod for Locals {}
 unsafe impl Zeroable for Locals {}
 
+/// Represents a point light packed to match the WGSL struct.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PackedPointLight {
+    // The world position of the light, w contains the radius.
+    position_radius: [f32; 4],
+
+    // The RGB color of the light, w contains the intensity.
+    color_intensity: [f32; 4]
+}
+
+/// Represents all the point lights sent to the GPU.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PointLights {
+    // The lights, only the first `count` are used.
+    lights: [PackedPointLight; MAX_POINT_LIGHTS],
+
+    // The number of valid lights.
+    count: u32,
+
+    // The uniforms must be 16 bytes aligned.
+    _padding: [u32; 3]
+}
+
+impl PointLights {
+    /// Adds a new light, returns false if the maximum number of lights was
+    /// already reached.
+    ///
+    /// # Arguments
+    ///
+    /// `position` - The world position of the light.
+    /// `light` - The light to be added.
+    pub fn push(&mut self, position: Vector3<f32>, light: &PointLight) -> bool {
+        let index = self.count as usize;
+        if index == MAX_POINT_LIGHTS {
+            return false;
+        }
+
+        self.lights[index] = PackedPointLight {
+            position_radius: position.extend(light.radius).into(),
+            color_intensity: light.color.extend(light.intensity).into()
+        };
+        self.count += 1;
+        true
+    }
+}
+
+/// Implements `RawBufferRepresentable` for the point lights.
+impl RawBufferRepresentable for PointLights {
+    /// Maps the content of the point lights to an array of Bytes.
+    fn get_raw<'a>(&'a self) -> Bytes<'a> {
+        Bytes(bytemuck::bytes_of(self))
+    }
+}
+
+unsafe impl Pod for PackedPointLight {}
+unsafe impl Zeroable for PackedPointLight {}
+unsafe impl Pod for PointLights {}
+unsafe impl Zeroable for PointLights {}
+
 /// Contains the initialization of the locals in Gpu.
 ///
 /// TODO(Angel): Find a better name for this.
 pub struct LocalsLayout {
     pub group: BindGroup,
-    pub layout: BindGroupLayout
+    /// The layout shared with the pipelines, it comes from the
+    /// `BindGroupLayoutCache`.
+    pub layout: Arc<BindGroupLayout>
 }
 
 /// Wrapps the locals buffer pointer.
 /// This is be exposed in the world as a resource.
-pub struct LocalsBuffer(pub Buffer);
+pub struct LocalsBuffer(pub Arc<Buffer>);
+
+/// Wrapps the point lights buffer, it is shared by all the cameras.
+pub struct PointLightsBuffer(pub Arc<Buffer>);
+
+/// Contains all the information needed to render one camera into its
+/// render target.
+pub struct CameraPass {
+    /// The buffer which contains the camera locals.
+    pub buffer: Arc<Buffer>,
+
+    /// The bind group used to bind the buffer into the pipelines.
+    pub group: BindGroup,
+
+    /// The texture where the camera renders.
+    pub color: Arc<Texture>,
+
+    /// The depth texture used by the camera.
+    pub depth: Arc<Texture>
+}
+
+/// Contains a locals buffer and bind group per camera entity, the main
+/// camera uses `LocalsBuffer` and `LocalsLayout`.
+#[derive(Default)]
+pub struct CameraLocalsPool {
+    /// Contains all the passes associated with the camera entities.
+    pub passes: HashMap<Entity, CameraPass>
+}
 
 /// Creates and return the locals bind group layout.
 ///
@@ -82,10 +210,10 @@ fn create_locals_bind_group_layout_entry() -> BindGroupLayoutEntry {
         // Normaly in wgsl that is extracted using the [[group(0), binding(0))]]
         // where 0 is the position.
         binding: LOCAL_BINDING_POSITION,
-        // Where the information is visible, in this case it is only visible
-        // for the vertex stage, we do not need the camera transformation 
-        // in the frag for now.
-        visibility: ShaderStage::VERTEX, 
+        // Where the information is visible, the camera transformation is
+        // used by the vertex stage and the voxel culling, the lights by the
+        // fragment stage.
+        visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT | ShaderStage::COMPUTE,
         // Defines the type of allocation that is needed, in this case is just
         // a camera so a normal buffer is ok, also we can send images if needed
         // if we need to do some specific task and extract information form a
@@ -104,71 +232,121 @@ fn create_locals_bind_group_layout_entry() -> BindGroupLayoutEntry {
     }
 }
 
+/// Creates and return the point lights bind group layout entry, the lights
+/// are only used by the fragment stage.
+fn create_point_lights_bind_group_layout_entry() -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding: POINT_LIGHTS_BINDING_POSITION,
+        visibility: ShaderStage::FRAGMENT,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None
+    }
+}
+
 /// Creates and returns the local bind group layout
 ///
 /// # Arguments
 ///
 /// `gpu` - The gpu used to generate the bing group.
-fn create_locals_bind_group_layout(gpu: &Gpu) -> BindGroupLayout {
+pub(crate) fn create_locals_bind_group_layout(gpu: &Gpu) -> BindGroupLayout {
     gpu.create_bind_group_layout(
         &BindGroupLayoutDescriptor {
             entries: &[
-                create_locals_bind_group_layout_entry()
+                create_locals_bind_group_layout_entry(),
+                create_point_lights_bind_group_layout_entry()
             ],
             label: None
         }
     )
 }
 
-/// Creates and returns a new locals bind group.
+/// Creates and returns a new locals bind group using an existing layout.
 ///
 /// # Arguments
 ///
 /// `gpu` - The gpu used to create the bind group.
+/// `layout` - The locals layout.
 /// `buffer` - The buffer address which conatins the information.
-fn create_locals_bind_group(gpu: &Gpu,
-                            buffer: &Buffer) -> (BindGroup, BindGroupLayout) {
-    // Create the layout for the bind group.
-    let layout = create_locals_bind_group_layout(gpu);
-    // Create the bind group.
-    let bind_group = gpu.create_bind_group(&BindGroupDescriptor {
-        layout: &layout,
+/// `point_lights` - The buffer which contains the point lights.
+pub fn create_locals_bind_group_with_layout(gpu: &Gpu,
+                                            layout: &BindGroupLayout,
+                                            buffer: &Buffer,
+                                            point_lights: &Buffer) -> BindGroup {
+    gpu.create_bind_group(&BindGroupDescriptor {
+        layout,
         entries: &[
             BindGroupEntry {
                 binding: LOCAL_BINDING_POSITION,
                 resource: buffer.as_entire_binding() 
+            },
+            BindGroupEntry {
+                binding: POINT_LIGHTS_BINDING_POSITION,
+                resource: point_lights.as_entire_binding()
             }
         ],
         label: None
-    });
-
-    (bind_group, layout)
+    })
 }
 
 /// Initialize the locals.
 ///
+/// The `BindGroupLayoutCache` must be registered before, the locals layout
+/// is taken from it.
+///
 /// # Arguments
 ///
 /// `gpu` - The gpu to be used to generate the buffers and layouts.
 /// `world` - The world used to register the resources.
 pub fn initialize_locals(gpu: &Gpu, world: &DefaultWorld) { 
     // Create a new locals in order to get memory layout and default data.
-    let locals: Locals = Locals::new();
+    let locals: Locals = Locals::new(
+        &Camera::default(),
+        &DirectionalLight::default()
+    );
 
     // Allocate space in GPU for locals data and get the reference.
     let locals_buffer: Buffer = gpu.create_uniform(locals);
 
-    // Create the locals bind group.
-    let (l_bind_group, l_bind_group_layout) = create_locals_bind_group(
+    // The point lights are filled every frame.
+    let point_lights_buffer: Buffer = gpu.create_uniform(PointLights::default());
+
+    // Create the locals bind group, the layout is shared with the pipelines.
+    let l_bind_group_layout = bind_group_layout(gpu, world, BindGroupLayoutKey::Locals);
+    let l_bind_group = create_locals_bind_group_with_layout(
         gpu,
-        &locals_buffer
+        &l_bind_group_layout,
+        &locals_buffer,
+        &point_lights_buffer
     );
 
     // Register the resource in the world.
-    world.register_unique(LocalsBuffer(locals_buffer));
+    world.register_unique(LocalsBuffer(Arc::new(locals_buffer)));
+    world.register_unique(PointLightsBuffer(Arc::new(point_lights_buffer)));
+    // Keep the last locals written in the buffer, used to know if the buffer
+    // needs to be updated.
+    world.register_unique(locals);
     // Reguster the locals layout.
     world.register_unique(LocalsLayout {
         group: l_bind_group,
         layout: l_bind_group_layout
     });
-}
\ No newline at end of file
+    // Register the pool used by the camera entities.
+    world.register_unique(CameraLocalsPool::default());
+}
+#[test]
+fn point_lights_capacity() {
+    // Must match the size of the WGSL struct.
+    assert_eq!(std::mem::size_of::<PointLights>(), 32 * MAX_POINT_LIGHTS + 16);
+
+    let mut lights = PointLights::default();
+    let light = PointLight::default();
+    for _ in 0..MAX_POINT_LIGHTS {
+        assert!(lights.push(Vector3 { x: 0.0, y: 0.0, z: 0.0 }, &light));
+    }
+    assert!(!lights.push(Vector3 { x: 0.0, y: 0.0, z: 0.0 }, &light));
+    assert_eq!(lights.count as usize, MAX_POINT_LIGHTS);
+}
@@ -1,3 +1,4 @@
+pub mod layout_cache;
 pub mod locals_bind_group;
 
 use wgpu::{
@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use cgmath::{Point3, Vector3, InnerSpace};
+use bytemuck::{Pod, Zeroable};
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    PrimitiveTopology,
+    VertexBufferLayout,
+    VertexAttribute,
+    VertexFormat,
+    InputStepMode,
+    BufferAddress,
+    DepthStencilState,
+    CompareFunction,
+    StencilState,
+    DepthBiasState,
+    Buffer
+};
+
+use ecs::DefaultWorld;
+use log::{info, warning};
+
+use crate::graphics::{
+    gpu::Gpu,
+    buffer::BufferCreator,
+    pipelines::{
+        bind_groups::layout_cache::{BindGroupLayoutKey, bind_group_layout},
+        pipeline_cache::shader_module
+    }
+};
+
+/// The maximum number of debug vertices drawn per frame, each line uses two.
+pub const MAX_DEBUG_VERTICES: u32 = 65536;
+
+/// Represents one end of a debug line.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugVertex {
+    position: [f32; 3],
+    color: [f32; 4]
+}
+
+unsafe impl Pod for DebugVertex {}
+unsafe impl Zeroable for DebugVertex {}
+
+/// Accumulates the debug lines drawn in the current frame.
+///
+/// The lines are drawn in world space by the `debug_draw_renderer_system`
+/// and removed at the end of the frame, so they must be added every frame.
+#[derive(Default)]
+pub struct DebugDraw {
+    /// The vertices of the lines, two per line.
+    vertices: Vec<DebugVertex>,
+
+    /// Defines if the vertex budget was already exceeded, it is only
+    /// reported once.
+    overflow_reported: bool
+}
+
+impl DebugDraw {
+    /// Adds a line.
+    ///
+    /// # Arguments
+    ///
+    /// `from` - The start of the line.
+    /// `to` - The end of the line.
+    /// `color` - The RGBA color of the line.
+    pub fn line(&mut self, from: Point3<f32>, to: Point3<f32>, color: [f32; 4]) {
+        if self.vertices.len() + 2 > MAX_DEBUG_VERTICES as usize {
+            if !self.overflow_reported {
+                warning("{DebugDraw} Too many debug lines, some are not rendered");
+                self.overflow_reported = true;
+            }
+            return;
+        }
+
+        self.vertices.push(DebugVertex { position: from.into(), color });
+        self.vertices.push(DebugVertex { position: to.into(), color });
+    }
+
+    /// Adds the twelve edges of an axis aligned box.
+    ///
+    /// # Arguments
+    ///
+    /// `min` - The minimum corner of the box.
+    /// `max` - The maximum corner of the box.
+    /// `color` - The RGBA color of the lines.
+    pub fn aabb(&mut self, min: Point3<f32>, max: Point3<f32>, color: [f32; 4]) {
+        let corner = |x: bool, y: bool, z: bool| Point3 {
+            x: if x { max.x } else { min.x },
+            y: if y { max.y } else { min.y },
+            z: if z { max.z } else { min.z }
+        };
+
+        for a in [false, true].iter() {
+            for b in [false, true].iter() {
+                // One edge along each axis.
+                self.line(corner(false, *a, *b), corner(true, *a, *b), color);
+                self.line(corner(*a, false, *b), corner(*a, true, *b), color);
+                self.line(corner(*a, *b, false), corner(*a, *b, true), color);
+            }
+        }
+    }
+
+    /// Adds a line which starts at `origin` and goes along `direction`.
+    ///
+    /// # Arguments
+    ///
+    /// `origin` - The start of the ray.
+    /// `direction` - The direction of the ray, it does not need to be
+    /// normalized.
+    /// `length` - The length of the line.
+    /// `color` - The RGBA color of the line.
+    pub fn ray(&mut self,
+               origin: Point3<f32>,
+               direction: Vector3<f32>,
+               length: f32,
+               color: [f32; 4]) {
+        if direction.magnitude2() == 0.0 {
+            return;
+        }
+
+        self.line(origin, origin + direction.normalize() * length, color);
+    }
+
+    /// Returns the vertices added in the current frame.
+    pub fn vertices(&self) -> &[DebugVertex] {
+        &self.vertices
+    }
+
+    /// Removes all the lines.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+}
+
+/// Contains the pipeline used to draw the debug lines.
+pub struct DebugLinePipeline {
+    /// Contains the Wgpu pipeline.
+    pub pipeline: RenderPipeline,
+
+    /// Contains the vertices of the lines, it is written every frame.
+    pub vertex_buffer: Arc<Buffer>
+}
+
+impl DebugLinePipeline {
+    /// Creates and returns a new debug line pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the locals layout.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
+        info("Creating DebugLinePipeline");
+
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+
+        let locals_layout = bind_group_layout(gpu, world, BindGroupLayoutKey::Locals);
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[locals_layout.as_ref()],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let pipeline = gpu.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Debug line pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader_module,
+                entry_point: "vs_main",
+                buffers: &[create_vertex_layout()]
+            },
+            fragment: Some(FragmentState {
+                module: &shader_module,
+                entry_point: "fs_main",
+                targets: &[gpu.scene_format().into()],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            // The lines are hidden by the voxels but they do not hide
+            // anything.
+            depth_stencil: Some(DepthStencilState {
+                format: gpu.depth_format,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+                clamp_depth: false
+            }),
+            multisample: wgpu::MultisampleState::default(),
+        });
+
+        let vertex_size = std::mem::size_of::<DebugVertex>() as u64;
+        let vertex_buffer = Arc::new(gpu.create_vertex_with_size(
+            MAX_DEBUG_VERTICES as u64 * vertex_size
+        ));
+
+        info("DebugLinePipeline created");
+
+        Self {
+            pipeline,
+            vertex_buffer
+        }
+    }
+}
+
+/// Creates and returns the layout of `DebugVertex`.
+fn create_vertex_layout<'a>() -> VertexBufferLayout<'a> {
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<DebugVertex>() as BufferAddress,
+        step_mode: InputStepMode::Vertex,
+        attributes: &[
+            // The position.
+            VertexAttribute {
+                format: VertexFormat::Float3,
+                offset: 0,
+                shader_location: 0
+            },
+            // The color, placed after the position.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: std::mem::size_of::<[f32; 3]>() as BufferAddress,
+                shader_location: 1
+            }
+        ]
+    }
+}
+
+/// The WGSL source of the debug lines.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/debug_line_shader.wgsl");
+
+#[test]
+fn debug_draw_budget() {
+    let mut debug_draw = DebugDraw::default();
+    let origin = Point3 { x: 0.0, y: 0.0, z: 0.0 };
+    let max = Point3 { x: 1.0, y: 1.0, z: 1.0 };
+
+    debug_draw.aabb(origin, max, [1.0; 4]);
+    assert_eq!(debug_draw.vertices().len(), 24);
+
+    for _ in 0..MAX_DEBUG_VERTICES {
+        debug_draw.line(origin, max, [1.0; 4]);
+    }
+    assert_eq!(debug_draw.vertices().len(), MAX_DEBUG_VERTICES as usize);
+    assert!(debug_draw.overflow_reported);
+
+    debug_draw.clear();
+    assert!(debug_draw.vertices().is_empty());
+}
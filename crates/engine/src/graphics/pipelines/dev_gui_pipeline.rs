@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    ShaderStage,
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingResource,
+    BindingType,
+    BufferBindingType,
+    TextureSampleType,
+    TextureViewDimension,
+    TextureFormat,
+    ColorTargetState,
+    ColorWrite,
+    BlendState,
+    BlendFactor,
+    BlendOperation,
+    VertexBufferLayout,
+    VertexAttribute,
+    VertexFormat,
+    InputStepMode,
+    BufferAddress,
+    Buffer
+};
+
+use ecs::DefaultWorld;
+use types::Size;
+use log::info;
+
+use crate::{
+    basics::dev_gui::GuiQuad,
+    graphics::{
+        gpu::Gpu,
+        buffer::BufferCreator,
+        pipelines::{
+            bind_groups::BindGroupGenerator,
+            pipeline_cache::shader_module,
+            sprite_pipeline::SpriteLocals
+        },
+        texture::{Texture, TextureGenerator},
+        font::{build_atlas, ATLAS_WIDTH, ATLAS_HEIGHT}
+    }
+};
+
+/// The maximum number of quads of the dev GUI drawn per frame.
+pub const MAX_GUI_QUADS: u32 = 65536;
+
+/// Contains the pipeline which draws the dev GUI.
+///
+/// The glyphs and the rectangles sample the font atlas, so the whole GUI is
+/// drawn with a single draw call.
+pub struct DevGuiPipeline {
+    /// Contains the Wgpu pipeline.
+    pub pipeline: RenderPipeline,
+
+    /// Contains the 4 corners of the quad.
+    pub vertex_buffer: Buffer,
+
+    /// Contains the 2 triangles of the quad.
+    pub index_buffer: Buffer,
+
+    /// Contains the per quad data.
+    pub instances_buffer: Arc<Buffer>,
+
+    /// Contains the screen projection, it uses the same layout as the
+    /// sprites.
+    pub locals_buffer: Arc<Buffer>,
+
+    /// The bind group which contains the locals and the font atlas.
+    pub group: BindGroup,
+
+    /// The font atlas, it is never read but it must live as long as the
+    /// bind group.
+    _atlas: Texture
+}
+
+impl DevGuiPipeline {
+    /// Creates and returns a new dev GUI pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the pipeline cache.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
+        info("Creating DevGuiPipeline");
+
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+
+        // The corners of the quad, the quad is placed in the shader.
+        let corners: Vec<[f32; 2]> = vec![
+            [0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]
+        ];
+        let indices: Vec<u16> = vec![0, 1, 2, 2, 3, 0];
+        let vertex_buffer = gpu.create_vertex(corners);
+        let index_buffer = gpu.create_index(indices);
+
+        let instances_buffer = Arc::new(gpu.create_vertex_with_size(
+            MAX_GUI_QUADS as u64 * std::mem::size_of::<GuiQuad>() as u64
+        ));
+
+        let size = Size::new(gpu.swap_chain_descriptor.width, gpu.swap_chain_descriptor.height);
+        let locals_buffer = Arc::new(gpu.create_uniform(SpriteLocals::new(size)));
+
+        let atlas = gpu.create_texture_with_data(
+            "Dev GUI font atlas",
+            Size::new(ATLAS_WIDTH, ATLAS_HEIGHT),
+            TextureFormat::R8Unorm,
+            1,
+            &build_atlas()
+        );
+
+        let layout = gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStage::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: true }
+                    },
+                    count: None
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Sampler {
+                        comparison: false,
+                        filtering: true
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Dev GUI bind group layout")
+        });
+
+        let group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: locals_buffer.as_entire_binding()
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&atlas.view)
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&atlas.sampler)
+                }
+            ],
+            label: Some("Dev GUI bind group")
+        });
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let pipeline: RenderPipeline = gpu.create_render_pipeline(
+            &RenderPipelineDescriptor {
+                label: Some("Dev GUI pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[
+                        create_corner_layout(),
+                        create_instance_layout()
+                    ]
+                },
+                fragment: Some(FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    // The GUI is drawn after the tone mapping, so it uses
+                    // the swap chain format and blends with the scene.
+                    targets: &[ColorTargetState {
+                        format: gpu.swap_chain_format(),
+                        alpha_blend: BlendState::REPLACE,
+                        color_blend: BlendState {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::OneMinusSrcAlpha,
+                            operation: BlendOperation::Add
+                        },
+                        write_mask: ColorWrite::ALL
+                    }],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+            }
+        );
+
+        info("DevGuiPipeline created");
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            instances_buffer,
+            locals_buffer,
+            group,
+            _atlas: atlas
+        }
+    }
+}
+
+/// The WGSL source of the dev GUI pipeline.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/dev_gui_shader.wgsl");
+
+/// Creates and returns the layout of the quad corners.
+fn create_corner_layout<'a>() -> VertexBufferLayout<'a> {
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+        step_mode: InputStepMode::Vertex,
+        attributes: &[
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: 0,
+                shader_location: 0
+            }
+        ]
+    }
+}
+
+/// Creates and returns the layout of `GuiQuad`.
+fn create_instance_layout<'a>() -> VertexBufferLayout<'a> {
+    const FLOAT_SIZE: BufferAddress = std::mem::size_of::<f32>() as BufferAddress;
+
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<GuiQuad>() as BufferAddress,
+        step_mode: InputStepMode::Instance,
+        attributes: &[
+            // The position.
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: 0,
+                shader_location: 1
+            },
+            // The size.
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: FLOAT_SIZE * 2,
+                shader_location: 2
+            },
+            // The atlas rect.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: FLOAT_SIZE * 4,
+                shader_location: 3
+            },
+            // The color.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: FLOAT_SIZE * 8,
+                shader_location: 4
+            }
+        ]
+    }
+}
+
+#[test]
+fn gui_quad_layout() {
+    assert_eq!(std::mem::size_of::<GuiQuad>(), 48);
+}
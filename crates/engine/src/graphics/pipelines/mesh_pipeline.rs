@@ -0,0 +1,145 @@
+use std::{
+    ops::Range,
+    sync::Arc
+};
+
+use cgmath::Vector3;
+
+use wgpu::{RenderPipeline, Buffer};
+
+use ecs::DefaultWorld;
+use log::info;
+
+use crate::graphics::{
+    gpu::Gpu,
+    mesh::Mesh,
+    buffer::BufferCreator,
+    pipelines::voxel_render_pipeline::{
+        create_voxel_render_pipeline,
+        allocate_transformations_buffer
+    }
+};
+
+/// The maximum number of mesh instances drawn per frame.
+pub const MAX_MESH_INSTANCES: u32 = 65536;
+
+/// Represents a mesh uploaded to the GPU.
+pub struct GpuMesh {
+    /// Contains the vertices of the mesh.
+    pub vertex_buffer: Buffer,
+
+    /// Contains the indices of the mesh.
+    pub index_buffer: Buffer,
+
+    /// Contains the number of indices in the index buffer.
+    pub index_len: u32,
+
+    /// The color of all the instances of the mesh.
+    pub color: Vector3<f32>
+}
+
+/// Contains all the meshes which can be drawn, the `MeshHandle` component
+/// is the position of the mesh in the registry.
+#[derive(Default)]
+pub struct MeshRegistry {
+    pub meshes: Vec<GpuMesh>
+}
+
+impl MeshRegistry {
+    /// Uploads the mesh to the GPU and returns its id.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the buffers.
+    /// `mesh` - The mesh to be uploaded.
+    pub fn register(&mut self, gpu: &Gpu, mesh: Mesh) -> usize {
+        let index_len = mesh.indices.len() as u32;
+        self.meshes.push(GpuMesh {
+            vertex_buffer: gpu.create_vertex(mesh.vertices),
+            index_buffer: gpu.create_index(mesh.indices),
+            index_len,
+            color: mesh.color
+        });
+        self.meshes.len() - 1
+    }
+}
+
+/// Represents the mesh id and the range of instances drawn with it.
+pub type MeshBatch = (usize, Range<u32>);
+
+/// Sorts the instances by mesh and returns the transformations along with
+/// the range of instances of each mesh.
+///
+/// # Arguments
+///
+/// `instances` - The mesh id and the world transformation of each instance.
+pub fn batch_by_mesh(
+    mut instances: Vec<(usize, [[f32; 4]; 4])>
+) -> (Vec<[[f32; 4]; 4]>, Vec<MeshBatch>) {
+    // Stable, so the instances of a mesh keep the same order every frame.
+    instances.sort_by_key(|(mesh, _)| *mesh);
+
+    let mut batches: Vec<MeshBatch> = Vec::new();
+    for (index, (mesh, _)) in instances.iter().enumerate() {
+        let index = index as u32;
+        match batches.last_mut() {
+            Some((last, range)) if last == mesh => range.end = index + 1,
+            _ => batches.push((*mesh, index..index + 1))
+        }
+    }
+
+    (instances.into_iter().map(|(_, t)| t).collect(), batches)
+}
+
+/// Contains the pipeline which draws the meshes, it uses the voxel shader so
+/// the meshes are lit in the same way.
+pub struct MeshRenderPipeline {
+    /// Contains the Wgpu pipeline, it is shared with the voxel pipeline.
+    pub pipeline: Arc<RenderPipeline>,
+
+    /// Contains the transformations of all the instances, sorted by mesh.
+    pub transformations_buffer: Arc<Buffer>,
+
+    /// Contains the color of each instance.
+    pub colors_buffer: Arc<Buffer>
+}
+
+impl MeshRenderPipeline {
+    /// Creates and returns a new mesh pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the locals layout.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
+        info("Creating MeshRenderPipeline");
+
+        // The meshes use the same shader, layouts and vertex buffers as the
+        // voxels, so the pipeline is taken from the cache.
+        let pipeline = create_voxel_render_pipeline(gpu, world);
+
+        info("MeshRenderPipeline created");
+
+        let colors_size = MAX_MESH_INSTANCES as u64 * std::mem::size_of::<[f32; 3]>() as u64;
+
+        Self {
+            pipeline,
+            transformations_buffer: allocate_transformations_buffer(gpu, MAX_MESH_INSTANCES),
+            colors_buffer: Arc::new(gpu.create_vertex_with_size(colors_size))
+        }
+    }
+}
+
+#[test]
+fn instances_are_batched_by_mesh() {
+    let transform = |v: f32| [[v; 4]; 4];
+    let (transforms, batches) = batch_by_mesh(vec![
+        (2, transform(0.0)),
+        (0, transform(1.0)),
+        (2, transform(2.0)),
+        (0, transform(3.0))
+    ]);
+
+    assert_eq!(batches, vec![(0, 0..2), (2, 2..4)]);
+    assert_eq!(transforms, vec![transform(1.0), transform(3.0), transform(0.0), transform(2.0)]);
+}
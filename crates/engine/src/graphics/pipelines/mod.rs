@@ -1,21 +1,154 @@
 pub mod bind_groups;
+pub mod debug_line_pipeline;
+pub mod dev_gui_pipeline;
+pub mod mesh_pipeline;
+pub mod outline_pipeline;
+pub mod pipeline_cache;
+pub mod sky_pipeline;
+pub mod sprite_pipeline;
+pub mod text_pipeline;
+pub mod tone_mapping_pipeline;
+pub mod voxel_culling;
 pub mod voxel_render_pipeline;
 
+use std::{sync::Arc, time::Instant};
+
 use ecs::{DefaultWorld, ComponentHandler};
+use log::info;
 
 use crate::{
 	graphics::{
 		gpu::Gpu,
-		pipelines::voxel_render_pipeline::VoxelRenderPipeline
+		buffer::BufferCreator,
+		texture::{Texture, TextureGenerator, HdrTexture},
+		pipelines::{
+			voxel_render_pipeline::{VoxelRenderPipeline, ResizeVoxelBuffer},
+			outline_pipeline::{OutlineRenderPipeline, OutlineColor},
+			debug_line_pipeline::{DebugLinePipeline, DebugDraw},
+			mesh_pipeline::{MeshRenderPipeline, MeshRegistry},
+			sprite_pipeline::{SpriteRenderPipeline, SpriteRenderer},
+			text_pipeline::TextRenderPipeline,
+			sky_pipeline::{SkyPipeline, SkyUniform, SkyUniformBuffer},
+			tone_mapping_pipeline::{
+				ToneMapping,
+				ToneMappingPipeline,
+				ToneMappingOperator
+			},
+			pipeline_cache::{PipelineCache, compile_shaders}
+		}
+	},
+	scene::{
+		camera::Camera,
+		light::{SunPosition, SkySettings}
 	}
 };
 
+/// Returns the WGSL sources of the pipelines created by the engine.
+///
+/// # Arguments
+///
+/// `hdr` - Defines if the tone mapping shader is needed.
+fn engine_shaders(hdr: bool) -> Vec<&'static str> {
+	let mut sources = vec![
+		voxel_render_pipeline::SHADER_SOURCE,
+		voxel_culling::SHADER_SOURCE,
+		outline_pipeline::SHADER_SOURCE,
+		debug_line_pipeline::SHADER_SOURCE,
+		sprite_pipeline::SHADER_SOURCE,
+		text_pipeline::SHADER_SOURCE,
+		sky_pipeline::SHADER_SOURCE
+	];
+	if hdr {
+		sources.push(tone_mapping_pipeline::SHADER_SOURCE);
+	}
+	sources
+}
+
+/// Creates the pipeline and logs the time it took, in this way the
+/// startup time of each pipeline can be compared.
+///
+/// # Arguments
+///
+/// `name` - The name of the pipeline.
+/// `create` - Creates the pipeline.
+fn timed<T, F: FnOnce() -> T>(name: &str, create: F) -> T {
+	let start = Instant::now();
+	let pipeline = create();
+	info(&format!(
+		"{{Pipelines}} {} built in {:.1} ms",
+		name,
+		start.elapsed().as_secs_f32() * 1000.0
+	));
+	pipeline
+}
+
 /// Inits all the default pipelines available in the engine.
 ///
+/// The shaders are compiled in parallel first, the pipelines take them from
+/// the `PipelineCache`, so the `Gpu` must be already registered in the
+/// world.
+///
 /// # Arguments
 ///
+/// `gpu` - The gpu used to create the pipelines.
 /// `world` - The world where the pipelines will be stored.
 pub fn initialize_pipelines(gpu: &Gpu, world: &DefaultWorld) {
+	world.register_unique(PipelineCache::default());
+	timed("Shaders", || compile_shaders(world, &engine_shaders(gpu.hdr)));
+
 	// Create and set the voxel pipeline.
-	world.register_unique(VoxelRenderPipeline::new(gpu, world));
+	world.register_unique(timed("VoxelRenderPipeline", || VoxelRenderPipeline::new(gpu, world)));
+	world.register_unique(ResizeVoxelBuffer::default());
+
+	// Create and set the meshes, they are registered later by the
+	// application.
+	world.register_unique(timed("MeshRenderPipeline", || MeshRenderPipeline::new(gpu, world)));
+	world.register_unique(MeshRegistry::default());
+
+	// Create and set the outline of the selected voxels.
+	world.register_unique(timed("OutlineRenderPipeline", || OutlineRenderPipeline::new(gpu, world)));
+	world.register_unique(OutlineColor::default());
+
+	// Create and set the debug lines, they are added by the application.
+	world.register_unique(timed("DebugLinePipeline", || DebugLinePipeline::new(gpu, world)));
+	world.register_unique(DebugDraw::default());
+
+	// Create and set the sprites, the textures are registered later by the
+	// application.
+	let sprite_renderer = SpriteRenderer::new(gpu, world);
+	world.register_unique(timed("SpriteRenderPipeline", || {
+		SpriteRenderPipeline::new(gpu, world, &sprite_renderer)
+	}));
+	world.register_unique(sprite_renderer);
+
+	// Create and set the text labels, the font atlas is created once.
+	world.register_unique(timed("TextRenderPipeline", || TextRenderPipeline::new(gpu, world)));
+
+	// The sky uniform is kept to know when the buffer must be updated.
+	let sky_uniform = SkyUniform::new(
+		&Camera::default(),
+		&SunPosition::default(),
+		&SkySettings::default()
+	);
+	let sky_buffer = gpu.create_uniform(sky_uniform);
+	world.register_unique(timed("SkyPipeline", || SkyPipeline::new(gpu, world, &sky_buffer)));
+	world.register_unique(SkyUniformBuffer(Arc::new(sky_buffer)));
+	world.register_unique(sky_uniform);
+
+	// When HDR is enabled the scene is rendered into a float texture which
+	// is tone mapped into the swap chain.
+	let hdr_texture: Option<Texture> = match gpu.hdr {
+		true => Some(gpu.create_hdr_texture()),
+		false => None
+	};
+	let cache = world.get_unique::<PipelineCache>();
+	let tone_mapping = hdr_texture
+		.as_ref()
+		.map(|texture| timed("ToneMappingPipeline", || {
+			ToneMappingPipeline::new(gpu, &cache.read(), texture)
+		}));
+
+	world.register_unique(ToneMapping(tone_mapping));
+	world.register_unique(HdrTexture(hdr_texture));
+	world.register_unique(ToneMappingOperator::default());
 }
\ No newline at end of file
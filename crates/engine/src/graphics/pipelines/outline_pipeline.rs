@@ -0,0 +1,278 @@
+use std::sync::Arc;
+
+use cgmath::Vector3;
+use bytemuck::{Pod, Zeroable};
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayout,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    ShaderModule,
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    ColorTargetState,
+    ColorWrite,
+    BlendState,
+    DepthStencilState,
+    DepthBiasState,
+    StencilState,
+    StencilFaceState,
+    StencilOperation,
+    CompareFunction,
+    Buffer
+};
+
+use ecs::DefaultWorld;
+use types::Bytes;
+use log::info;
+
+use crate::graphics::{
+    gpu::Gpu,
+    buffer::{BufferCreator, RawBufferRepresentable},
+    pipelines::{
+        pipeline_cache::shader_module,
+        bind_groups::{
+            BindGroupGenerator,
+            layout_cache::{BindGroupLayoutKey, bind_group_layout}
+        },
+        voxel_render_pipeline::{
+            create_vertex_layout,
+            create_transformation_layout,
+            allocate_transformations_buffer
+        }
+    },
+    texture::has_stencil
+};
+
+/// The maximum number of selected voxels which are outlined.
+pub const MAX_OUTLINED_VOXELS: u32 = 4096;
+
+/// The value written in the stencil buffer by the selected voxels.
+pub const OUTLINE_STENCIL_REFERENCE: u32 = 1;
+
+/// Defines the color of the outline drawn around the `Selected` voxels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutlineColor(pub Vector3<f32>);
+
+impl Default for OutlineColor {
+    /// Creates and returns a new orange `OutlineColor`.
+    fn default() -> Self {
+        OutlineColor(Vector3 { x: 1.0, y: 0.6, z: 0.0 })
+    }
+}
+
+/// Represents the uniform sent to the outline shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlineSettings {
+    /// The RGB color of the outline, w is not used.
+    color: [f32; 4]
+}
+
+impl OutlineSettings {
+    /// Creates and returns new settings for the provided color.
+    ///
+    /// # Arguments
+    ///
+    /// `color` - The color of the outline.
+    pub fn new(color: OutlineColor) -> Self {
+        Self {
+            color: color.0.extend(1.0).into()
+        }
+    }
+}
+
+/// Implements `RawBufferRepresentable` for the outline settings.
+impl RawBufferRepresentable for OutlineSettings {
+    /// Maps the content of the settings to an array of Bytes.
+    fn get_raw<'a>(&'a self) -> Bytes<'a> {
+        Bytes(bytemuck::bytes_of(self))
+    }
+}
+
+unsafe impl Pod for OutlineSettings {}
+unsafe impl Zeroable for OutlineSettings {}
+
+/// Contains the two pipelines used to draw the outline of the selected
+/// voxels.
+///
+/// The mask pipeline marks the selected voxels in the stencil buffer and the
+/// outline pipeline draws them slightly enlarged only where the stencil was
+/// not marked.
+pub struct OutlineRenderPipeline {
+    /// Writes the selected voxels into the stencil buffer.
+    pub mask_pipeline: RenderPipeline,
+
+    /// Draws the enlarged voxels where the stencil is zero.
+    pub outline_pipeline: RenderPipeline,
+
+    /// The bind group which contains the outline settings.
+    pub group: BindGroup,
+
+    /// The buffer which contains the outline settings.
+    pub settings_buffer: Arc<Buffer>,
+
+    /// Contains the transformations of the selected voxels.
+    pub transformations_buffer: Arc<Buffer>
+}
+
+impl OutlineRenderPipeline {
+    /// Creates and returns a new outline pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the locals layout.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
+        info("Creating OutlineRenderPipeline");
+
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+
+        let settings_buffer = Arc::new(gpu.create_uniform(
+            OutlineSettings::new(OutlineColor::default())
+        ));
+
+        let layout = bind_group_layout(gpu, world, BindGroupLayoutKey::FragmentUniform);
+
+        let group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: settings_buffer.as_entire_binding()
+                }
+            ],
+            label: Some("Outline bind group")
+        });
+
+        let locals_layout = bind_group_layout(gpu, world, BindGroupLayoutKey::Locals);
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[locals_layout.as_ref(), layout.as_ref()],
+                push_constant_ranges: &[]
+            }
+        );
+
+        // The mask only writes the stencil, the color is not touched.
+        let mask_pipeline = create_pipeline(
+            gpu,
+            &pipeline_layout,
+            &shader_module,
+            "vs_mask",
+            ColorWrite::empty(),
+            StencilFaceState {
+                compare: CompareFunction::Always,
+                fail_op: StencilOperation::Keep,
+                depth_fail_op: StencilOperation::Keep,
+                pass_op: StencilOperation::Replace
+            }
+        );
+
+        // The outline is only drawn where the mask was not written.
+        let outline_pipeline = create_pipeline(
+            gpu,
+            &pipeline_layout,
+            &shader_module,
+            "vs_outline",
+            ColorWrite::ALL,
+            StencilFaceState {
+                compare: CompareFunction::NotEqual,
+                fail_op: StencilOperation::Keep,
+                depth_fail_op: StencilOperation::Keep,
+                pass_op: StencilOperation::Keep
+            }
+        );
+
+        info("OutlineRenderPipeline created");
+
+        Self {
+            mask_pipeline,
+            outline_pipeline,
+            group,
+            settings_buffer,
+            transformations_buffer: allocate_transformations_buffer(gpu, MAX_OUTLINED_VOXELS)
+        }
+    }
+}
+
+/// Creates and returns one of the outline pipelines.
+///
+/// Both pipelines ignore the depth, so the outline is visible even when the
+/// voxel is behind other voxels.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the pipeline.
+/// `layout` - The pipeline layout.
+/// `shader_module` - The outline shader.
+/// `vertex_entry` - The vertex entry point.
+/// `write_mask` - Defines which color channels are written.
+/// `stencil_face` - The stencil operations.
+fn create_pipeline(gpu: &Gpu,
+                   layout: &PipelineLayout,
+                   shader_module: &ShaderModule,
+                   vertex_entry: &str,
+                   write_mask: ColorWrite,
+                   stencil_face: StencilFaceState) -> RenderPipeline {
+    gpu.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("Outline pipeline"),
+        layout: Some(layout),
+        vertex: VertexState {
+            module: shader_module,
+            entry_point: vertex_entry,
+            buffers: &[
+                create_vertex_layout(),
+                create_transformation_layout()
+            ]
+        },
+        fragment: Some(FragmentState {
+            module: shader_module,
+            entry_point: "fs_main",
+            targets: &[ColorTargetState {
+                format: gpu.scene_format(),
+                alpha_blend: BlendState::REPLACE,
+                color_blend: BlendState::REPLACE,
+                write_mask
+            }],
+        }),
+        primitive: PrimitiveState {
+            cull_mode: wgpu::CullMode::Back,
+            ..Default::default()
+        },
+        depth_stencil: Some(DepthStencilState {
+            format: gpu.depth_format,
+            depth_write_enabled: false,
+            depth_compare: CompareFunction::Always,
+            // Without stencil the outline is drawn without mask.
+            stencil: match has_stencil(gpu.depth_format) {
+                true => StencilState {
+                    front: stencil_face.clone(),
+                    back: stencil_face,
+                    read_mask: 0xff,
+                    write_mask: 0xff
+                },
+                false => StencilState::default()
+            },
+            bias: DepthBiasState::default(),
+            clamp_depth: false
+        }),
+        multisample: wgpu::MultisampleState::default(),
+    })
+}
+
+/// The WGSL source of the outline pipeline.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/outline_shader.wgsl");
+
+#[test]
+fn outline_settings_layout() {
+    assert_eq!(std::mem::size_of::<OutlineSettings>(), 16);
+
+    let settings = OutlineSettings::new(OutlineColor(Vector3 { x: 0.5, y: 0.25, z: 1.0 }));
+    assert_eq!(settings.color, [0.5, 0.25, 1.0, 1.0]);
+}
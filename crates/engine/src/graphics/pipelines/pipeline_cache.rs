@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::Instant
+};
+
+use wgpu::{
+    CullMode,
+    FrontFace,
+    IndexFormat,
+    PolygonMode,
+    PrimitiveState,
+    PrimitiveTopology,
+    RenderPipeline,
+    ShaderModule
+};
+
+use ecs::{DefaultWorld, ComponentHandler, TaskSync, TaskWaitable};
+use tasks::{Workers, WorkersDescriptor, Dispatcher, TaskPriority};
+use log::info;
+
+use crate::graphics::{
+    gpu::Gpu,
+    shaders::{ShaderProvider, ShaderGenerator},
+    pipelines::bind_groups::layout_cache::BindGroupLayoutKey
+};
+
+/// Identifies a render pipeline, the pipelines which use the same shader,
+/// entry point, bind group layouts and primitive state share the compiled
+/// pipeline.
+///
+/// The vertex buffers and the targets are not part of the key, the vertex
+/// entry point defines them so the pipelines with the same entry point must
+/// use the same ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    /// The hash of the shader source, see `shader_hash`.
+    pub shader: u64,
+
+    /// The vertex entry point of the shader.
+    pub vertex_entry_point: &'static str,
+
+    /// The bind group layouts in the order they are bound.
+    pub layouts: Vec<BindGroupLayoutKey>,
+
+    /// The primitive state of the pipeline.
+    pub primitive: PrimitiveKey
+}
+
+/// Contains the fields of a `PrimitiveState`, `wgpu` does not implement
+/// `Hash` for it so it can not be used directly in the `PipelineKey`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrimitiveKey {
+    /// The primitive topology used to interpret vertices.
+    pub topology: PrimitiveTopology,
+
+    /// The index format of the strip topologies.
+    pub strip_index_format: Option<IndexFormat>,
+
+    /// The face considered the front.
+    pub front_face: FrontFace,
+
+    /// The faces which are culled.
+    pub cull_mode: CullMode,
+
+    /// Defines how the polygons are rasterized.
+    pub polygon_mode: PolygonMode
+}
+
+impl From<&PrimitiveState> for PrimitiveKey {
+    /// Creates and returns the key of the primitive state.
+    fn from(state: &PrimitiveState) -> Self {
+        Self {
+            topology: state.topology,
+            strip_index_format: state.strip_index_format,
+            front_face: state.front_face,
+            cull_mode: state.cull_mode,
+            polygon_mode: state.polygon_mode
+        }
+    }
+}
+
+/// Contains the shader modules and the render pipelines created so far, in
+/// this way the same WGSL source is compiled only once.
+///
+/// It is registered as a unique resource, the shaders of the engine are
+/// compiled in parallel by `compile_shaders` before the pipelines are
+/// created.
+#[derive(Default)]
+pub struct PipelineCache {
+    shaders: Mutex<HashMap<u64, Arc<ShaderModule>>>,
+    pipelines: Mutex<HashMap<PipelineKey, Arc<RenderPipeline>>>
+}
+
+impl PipelineCache {
+    /// Returns the shader compiled from the WGSL source, it is compiled the
+    /// first time it is requested.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to compile the shader.
+    /// `source` - The WGSL source.
+    pub fn shader(&self, gpu: &Gpu, source: &str) -> Arc<ShaderModule> {
+        get_or_insert_with(&self.shaders, shader_hash(source), || {
+            gpu.create_shader(&ShaderProvider::Wgsl(source.to_string()))
+        })
+    }
+
+    /// Returns the pipeline associated with the key, if it does not exist
+    /// it is created using the provided function.
+    ///
+    /// # Arguments
+    ///
+    /// `key` - The pipeline to be returned.
+    /// `create` - Creates the pipeline if it is not in the cache.
+    pub fn pipeline_or_insert_with<F: FnOnce() -> RenderPipeline>(
+        &self,
+        key: PipelineKey,
+        create: F) -> Arc<RenderPipeline> {
+        get_or_insert_with(&self.pipelines, key, create)
+    }
+}
+
+/// Returns the value associated with the key, if it does not exist it is
+/// created using the provided function.
+///
+/// The lock is not held while the value is created, so the workers can
+/// compile different shaders at the same time. If other thread created the
+/// same value meanwhile its value is kept.
+///
+/// # Arguments
+///
+/// `map` - The cached values.
+/// `key` - The value to be returned.
+/// `create` - Creates the value if it is not in the cache.
+fn get_or_insert_with<K, V, F>(map: &Mutex<HashMap<K, Arc<V>>>, key: K, create: F) -> Arc<V>
+where
+    K: Hash + Eq,
+    F: FnOnce() -> V {
+    if let Some(value) = map.lock().unwrap().get(&key) {
+        return value.clone();
+    }
+
+    let value = Arc::new(create());
+    map.lock().unwrap().entry(key).or_insert(value).clone()
+}
+
+/// Returns the hash used to identify a shader source.
+///
+/// # Arguments
+///
+/// `source` - The source of the shader.
+pub fn shader_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the shader compiled from the WGSL source using the cache
+/// registered in the world, it is used by the pipeline constructors.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to compile the shader.
+/// `world` - The world which contains the `PipelineCache`.
+/// `source` - The WGSL source.
+pub fn shader_module(gpu: &Gpu, world: &DefaultWorld, source: &str) -> Arc<ShaderModule> {
+    world.get_unique::<PipelineCache>().read().shader(gpu, source)
+}
+
+/// Compiles the shaders in parallel and stores them in the cache registered
+/// in the world, the function returns when all of them are compiled.
+///
+/// The `Gpu` must be already registered in the world, the workers take it
+/// from there.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the `Gpu` and the `PipelineCache`.
+/// `sources` - The WGSL sources to be compiled.
+pub fn compile_shaders(world: &DefaultWorld, sources: &[&'static str]) {
+    let mut workers = Workers::new(WorkersDescriptor {
+        name: "Crystal shader workers".to_string(),
+        ..WorkersDescriptor::default()
+    });
+    workers.start();
+
+    let mut syncs: Vec<Arc<TaskSync>> = Vec::with_capacity(sources.len());
+    for source in sources {
+        let source: &'static str = source;
+        let gpu = world.get_unique::<Gpu>();
+        let cache = world.get_unique::<PipelineCache>();
+        let sync = Arc::new(TaskSync::named("shader compilation"));
+        let task_sync = sync.clone();
+
+        workers.execute_dyn(Box::new(move || {
+            let start = Instant::now();
+            cache.read().shader(&gpu.read(), source);
+            info(&format!(
+                "{{PipelineCache}} Shader {:x} compiled in {:.1} ms",
+                shader_hash(source),
+                start.elapsed().as_secs_f32() * 1000.0
+            ));
+            task_sync.mark_as_finish();
+        }), TaskPriority::High, None);
+
+        syncs.push(sync);
+    }
+
+    syncs.wait();
+}
+
+#[test]
+fn same_source_compiles_once() {
+    let shaders: Mutex<HashMap<u64, Arc<u32>>> = Mutex::new(HashMap::new());
+    let mut compiled = 0;
+
+    // The voxel and the mesh pipelines use the same shader.
+    let voxel = get_or_insert_with(&shaders, shader_hash("voxel"), || { compiled += 1; 1 });
+    let mesh = get_or_insert_with(&shaders, shader_hash("voxel"), || { compiled += 1; 2 });
+    let sky = get_or_insert_with(&shaders, shader_hash("sky"), || { compiled += 1; 3 });
+
+    assert!(Arc::ptr_eq(&voxel, &mesh));
+    assert!(!Arc::ptr_eq(&voxel, &sky));
+    assert_eq!(compiled, 2);
+
+    let pipelines: Mutex<HashMap<PipelineKey, Arc<u32>>> = Mutex::new(HashMap::new());
+    let key = PipelineKey {
+        shader: shader_hash("voxel"),
+        vertex_entry_point: "vs_main",
+        layouts: vec![BindGroupLayoutKey::Locals],
+        primitive: PrimitiveKey::from(&PrimitiveState::default())
+    };
+    let first = get_or_insert_with(&pipelines, key.clone(), || 4);
+    let second = get_or_insert_with(&pipelines, key, || 5);
+    assert!(Arc::ptr_eq(&first, &second));
+}
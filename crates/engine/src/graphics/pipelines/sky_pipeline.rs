@@ -0,0 +1,200 @@
+use std::sync::Arc;
+
+use cgmath::{Matrix4, Vector4, SquareMatrix};
+use bytemuck::{Pod, Zeroable};
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    Buffer
+};
+
+use ecs::DefaultWorld;
+use types::Bytes;
+use log::info;
+
+use crate::{
+    graphics::{
+        gpu::Gpu,
+        buffer::RawBufferRepresentable,
+        pipelines::{
+            bind_groups::{
+                BindGroupGenerator,
+                layout_cache::{BindGroupLayoutKey, bind_group_layout}
+            },
+            pipeline_cache::shader_module
+        }
+    },
+    scene::{
+        camera::Camera,
+        light::{SunPosition, SkySettings}
+    }
+};
+
+/// Represents the uniforms sent to the sky shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyUniform {
+    /// The inverse of the camera view projection, used to calculate the
+    /// view ray of each fragment.
+    inv_view_proj: Matrix4<f32>,
+
+    /// The position of the camera, w is not used.
+    camera_position: Vector4<f32>,
+
+    /// The normalized direction from the ground to the sun.
+    sun_direction: [f32; 3],
+
+    /// The amount of haze in the atmosphere.
+    turbidity: f32,
+
+    /// The color of the sun disc in linear space.
+    sun_color: [f32; 3],
+
+    /// Scales the brightness of the sky.
+    sun_intensity: f32
+}
+
+impl SkyUniform {
+    /// Creates and returns a new `SkyUniform`.
+    ///
+    /// # Arguments
+    ///
+    /// `camera` - The camera which renders the sky.
+    /// `sun` - The position of the sun.
+    /// `settings` - The atmosphere parameters.
+    pub fn new(camera: &Camera, sun: &SunPosition, settings: &SkySettings) -> Self {
+        let inv_view_proj = camera
+            .view_projection()
+            .invert()
+            .unwrap_or_else(Matrix4::identity);
+        let [r, g, b, _] = settings.sun_color.to_linear().to_array();
+
+        Self {
+            inv_view_proj,
+            camera_position: camera.eye.to_homogeneous(),
+            sun_direction: sun.direction().into(),
+            turbidity: settings.turbidity,
+            sun_color: [r, g, b],
+            sun_intensity: settings.sun_intensity
+        }
+    }
+}
+
+/// Implements `RawBufferRepresentable` for the sky uniform.
+impl RawBufferRepresentable for SkyUniform {
+    /// Maps the content of the uniform to an array of Bytes.
+    fn get_raw<'a>(&'a self) -> Bytes<'a> {
+        Bytes(bytemuck::bytes_of(self))
+    }
+}
+
+unsafe impl Pod for SkyUniform {}
+unsafe impl Zeroable for SkyUniform {}
+
+/// Wrapps the sky uniform buffer.
+pub struct SkyUniformBuffer(pub Arc<Buffer>);
+
+/// Contains a full screen pass which renders the sky behind the scene.
+pub struct SkyPipeline {
+    /// Contains the Wgpu pipeline.
+    pub pipeline: RenderPipeline,
+
+    /// The bind group which contains the sky uniform.
+    pub group: BindGroup
+}
+
+impl SkyPipeline {
+    /// Creates and returns a new sky pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the layout cache.
+    /// `buffer` - The buffer which contains the sky uniform.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld, buffer: &Buffer) -> Self {
+        info("Creating SkyPipeline");
+
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+
+        let layout = bind_group_layout(gpu, world, BindGroupLayoutKey::FragmentUniform);
+
+        let group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding()
+                }
+            ],
+            label: Some("Sky bind group")
+        });
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[layout.as_ref()],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let pipeline: RenderPipeline = gpu.create_render_pipeline(
+            &RenderPipelineDescriptor {
+                label: Some("Sky pipeline"),
+                layout: Some(&pipeline_layout),
+                // The vertices are generated in the shader.
+                vertex: VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[]
+                },
+                fragment: Some(FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    // The sky is part of the scene, so it could be HDR.
+                    targets: &[gpu.scene_format().into()],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+            }
+        );
+
+        info("SkyPipeline created");
+
+        Self {
+            pipeline,
+            group
+        }
+    }
+}
+
+/// The WGSL source of the sky pipeline.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/sky_shader.wgsl");
+
+#[test]
+fn sky_uniform_layout() {
+    // Must match the size of the WGSL struct.
+    assert_eq!(std::mem::size_of::<SkyUniform>(), 112);
+
+    let uniform = SkyUniform::new(
+        &Camera::default(),
+        &SunPosition::default(),
+        &SkySettings::default()
+    );
+    let length: f32 = uniform.sun_direction.iter().map(|v| v * v).sum();
+    assert!((length - 1.0).abs() < 1e-5);
+
+    // The default sun color is sent in linear space.
+    let expected = [1.0, 0.95, 0.85];
+    for (channel, value) in uniform.sun_color.iter().zip(expected.iter()) {
+        assert!((channel - value).abs() < 0.01);
+    }
+}
@@ -0,0 +1,399 @@
+use std::sync::Arc;
+
+use cgmath::Matrix4;
+use bytemuck::{Pod, Zeroable};
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    ShaderStage,
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingResource,
+    BindingType,
+    BufferBindingType,
+    TextureView,
+    Sampler,
+    SamplerDescriptor,
+    ColorTargetState,
+    ColorWrite,
+    BlendState,
+    BlendFactor,
+    BlendOperation,
+    VertexBufferLayout,
+    VertexAttribute,
+    VertexFormat,
+    InputStepMode,
+    BufferAddress,
+    Buffer
+};
+
+use ecs::DefaultWorld;
+use types::{Bytes, Size};
+use log::info;
+
+use crate::{
+    graphics::{
+        gpu::Gpu,
+        buffer::{BufferCreator, RawBufferRepresentable},
+        pipelines::{
+            bind_groups::{
+                BindGroupGenerator,
+                layout_cache::{BindGroupLayoutKey, bind_group_layout}
+            },
+            pipeline_cache::shader_module
+        }
+    },
+    scene::sprite::{Sprite, SpriteTransform, screen_projection}
+};
+
+/// The maximum number of sprites rendered per frame.
+pub const MAX_SPRITES: u32 = 10000;
+
+/// Identifies a texture registered in the `SpriteRenderer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(pub usize);
+
+/// Represents the per sprite data sent to the GPU.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteInstance {
+    position: [f32; 2],
+    size: [f32; 2],
+    src_rect: [f32; 4],
+    color: [f32; 4],
+    rotation: f32
+}
+
+impl SpriteInstance {
+    /// Creates and returns a new `SpriteInstance`.
+    ///
+    /// # Arguments
+    ///
+    /// `sprite` - The sprite.
+    /// `transform` - The placement of the sprite.
+    pub fn new(sprite: &Sprite, transform: &SpriteTransform) -> Self {
+        Self {
+            position: transform.position,
+            size: transform.size,
+            src_rect: sprite.src_rect,
+            color: sprite.color,
+            rotation: transform.rotation
+        }
+    }
+}
+
+unsafe impl Pod for SpriteInstance {}
+unsafe impl Zeroable for SpriteInstance {}
+
+/// Represents the uniforms used by the sprite shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteLocals {
+    /// Maps the screen pixels to the clip space.
+    projection: Matrix4<f32>
+}
+
+impl SpriteLocals {
+    /// Creates and returns the locals for the provided screen size.
+    ///
+    /// # Arguments
+    ///
+    /// `size` - The size of the screen in physical pixels.
+    pub fn new(size: Size<u32>) -> Self {
+        Self {
+            projection: screen_projection(size)
+        }
+    }
+}
+
+/// Implements `RawBufferRepresentable` for the sprite locals.
+impl RawBufferRepresentable for SpriteLocals {
+    /// Maps the content of the locals to an array of Bytes.
+    fn get_raw<'a>(&'a self) -> Bytes<'a> {
+        Bytes(bytemuck::bytes_of(self))
+    }
+}
+
+unsafe impl Pod for SpriteLocals {}
+unsafe impl Zeroable for SpriteLocals {}
+
+/// Contains the textures used by the sprites, the handles are the position
+/// of the texture in the lookup table.
+pub struct SpriteRenderer {
+    /// The layout of the texture bind groups.
+    layout: Arc<BindGroupLayout>,
+
+    /// The sampler shared by all the textures.
+    sampler: Sampler,
+
+    /// Contains a bind group per registered texture.
+    textures: Vec<BindGroup>
+}
+
+impl SpriteRenderer {
+    /// Creates and returns a new `SpriteRenderer` without textures.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the sampler and the layout.
+    /// `world` - The world which contains the layout cache.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
+        let sampler = gpu.device.create_sampler(&SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            layout: bind_group_layout(gpu, world, BindGroupLayoutKey::Texture),
+            sampler,
+            textures: Vec::new()
+        }
+    }
+
+    /// Registers a new texture and returns the handle used by the sprites.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the bind group.
+    /// `view` - The view of the texture.
+    pub fn register_texture(&mut self, gpu: &Gpu, view: &TextureView) -> TextureHandle {
+        let group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &self.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(view)
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&self.sampler)
+                }
+            ],
+            label: Some("Sprite texture bind group")
+        });
+
+        self.textures.push(group);
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    /// Returns the bind group of the texture, if the handle was not
+    /// registered it returns None.
+    ///
+    /// # Arguments
+    ///
+    /// `handle` - The handle of the texture.
+    pub fn texture(&self, handle: TextureHandle) -> Option<&BindGroup> {
+        self.textures.get(handle.0)
+    }
+}
+
+/// Contains the pipeline which draws the sprites on top of the screen.
+pub struct SpriteRenderPipeline {
+    /// Contains the Wgpu pipeline.
+    pub pipeline: RenderPipeline,
+
+    /// Contains the 4 corners of the quad.
+    pub vertex_buffer: Buffer,
+
+    /// Contains the 2 triangles of the quad.
+    pub index_buffer: Buffer,
+
+    /// Contains the per sprite data.
+    pub instances_buffer: Arc<Buffer>,
+
+    /// Contains the screen projection.
+    pub locals_buffer: Arc<Buffer>,
+
+    /// The bind group which contains the locals.
+    pub locals_group: BindGroup
+}
+
+impl SpriteRenderPipeline {
+    /// Creates and returns a new sprite pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the pipeline cache.
+    /// `renderer` - The sprite renderer which contains the texture layout.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld, renderer: &SpriteRenderer) -> Self {
+        info("Creating SpriteRenderPipeline");
+
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+
+        // The corners of the quad, the sprite is placed in the shader.
+        let corners: Vec<[f32; 2]> = vec![
+            [0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]
+        ];
+        let indices: Vec<u16> = vec![0, 1, 2, 2, 3, 0];
+        let vertex_buffer = gpu.create_vertex(corners);
+        let index_buffer = gpu.create_index(indices);
+
+        let instances_buffer = Arc::new(gpu.create_vertex_with_size(
+            MAX_SPRITES as u64 * std::mem::size_of::<SpriteInstance>() as u64
+        ));
+
+        let size = Size::new(gpu.swap_chain_descriptor.width, gpu.swap_chain_descriptor.height);
+        let locals_buffer = Arc::new(gpu.create_uniform(SpriteLocals::new(size)));
+
+        let locals_layout = gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStage::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Sprite locals layout")
+        });
+
+        let locals_group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &locals_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: locals_buffer.as_entire_binding()
+                }
+            ],
+            label: Some("Sprite locals bind group")
+        });
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&locals_layout, renderer.layout.as_ref()],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let pipeline: RenderPipeline = gpu.create_render_pipeline(
+            &RenderPipelineDescriptor {
+                label: Some("Sprite pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[
+                        create_corner_layout(),
+                        create_instance_layout()
+                    ]
+                },
+                fragment: Some(FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    // The sprites are drawn after the tone mapping, so they
+                    // use the swap chain format and blend with the scene.
+                    targets: &[ColorTargetState {
+                        format: gpu.swap_chain_format(),
+                        alpha_blend: BlendState::REPLACE,
+                        color_blend: BlendState {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::OneMinusSrcAlpha,
+                            operation: BlendOperation::Add
+                        },
+                        write_mask: ColorWrite::ALL
+                    }],
+                }),
+                // The rotation could flip the winding, so nothing is culled.
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+            }
+        );
+
+        info("SpriteRenderPipeline created");
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            instances_buffer,
+            locals_buffer,
+            locals_group
+        }
+    }
+}
+
+/// The WGSL source of the sprite pipeline.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/sprite_shader.wgsl");
+
+/// Creates and returns the layout of the quad corners.
+fn create_corner_layout<'a>() -> VertexBufferLayout<'a> {
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+        step_mode: InputStepMode::Vertex,
+        attributes: &[
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: 0,
+                shader_location: 0
+            }
+        ]
+    }
+}
+
+/// Creates and returns the layout of the per sprite data.
+fn create_instance_layout<'a>() -> VertexBufferLayout<'a> {
+    const UNIT_SIZE: u64 = std::mem::size_of::<f32>() as u64;
+
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<SpriteInstance>() as BufferAddress,
+        step_mode: InputStepMode::Instance,
+        attributes: &[
+            // The position.
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: 0,
+                shader_location: 1
+            },
+            // The size.
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: UNIT_SIZE * 2,
+                shader_location: 2
+            },
+            // The source rect.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: UNIT_SIZE * 4,
+                shader_location: 3
+            },
+            // The color.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: UNIT_SIZE * 8,
+                shader_location: 4
+            },
+            // The rotation.
+            VertexAttribute {
+                format: VertexFormat::Float,
+                offset: UNIT_SIZE * 12,
+                shader_location: 5
+            }
+        ]
+    }
+}
+
+#[test]
+fn sprite_instance_layout() {
+    // Must match the offsets of the instance layout.
+    assert_eq!(std::mem::size_of::<SpriteInstance>(), 13 * 4);
+}
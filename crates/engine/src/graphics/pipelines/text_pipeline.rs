@@ -0,0 +1,408 @@
+use std::sync::Arc;
+
+use cgmath::{Matrix4, Point3};
+use bytemuck::{Pod, Zeroable};
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    ShaderStage,
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingResource,
+    BindingType,
+    BufferBindingType,
+    TextureSampleType,
+    TextureViewDimension,
+    TextureFormat,
+    VertexBufferLayout,
+    VertexAttribute,
+    VertexFormat,
+    InputStepMode,
+    BufferAddress,
+    DepthStencilState,
+    CompareFunction,
+    StencilState,
+    DepthBiasState,
+    Buffer
+};
+
+use ecs::DefaultWorld;
+use types::{Bytes, Size};
+use log::info;
+
+use crate::{
+    graphics::{
+        gpu::Gpu,
+        buffer::{BufferCreator, RawBufferRepresentable},
+        pipelines::{
+            bind_groups::BindGroupGenerator,
+            pipeline_cache::shader_module
+        },
+        texture::{Texture, TextureGenerator},
+        font::{
+            build_atlas,
+            glyph_rect,
+            ATLAS_WIDTH,
+            ATLAS_HEIGHT,
+            CELL_WIDTH,
+            GLYPH_WIDTH
+        }
+    },
+    scene::{
+        camera::Camera,
+        text_label::TextLabel
+    }
+};
+
+/// The maximum number of glyphs drawn per frame.
+pub const MAX_GLYPHS: u32 = 65536;
+
+/// Represents the per glyph data sent to the GPU.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphInstance {
+    /// The world position of the label.
+    anchor: [f32; 3],
+
+    /// The position of the glyph inside the label in font pixels.
+    offset: [f32; 2],
+
+    /// The area of the glyph in the atlas.
+    rect: [f32; 4],
+
+    color: [f32; 4],
+    scale: f32
+}
+
+unsafe impl Pod for GlyphInstance {}
+unsafe impl Zeroable for GlyphInstance {}
+
+/// Appends the glyphs of the label, the text is centered horizontally over
+/// the anchor.
+///
+/// # Arguments
+///
+/// `instances` - Where the glyphs are appended.
+/// `anchor` - The world position of the label.
+/// `label` - The label.
+pub fn append_label_glyphs(instances: &mut Vec<GlyphInstance>,
+                           anchor: Point3<f32>,
+                           label: &TextLabel) {
+    let count = label.text.chars().count() as u32;
+    if count == 0 {
+        return;
+    }
+
+    let width = (count * CELL_WIDTH - (CELL_WIDTH - GLYPH_WIDTH)) as f32;
+    let start = -width / 2.0;
+
+    for (index, c) in label.text.chars().enumerate() {
+        // The spaces are only used to advance.
+        if c == ' ' {
+            continue;
+        }
+
+        instances.push(GlyphInstance {
+            anchor: anchor.into(),
+            offset: [start + (index as u32 * CELL_WIDTH) as f32, 0.0],
+            rect: glyph_rect(c),
+            color: label.color,
+            scale: label.scale
+        });
+    }
+}
+
+/// Represents the uniforms used by the text shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextLocals {
+    /// The view projection of the main camera.
+    view_projection: Matrix4<f32>,
+
+    /// The right direction of the camera, w is not used.
+    right: [f32; 4],
+
+    /// The up direction of the camera, w is not used.
+    up: [f32; 4]
+}
+
+impl TextLocals {
+    /// Creates and returns the locals for the provided camera.
+    ///
+    /// # Arguments
+    ///
+    /// `camera` - The camera the labels face.
+    pub fn new(camera: &Camera) -> Self {
+        Self {
+            view_projection: camera.view_projection(),
+            right: camera.right().extend(0.0).into(),
+            up: camera.up_vector().extend(0.0).into()
+        }
+    }
+}
+
+/// Implements `RawBufferRepresentable` for the text locals.
+impl RawBufferRepresentable for TextLocals {
+    /// Maps the content of the locals to an array of Bytes.
+    fn get_raw<'a>(&'a self) -> Bytes<'a> {
+        Bytes(bytemuck::bytes_of(self))
+    }
+}
+
+unsafe impl Pod for TextLocals {}
+unsafe impl Zeroable for TextLocals {}
+
+/// Contains the pipeline which draws the text labels.
+///
+/// The font atlas is created once and shared by all the labels, so all the
+/// glyphs are drawn with a single draw call.
+pub struct TextRenderPipeline {
+    /// Contains the Wgpu pipeline.
+    pub pipeline: RenderPipeline,
+
+    /// Contains the 4 corners of the quad.
+    pub vertex_buffer: Buffer,
+
+    /// Contains the 2 triangles of the quad.
+    pub index_buffer: Buffer,
+
+    /// Contains the per glyph data.
+    pub instances_buffer: Arc<Buffer>,
+
+    /// Contains the camera data.
+    pub locals_buffer: Arc<Buffer>,
+
+    /// The bind group which contains the locals and the font atlas.
+    pub group: BindGroup,
+
+    /// The font atlas, it is never read but it must live as long as the
+    /// bind group.
+    _atlas: Texture
+}
+
+impl TextRenderPipeline {
+    /// Creates and returns a new text pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `world` - The world which contains the pipeline cache.
+    pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
+        info("Creating TextRenderPipeline");
+
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+
+        // The corners of the quad, the glyph is placed in the shader.
+        let corners: Vec<[f32; 2]> = vec![
+            [0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]
+        ];
+        let indices: Vec<u16> = vec![0, 1, 2, 2, 3, 0];
+        let vertex_buffer = gpu.create_vertex(corners);
+        let index_buffer = gpu.create_index(indices);
+
+        let instances_buffer = Arc::new(gpu.create_vertex_with_size(
+            MAX_GLYPHS as u64 * std::mem::size_of::<GlyphInstance>() as u64
+        ));
+
+        let locals_buffer = Arc::new(gpu.create_uniform(TextLocals::new(&Camera::default())));
+
+        let atlas = gpu.create_texture_with_data(
+            "Font atlas",
+            Size::new(ATLAS_WIDTH, ATLAS_HEIGHT),
+            TextureFormat::R8Unorm,
+            1,
+            &build_atlas()
+        );
+
+        let layout = gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStage::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None
+                    },
+                    count: None
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: true }
+                    },
+                    count: None
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStage::FRAGMENT,
+                    ty: BindingType::Sampler {
+                        comparison: false,
+                        filtering: true
+                    },
+                    count: None
+                }
+            ],
+            label: Some("Text bind group layout")
+        });
+
+        let group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: locals_buffer.as_entire_binding()
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::TextureView(&atlas.view)
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::Sampler(&atlas.sampler)
+                }
+            ],
+            label: Some("Text bind group")
+        });
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let pipeline: RenderPipeline = gpu.create_render_pipeline(
+            &RenderPipelineDescriptor {
+                label: Some("Text pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[
+                        create_corner_layout(),
+                        create_instance_layout()
+                    ]
+                },
+                fragment: Some(FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    targets: &[gpu.scene_format().into()],
+                }),
+                // The quads face the camera, so nothing is culled.
+                primitive: PrimitiveState::default(),
+                // The empty pixels are discarded, so the glyphs can be depth
+                // tested without blending.
+                depth_stencil: Some(DepthStencilState {
+                    format: gpu.depth_format,
+                    depth_write_enabled: false,
+                    depth_compare: CompareFunction::Less,
+                    stencil: StencilState::default(),
+                    bias: DepthBiasState::default(),
+                    clamp_depth: false
+                }),
+                multisample: wgpu::MultisampleState::default(),
+            }
+        );
+
+        info("TextRenderPipeline created");
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            instances_buffer,
+            locals_buffer,
+            group,
+            _atlas: atlas
+        }
+    }
+}
+
+/// The WGSL source of the text pipeline.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/text_shader.wgsl");
+
+/// Creates and returns the layout of the quad corners.
+fn create_corner_layout<'a>() -> VertexBufferLayout<'a> {
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+        step_mode: InputStepMode::Vertex,
+        attributes: &[
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: 0,
+                shader_location: 0
+            }
+        ]
+    }
+}
+
+/// Creates and returns the layout of `GlyphInstance`.
+fn create_instance_layout<'a>() -> VertexBufferLayout<'a> {
+    const FLOAT_SIZE: BufferAddress = std::mem::size_of::<f32>() as BufferAddress;
+
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<GlyphInstance>() as BufferAddress,
+        step_mode: InputStepMode::Instance,
+        attributes: &[
+            // The anchor.
+            VertexAttribute {
+                format: VertexFormat::Float3,
+                offset: 0,
+                shader_location: 1
+            },
+            // The offset.
+            VertexAttribute {
+                format: VertexFormat::Float2,
+                offset: FLOAT_SIZE * 3,
+                shader_location: 2
+            },
+            // The atlas rect.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: FLOAT_SIZE * 5,
+                shader_location: 3
+            },
+            // The color.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: FLOAT_SIZE * 9,
+                shader_location: 4
+            },
+            // The scale.
+            VertexAttribute {
+                format: VertexFormat::Float,
+                offset: FLOAT_SIZE * 13,
+                shader_location: 5
+            }
+        ]
+    }
+}
+
+#[test]
+fn label_glyphs_are_centered() {
+    assert_eq!(std::mem::size_of::<GlyphInstance>(), 56);
+
+    let mut instances = Vec::new();
+    let anchor = Point3 { x: 1.0, y: 2.0, z: 3.0 };
+    append_label_glyphs(&mut instances, anchor, &TextLabel::new("A B"));
+
+    // The space does not generate a glyph.
+    assert_eq!(instances.len(), 2);
+
+    let first = instances[0].offset[0];
+    let last = instances[1].offset[0] + GLYPH_WIDTH as f32;
+    assert!((first + last).abs() < 1e-6);
+}
@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+
+use wgpu::{
+    RenderPipeline,
+    RenderPipelineDescriptor,
+    PipelineLayoutDescriptor,
+    VertexState,
+    FragmentState,
+    PrimitiveState,
+    ShaderStage,
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingResource,
+    BindingType,
+    BufferBindingType,
+    TextureSampleType,
+    TextureViewDimension,
+    Buffer
+};
+
+use types::Bytes;
+use log::info;
+
+use crate::graphics::{
+    gpu::Gpu,
+    buffer::{BufferCreator, RawBufferRepresentable},
+    pipelines::{
+        bind_groups::BindGroupGenerator,
+        pipeline_cache::PipelineCache
+    },
+    texture::Texture
+};
+
+/// Defines the curve used to map the HDR colors into the screen range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToneMappingOperator {
+    /// Simple `color / (color + 1)` curve, it keeps the colors but the
+    /// bright areas look washed out.
+    Reinhard,
+
+    /// Filmic curve used by the ACES standard, it gives more contrast.
+    Aces
+}
+
+impl Default for ToneMappingOperator {
+    /// Creates and returns the default operator, `Aces`.
+    fn default() -> Self {
+        ToneMappingOperator::Aces
+    }
+}
+
+impl ToneMappingOperator {
+    /// Returns the number used by the shader to identify the operator.
+    fn as_shader_index(&self) -> u32 {
+        match self {
+            ToneMappingOperator::Reinhard => 0,
+            ToneMappingOperator::Aces => 1
+        }
+    }
+}
+
+/// Represents the settings sent to the tone mapping shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ToneMappingSettings {
+    /// The operator used by the shader.
+    operator: u32,
+
+    /// The uniforms must be 16 bytes aligned.
+    _padding: [u32; 3]
+}
+
+impl ToneMappingSettings {
+    /// Creates and returns new settings for the provided operator.
+    ///
+    /// # Arguments
+    ///
+    /// `operator` - The tone mapping operator.
+    pub fn new(operator: ToneMappingOperator) -> Self {
+        Self {
+            operator: operator.as_shader_index(),
+            _padding: [0; 3]
+        }
+    }
+}
+
+/// Implements `RawBufferRepresentable` for the settings.
+impl RawBufferRepresentable for ToneMappingSettings {
+    /// Maps the content of the settings to an array of Bytes.
+    fn get_raw<'a>(&'a self) -> Bytes<'a> {
+        Bytes(bytemuck::bytes_of(self))
+    }
+}
+
+unsafe impl Pod for ToneMappingSettings {}
+unsafe impl Zeroable for ToneMappingSettings {}
+
+/// Contains a full screen pass which tone maps the HDR texture into the
+/// swap chain.
+pub struct ToneMappingPipeline {
+    /// Contains the Wgpu pipeline.
+    pub pipeline: RenderPipeline,
+
+    /// The bind group which contains the HDR texture and the settings.
+    pub group: BindGroup,
+
+    /// Contains the settings used by the shader.
+    pub settings_buffer: Arc<Buffer>
+}
+
+impl ToneMappingPipeline {
+    /// Creates and returns a new tone mapping pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline.
+    /// `cache` - The cache which contains the compiled shader, the pipeline
+    /// is recreated when the window is resized.
+    /// `hdr_texture` - The texture where the scene is rendered.
+    pub fn new(gpu: &Gpu, cache: &PipelineCache, hdr_texture: &Texture) -> Self {
+        info("Creating ToneMappingPipeline");
+
+        let shader_module = cache.shader(gpu, SHADER_SOURCE);
+
+        let settings_buffer: Arc<Buffer> = Arc::new(gpu.create_uniform(
+            ToneMappingSettings::new(ToneMappingOperator::default())
+        ));
+
+        let layout = create_bind_group_layout(gpu);
+        let group = gpu.create_bind_group(&BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&hdr_texture.view)
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&hdr_texture.sampler)
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: settings_buffer.as_entire_binding()
+                }
+            ],
+            label: Some("Tone mapping bind group")
+        });
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(
+            &PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[]
+            }
+        );
+
+        let pipeline: RenderPipeline = gpu.create_render_pipeline(
+            &RenderPipelineDescriptor {
+                label: Some("Tone mapping pipeline"),
+                layout: Some(&pipeline_layout),
+                // The vertices are generated in the shader.
+                vertex: VertexState {
+                    module: &shader_module,
+                    entry_point: "vs_main",
+                    buffers: &[]
+                },
+                fragment: Some(FragmentState {
+                    module: &shader_module,
+                    entry_point: "fs_main",
+                    // The result is written into the swap chain.
+                    targets: &[gpu.swap_chain_format().into()],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+            }
+        );
+
+        info("ToneMappingPipeline created");
+
+        Self {
+            pipeline,
+            group,
+            settings_buffer
+        }
+    }
+}
+
+/// Contains the tone mapping pipeline, it is only present when the scene is
+/// rendered in HDR.
+pub struct ToneMapping(pub Option<ToneMappingPipeline>);
+
+/// The WGSL source of the tone mapping pipeline.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/tone_mapping_shader.wgsl");
+
+/// Creates and returns the layout of the tone mapping bind group, it
+/// contains the HDR texture, its sampler and the settings.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the layout.
+fn create_bind_group_layout(gpu: &Gpu) -> BindGroupLayout {
+    gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStage::FRAGMENT,
+                ty: BindingType::Texture {
+                    multisampled: false,
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2
+                },
+                count: None
+            },
+            BindGroupLayoutEntry {
+                binding: 1,
+                visibility: ShaderStage::FRAGMENT,
+                ty: BindingType::Sampler {
+                    filtering: true,
+                    comparison: false
+                },
+                count: None
+            },
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStage::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            }
+        ],
+        label: Some("Tone mapping bind group layout")
+    })
+}
+
+#[test]
+fn operator_shader_index() {
+    assert_eq!(ToneMappingOperator::default(), ToneMappingOperator::Aces);
+    assert_eq!(ToneMappingSettings::new(ToneMappingOperator::Reinhard).operator, 0);
+    assert_eq!(ToneMappingSettings::new(ToneMappingOperator::Aces).operator, 1);
+    assert_eq!(std::mem::size_of::<ToneMappingSettings>(), 16);
+}
@@ -0,0 +1,457 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::Matrix4;
+#[cfg(test)]
+use cgmath::{InnerSpace, Vector3, Vector4};
+
+use wgpu::{
+    BindGroup,
+    BindGroupDescriptor,
+    BindGroupEntry,
+    BindGroupLayout,
+    BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry,
+    BindingType,
+    Buffer,
+    BufferAddress,
+    BufferBindingType,
+    BufferUsage,
+    CommandEncoder,
+    ComputePassDescriptor,
+    ComputePipeline,
+    ComputePipelineDescriptor,
+    PipelineLayoutDescriptor,
+    ShaderStage,
+    util::{DeviceExt, BufferInitDescriptor}
+};
+
+use ecs::DefaultWorld;
+
+use crate::{
+    graphics::{
+        gpu::Gpu,
+        buffer::{BufferCreator, BufferManipulator},
+        pipelines::{
+            bind_groups::{
+                BindGroupGenerator,
+                layout_cache::{BindGroupLayoutKey, bind_group_layout}
+            },
+            pipeline_cache::shader_module,
+            voxel_render_pipeline::VoxelMesh
+        }
+    },
+    scene::{components::Voxel, lod::LOD_LEVELS}
+};
+
+/// The levels of detail drawn with a matrix, the last one is always drawn
+/// as a billboard.
+pub const CULLED_LEVELS: usize = LOD_LEVELS - 1;
+
+/// The number of voxels culled by each invocation group, it must match the
+/// `workgroup_size` of the shader.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Represents the arguments of `draw_indexed_indirect`, the instance count
+/// is written by the culling shader.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32
+}
+
+unsafe impl Pod for DrawIndexedIndirectArgs {}
+unsafe impl Zeroable for DrawIndexedIndirectArgs {}
+
+/// Contains the number of voxels of each level sent to the culling shader,
+/// the levels are stored one after the other in the input buffers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct CullingParams {
+    counts: [u32; 4]
+}
+
+unsafe impl Pod for CullingParams {}
+unsafe impl Zeroable for CullingParams {}
+
+/// Culls the voxels drawn with a matrix against the camera frustum in the
+/// GPU, the visible ones are compacted into the culled buffers and counted
+/// in `draw_args_buffer` which is used by `draw_indexed_indirect`.
+///
+/// Each level keeps the range it has in the input buffers, the vertex
+/// buffers are bound at the start of the range so the arguments always use
+/// the first instance 0 and `Features::INDIRECT_FIRST_INSTANCE` is not
+/// needed.
+pub struct VoxelCulling {
+    /// Contains the compute pipeline which culls the voxels.
+    pub pipeline: ComputePipeline,
+
+    /// Contains the layout of the buffers read and written by the culling.
+    layout: BindGroupLayout,
+
+    /// Contains the buffers used by the culling, it is recreated when the
+    /// instance buffers grow.
+    pub group: BindGroup,
+
+    /// Contains the number of voxels of each level.
+    params_buffer: Arc<Buffer>,
+
+    /// Contains the transformations of the visible voxels.
+    pub culled_transformations_buffer: Buffer,
+
+    /// Contains the colors of the visible voxels.
+    pub culled_voxels_buffer: Buffer,
+
+    /// Contains a `DrawIndexedIndirectArgs` per level.
+    pub draw_args_buffer: Buffer,
+
+    /// Contains the arguments with the instance count set to zero, they are
+    /// copied before each culling.
+    draw_args_reset_buffer: Buffer
+}
+
+impl VoxelCulling {
+    /// Creates and returns the culling of the voxels.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the pipeline and the buffers.
+    /// `world` - The world which contains the layout and the pipeline caches.
+    /// `lods` - The geometry of the levels, the index counts are used in the arguments.
+    /// `transformations_buffer` - The transformations of the voxels.
+    /// `voxels_buffer` - The colors of the voxels.
+    /// `max_instances` - The number of instances the buffers can host.
+    pub fn new(
+        gpu: &Gpu,
+        world: &DefaultWorld,
+        lods: &[VoxelMesh],
+        transformations_buffer: &Buffer,
+        voxels_buffer: &Buffer,
+        max_instances: u32) -> Self {
+        let layout = create_culling_layout(gpu);
+        let pipeline = create_culling_pipeline(gpu, world, &layout);
+
+        let params_buffer = Arc::new(gpu.create_uniform(vec![CullingParams::default()]));
+
+        let args: Vec<DrawIndexedIndirectArgs> = lods[..CULLED_LEVELS].iter()
+            .map(|mesh| DrawIndexedIndirectArgs {
+                index_count: mesh.index_len,
+                ..Default::default()
+            })
+            .collect();
+        let draw_args_reset_buffer = gpu.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Voxel draw arguments reset buffer"),
+            contents: bytemuck::cast_slice(&args),
+            usage: BufferUsage::COPY_SRC
+        });
+        let draw_args_buffer = gpu.create_storage_with_size(
+            std::mem::size_of_val(args.as_slice()) as u64,
+            BufferUsage::INDIRECT
+        );
+
+        let (culled_transformations_buffer, culled_voxels_buffer) = allocate_culled_buffers(
+            gpu,
+            max_instances
+        );
+        let group = create_culling_group(
+            gpu,
+            &layout,
+            &params_buffer,
+            transformations_buffer,
+            voxels_buffer,
+            &culled_transformations_buffer,
+            &culled_voxels_buffer,
+            &draw_args_buffer
+        );
+
+        Self {
+            pipeline,
+            layout,
+            group,
+            params_buffer,
+            culled_transformations_buffer,
+            culled_voxels_buffer,
+            draw_args_buffer,
+            draw_args_reset_buffer
+        }
+    }
+
+    /// Replaces the culled buffers and the group after the instance buffers
+    /// grew.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to allocate the buffers.
+    /// `transformations_buffer` - The new transformations of the voxels.
+    /// `voxels_buffer` - The new colors of the voxels.
+    /// `max_instances` - The new capacity.
+    pub fn resize(
+        &mut self,
+        gpu: &Gpu,
+        transformations_buffer: &Buffer,
+        voxels_buffer: &Buffer,
+        max_instances: u32) {
+        let (culled_transformations_buffer, culled_voxels_buffer) = allocate_culled_buffers(
+            gpu,
+            max_instances
+        );
+        self.group = create_culling_group(
+            gpu,
+            &self.layout,
+            &self.params_buffer,
+            transformations_buffer,
+            voxels_buffer,
+            &culled_transformations_buffer,
+            &culled_voxels_buffer,
+            &self.draw_args_buffer
+        );
+        self.culled_transformations_buffer = culled_transformations_buffer;
+        self.culled_voxels_buffer = culled_voxels_buffer;
+    }
+
+    /// Writes the number of voxels of each level, it is done once per frame
+    /// before the passes are recorded.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to write the buffer.
+    /// `counts` - The number of voxels of each level.
+    pub fn write_counts(&self, gpu: &Gpu, counts: [u32; CULLED_LEVELS]) {
+        let mut params = CullingParams::default();
+        params.counts[..CULLED_LEVELS].copy_from_slice(&counts);
+        gpu.copy_to_buffer(&self.params_buffer, bytemuck::bytes_of(&params));
+    }
+
+    /// Records the culling of the voxels for a camera, the arguments are
+    /// reset first so the same buffers are reused by all the cameras.
+    ///
+    /// # Arguments
+    ///
+    /// `encoder` - The encoder where the culling is recorded.
+    /// `locals_group` - The bind group of the camera locals.
+    /// `total` - The number of voxels of all the levels.
+    pub fn record(&self, encoder: &mut CommandEncoder, locals_group: &BindGroup, total: u32) {
+        encoder.copy_buffer_to_buffer(
+            &self.draw_args_reset_buffer,
+            0,
+            &self.draw_args_buffer,
+            0,
+            (std::mem::size_of::<DrawIndexedIndirectArgs>() * CULLED_LEVELS) as BufferAddress
+        );
+
+        let mut cpass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: Some("Voxel culling pass")
+        });
+        cpass.set_pipeline(&self.pipeline);
+        cpass.set_bind_group(0, locals_group, &[]);
+        cpass.set_bind_group(1, &self.group, &[]);
+        cpass.dispatch(total.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Returns the offset of the arguments of the level in `draw_args_buffer`.
+    ///
+    /// # Arguments
+    ///
+    /// `level` - The level of detail.
+    pub fn draw_args_offset(level: usize) -> BufferAddress {
+        (level * std::mem::size_of::<DrawIndexedIndirectArgs>()) as BufferAddress
+    }
+}
+
+/// Returns the planes of the frustum of the view projection, the normals
+/// point inside and the depth goes from 0 to 1 as in Wgpu.
+///
+/// The same planes are calculated by `cs_main` in the culling shader, this
+/// version is only used to test the culling math.
+///
+/// # Arguments
+///
+/// `view_proj` - The view projection of the camera.
+#[cfg(test)]
+fn frustum_planes(view_proj: &Matrix4<f32>) -> [Vector4<f32>; 6] {
+    let row = |i: usize| Vector4::new(view_proj.x[i], view_proj.y[i], view_proj.z[i], view_proj.w[i]);
+    let (row0, row1, row2, row3) = (row(0), row(1), row(2), row(3));
+    [
+        row3 + row0,
+        row3 - row0,
+        row3 + row1,
+        row3 - row1,
+        row2,
+        row3 - row2
+    ]
+}
+
+/// Returns the radius of a sphere which contains the voxel, the voxel is a
+/// cube from -1 to 1 so the corners are inside the sum of the axes.
+///
+/// # Arguments
+///
+/// `transform` - The world transformation of the voxel.
+#[cfg(test)]
+fn bounding_radius(transform: &Matrix4<f32>) -> f32 {
+    transform.x.truncate().magnitude() +
+    transform.y.truncate().magnitude() +
+    transform.z.truncate().magnitude()
+}
+
+/// Returns true if the sphere is inside or intersects the frustum.
+///
+/// # Arguments
+///
+/// `planes` - The planes returned by `frustum_planes`.
+/// `center` - The center of the sphere.
+/// `radius` - The radius of the sphere.
+#[cfg(test)]
+fn sphere_in_frustum(planes: &[Vector4<f32>; 6], center: Vector3<f32>, radius: f32) -> bool {
+    planes.iter().all(|plane| {
+        let normal = plane.truncate();
+        (normal.dot(center) + plane.w) / normal.magnitude() >= -radius
+    })
+}
+
+/// The WGSL source of the voxel culling.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/voxel_culling_shader.wgsl");
+
+/// Creates and returns the layout of the buffers used by the culling.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the layout.
+fn create_culling_layout(gpu: &Gpu) -> BindGroupLayout {
+    let storage = |binding: u32, read_only: bool| BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStage::COMPUTE,
+        ty: BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None
+        },
+        count: None
+    };
+
+    gpu.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        entries: &[
+            BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStage::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            },
+            storage(1, true),
+            storage(2, true),
+            storage(3, false),
+            storage(4, false),
+            storage(5, false)
+        ],
+        label: Some("Voxel culling bind group layout")
+    })
+}
+
+/// Creates and returns the compute pipeline which culls the voxels.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the pipeline.
+/// `world` - The world which contains the layout and the pipeline caches.
+/// `layout` - The layout of the culling buffers.
+fn create_culling_pipeline(gpu: &Gpu, world: &DefaultWorld, layout: &BindGroupLayout) -> ComputePipeline {
+    let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+    let locals_layout = bind_group_layout(gpu, world, BindGroupLayoutKey::Locals);
+
+    let pipeline_layout = gpu.device.create_pipeline_layout(
+        &PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[locals_layout.as_ref(), layout],
+            push_constant_ranges: &[]
+        }
+    );
+
+    gpu.device.create_compute_pipeline(&ComputePipelineDescriptor {
+        label: Some("Voxel culling pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &shader_module,
+        entry_point: "cs_main"
+    })
+}
+
+/// Creates and returns the group of the culling buffers.
+#[allow(clippy::too_many_arguments)]
+fn create_culling_group(
+    gpu: &Gpu,
+    layout: &BindGroupLayout,
+    params_buffer: &Buffer,
+    transformations_buffer: &Buffer,
+    voxels_buffer: &Buffer,
+    culled_transformations_buffer: &Buffer,
+    culled_voxels_buffer: &Buffer,
+    draw_args_buffer: &Buffer) -> BindGroup {
+    let buffers = [
+        params_buffer,
+        transformations_buffer,
+        voxels_buffer,
+        culled_transformations_buffer,
+        culled_voxels_buffer,
+        draw_args_buffer
+    ];
+    let entries: Vec<BindGroupEntry> = buffers.iter()
+        .enumerate()
+        .map(|(binding, buffer)| BindGroupEntry {
+            binding: binding as u32,
+            resource: buffer.as_entire_binding()
+        })
+        .collect();
+
+    gpu.create_bind_group(&BindGroupDescriptor {
+        layout,
+        entries: &entries,
+        label: Some("Voxel culling bind group")
+    })
+}
+
+/// Creates and returns the buffers which contain the visible voxels, they
+/// are written by the culling and read as vertex buffers.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to allocate the buffers.
+/// `instances` - The number of instances the buffers can host.
+fn allocate_culled_buffers(gpu: &Gpu, instances: u32) -> (Buffer, Buffer) {
+    let matrix_size = std::mem::size_of::<Matrix4<f32>>() as u64;
+    let transformations = gpu.create_storage_with_size(instances as u64 * matrix_size, BufferUsage::VERTEX);
+    let voxels = gpu.create_storage_with_size(instances as u64 * Voxel::size() as u64, BufferUsage::VERTEX);
+    (transformations, voxels)
+}
+
+#[test]
+fn voxels_outside_the_frustum_are_culled() {
+    use cgmath::{Deg, Point3, perspective};
+
+    let view = Matrix4::look_at_rh(Point3::new(0.0, 0.0, 10.0), Point3::new(0.0, 0.0, 0.0), Vector3::unit_y());
+    // The depth of Wgpu goes from 0 to 1.
+    let to_wgpu = Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.0,
+        0.0, 0.0, 0.5, 1.0
+    );
+    let planes = frustum_planes(&(to_wgpu * perspective(Deg(60.0), 1.0, 0.1, 100.0) * view));
+
+    let radius = bounding_radius(&Matrix4::from_scale(0.5));
+    assert!((radius - 1.5).abs() < 1e-6);
+
+    assert!(sphere_in_frustum(&planes, Vector3::new(0.0, 0.0, 0.0), radius));
+    // Behind the camera, beyond the far plane and far to the side.
+    assert!(!sphere_in_frustum(&planes, Vector3::new(0.0, 0.0, 20.0), radius));
+    assert!(!sphere_in_frustum(&planes, Vector3::new(0.0, 0.0, -200.0), radius));
+    assert!(!sphere_in_frustum(&planes, Vector3::new(50.0, 0.0, 0.0), radius));
+    // The center is outside but the voxel still touches the frustum.
+    assert!(sphere_in_frustum(&planes, Vector3::new(0.0, 0.0, 10.5), radius));
+}
@@ -1,13 +1,14 @@
-use cgmath::{Vector3, Matrix4};
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::{InnerSpace, Vector3, Vector4, Matrix4};
 
 use wgpu::{ 
     RenderPipeline,
     RenderPipelineDescriptor,
     Buffer,
-    PipelineLayoutDescriptor,
     VertexState,
     FragmentState,
-    ShaderModule,
     PrimitiveState,
     VertexBufferLayout,
     BufferAddress,
@@ -17,10 +18,11 @@ use wgpu::{
     DepthStencilState,
     CompareFunction,
     StencilState,
-    DepthBiasState
+    DepthBiasState,
+    BufferUsage
 };
 
-use ecs::{DefaultWorld, UniqueRead, ComponentHandler};
+use ecs::{DefaultWorld, ComponentHandler};
 use log::info;
 
 use crate::{
@@ -28,36 +30,137 @@ use crate::{
         gpu::Gpu,
         vertex::Vertex,
         buffer::BufferCreator,
-        shaders::{ShaderProvider, ShaderGenerator},
-        pipelines::bind_groups::locals_bind_group::LocalsLayout,
-        texture::DEPTH_FORMAT
+        pipelines::{
+            bind_groups::layout_cache::{BindGroupLayoutKey, bind_group_layout},
+            pipeline_cache::{PipelineCache, PipelineKey, PrimitiveKey, shader_module, shader_hash},
+            voxel_culling::VoxelCulling
+        }
     },
-    scene::components::{Voxel, Transform},
+    scene::{components::Voxel, lod::LOD_LEVELS},
 };
 
-/// The limit of instances that could be rendererd at the same time.
+/// The number of instances that could be rendererd at the same time when
+/// the pipeline is created, the buffers grow when more are needed.
 const MAX_NUMBER_OF_INSTANCES: u32 = 200000;
 
+/// The factor used to grow the instance buffers.
+const INSTANCES_GROWTH_FACTOR: f64 = 1.5;
+
+/// Contains the new capacity requested for the voxel instance buffers, the
+/// buffers are replaced at the end of the frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResizeVoxelBuffer(pub Option<u32>);
+
+/// The tolerance used to decide if a transformation is axis aligned.
+const AXIS_ALIGNED_EPSILON: f32 = 1e-6;
+
+/// Represents the per voxel data sent to the GPU by the axis aligned voxels,
+/// the transformation is reconstructed in the shader from the position and
+/// the uniform scale.
+///
+/// It takes 20 bytes instead of the 76 bytes of a matrix and a color.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoxelInstance {
+    position: [f32; 3],
+    scale: f32,
+    color: [u8; 4]
+}
+
+impl VoxelInstance {
+    /// Creates and returns the instance of the voxel if the transformation
+    /// only translates and scales uniformly by a positive factor, otherwise None is returned and
+    /// the voxel must use the matrix path.
+    ///
+    /// # Arguments
+    ///
+    /// `transform` - The world transformation of the voxel.
+    /// `color` - The color of the voxel, each channel is in [0, 1].
+    pub fn from_matrix(transform: &Matrix4<f32>, color: [f32; 3]) -> Option<Self> {
+        let scale = transform.x.x;
+        let expected = Matrix4::new(
+            scale, 0.0, 0.0, 0.0,
+            0.0, scale, 0.0, 0.0,
+            0.0, 0.0, scale, 0.0,
+            transform.w.x, transform.w.y, transform.w.z, 1.0
+        );
+        let aligned = (0..4).all(|c| (0..4).all(|r| {
+            (transform[c][r] - expected[c][r]).abs() <= AXIS_ALIGNED_EPSILON
+        }));
+        // A negative scale mirrors the voxel so the faces must be culled
+        // using the matrix path.
+        if !aligned || scale <= 0.0 {
+            return None;
+        }
+
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Some(Self {
+            position: [transform.w.x, transform.w.y, transform.w.z],
+            scale,
+            color: [channel(color[0]), channel(color[1]), channel(color[2]), 255]
+        })
+    }
+
+    /// Creates and returns the instance of a voxel drawn as a billboard, the
+    /// quad only needs the position and the size so any transformation is
+    /// accepted.
+    ///
+    /// # Arguments
+    ///
+    /// `transform` - The world transformation of the voxel.
+    /// `color` - The color of the voxel, each channel is in [0, 1].
+    pub fn billboard(transform: &Matrix4<f32>, color: [f32; 3]) -> Self {
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self {
+            position: [transform.w.x, transform.w.y, transform.w.z],
+            scale: transform.x.truncate().magnitude(),
+            color: [channel(color[0]), channel(color[1]), channel(color[2]), 255]
+        }
+    }
+}
+
+unsafe impl Pod for VoxelInstance {}
+unsafe impl Zeroable for VoxelInstance {}
+
 /// TODO: Rename this to pipeline the module already defines context and Rust is
 /// super nice and we can use them as namespaces.
+///
+/// The axis aligned voxels are drawn with `instance_pipeline` using the
+/// compact `VoxelInstance`, the rest use `pipeline` with a matrix and a
+/// color per voxel. The voxels in the last level of detail are drawn with
+/// `billboard_pipeline`.
+///
+/// The voxels which use a matrix are culled in the GPU by `culling` and
+/// drawn with indirect draws.
 pub struct VoxelRenderPipeline {
-    /// Contains the Wgpu pipeline.
-    pub pipeline: RenderPipeline,
+    /// Contains the Wgpu pipeline of the matrix path, it is shared with the
+    /// mesh pipeline.
+    pub pipeline: Arc<RenderPipeline>,
 
-    /// Contains a reference to all the vertices in the Gpu.
-    pub vertex_buffer: Buffer,
+    /// Contains the Wgpu pipeline of the axis aligned voxels.
+    pub instance_pipeline: Arc<RenderPipeline>,
 
-    /// Contains a reference to the indices in the Gpu
-    pub index_buffer: Buffer,
+    /// Contains the Wgpu pipeline of the quads which face the camera.
+    pub billboard_pipeline: Arc<RenderPipeline>,
 
-    /// Contains the number of indices in the index buffer.
-    pub index_len: u32,
+    /// Contains the geometry of each level of detail, 0 is the full cube.
+    pub lods: [VoxelMesh; LOD_LEVELS],
 
     /// Contains the buffer which contains all the transformations.
-    pub transformations_buffer: Buffer,
+    pub transformations_buffer: Arc<Buffer>,
     
     /// Contains the buffer which conatins all the colors.
-    pub voxels_buffer: Buffer
+    pub voxels_buffer: Arc<Buffer>,
+
+    /// Contains the `VoxelInstance` of the axis aligned voxels.
+    pub instances_buffer: Arc<Buffer>,
+
+    /// The number of instances the buffers can host.
+    pub max_instances: u32,
+
+    /// Culls the voxels which use a matrix, it reads the transformations
+    /// and the colors buffers.
+    pub culling: VoxelCulling
 }
 
 impl VoxelRenderPipeline {
@@ -69,108 +172,240 @@ impl VoxelRenderPipeline {
     pub fn new(gpu: &Gpu, world: &DefaultWorld) -> Self {
         info("Creating VoxelRenderPipeline");
 
-        // Generate the needed vertices and indices. 
+        // Generate the vertices and indices of each level of detail.
         let vertices = create_voxel_vertices();
         let indices = create_voxel_indices();
-        let indices_len = indices.len();
+        let (simplified_vertices, simplified_indices) = create_simplified_voxel(&indices);
+        let lods = [
+            VoxelMesh::new(gpu, vertices, indices),
+            VoxelMesh::new(gpu, simplified_vertices, simplified_indices),
+            VoxelMesh::new(gpu, create_billboard_vertices(), create_billboard_indices())
+        ];
 
-        // Generates the shader.
-        let shader_module = create_shader(&gpu);
+        let render_pipeline = create_voxel_render_pipeline(gpu, world);
+        let instance_pipeline = create_voxel_instance_pipeline(gpu, world);
+        let billboard_pipeline = create_voxel_billboard_pipeline(gpu, world);
 
-        // Create the basic needed buffers on GPU.
-        let vertices_buffer: Buffer = gpu.create_vertex(vertices);
-        let indices_buffer: Buffer = gpu.create_index(indices);
+        info("{VoxelRenderPipeline} Voxel pipeline created");
 
-        let locals_layout = world.get::<UniqueRead<LocalsLayout>>();
+        let (transformations_buffer, voxels_buffer, instances_buffer) = allocate_gpu_buffers(
+            &gpu,
+            MAX_NUMBER_OF_INSTANCES
+        );
 
-        info("{VoxelRenderPipeline} Crearing pipeline layout");
+        let culling = VoxelCulling::new(
+            gpu,
+            world,
+            &lods,
+            &transformations_buffer,
+            &voxels_buffer,
+            MAX_NUMBER_OF_INSTANCES
+        );
 
-        // Creates the pipeline layout.
-        let pipeline_layout = gpu.device.create_pipeline_layout(
-            &PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: &[
-                    // Creates the layout for the locals.
-                    &locals_layout.read().layout
-                ],
-                push_constant_ranges: &[]
-            }
+        Self {
+            pipeline: render_pipeline,
+            instance_pipeline,
+            billboard_pipeline,
+            lods,
+            transformations_buffer,
+            voxels_buffer,
+            instances_buffer,
+            max_instances: MAX_NUMBER_OF_INSTANCES,
+            culling
+        }
+    }
+
+    /// Replaces the instance buffers with new ones which can host the
+    /// provided number of instances, the content is not copied due it is
+    /// written every frame.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to allocate the buffers.
+    /// `max_instances` - The new capacity.
+    pub fn resize_instances(&mut self, gpu: &Gpu, max_instances: u32) {
+        let (transformations_buffer, voxels_buffer, instances_buffer) = allocate_gpu_buffers(
+            gpu,
+            max_instances
         );
 
-        info("{VoxelRenderPipeline} Finish creating pipeline layout");
+        self.transformations_buffer = transformations_buffer;
+        self.voxels_buffer = voxels_buffer;
+        self.instances_buffer = instances_buffer;
+        self.max_instances = max_instances;
+
+        self.culling.resize(gpu, &self.transformations_buffer, &self.voxels_buffer, max_instances);
+    }
+}
+
+/// Contains the geometry of a level of detail of the voxel.
+pub struct VoxelMesh {
+    /// Contains a reference to all the vertices in the Gpu.
+    pub vertex_buffer: Buffer,
+
+    /// Contains a reference to the indices in the Gpu
+    pub index_buffer: Buffer,
+
+    /// Contains the number of indices in the index buffer.
+    pub index_len: u32
+}
+
+impl VoxelMesh {
+    /// Creates and returns the mesh uploading the geometry to the GPU.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the buffers.
+    /// `vertices` - The vertices of the mesh.
+    /// `indices` - The indices of the triangles.
+    fn new(gpu: &Gpu, vertices: Vec<Vertex>, indices: Vec<u16>) -> Self {
+        let index_len = indices.len() as u32;
+        Self {
+            vertex_buffer: gpu.create_vertex(vertices),
+            index_buffer: gpu.create_index(indices),
+            index_len
+        }
+    }
+
+    /// Returns the number of triangles of the mesh.
+    pub fn triangles(&self) -> u32 {
+        self.index_len / 3
+    }
+}
+
+/// Returns the render pipeline which draws the voxel shader using a matrix
+/// per instance, the rotated voxels and the meshes use the same pipeline so
+/// it is created only once.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the pipeline.
+/// `world` - The world which contains the layout and the pipeline caches.
+pub fn create_voxel_render_pipeline(gpu: &Gpu, world: &DefaultWorld) -> Arc<RenderPipeline> {
+    voxel_pipeline(gpu, world, "vs_main", &[
+        create_vertex_layout(),
+        create_style_layout(),
+        create_transformation_layout()
+    ])
+}
+
+/// Returns the render pipeline which draws the axis aligned voxels using a
+/// `VoxelInstance` per voxel.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the pipeline.
+/// `world` - The world which contains the layout and the pipeline caches.
+pub fn create_voxel_instance_pipeline(gpu: &Gpu, world: &DefaultWorld) -> Arc<RenderPipeline> {
+    voxel_pipeline(gpu, world, "vs_instance", &[
+        create_vertex_layout(),
+        create_instance_layout()
+    ])
+}
 
-        // Get the swap chain format.
-        let swapchain_format = gpu.swap_chain_format();
+/// Returns the render pipeline which draws the voxels as quads which face
+/// the camera, it uses the same buffers as the instance pipeline.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the pipeline.
+/// `world` - The world which contains the layout and the pipeline caches.
+pub fn create_voxel_billboard_pipeline(gpu: &Gpu, world: &DefaultWorld) -> Arc<RenderPipeline> {
+    voxel_pipeline(gpu, world, "vs_billboard", &[
+        create_vertex_layout(),
+        create_instance_layout()
+    ])
+}
 
-        info("{VoxelRenderPipeline} Crearing render pipeline");
+/// Returns the render pipeline which draws the voxel shader with the
+/// provided vertex entry point.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to create the pipeline.
+/// `world` - The world which contains the layout and the pipeline caches.
+/// `entry_point` - The vertex entry point of the shader.
+/// `buffers` - The vertex buffers used by the entry point.
+fn voxel_pipeline(
+    gpu: &Gpu,
+    world: &DefaultWorld,
+    entry_point: &'static str,
+    buffers: &[VertexBufferLayout]) -> Arc<RenderPipeline> {
+    let primitive = PrimitiveState {
+        cull_mode: wgpu::CullMode::Back,
+        ..Default::default()
+    };
+    let key = PipelineKey {
+        shader: shader_hash(SHADER_SOURCE),
+        vertex_entry_point: entry_point,
+        layouts: vec![BindGroupLayoutKey::Locals],
+        primitive: PrimitiveKey::from(&primitive)
+    };
+
+    world.get_unique::<PipelineCache>().read().pipeline_or_insert_with(key, || {
+        let shader_module = shader_module(gpu, world, SHADER_SOURCE);
+        let locals_layout = bind_group_layout(gpu, world, BindGroupLayoutKey::Locals);
+
+        // Creates the pipeline layout, the voxels do not use push constants.
+        let pipeline_layout = gpu.create_pipeline_layout(
+            None,
+            &[
+                // Creates the layout for the locals.
+                locals_layout.as_ref()
+            ],
+            None
+        );
 
-        let render_pipeline: RenderPipeline = gpu.create_render_pipeline(
+        gpu.create_render_pipeline(
             &RenderPipelineDescriptor {
-                label: None,
+                label: Some("Voxel pipeline"),
                 layout: Some(&pipeline_layout),
                 vertex: VertexState {
                     module: &shader_module,
-                    entry_point: "vs_main",
-                    buffers: &[
-                        create_vertex_layout(),
-                        create_style_layout(),
-                        create_transformation_layout()
-                    ]
+                    entry_point,
+                    buffers
                 },
                 fragment: Some(FragmentState {
                     module: &shader_module,
                     entry_point: "fs_main",
-                    targets: &[swapchain_format.into()],
+                    targets: &[gpu.scene_format().into()],
                 }),
-                primitive: PrimitiveState {
-                    cull_mode: wgpu::CullMode::Back,
-                    ..Default::default()
-                },
+                primitive,
                 depth_stencil: Some(
                     DepthStencilState {
-                        format: DEPTH_FORMAT,
+                        format: gpu.depth_format,
                         depth_write_enabled: true,
                         depth_compare: CompareFunction::Less,
                         stencil: StencilState::default(),
                         bias: DepthBiasState::default(),
                         clamp_depth: false
-                    } 
+                    }
                 ),
                 multisample: wgpu::MultisampleState::default(),
             }
-        );
-
-        info("{VoxelRenderPipeline} Voxel pipeline created");
-
-        let (transformations_buffer, voxels_buffer) = allocate_gpu_buffers(&gpu);
-
-        Self {
-            pipeline: render_pipeline,
-            vertex_buffer: vertices_buffer,
-            index_buffer: indices_buffer,
-            index_len: indices_len as u32,
-            transformations_buffer,
-            voxels_buffer
-        }
-    }
+        )
+    })
 }
 
-/// Creates and returns the shader module for the Voxel render pipeline.
+/// Returns the capacity needed to host the required instances, the current
+/// capacity is grown by `INSTANCES_GROWTH_FACTOR` until it is enough.
 ///
 /// # Arguments
 ///
-/// * `gpu` - The gpu used to create the shader.
-fn create_shader(gpu: &Gpu) -> ShaderModule {
-    // Generate a string shader from the static string and create
-    // the shader provieder using wgsl.
-    let provider: ShaderProvider = ShaderProvider::Wgsl(
-        String::from(include_str!("../shaders/voxel_shader.wgsl"))
-    );
-    
-    // Call the gpu in order to create the shader.
-    gpu.create_shader(&provider)
+/// `current` - The current capacity.
+/// `required` - The number of instances that must fit.
+pub fn grown_capacity(current: u32, required: u32) -> u32 {
+    let mut capacity = current.max(1);
+    while capacity < required {
+        capacity = ((capacity as f64 * INSTANCES_GROWTH_FACTOR).ceil() as u32)
+            .max(capacity + 1);
+    }
+    capacity
 }
 
+/// The WGSL source of the voxel and the mesh pipelines.
+pub const SHADER_SOURCE: &str = include_str!("../shaders/voxel_shader.wgsl");
+
 /// Creates and returns the vertex layout, this is used to know how the
 /// GPU should align the memory sent by the CPU.
 /// 
@@ -179,7 +414,7 @@ fn create_shader(gpu: &Gpu) -> ShaderModule {
 /// we need this layout.
 ///
 /// We can send the data to the GPU using the set_vertex_buffer function.
-fn create_vertex_layout<'a>() -> VertexBufferLayout<'a> {
+pub(crate) fn create_vertex_layout<'a>() -> VertexBufferLayout<'a> {
     VertexBufferLayout {
         // How long is the data that we want to send.
         array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
@@ -196,6 +431,13 @@ fn create_vertex_layout<'a>() -> VertexBufferLayout<'a> {
                 // Where it should map the data in the shader.
                 shader_location: 0
             },
+            // Describes the normal of the `Vertex`.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                // Placed after the position.
+                offset: std::mem::size_of::<Vector4<f32>>() as BufferAddress,
+                shader_location: 6
+            },
             // TODO(Angel): Add the rest of the parameters like UV etc.
         ]
     }
@@ -205,7 +447,7 @@ fn create_vertex_layout<'a>() -> VertexBufferLayout<'a> {
 /// GPU should align the memory sent by the CPU.
 ///
 /// This is useful to send the per voxel style.
-fn create_style_layout<'a>() -> VertexBufferLayout<'a> {
+pub(crate) fn create_style_layout<'a>() -> VertexBufferLayout<'a> {
     VertexBufferLayout {
         // The size of the Voxel content.
         array_stride: std::mem::size_of::<Voxel>() as BufferAddress,
@@ -227,9 +469,36 @@ fn create_style_layout<'a>() -> VertexBufferLayout<'a> {
     }
 }
 
+/// Creates and returns the instance layout of the axis aligned voxels, it
+/// describes a `VoxelInstance`.
+pub(crate) fn create_instance_layout<'a>() -> VertexBufferLayout<'a> {
+    VertexBufferLayout {
+        array_stride: std::mem::size_of::<VoxelInstance>() as BufferAddress,
+        // Iterate over the data per instance.
+        step_mode: InputStepMode::Instance,
+        attributes: &[
+            // The position in xyz and the uniform scale in w.
+            VertexAttribute {
+                format: VertexFormat::Float4,
+                offset: 0,
+                shader_location: 7
+            },
+            // The color, normalized to [0, 1] by the GPU.
+            VertexAttribute {
+                format: VertexFormat::Uchar4Norm,
+                offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                shader_location: 8
+            }
+        ]
+    }
+}
+
 /// Creates and returns the transformation layout, this is used to translate the
 /// vertex on the GPU side.
-fn create_transformation_layout<'a>() -> VertexBufferLayout<'a> {
+///
+/// It is used by the matrix path: the meshes, the outline and the voxels
+/// which are not axis aligned.
+pub(crate) fn create_transformation_layout<'a>() -> VertexBufferLayout<'a> {
     // Take the size of the internal type.
     const UNIT_SIZE: usize = std::mem::size_of::<f32>();
     const ROW_SIZE: u64 = UNIT_SIZE as u64 * 4;
@@ -277,37 +546,45 @@ fn create_transformation_layout<'a>() -> VertexBufferLayout<'a> {
 
 /// Creates and returns the needed vertices.
 fn create_voxel_vertices() -> Vec<Vertex> {
+    // The normal of each face, used by the lighting.
+    let top: Vector3<f32> = Vector3::unit_z();
+    let bottom: Vector3<f32> = -Vector3::unit_z();
+    let right: Vector3<f32> = Vector3::unit_x();
+    let left: Vector3<f32> = -Vector3::unit_x();
+    let front: Vector3<f32> = Vector3::unit_y();
+    let back: Vector3<f32> = -Vector3::unit_y();
+
     [
         // Top face.
-        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 1.0 }, [0.0, 0.0]),
-        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 1.0 }, [1.0, 0.0]),
-        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, [1.0, 1.0]),
-        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 1.0 }, [0.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 1.0 }, top, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 1.0 }, top, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, top, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 1.0 }, top, [0.0, 1.0]),
         // Bottom face.
-        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: -1.0 }, [1.0, 0.0]),
-        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: -1.0 }, [0.0, 0.0]),
-        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: -1.0 }, [0.0, 1.0]),
-        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: -1.0 }, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: -1.0 }, bottom, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: -1.0 }, bottom, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: -1.0 }, bottom, [0.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: -1.0 }, bottom, [1.0, 1.0]),
         // Right face.
-        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: -1.0 }, [0.0, 0.0]),
-        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: -1.0 }, [1.0, 0.0]),
-        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, [1.0, 1.0]),
-        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 1.0 }, [0.0, 1.0]),
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: -1.0 }, right, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: -1.0 }, right, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, right, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 1.0 }, right, [0.0, 1.0]),
         // Left face.
-        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 1.0 }, [1.0, 0.0]),
-        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 1.0 }, [0.0, 0.0]),
-        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: -1.0 }, [0.0, 1.0]),
-        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: -1.0 }, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 1.0 }, left, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 1.0 }, left, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: -1.0 }, left, [0.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: -1.0 }, left, [1.0, 1.0]),
         // Front face.
-        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: -1.0 }, [1.0, 0.0]),
-        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: -1.0 }, [0.0, 0.0]),
-        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 1.0 }, [0.0, 1.0]),
-        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: -1.0 }, front, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: -1.0 }, front, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 1.0 }, front, [0.0, 1.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 1.0 }, front, [1.0, 1.0]),
         // Back face.
-        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 1.0 }, [0.0, 0.0]),
-        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 1.0 }, [1.0, 0.0]),
-        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: -1.0 }, [1.0, 1.0]),
-        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: -1.0 }, [0.0, 1.0])
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 1.0 }, back, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 1.0 }, back, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: -1.0 }, back, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: -1.0 }, back, [0.0, 1.0])
     ].to_vec()
 }
 
@@ -325,21 +602,146 @@ fn create_voxel_indices() -> Vec<u16> {
     index_data.to_vec()
 }
 
+/// Returns the corner of the simplified cube for the provided position, the
+/// bits are set by the positive axes.
+///
+/// # Arguments
+///
+/// `position` - The position of a vertex of the full cube.
+fn corner_index(position: Vector4<f32>) -> u16 {
+    (position.x > 0.0) as u16 | ((position.y > 0.0) as u16) << 1 | ((position.z > 0.0) as u16) << 2
+}
+
+/// Creates and returns the vertices and indices of the simplified cube, the
+/// faces share the 8 corners so the normals point outwards from the center
+/// and the lighting is smoothed.
+///
+/// # Arguments
+///
+/// `indices` - The indices of the full cube, they keep the same winding.
+fn create_simplified_voxel(indices: &[u16]) -> (Vec<Vertex>, Vec<u16>) {
+    let vertices = (0..8u16).map(|corner| {
+        let sign = |bit: u16| if corner & bit != 0 { 1.0 } else { -1.0 };
+        let position = Vector3::new(sign(1), sign(2), sign(4));
+        Vertex::new(position, position.normalize(), [0.0, 0.0])
+    }).collect();
+
+    let full = create_voxel_vertices();
+    let indices = indices.iter().map(|i| corner_index(full[*i as usize].pos)).collect();
+    (vertices, indices)
+}
+
+/// Creates and returns the vertices of the billboard, a quad in the XY plane
+/// which is oriented to the camera in the shader.
+fn create_billboard_vertices() -> Vec<Vertex> {
+    let normal: Vector3<f32> = Vector3::unit_z();
+    [
+        Vertex::new(Vector3 { x: -1.0, y: -1.0, z: 0.0 }, normal, [0.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: -1.0, z: 0.0 }, normal, [1.0, 0.0]),
+        Vertex::new(Vector3 { x: 1.0, y: 1.0, z: 0.0 }, normal, [1.0, 1.0]),
+        Vertex::new(Vector3 { x: -1.0, y: 1.0, z: 0.0 }, normal, [0.0, 1.0])
+    ].to_vec()
+}
+
+/// Creates and returns the indices of the billboard.
+fn create_billboard_indices() -> Vec<u16> {
+    [0, 1, 2, 2, 3, 0].to_vec()
+}
+
 /// Creates and returns all the needed buffers.
 ///
 /// # Arguments
 ///
 /// `gpu` - The gpu used to allocate the buffers.
-fn allocate_gpu_buffers(gpu: &Gpu) -> (Buffer, Buffer) {
-    // Calculate the max size needed to host the max number of 
-    // voxel transformations.
-    let trs_size = (MAX_NUMBER_OF_INSTANCES * Transform::size()) as u64; 
-    let transformations_buffer = gpu.create_vertex_with_size(trs_size); 
+/// `instances` - The number of instances the buffers can host.
+fn allocate_gpu_buffers(gpu: &Gpu, instances: u32) -> (Arc<Buffer>, Arc<Buffer>, Arc<Buffer>) {
+    // The transformations are sent as 4x4 matrices, not as `LocalTransform`,
+    // they are read by the culling.
+    let matrix_size = std::mem::size_of::<Matrix4<f32>>() as u64;
+    let transformations_buffer = gpu.create_storage_with_size(
+        instances as u64 * matrix_size,
+        BufferUsage::VERTEX
+    );
 
     // Caluclate the max size needed to host the max number of
     // voxel properties.
-    let voxel_size = (MAX_NUMBER_OF_INSTANCES * Voxel::size()) as u64;
-    let voxels_buffer = gpu.create_vertex_with_size(voxel_size);
+    let voxel_size = instances as u64 * Voxel::size() as u64;
+    let voxels_buffer = gpu.create_storage_with_size(voxel_size, BufferUsage::VERTEX);
 
-    (transformations_buffer, voxels_buffer)
+    // The axis aligned voxels only send a `VoxelInstance`.
+    let instance_size = std::mem::size_of::<VoxelInstance>() as u64;
+    let instances_buffer = gpu.create_vertex_with_size(instances as u64 * instance_size);
+
+    (Arc::new(transformations_buffer), Arc::new(voxels_buffer), Arc::new(instances_buffer))
+}
+
+/// Creates and returns a buffer which can host the provided number of
+/// transformations.
+///
+/// # Arguments
+///
+/// `gpu` - The gpu used to allocate the buffer.
+/// `instances` - The maximum number of transformations.
+pub(crate) fn allocate_transformations_buffer(gpu: &Gpu, instances: u32) -> Arc<Buffer> {
+    let matrix_size = std::mem::size_of::<Matrix4<f32>>() as u64;
+    Arc::new(gpu.create_vertex_with_size(instances as u64 * matrix_size))
+}
+
+#[test]
+fn instances_growth() {
+    assert_eq!(grown_capacity(100, 50), 100);
+    assert_eq!(grown_capacity(100, 101), 150);
+    assert_eq!(grown_capacity(100, 200), 225);
+    assert_eq!(grown_capacity(0, 1), 1);
+    assert!(grown_capacity(1, 2) >= 2);
+}
+
+#[test]
+fn voxel_instance_matches_matrix() {
+    use cgmath::{Deg, InnerSpace, Quaternion, Rotation3, SquareMatrix};
+
+    // The same reconstruction done by `vs_instance`.
+    let reconstruct = |i: &VoxelInstance, v: Vector3<f32>| {
+        Vector3::new(i.position[0], i.position[1], i.position[2]) + v * i.scale
+    };
+    let corners = [
+        Vector3::new(-1.0, -1.0, -1.0),
+        Vector3::new(1.0, -1.0, 1.0),
+        Vector3::new(1.0, 1.0, -1.0)
+    ];
+
+    let transforms = [
+        Matrix4::identity(),
+        Matrix4::from_translation(Vector3::new(3.0, -2.0, 10.0)),
+        Matrix4::from_translation(Vector3::new(-5.0, 0.5, 1.0)) * Matrix4::from_scale(0.25)
+    ];
+    for transform in transforms.iter() {
+        let instance = VoxelInstance::from_matrix(transform, [1.0, 0.5, 0.0]).unwrap();
+        assert_eq!(instance.color, [255, 128, 0, 255]);
+        for corner in corners.iter() {
+            let expected: Vector4<f32> = *transform * corner.extend(1.0);
+            let position = reconstruct(&instance, *corner);
+            assert!((position - expected.truncate()).magnitude() < 1e-5);
+        }
+    }
+
+    // The rotated and the non uniformly scaled voxels use the matrix path.
+    let rotation = Quaternion::from_angle_z(Deg(45.0));
+    assert!(VoxelInstance::from_matrix(&Matrix4::from(rotation), [1.0; 3]).is_none());
+    assert!(VoxelInstance::from_matrix(&Matrix4::from_nonuniform_scale(1.0, 2.0, 1.0), [1.0; 3]).is_none());
+    assert_eq!(std::mem::size_of::<VoxelInstance>(), 20);
+}
+
+#[test]
+fn simplified_voxel_keeps_faces() {
+    let indices = create_voxel_indices();
+    let (vertices, simplified) = create_simplified_voxel(&indices);
+    let full = create_voxel_vertices();
+
+    assert_eq!(vertices.len(), 8);
+    assert_eq!(simplified.len(), indices.len());
+    // Every triangle keeps the positions of the full cube.
+    for (i, s) in indices.iter().zip(simplified.iter()) {
+        assert_eq!(full[*i as usize].pos, vertices[*s as usize].pos);
+    }
 }
@@ -0,0 +1,85 @@
+use std::ops::Range;
+
+use bytemuck::Pod;
+use wgpu::{PushConstantRange, RenderPass, ShaderStage};
+
+/// The maximum size in bytes of the push constants requested to the device,
+/// it is the minimum size guaranteed by Vulkan.
+pub const MAX_PUSH_CONSTANT_SIZE: u32 = 128;
+
+/// The push constants must be aligned to 4 bytes.
+const PUSH_CONSTANT_ALIGNMENT: u32 = 4;
+
+/// Returns the bytes of the push constants covered by a value of the
+/// provided size, None if the range is not aligned or does not fit in
+/// `MAX_PUSH_CONSTANT_SIZE`.
+///
+/// # Arguments
+///
+/// `offset` - The offset of the value in bytes.
+/// `size` - The size of the value in bytes.
+pub fn push_constant_bytes(offset: u32, size: u32) -> Option<Range<u32>> {
+    let end = offset.checked_add(size)?;
+    if !offset.is_multiple_of(PUSH_CONSTANT_ALIGNMENT) || !size.is_multiple_of(PUSH_CONSTANT_ALIGNMENT) ||
+        size == 0 || end > MAX_PUSH_CONSTANT_SIZE {
+        return None;
+    }
+    Some(offset..end)
+}
+
+/// Returns the range used in the pipeline layout for a value of type `T`,
+/// the pipelines pass it to `Gpu::create_pipeline_layout`.
+///
+/// # Arguments
+///
+/// `stages` - The stages which read the value.
+/// `offset` - The offset of the value in bytes.
+pub fn push_constant_range<T: Pod>(stages: ShaderStage, offset: u32) -> PushConstantRange {
+    let size = std::mem::size_of::<T>() as u32;
+    match push_constant_bytes(offset, size) {
+        Some(range) => PushConstantRange { stages, range },
+        None => panic!(
+            "Invalid push constant range, offset {} and size {} must be 4 bytes aligned and fit in {} bytes",
+            offset,
+            size,
+            MAX_PUSH_CONSTANT_SIZE
+        )
+    }
+}
+
+/// Provides to the render passes the hability to set typed push constants.
+pub trait PushConstantsSetter {
+    /// Should set the value in the push constants of the stages.
+    fn push_constants<T: Pod>(&mut self, stages: ShaderStage, offset: u32, value: &T);
+}
+
+impl<'a> PushConstantsSetter for RenderPass<'a> {
+    /// Sets the value in the push constants, the pipeline layout must
+    /// contain the range returned by `push_constant_range` for the same
+    /// type, stages and offset.
+    ///
+    /// # Arguments
+    ///
+    /// `stages` - The stages which read the value.
+    /// `offset` - The offset of the value in bytes.
+    /// `value` - The value to be sent.
+    fn push_constants<T: Pod>(&mut self, stages: ShaderStage, offset: u32, value: &T) {
+        let data: &[u8] = bytemuck::bytes_of(value);
+        if push_constant_bytes(offset, data.len() as u32).is_none() {
+            panic!("Invalid push constant range, offset {} and size {}", offset, data.len());
+        }
+        self.set_push_constants(stages, offset, data);
+    }
+}
+
+#[test]
+fn push_constant_ranges() {
+    assert_eq!(push_constant_bytes(0, 4), Some(0..4));
+    assert_eq!(push_constant_bytes(64, 64), Some(64..128));
+    // Unaligned, empty or bigger than the guaranteed size.
+    assert_eq!(push_constant_bytes(2, 4), None);
+    assert_eq!(push_constant_bytes(0, 6), None);
+    assert_eq!(push_constant_bytes(0, 0), None);
+    assert_eq!(push_constant_bytes(64, 68), None);
+    assert_eq!(push_constant_bytes(u32::MAX - 3, 4), None);
+}
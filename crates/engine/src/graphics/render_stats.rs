@@ -0,0 +1,119 @@
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Mutex
+};
+
+use ecs::UniqueRead;
+
+/// Contains the number of draw calls, triangles and instances submitted
+/// during the current frame.
+///
+/// The renderers run in parallel so the counters are atomic, each renderer
+/// adds its draws with `record` and the counters are reset at the start of
+/// the frame.
+///
+/// When the GPU profiling is enabled it also contains the GPU time of each
+/// pass, the timestamps are read back once the GPU finishes so the times
+/// belong to a previous frame, usually the last one. The dev GUI displays
+/// them in its top bar.
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    /// The number of draw calls.
+    pub draw_calls: AtomicU32,
+
+    /// The number of triangles, including all the instances.
+    pub triangles: AtomicU32,
+
+    /// The number of instances.
+    pub instances: AtomicU32,
+
+    /// The label and the GPU time in milliseconds of each profiled pass, in
+    /// submission order.
+    pass_times: Mutex<Vec<(String, f32)>>
+}
+
+impl RenderStats {
+    /// Adds a draw call to the stats.
+    ///
+    /// # Arguments
+    ///
+    /// `triangles` - The triangles of a single instance.
+    /// `instances` - The number of instances drawn.
+    pub fn record(&self, triangles: u32, instances: u32) {
+        self.draw_calls.fetch_add(1, Ordering::Relaxed);
+        self.triangles.fetch_add(triangles * instances, Ordering::Relaxed);
+        self.instances.fetch_add(instances, Ordering::Relaxed);
+    }
+
+    /// Sets all the counters to zero.
+    pub fn reset(&self) {
+        self.draw_calls.store(0, Ordering::Relaxed);
+        self.triangles.store(0, Ordering::Relaxed);
+        self.instances.store(0, Ordering::Relaxed);
+    }
+
+    /// Replaces the GPU times of the passes, they are not reset every frame
+    /// due they are published a frame late.
+    ///
+    /// # Arguments
+    ///
+    /// `times` - The label and the GPU time in milliseconds of each pass.
+    pub fn set_pass_times(&self, times: Vec<(String, f32)>) {
+        *self.pass_times.lock().unwrap() = times;
+    }
+
+    /// Returns the label and the GPU time in milliseconds of each pass.
+    pub fn pass_times(&self) -> Vec<(String, f32)> {
+        self.pass_times.lock().unwrap().clone()
+    }
+
+    /// Returns the number of instances drawn in the current frame.
+    pub fn instances(&self) -> u32 {
+        self.instances.load(Ordering::Relaxed)
+    }
+
+    /// Returns the GPU time in milliseconds of all the profiled passes,
+    /// None if no pass was profiled.
+    pub fn gpu_time(&self) -> Option<f32> {
+        let pass_times = self.pass_times.lock().unwrap();
+        if pass_times.is_empty() {
+            return None;
+        }
+        Some(pass_times.iter().map(|(_, time)| time).sum())
+    }
+
+    /// Returns the text displayed by the overlay, a line per profiled pass
+    /// is added after the counters.
+    pub fn overlay_text(&self) -> String {
+        let mut text = format!(
+            "Draw calls: {} | Triangles: {} | Instances: {}",
+            self.draw_calls.load(Ordering::Relaxed),
+            self.triangles.load(Ordering::Relaxed),
+            self.instances.load(Ordering::Relaxed)
+        );
+        for (label, time) in self.pass_times.lock().unwrap().iter() {
+            text.push_str(&format!("\n{}: {:.2} ms", label, time));
+        }
+        text
+    }
+}
+
+/// Resets the stats, it must run before any renderer in the frame.
+pub fn reset_render_stats_system(stats: UniqueRead<RenderStats>) {
+    stats.read().reset();
+}
+
+#[test]
+fn render_stats_count_draws() {
+    let stats = RenderStats::default();
+    // A cube drawn 10 times and a full screen triangle.
+    stats.record(12, 10);
+    stats.record(1, 1);
+    assert_eq!(stats.overlay_text(), "Draw calls: 2 | Triangles: 121 | Instances: 11");
+
+    stats.reset();
+    assert_eq!(stats.overlay_text(), "Draw calls: 0 | Triangles: 0 | Instances: 0");
+
+    stats.set_pass_times(vec![("Sky_System".to_string(), 0.25)]);
+    assert!(stats.overlay_text().ends_with("\nSky_System: 0.25 ms"));
+}
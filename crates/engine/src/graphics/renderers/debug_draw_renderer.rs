@@ -0,0 +1,103 @@
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    RenderPassDepthStencilAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::{UniqueRead, UniqueWrite};
+use log::warning;
+
+use crate::graphics::{
+    CommandBufferQueue,
+    gpu::Gpu,
+    pipelines::{
+        debug_line_pipeline::{DebugLinePipeline, DebugDraw},
+        bind_groups::locals_bind_group::LocalsLayout
+    },
+    renderers::{RenderOrder, CurrentSwapChainOutput, main_target_view},
+    buffer::BufferManipulator,
+    texture::{DepthTexture, HdrTexture},
+    render_stats::RenderStats,
+    gpu_profiler::ProfiledEncoder
+};
+
+/// Draws the debug lines added in the current frame using the main camera.
+#[allow(clippy::too_many_arguments)]
+pub fn debug_draw_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    pipeline: UniqueRead<DebugLinePipeline>,
+    debug_draw: UniqueRead<DebugDraw>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    locals_layout: UniqueRead<LocalsLayout>,
+    depth_texture: UniqueRead<DepthTexture>,
+    hdr_texture: UniqueRead<HdrTexture>,
+    stats: UniqueRead<RenderStats>) {
+    let debug_draw_read = debug_draw.read();
+    let vertices = debug_draw_read.vertices();
+    if vertices.is_empty() {
+        return;
+    }
+
+    let frame = current_frame.read();
+    let hdr_texture_read = hdr_texture.read();
+    let target = match main_target_view(&hdr_texture_read, &frame) {
+        Some(t) => t,
+        None => return
+    };
+
+    let gpu_read = gpu.read();
+    let pipeline_read = pipeline.read();
+    gpu_read.copy_to_buffer(&pipeline_read.vertex_buffer, bytemuck::cast_slice(vertices));
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Debug_Draw_System");
+
+    {
+        let depth_texture_read = depth_texture.read();
+        let layout_read = locals_layout.read();
+
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Debug draw render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: Some(
+                RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &depth_texture_read.0.view,
+                    depth_ops: Some(
+                        Operations {
+                            load: LoadOp::Load,
+                            store: true
+                        }
+                    ),
+                    stencil_ops: None
+                }
+            )
+        });
+
+        rpass.set_pipeline(&pipeline_read.pipeline);
+        rpass.set_bind_group(0, &layout_read.group, &[]);
+        rpass.set_vertex_buffer(0, pipeline_read.vertex_buffer.slice(..));
+        rpass.draw(0..vertices.len() as u32, 0..1);
+        // The lines do not add triangles.
+        stats.read().record(0, 1);
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::DEBUG_DRAW)
+    ).is_err() {
+        warning("{DebugDraw} Render pass error");
+    }
+}
+
+/// Removes the debug lines, this must only be called when the frame ends.
+pub fn clear_debug_draw_system(debug_draw: UniqueWrite<DebugDraw>) {
+    debug_draw.write().clear();
+}
@@ -0,0 +1,100 @@
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::UniqueRead;
+use types::Size;
+use log::warning;
+
+use crate::{
+    basics::dev_gui::DevGui,
+    graphics::{
+        CommandBufferQueue,
+        gpu::Gpu,
+        pipelines::{
+            dev_gui_pipeline::{DevGuiPipeline, MAX_GUI_QUADS},
+            sprite_pipeline::SpriteLocals
+        },
+        renderers::{RenderOrder, CurrentSwapChainOutput},
+        buffer::BufferManipulator,
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
+    }
+};
+
+/// Draws the dev GUI on top of the scene.
+///
+/// The quads come from the layout of the previous frame, all of them are
+/// drawn with a single draw call.
+pub fn dev_gui_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    dev_gui: UniqueRead<DevGui>,
+    dev_gui_pipeline: UniqueRead<DevGuiPipeline>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    stats: UniqueRead<RenderStats>) {
+    let dev_gui_read = dev_gui.read();
+    let mut quads = dev_gui_read.quads();
+
+    if quads.is_empty() {
+        return;
+    }
+
+    if quads.len() > MAX_GUI_QUADS as usize {
+        warning("{DevGuiRenderer} Too many quads, some are not rendered");
+        quads = &quads[..MAX_GUI_QUADS as usize];
+    }
+
+    let frame = current_frame.read();
+    let output = match &frame.0 {
+        Some(o) => o,
+        None => return
+    };
+
+    let gpu_read = gpu.read();
+    let pipeline_read = dev_gui_pipeline.read();
+
+    // The screen could be resized at any moment.
+    let size = Size::new(gpu_read.swap_chain_descriptor.width, gpu_read.swap_chain_descriptor.height);
+    let locals = SpriteLocals::new(size);
+    gpu_read.copy_to_buffer(&pipeline_read.locals_buffer, bytemuck::bytes_of(&locals));
+    gpu_read.copy_to_buffer(&pipeline_read.instances_buffer, bytemuck::cast_slice(quads));
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "DevGui_System");
+
+    {
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Dev GUI render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: &output.view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: None
+        });
+
+        rpass.set_pipeline(&pipeline_read.pipeline);
+        rpass.set_bind_group(0, &pipeline_read.group, &[]);
+        rpass.set_index_buffer(
+            pipeline_read.index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16
+        );
+        rpass.set_vertex_buffer(0, pipeline_read.vertex_buffer.slice(..));
+        rpass.set_vertex_buffer(1, pipeline_read.instances_buffer.slice(..));
+        rpass.draw_indexed(0..6, 0, 0..quads.len() as u32);
+        // Each quad is 2 triangles.
+        stats.read().record(2, quads.len() as u32);
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::DEBUG_GUI)
+    ).is_err() {
+        warning("{DevGuiRenderer} Render pass error");
+    }
+}
@@ -0,0 +1,157 @@
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    RenderPassDepthStencilAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::{UniqueRead, Read, Searchable};
+use log::warning;
+
+use crate::{
+    graphics::{
+        CommandBufferQueue,
+        gpu::Gpu,
+        pipelines::{
+            mesh_pipeline::{MeshRenderPipeline, MeshRegistry, batch_by_mesh, MAX_MESH_INSTANCES},
+            bind_groups::locals_bind_group::LocalsLayout
+        },
+        renderers::{RenderOrder, CurrentSwapChainOutput, main_target_view},
+        buffer::BufferManipulator,
+        texture::{DepthTexture, HdrTexture},
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
+    },
+    scene::{
+        components::LocalTransform,
+        hierarchy::WorldTransforms,
+        mesh::MeshHandle
+    }
+};
+
+/// Draws the entities which contain a `MeshHandle`, the instances of each
+/// mesh are drawn with a single draw call.
+#[allow(clippy::too_many_arguments)]
+pub fn mesh_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    mesh_pipeline: UniqueRead<MeshRenderPipeline>,
+    mesh_registry: UniqueRead<MeshRegistry>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    locals_layout: UniqueRead<LocalsLayout>,
+    depth_texture: UniqueRead<DepthTexture>,
+    hdr_texture: UniqueRead<HdrTexture>,
+    world_transforms: UniqueRead<WorldTransforms>,
+    stats: UniqueRead<RenderStats>,
+    // Components
+    meshes: Read<MeshHandle>,
+    transforms: Read<LocalTransform>) {
+    let registry_read = mesh_registry.read();
+    let world_transforms_read = world_transforms.read();
+
+    let mut instances: Vec<(usize, [[f32; 4]; 4])> = Vec::new();
+    meshes.entities().iter()
+        .zip((meshes.iter(), transforms.iter()).query())
+        .for_each(|(entity, (mesh, transform))| {
+            let id = mesh.read().0;
+            // The handles which were not returned by the registry are
+            // ignored.
+            if id >= registry_read.meshes.len() {
+                return;
+            }
+
+            let raw_transform: [[f32; 4]; 4] = match world_transforms_read.matrices.get(entity) {
+                Some(matrix) => (*matrix).into(),
+                None => transform.read().as_matrix_array()
+            };
+            instances.push((id, raw_transform));
+        });
+
+    if instances.is_empty() {
+        return;
+    }
+
+    if instances.len() > MAX_MESH_INSTANCES as usize {
+        warning("{MeshRenderer} Too many mesh instances, some are not rendered");
+        instances.truncate(MAX_MESH_INSTANCES as usize);
+    }
+
+    let frame = current_frame.read();
+    let hdr_texture_read = hdr_texture.read();
+    let target = match main_target_view(&hdr_texture_read, &frame) {
+        Some(t) => t,
+        None => return
+    };
+
+    let (raw_transforms, batches) = batch_by_mesh(instances);
+    let raw_colors: Vec<[f32; 3]> = batches
+        .iter()
+        .flat_map(|(id, range)| {
+            let color: [f32; 3] = registry_read.meshes[*id].color.into();
+            std::iter::repeat_n(color, range.len())
+        })
+        .collect();
+
+    let gpu_read = gpu.read();
+    let pipeline_read = mesh_pipeline.read();
+
+    gpu_read.copy_to_buffer(
+        &pipeline_read.transformations_buffer,
+        bytemuck::cast_slice(&raw_transforms)
+    );
+    gpu_read.copy_to_buffer(&pipeline_read.colors_buffer, bytemuck::cast_slice(&raw_colors));
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Mesh_Render_System");
+
+    {
+        let depth_texture_read = depth_texture.read();
+        let layout_read = locals_layout.read();
+
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Mesh render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: Some(
+                RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &depth_texture_read.0.view,
+                    depth_ops: Some(
+                        Operations {
+                            load: LoadOp::Load,
+                            store: true
+                        }
+                    ),
+                    stencil_ops: None
+                }
+            )
+        });
+
+        rpass.set_pipeline(&pipeline_read.pipeline);
+        rpass.set_bind_group(0, &layout_read.group, &[]);
+        rpass.set_vertex_buffer(1, pipeline_read.colors_buffer.slice(..));
+        rpass.set_vertex_buffer(2, pipeline_read.transformations_buffer.slice(..));
+
+        // Each mesh has its own buffers, the instances are already sorted
+        // by mesh.
+        let stats_read = stats.read();
+        for (id, range) in batches {
+            let mesh = &registry_read.meshes[id];
+            stats_read.record(mesh.index_len / 3, range.end - range.start);
+            rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            rpass.draw_indexed(0..mesh.index_len, 0, range);
+        }
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::MESH)
+    ).is_err() {
+        warning("{MeshRenderer} Render pass error");
+    }
+}
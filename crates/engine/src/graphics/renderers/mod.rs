@@ -1,6 +1,14 @@
+pub mod debug_draw_renderer;
+pub mod dev_gui_renderer;
+pub mod mesh_renderer;
+pub mod outline_renderer;
+pub mod sky_renderer;
+pub mod sprite_renderer;
+pub mod text_renderer;
+pub mod tone_mapping_renderer;
 pub mod voxel_renderer;
 
-use wgpu::{CommandBuffer, SwapChainTexture};
+use wgpu::{CommandBuffer, SwapChainTexture, TextureView};
 
 use ecs::{UniqueRead, UniqueWrite};
 use log::warning;
@@ -8,6 +16,8 @@ use log::warning;
 use crate::{
     graphics::{
         gpu::Gpu,
+        texture::HdrTexture,
+        render_stats::RenderStats,
         CommandBufferQueue,
         OrderedCommandBuffer
     }
@@ -23,20 +33,74 @@ pub type CurrentSwapChainOutput = (Option<SwapChainTexture>, );
 /// This is needed due the render systems are executed in parallel so the
 /// rendering order is not guaranteed.
 ///
-/// The position on the enum describes the order for submition.
-/// TODO(Angel): Now the render order is inverted fix that.
-#[derive(Copy, Clone)]
-pub enum RenderOrder {
-    /// Render EGui.
-    DebugGui,
+/// The command buffers are submitted from the lowest to the highest
+/// priority, the engine passes leave gaps between them so custom passes can
+/// be placed in the middle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RenderOrder(pub u32);
+
+impl RenderOrder {
+    /// The buffer uploads of the frame, they are submitted before any pass.
+    pub const UPLOAD: RenderOrder = RenderOrder(0);
+
+    /// Sky rendering order, it is rendered before everything.
+    pub const SKY: RenderOrder = RenderOrder(100);
+
     /// Voxel rendering order.
-    Voxel
+    pub const VOXEL: RenderOrder = RenderOrder(200);
+
+    /// Meshes, they are drawn after the voxels which clear the depth.
+    pub const MESH: RenderOrder = RenderOrder(250);
+
+    /// Outline of the selected voxels, it is drawn on top of the voxels.
+    pub const OUTLINE: RenderOrder = RenderOrder(300);
+
+    /// Debug lines, they are drawn after the scene so they are tested
+    /// against its depth.
+    pub const DEBUG_DRAW: RenderOrder = RenderOrder(400);
+
+    /// Text labels, they are drawn after the voxels and tested against their
+    /// depth.
+    pub const TEXT: RenderOrder = RenderOrder(450);
+
+    /// Tone mapping order, it is rendered after all the 3D passes.
+    pub const TONE_MAPPING: RenderOrder = RenderOrder(900);
+
+    /// The dev GUI, it is rendered on top of the scene.
+    pub const DEBUG_GUI: RenderOrder = RenderOrder(1000);
+
+    /// Sprites, they are drawn on top of everything.
+    pub const SPRITE: RenderOrder = RenderOrder(1100);
+
+    /// Creates and returns a new `RenderOrder` for a user defined pass.
+    ///
+    /// # Arguments
+    ///
+    /// `priority` - The priority of the pass, lower values are submitted
+    /// first.
+    pub const fn custom(priority: u32) -> Self {
+        RenderOrder(priority)
+    }
+
+    /// Returns the position used to sort the command buffers.
+    pub fn as_index(&self) -> usize {
+        self.0 as usize
+    }
 }
 
-impl RenderOrder {
-    /// Convert the enum from a option set representation to a number.
-    fn as_index(&self) -> usize {
-        *self as usize
+/// Returns the view where the main camera renders, the HDR texture if it is
+/// enabled otherwise the swap chain output.
+///
+/// # Arguments
+///
+/// `hdr_texture` - The HDR texture.
+/// `output` - The current swap chain output.
+pub fn main_target_view<'a>(
+    hdr_texture: &'a HdrTexture,
+    output: &'a CurrentSwapChainOutput) -> Option<&'a TextureView> {
+    match &hdr_texture.0 {
+        Some(texture) => Some(&texture.view),
+        None => output.0.as_ref().map(|o| &o.view)
     }
 }
 
@@ -66,10 +130,15 @@ pub fn clean_and_drop_system(
 }
 
 /// Submits all the commands to the GPU.
+///
+/// When the GPU profiling is enabled the timestamps of the frame are
+/// resolved after the passes and the times of a previous frame, the last
+/// one the GPU finished, are published into the `RenderStats`.
 /// TODO: Make it better, it is copying all over the place to order.
 pub fn submit_commnads_system(
     gpu: UniqueRead<Gpu>,
-    commnad_buffer_queue: UniqueRead<CommandBufferQueue>) {
+    commnad_buffer_queue: UniqueRead<CommandBufferQueue>,
+    stats: UniqueRead<RenderStats>) {
     let commmand_buffer_queue_read = commnad_buffer_queue.read();
 
     // Get the number of all the commands.
@@ -82,15 +151,43 @@ pub fn submit_commnads_system(
         all_commands.push(c);
     }
 
-    // Short the commands.
+    // Short the commands, the lowest order is submitted first.
     all_commands.sort_by_key(|c| c.order);
 
-    // Extract the commands from the other vector.
-    let mut order_commands = Vec::<CommandBuffer>::new();
-    while let Some(c) = all_commands.pop() {
-        order_commands.push(c.command);
+    let gpu_read = gpu.read();
+
+    // The read back must finish before the queries are resolved again.
+    if let Some(times) = gpu_read.profiler.collect(&gpu_read.device) {
+        stats.read().set_pass_times(times);
     }
+    let resolve = gpu_read.profiler.resolve(&gpu_read.device, &all_commands);
+
+    // Extract the commands from the other vector.
+    let mut order_commands: Vec<CommandBuffer> = all_commands
+        .into_iter()
+        .map(|c| c.command)
+        .collect();
+    order_commands.extend(resolve);
 
     // Submit all.
-    gpu.read().queue.submit(order_commands);
-}
\ No newline at end of file
+    gpu_read.queue.submit(order_commands);
+    gpu_read.profiler.start_readback();
+}
+
+#[test]
+fn render_order_priorities() {
+    assert!(RenderOrder::UPLOAD < RenderOrder::SKY);
+    assert!(RenderOrder::SKY < RenderOrder::VOXEL);
+    assert!(RenderOrder::VOXEL < RenderOrder::MESH);
+    assert!(RenderOrder::MESH < RenderOrder::OUTLINE);
+    assert!(RenderOrder::OUTLINE < RenderOrder::DEBUG_DRAW);
+    assert!(RenderOrder::DEBUG_DRAW < RenderOrder::TEXT);
+    assert!(RenderOrder::TEXT < RenderOrder::TONE_MAPPING);
+    assert!(RenderOrder::TONE_MAPPING < RenderOrder::DEBUG_GUI);
+    assert!(RenderOrder::DEBUG_GUI < RenderOrder::SPRITE);
+
+    // A custom pass between the voxels and the gui.
+    let outline = RenderOrder::custom(500);
+    assert!(RenderOrder::VOXEL < outline && outline < RenderOrder::DEBUG_GUI);
+    assert_eq!(outline.as_index(), 500);
+}
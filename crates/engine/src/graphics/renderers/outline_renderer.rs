@@ -0,0 +1,161 @@
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    RenderPassDepthStencilAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::{UniqueRead, Read, Searchable};
+use log::warning;
+
+use crate::{
+    graphics::{
+        CommandBufferQueue,
+        gpu::Gpu,
+        pipelines::{
+            voxel_render_pipeline::VoxelRenderPipeline,
+            bind_groups::locals_bind_group::LocalsLayout,
+            outline_pipeline::{
+                OutlineRenderPipeline,
+                OutlineColor,
+                OutlineSettings,
+                OUTLINE_STENCIL_REFERENCE,
+                MAX_OUTLINED_VOXELS
+            }
+        },
+        renderers::{RenderOrder, CurrentSwapChainOutput, main_target_view},
+        buffer::BufferManipulator,
+        texture::{DepthTexture, HdrTexture, has_stencil},
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
+    },
+    scene::{
+        components::{Voxel, LocalTransform, Selected},
+        hierarchy::WorldTransforms
+    }
+};
+
+/// Draws an outline around the `Selected` voxels of the main camera.
+///
+/// The selected voxels are first written into the stencil buffer, then they
+/// are drawn slightly enlarged only where the stencil is zero.
+#[allow(clippy::too_many_arguments)]
+pub fn outline_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    voxel_pipeline: UniqueRead<VoxelRenderPipeline>,
+    outline_pipeline: UniqueRead<OutlineRenderPipeline>,
+    outline_color: UniqueRead<OutlineColor>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    locals_layout: UniqueRead<LocalsLayout>,
+    depth_texture: UniqueRead<DepthTexture>,
+    hdr_texture: UniqueRead<HdrTexture>,
+    world_transforms: UniqueRead<WorldTransforms>,
+    stats: UniqueRead<RenderStats>,
+    // Components
+    voxels: Read<Voxel>,
+    selected: Read<Selected>,
+    transformations: Read<LocalTransform>) {
+    // Get the transformations of the selected voxels.
+    let world_transforms_read = world_transforms.read();
+    let mut raw_transforms: Vec<u8> = Vec::new();
+    let mut num_inst: u32 = 0;
+    selected.entities().iter()
+        .zip((voxels.iter(), transformations.iter()).query())
+        .take(MAX_OUTLINED_VOXELS as usize)
+        .for_each(|(entity, (_, transform))| {
+            let raw_transform: [[f32; 4]; 4] =
+                match world_transforms_read.matrices.get(entity) {
+                    Some(matrix) => (*matrix).into(),
+                    None => transform.read().as_matrix_array()
+                };
+            raw_transforms.extend_from_slice(bytemuck::cast_slice(&raw_transform));
+            num_inst += 1;
+        });
+
+    if num_inst == 0 {
+        return;
+    }
+
+    let frame = current_frame.read();
+    let hdr_texture_read = hdr_texture.read();
+    let target = match main_target_view(&hdr_texture_read, &frame) {
+        Some(t) => t,
+        None => return
+    };
+
+    let gpu_read = gpu.read();
+    let voxel_pipeline_read = voxel_pipeline.read();
+    let outline_read = outline_pipeline.read();
+
+    let settings = OutlineSettings::new(**outline_color.read());
+    gpu_read.copy_to_buffer(&outline_read.settings_buffer, bytemuck::bytes_of(&settings));
+    gpu_read.copy_to_buffer(&outline_read.transformations_buffer, &raw_transforms);
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Outline_System");
+
+    {
+        let depth_texture_read = depth_texture.read();
+        let layout_read = locals_layout.read();
+
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Outline render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: Some(
+                RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &depth_texture_read.0.view,
+                    depth_ops: Some(
+                        Operations {
+                            load: LoadOp::Load,
+                            store: true
+                        }
+                    ),
+                    // The stencil only contains the selected voxels.
+                    stencil_ops: match has_stencil(gpu_read.depth_format) {
+                        true => Some(Operations {
+                            load: LoadOp::Clear(0),
+                            store: true
+                        }),
+                        false => None
+                    }
+                }
+            )
+        });
+
+        rpass.set_stencil_reference(OUTLINE_STENCIL_REFERENCE);
+        rpass.set_bind_group(0, &layout_read.group, &[]);
+        rpass.set_bind_group(1, &outline_read.group, &[]);
+        rpass.set_index_buffer(
+            voxel_pipeline_read.lods[0].index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16
+        );
+        rpass.set_vertex_buffer(0, voxel_pipeline_read.lods[0].vertex_buffer.slice(..));
+        rpass.set_vertex_buffer(1, outline_read.transformations_buffer.slice(..));
+
+        // Mark the selected voxels.
+        rpass.set_pipeline(&outline_read.mask_pipeline);
+        rpass.draw_indexed(0..voxel_pipeline_read.lods[0].index_len, 0, 0..num_inst);
+
+        // Draw the enlarged voxels around the marks.
+        rpass.set_pipeline(&outline_read.outline_pipeline);
+        rpass.draw_indexed(0..voxel_pipeline_read.lods[0].index_len, 0, 0..num_inst);
+
+        let stats_read = stats.read();
+        stats_read.record(voxel_pipeline_read.lods[0].triangles(), num_inst);
+        stats_read.record(voxel_pipeline_read.lods[0].triangles(), num_inst);
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::OUTLINE)
+    ).is_err() {
+        warning("{Outline} Render pass error");
+    }
+}
@@ -0,0 +1,96 @@
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::{UniqueRead, UniqueWrite};
+use log::warning;
+
+use crate::{
+    graphics::{
+        CommandBufferQueue,
+        gpu::Gpu,
+        pipelines::sky_pipeline::{SkyPipeline, SkyUniform, SkyUniformBuffer},
+        renderers::{RenderOrder, CurrentSwapChainOutput, main_target_view},
+        buffer::BufferManipulator,
+        texture::HdrTexture,
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
+    },
+    scene::{
+        camera::Camera,
+        light::{SunPosition, SkySettings}
+    }
+};
+
+/// Mantains the sky uniform buffer with respect to the camera, the sun and
+/// the sky settings.
+///
+/// The buffer is only updated when one of them changes.
+pub fn mantain_sky_system(
+    gpu: UniqueRead<Gpu>,
+    camera: UniqueRead<Camera>,
+    sun: UniqueRead<SunPosition>,
+    settings: UniqueRead<SkySettings>,
+    last_uniform: UniqueWrite<SkyUniform>,
+    buffer: UniqueRead<SkyUniformBuffer>) {
+    let uniform = SkyUniform::new(&camera.read(), &sun.read(), &settings.read());
+
+    let mut last_write = last_uniform.write();
+    if **last_write == uniform {
+        return;
+    }
+    **last_write = uniform;
+
+    gpu.read().copy_to_buffer(&buffer.read().0, bytemuck::bytes_of(&uniform));
+}
+
+/// Renders the sky into the main target, it clears the target so it must be
+/// the first pass of the frame.
+pub fn sky_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    sky: UniqueRead<SkyPipeline>,
+    hdr_texture: UniqueRead<HdrTexture>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    stats: UniqueRead<RenderStats>) {
+    let frame = current_frame.read();
+    let hdr_texture_read = hdr_texture.read();
+    let target = match main_target_view(&hdr_texture_read, &frame) {
+        Some(t) => t,
+        None => return
+    };
+
+    let sky_read = sky.read();
+
+    let gpu_read = gpu.read();
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Sky_System");
+
+    {
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Sky render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: None
+        });
+        rpass.set_pipeline(&sky_read.pipeline);
+        rpass.set_bind_group(0, &sky_read.group, &[]);
+        // A single triangle which covers the entire screen.
+        rpass.draw(0..3, 0..1);
+        stats.read().record(1, 1);
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::SKY)
+    ).is_err() {
+        warning("{Sky} Render pass error");
+    }
+}
@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::{UniqueRead, Read, Searchable};
+use types::Size;
+use log::warning;
+
+use crate::{
+    graphics::{
+        CommandBufferQueue,
+        gpu::Gpu,
+        pipelines::sprite_pipeline::{
+            SpriteRenderPipeline,
+            SpriteRenderer,
+            SpriteInstance,
+            SpriteLocals,
+            TextureHandle,
+            MAX_SPRITES
+        },
+        renderers::{RenderOrder, CurrentSwapChainOutput},
+        buffer::BufferManipulator,
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
+    },
+    scene::sprite::{Sprite, SpriteTransform}
+};
+
+/// Draws all the sprites on top of the screen.
+///
+/// The sprites are sorted by texture, so each texture is bound only once
+/// per frame, the sprites which use the same texture keep their order.
+#[allow(clippy::too_many_arguments)]
+pub fn sprite_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    sprite_pipeline: UniqueRead<SpriteRenderPipeline>,
+    sprite_renderer: UniqueRead<SpriteRenderer>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    stats: UniqueRead<RenderStats>,
+    // Components
+    sprites: Read<Sprite>,
+    transforms: Read<SpriteTransform>) {
+    let mut instances: Vec<(usize, SpriteInstance)> = (sprites.iter(), transforms.iter())
+        .query()
+        .map(|(sprite, transform)| {
+            let sprite_read = sprite.read();
+            (
+                sprite_read.texture_handle,
+                SpriteInstance::new(&sprite_read, &transform.read())
+            )
+        })
+        .collect();
+
+    if instances.is_empty() {
+        return;
+    }
+
+    if instances.len() > MAX_SPRITES as usize {
+        warning("{SpriteRenderer} Too many sprites, some are not rendered");
+        instances.truncate(MAX_SPRITES as usize);
+    }
+
+    // The sort is stable, so the order inside a texture is kept.
+    instances.sort_by_key(|(handle, _)| *handle);
+
+    let frame = current_frame.read();
+    let output = match &frame.0 {
+        Some(o) => o,
+        None => return
+    };
+
+    let gpu_read = gpu.read();
+    let pipeline_read = sprite_pipeline.read();
+    let renderer_read = sprite_renderer.read();
+
+    // The screen could be resized at any moment.
+    let size = Size::new(gpu_read.swap_chain_descriptor.width, gpu_read.swap_chain_descriptor.height);
+    let locals = SpriteLocals::new(size);
+    gpu_read.copy_to_buffer(&pipeline_read.locals_buffer, bytemuck::bytes_of(&locals));
+
+    let raw_instances: Vec<SpriteInstance> = instances.iter().map(|(_, i)| *i).collect();
+    gpu_read.copy_to_buffer(&pipeline_read.instances_buffer, bytemuck::cast_slice(&raw_instances));
+
+    let handles: Vec<usize> = instances.iter().map(|(h, _)| *h).collect();
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Sprite_System");
+
+    {
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Sprite render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: &output.view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: None
+        });
+
+        rpass.set_pipeline(&pipeline_read.pipeline);
+        rpass.set_bind_group(0, &pipeline_read.locals_group, &[]);
+        rpass.set_index_buffer(
+            pipeline_read.index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16
+        );
+        rpass.set_vertex_buffer(0, pipeline_read.vertex_buffer.slice(..));
+        rpass.set_vertex_buffer(1, pipeline_read.instances_buffer.slice(..));
+
+        let stats_read = stats.read();
+        for (handle, range) in batch_by_texture(&handles) {
+            let texture = match renderer_read.texture(TextureHandle(handle)) {
+                Some(t) => t,
+                None => {
+                    warning("{SpriteRenderer} Sprite with an unknown texture handle");
+                    continue;
+                }
+            };
+
+            rpass.set_bind_group(1, texture, &[]);
+            stats_read.record(2, range.end - range.start);
+            rpass.draw_indexed(0..6, 0, range);
+        }
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::SPRITE)
+    ).is_err() {
+        warning("{SpriteRenderer} Render pass error");
+    }
+}
+
+/// Returns the instance range of each texture.
+///
+/// # Arguments
+///
+/// `handles` - The texture handle of each instance, sorted.
+fn batch_by_texture(handles: &[usize]) -> Vec<(usize, Range<u32>)> {
+    let mut batches: Vec<(usize, Range<u32>)> = Vec::new();
+
+    for (index, handle) in handles.iter().enumerate() {
+        let index = index as u32;
+        match batches.last_mut() {
+            Some((last, range)) if last == handle => range.end = index + 1,
+            _ => batches.push((*handle, index..index + 1))
+        }
+    }
+
+    batches
+}
+
+#[test]
+fn sprite_batches() {
+    assert!(batch_by_texture(&[]).is_empty());
+    assert_eq!(
+        batch_by_texture(&[0, 0, 1, 3, 3, 3]),
+        vec![(0, 0..2), (1, 2..3), (3, 3..6)]
+    );
+}
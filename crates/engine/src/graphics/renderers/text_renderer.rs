@@ -0,0 +1,151 @@
+use cgmath::{Point3, EuclideanSpace, InnerSpace};
+
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    RenderPassDepthStencilAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::{UniqueRead, Read, Searchable};
+use log::warning;
+
+use crate::{
+    graphics::{
+        CommandBufferQueue,
+        gpu::Gpu,
+        pipelines::text_pipeline::{
+            TextRenderPipeline,
+            TextLocals,
+            GlyphInstance,
+            append_label_glyphs,
+            MAX_GLYPHS
+        },
+        renderers::{RenderOrder, CurrentSwapChainOutput, main_target_view},
+        buffer::BufferManipulator,
+        texture::{DepthTexture, HdrTexture},
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
+    },
+    scene::{
+        camera::Camera,
+        components::LocalTransform,
+        hierarchy::WorldTransforms,
+        text_label::TextLabel
+    }
+};
+
+/// Draws the text labels facing the main camera.
+///
+/// All the glyphs share the font atlas, so they are drawn with a single draw
+/// call. The labels behind the camera are skipped.
+#[allow(clippy::too_many_arguments)]
+pub fn text_renderer_system(
+    gpu: UniqueRead<Gpu>,
+    camera: UniqueRead<Camera>,
+    text_pipeline: UniqueRead<TextRenderPipeline>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    depth_texture: UniqueRead<DepthTexture>,
+    hdr_texture: UniqueRead<HdrTexture>,
+    world_transforms: UniqueRead<WorldTransforms>,
+    stats: UniqueRead<RenderStats>,
+    // Components
+    labels: Read<TextLabel>,
+    transforms: Read<LocalTransform>) {
+    let camera_read = camera.read();
+    let forward = camera_read.forward();
+    let world_transforms_read = world_transforms.read();
+
+    let mut instances: Vec<GlyphInstance> = Vec::new();
+    labels.entities().iter()
+        .zip((labels.iter(), transforms.iter()).query())
+        .for_each(|(entity, (label, transform))| {
+            let position = match world_transforms_read.matrices.get(entity) {
+                Some(matrix) => Point3::from_vec(matrix.w.truncate()),
+                None => Point3::from_vec(transform.read().position)
+            };
+
+            let label_read = label.read();
+            let anchor = position + label_read.offset;
+
+            // Skip the labels behind the camera.
+            if (anchor - camera_read.eye).dot(forward) < camera_read.znear {
+                return;
+            }
+
+            append_label_glyphs(&mut instances, anchor, &label_read);
+        });
+
+    if instances.is_empty() {
+        return;
+    }
+
+    if instances.len() > MAX_GLYPHS as usize {
+        warning("{TextRenderer} Too many glyphs, some are not rendered");
+        instances.truncate(MAX_GLYPHS as usize);
+    }
+
+    let frame = current_frame.read();
+    let hdr_texture_read = hdr_texture.read();
+    let target = match main_target_view(&hdr_texture_read, &frame) {
+        Some(t) => t,
+        None => return
+    };
+
+    let gpu_read = gpu.read();
+    let pipeline_read = text_pipeline.read();
+
+    let locals = TextLocals::new(&camera_read);
+    gpu_read.copy_to_buffer(&pipeline_read.locals_buffer, bytemuck::bytes_of(&locals));
+    gpu_read.copy_to_buffer(&pipeline_read.instances_buffer, bytemuck::cast_slice(&instances));
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Text_System");
+
+    {
+        let depth_texture_read = depth_texture.read();
+
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Text render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: Some(
+                RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &depth_texture_read.0.view,
+                    depth_ops: Some(
+                        Operations {
+                            load: LoadOp::Load,
+                            store: true
+                        }
+                    ),
+                    stencil_ops: None
+                }
+            )
+        });
+
+        rpass.set_pipeline(&pipeline_read.pipeline);
+        rpass.set_bind_group(0, &pipeline_read.group, &[]);
+        rpass.set_index_buffer(
+            pipeline_read.index_buffer.slice(..),
+            wgpu::IndexFormat::Uint16
+        );
+        rpass.set_vertex_buffer(0, pipeline_read.vertex_buffer.slice(..));
+        rpass.set_vertex_buffer(1, pipeline_read.instances_buffer.slice(..));
+        rpass.draw_indexed(0..6, 0, 0..instances.len() as u32);
+        // Each glyph is a quad.
+        stats.read().record(2, instances.len() as u32);
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::TEXT)
+    ).is_err() {
+        warning("{TextRenderer} Render pass error");
+    }
+}
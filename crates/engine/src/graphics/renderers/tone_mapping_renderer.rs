@@ -0,0 +1,79 @@
+use wgpu::{
+    RenderPassDescriptor,
+    RenderPassColorAttachmentDescriptor,
+    Operations,
+    LoadOp
+};
+
+use ecs::UniqueRead;
+use log::warning;
+
+use crate::graphics::{
+    CommandBufferQueue,
+    gpu::Gpu,
+    pipelines::tone_mapping_pipeline::{
+        ToneMapping,
+        ToneMappingOperator,
+        ToneMappingSettings
+    },
+    renderers::{RenderOrder, CurrentSwapChainOutput},
+    buffer::BufferManipulator,
+    render_stats::RenderStats,
+    gpu_profiler::ProfiledEncoder
+};
+
+/// Tone maps the HDR texture into the swap chain, it does nothing if the
+/// scene is not rendered in HDR.
+pub fn tone_mapping_system(
+    gpu: UniqueRead<Gpu>,
+    tone_mapping: UniqueRead<ToneMapping>,
+    operator: UniqueRead<ToneMappingOperator>,
+    command_buffer: UniqueRead<CommandBufferQueue>,
+    current_frame: UniqueRead<CurrentSwapChainOutput>,
+    stats: UniqueRead<RenderStats>) {
+    let tone_mapping_read = tone_mapping.read();
+    let pipeline = match &tone_mapping_read.0 {
+        Some(p) => p,
+        None => return
+    };
+
+    let frame = current_frame.read();
+    let output = match &frame.0 {
+        Some(o) => o,
+        None => return
+    };
+
+    let gpu_read = gpu.read();
+
+    // The operator could be changed at any moment.
+    let settings = ToneMappingSettings::new(**operator.read());
+    gpu_read.copy_to_buffer(&pipeline.settings_buffer, bytemuck::bytes_of(&settings));
+
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Tone_Mapping_System");
+
+    {
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Tone mapping render pass"),
+            color_attachments: &[RenderPassColorAttachmentDescriptor {
+                attachment: &output.view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true
+                }
+            }],
+            depth_stencil_attachment: None
+        });
+        rpass.set_pipeline(&pipeline.pipeline);
+        rpass.set_bind_group(0, &pipeline.group, &[]);
+        // A single triangle which covers the entire screen.
+        rpass.draw(0..3, 0..1);
+        stats.read().record(1, 1);
+    }
+
+    if command_buffer.read().push(
+        encoder.finish(RenderOrder::TONE_MAPPING)
+    ).is_err() {
+        warning("{ToneMapping} Render pass error");
+    }
+}
@@ -1,15 +1,27 @@
+use std::sync::OnceLock;
+
 use wgpu::{
-    CommandEncoderDescriptor,
+    BindGroup,
+    CommandEncoder,
+    RenderPass,
     RenderPassDescriptor,
     RenderPassDepthStencilAttachmentDescriptor,
     Operations,
-    LoadOp
+    LoadOp,
+    TextureView,
+    BufferAddress
 };
+use cgmath::Matrix4;
 
 use ecs::{
     UniqueRead,
+    UniqueWrite,
     Read,
-    Searchable
+    Reader,
+    Searchable,
+    ParallelQuery,
+    WorldWorkers,
+    Entity
 };
 
 use log::{info, warning};
@@ -17,25 +29,46 @@ use log::{info, warning};
 use crate::{
     graphics::{
         CommandBufferQueue,
-        OrderedCommandBuffer,
         gpu::Gpu,
         pipelines::{ 
-            voxel_render_pipeline::VoxelRenderPipeline,
-            bind_groups::locals_bind_group::LocalsLayout
+            voxel_render_pipeline::{
+                VoxelRenderPipeline,
+                VoxelInstance,
+                VoxelMesh,
+                ResizeVoxelBuffer,
+                grown_capacity
+            },
+            voxel_culling::VoxelCulling,
+            bind_groups::locals_bind_group::{LocalsLayout, CameraLocalsPool}
         },
-        renderers::{RenderOrder, CurrentSwapChainOutput},
+        renderers::{RenderOrder, CurrentSwapChainOutput, main_target_view},
         buffer::{BufferManipulator},
-        texture::DepthTexture
+        texture::{DepthTexture, HdrTexture},
+        render_stats::RenderStats,
+        gpu_profiler::ProfiledEncoder
     },
     scene::{ 
-        components::{Voxel, Transform}
+        components::{Voxel, LocalTransform},
+        hierarchy::WorldTransforms,
+        lod::{VoxelLods, LOD_LEVELS}
     }
 };
 
 // /// Reprsets a system voxel renderer.
 
+/// The voxels are grouped by level of detail, the last level is always drawn
+/// as a billboard so only the cube levels have a matrix path.
+const CUBE_LEVELS: usize = LOD_LEVELS - 1;
+
+/// The number of voxels from which they are packed using the workers.
+const PARALLEL_PACKING_THRESHOLD: usize = 4096;
+
+/// The number of voxels packed by each task of the workers.
+const PACKING_CHUNK_SIZE: usize = 1024;
+
 
 /// Runs the system, executed by the world.
+#[allow(clippy::too_many_arguments)]
 pub fn voxel_renderer_system(
     gpu: UniqueRead<Gpu>,
     voxel_pipeline: UniqueRead<VoxelRenderPipeline>,
@@ -43,126 +76,177 @@ pub fn voxel_renderer_system(
     current_frame: UniqueRead<CurrentSwapChainOutput>,
     locals_layout: UniqueRead<LocalsLayout>,
     depth_texture: UniqueRead<DepthTexture>,
+    hdr_texture: UniqueRead<HdrTexture>,
+    camera_passes: UniqueRead<CameraLocalsPool>,
+    world_transforms: UniqueRead<WorldTransforms>,
+    resize_voxel_buffer: UniqueWrite<ResizeVoxelBuffer>,
+    stats: UniqueRead<RenderStats>,
+    lods: UniqueRead<VoxelLods>,
+    workers: UniqueRead<WorldWorkers>,
     // Components
     voxels: Read<Voxel>,
-    transformations: Read<Transform>) {
+    transformations: Read<LocalTransform>) {
 
-    // Create the command enconder descriptor.
-    let e_descriptor = CommandEncoderDescriptor {
-        label: None
-    };
     let handle = std::thread::current();
     println!("Voxel thread: {}", handle.name().unwrap());
  
-    // Create a new enconder.
-    let mut encoder = gpu.read().device.create_command_encoder(&e_descriptor);
+    // Create a new enconder, the pass is timed if the GPU profiling is
+    // enabled.
+    let gpu_read = gpu.read();
+    let mut encoder = ProfiledEncoder::new(&gpu_read.device, &gpu_read.profiler, "Voxel_Render_System");
 
     // Create a buffer for all the transformations, at this point we should have
     // a cache system so if there are not changes on the items we could avoid 
     // this part.
-    let mut raw_transforms: Vec<u8> = Vec::<u8>::new();
+    let mut raw_transforms: [Vec<u8>; CUBE_LEVELS] = Default::default();
 
     // Creates a buffer for all the colors, we should implement a cache system
     // for this.
-    let mut raw_colors: Vec<u8> = Vec::<u8>::new();
-
-    // Generate the transformation buffer.
-    // TODO(Angel): Limit this loop due the pipeline only supports 200000 of 
-    // them.
-    (voxels.iter(), transformations.iter())
-        .query()
-        .for_each(|(voxel, transfrom)| {
-            // Get the raw transformation.
-            let raw_transform = transfrom.read().as_matrix_array();
-            // Transform the raw information to a binary array.
-            let data = bytemuck::cast_slice(&raw_transform);
-            // Append that to the vector.
-            raw_transforms.append(&mut Vec::from(data));
-
-            // Get the raw color.
-            let raw_color: [f32; 3] = voxel.read().color_as_array();
-            // Get a representation of the color in bytes.
-            let data: &[u8] = bytemuck::bytes_of(&raw_color);
-            // Conver the array into a vector and append that vector to the 
-            // colors vector.
-            raw_colors.append(&mut Vec::from(data));
-        });
+    let mut raw_colors: [Vec<u8>; CUBE_LEVELS] = Default::default();
+
+    // The axis aligned voxels only send position, scale and color, the
+    // matrix and the color above are only used by the rest.
+    let mut instances: [Vec<VoxelInstance>; CUBE_LEVELS] = Default::default();
+
+    // The far voxels only need the position and the size.
+    let mut billboards: Vec<VoxelInstance> = Vec::new();
+
+    // When there are more voxels than the buffers can host the buffers are
+    // grown at the end of the frame, meanwhile only the ones that fit are
+    // rendered.
+    let max_instances: u32 = voxel_pipeline.read().max_instances;
+    let total_instances: u32 = transformations.len() as u32;
+    if total_instances > max_instances {
+        let new_capacity = grown_capacity(max_instances, total_instances);
+        resize_voxel_buffer.write().0 = Some(new_capacity);
+    }
+
+    // Pack the voxels, with many voxels the work is split between the
+    // workers. The packed voxels keep the order of the entities, so both
+    // paths fill the buffers in the same way.
+    let world_transforms_read = world_transforms.read();
+    let lods_read = lods.read();
+    let query = voxels.entities().iter()
+        .zip((voxels.iter(), transformations.iter()).query())
+        .take(max_instances as usize);
+    let packed: Vec<PackedVoxel> = if total_instances as usize >= PARALLEL_PACKING_THRESHOLD {
+        let slots: Vec<OnceLock<PackedVoxel>> = (0..total_instances.min(max_instances))
+            .map(|_| OnceLock::new())
+            .collect();
+        query.enumerate().par_for_each(
+            &workers.read(),
+            PACKING_CHUNK_SIZE,
+            |(index, (entity, (voxel, transform)))| {
+                let packed = pack_voxel(entity, &voxel, &transform, &world_transforms_read, &lods_read);
+                let _ = slots[index].set(packed);
+            }
+        );
+        slots.into_iter().filter_map(OnceLock::into_inner).collect()
+    } else {
+        query
+            .map(|(entity, (voxel, transform))| {
+                pack_voxel(entity, &voxel, &transform, &world_transforms_read, &lods_read)
+            })
+            .collect()
+    };
+
+    // Group the voxels by path and level of detail.
+    for voxel in packed {
+        match voxel {
+            PackedVoxel::Billboard(instance) => billboards.push(instance),
+            PackedVoxel::Aligned(level, instance) => instances[level].push(instance),
+            PackedVoxel::Matrix(level, raw_transform, raw_color) => {
+                // Transform the raw information to a binary array.
+                raw_transforms[level].extend_from_slice(bytemuck::cast_slice(&raw_transform));
+                // Append the color to the colors of the level.
+                raw_colors[level].extend_from_slice(bytemuck::bytes_of(&raw_color));
+            }
+        }
+    }
+
+    // Get the number of instances of each path, the entities which contain a
+    // transform but not a voxel are not counted.
+    let matrix_size = std::mem::size_of::<[[f32; 4]; 4]>();
+    let counts = VoxelCounts {
+        aligned: [instances[0].len() as u32, instances[1].len() as u32],
+        matrix: [
+            (raw_transforms[0].len() / matrix_size) as u32,
+            (raw_transforms[1].len() / matrix_size) as u32
+        ],
+        billboards: billboards.len() as u32
+    };
 
+    // The levels are uploaded one after the other, the billboards are placed
+    // after the aligned voxels in the instance buffer.
+    let instances: Vec<VoxelInstance> = instances.concat().into_iter().chain(billboards).collect();
+    let raw_transforms: Vec<u8> = raw_transforms.concat();
+    let raw_colors: Vec<u8> = raw_colors.concat();
+
+    let voxel_pipeline_read = voxel_pipeline.read();
+
+    // If it has transformations it means there are some entities to be 
+    // rendererd.
+    if !instances.is_empty() {
+        gpu_read.copy_to_buffer(
+            &voxel_pipeline_read.instances_buffer,
+            bytemuck::cast_slice(&instances)
+        );
+    }
+    if !raw_transforms.is_empty() {
+        // Copy data to the buffer
+        gpu_read.copy_to_buffer(
+            &voxel_pipeline_read.transformations_buffer,
+            &raw_transforms);
+        gpu_read.copy_to_buffer( 
+            &voxel_pipeline_read.voxels_buffer,
+            &raw_colors
+        );
+        voxel_pipeline_read.culling.write_counts(&gpu_read, counts.matrix);
+    }
+
+    // The passes are recorded even without voxels, they clear the depth used
+    // by the rest of the passes.
+    //
+    // The main camera renders into the swap chain, or into the HDR texture
+    // which is tone mapped later.
     let frame = current_frame.read();
-    if let Some(output) = &frame.0 {
+    let hdr_texture_read = hdr_texture.read();
+    let stats_read = stats.read();
+    if let Some(target) = main_target_view(&hdr_texture_read, &frame) {
         let depth_texture_read = depth_texture.read();
-        let depth_texture_attachment = &depth_texture_read.0.view;
-
-        let rp_descriptor = RenderPassDescriptor {
-            label: Some("Voxel render pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &output.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: Some(
-                RenderPassDepthStencilAttachmentDescriptor {
-                    attachment: depth_texture_attachment,
-                    depth_ops: Some(
-                        Operations {
-                            load: LoadOp::Clear(1.0),
-                            store: true
-                        }
-                    ),
-                    stencil_ops: None
-                }
-            ),
-        };
-
-        // If it has transformations it means there are some entities to be 
-        // rendererd.
-        if !raw_transforms.is_empty() {
-            let layout_read = locals_layout.read();
-            let group = &layout_read.group;
-
-            // Get the number of instances.
-            let num_inst: u32 = transformations.len() as u32;
-            
-            // Copy data to the buffer
-            let gpu_read = gpu.read();
-            let voxel_pipeline_read = voxel_pipeline.read();
-
-            gpu_read.copy_to_buffer(
-                &voxel_pipeline_read.transformations_buffer,
-                &raw_transforms);
-            gpu_read.copy_to_buffer( 
-                &voxel_pipeline_read.voxels_buffer,
-                &raw_colors
-            );
-
-            // Create the render pass.
-            let mut rpass = encoder.begin_render_pass(&rp_descriptor);
-            rpass.set_pipeline(&voxel_pipeline_read.pipeline);
-            // Bind the locals bind group to the group 0. 
-            rpass.set_bind_group(0, group, &[]);
-            // Set the vertex buffer.
-            rpass.set_index_buffer(
-                voxel_pipeline_read.index_buffer.slice(..),
-                wgpu::IndexFormat::Uint16
-            );
-            // Set the vertex buffer.
-            rpass.set_vertex_buffer(0, voxel_pipeline_read.vertex_buffer.slice(..));
-            rpass.set_vertex_buffer(1, voxel_pipeline_read.voxels_buffer.slice(..));
-            rpass.set_vertex_buffer(2, voxel_pipeline_read.transformations_buffer.slice(..));
-            rpass.draw_indexed(0..voxel_pipeline_read.index_len, 0, 0..num_inst);
-        }
+        let layout_read = locals_layout.read();
+
+        record_voxel_pass(
+            &mut encoder,
+            &voxel_pipeline_read,
+            &layout_read.group,
+            target,
+            &depth_texture_read.0.view,
+            // The sky is already rendered in the main target.
+            LoadOp::Load,
+            counts,
+            &stats_read
+        );
+    }
+
+    // The rest of the cameras render into their own targets.
+    let camera_passes_read = camera_passes.read();
+    for pass in camera_passes_read.passes.values() {
+        record_voxel_pass(
+            &mut encoder,
+            &voxel_pipeline_read,
+            &pass.group,
+            &pass.color.view,
+            &pass.depth.view,
+            LoadOp::Clear(wgpu::Color::BLACK),
+            counts,
+            &stats_read
+        );
     }
 
     // Send the commander buffer
     match command_buffer.read().push(
-        OrderedCommandBuffer {
-            label: Some("Voxel_Render_System".to_string()),
-            order: RenderOrder::Voxel.as_index(),
-            command: encoder.finish()
-        }   
+        encoder.finish(RenderOrder::VOXEL)   
     ) {
         Ok(_) => {
             info("{VoxelRenderer} Render pass finished correclty");
@@ -172,3 +256,199 @@ pub fn voxel_renderer_system(
         }
     }
 }
+
+/// Contains a voxel ready to be copied into the buffer of its path.
+enum PackedVoxel {
+    /// A far voxel drawn as a quad which faces the camera.
+    Billboard(VoxelInstance),
+
+    /// An axis aligned voxel and its level of detail.
+    Aligned(usize, VoxelInstance),
+
+    /// A voxel drawn using a matrix, its level of detail and its color.
+    Matrix(usize, [[f32; 4]; 4], [f32; 3])
+}
+
+/// Returns the packed voxel of the entity.
+///
+/// # Arguments
+///
+/// `entity` - The entity which owns the voxel.
+/// `voxel` - The voxel component of the entity.
+/// `transform` - The local transform of the entity.
+/// `world_transforms` - The world transforms of the hierarchy.
+/// `lods` - The levels of detail of the voxels.
+fn pack_voxel(
+    entity: &Entity,
+    voxel: &Reader<Voxel>,
+    transform: &Reader<LocalTransform>,
+    world_transforms: &WorldTransforms,
+    lods: &VoxelLods) -> PackedVoxel {
+    // Get the raw world transformation, the local one is only used by the
+    // entities created after the last propagation.
+    let matrix: Matrix4<f32> = match world_transforms.matrices.get(entity) {
+        Some(matrix) => *matrix,
+        None => transform.read().as_matrix_array().into()
+    };
+
+    // Get the raw color.
+    let raw_color: [f32; 3] = voxel.read().color_as_array();
+
+    let level = lods.level(entity.id());
+    if level == CUBE_LEVELS {
+        return PackedVoxel::Billboard(VoxelInstance::billboard(&matrix, raw_color));
+    }
+
+    match VoxelInstance::from_matrix(&matrix, raw_color) {
+        Some(instance) => PackedVoxel::Aligned(level, instance),
+        None => PackedVoxel::Matrix(level, matrix.into(), raw_color)
+    }
+}
+
+/// Contains the number of voxels drawn by each path, the cube paths are
+/// split by level of detail.
+#[derive(Clone, Copy, Debug)]
+struct VoxelCounts {
+    /// The voxels drawn using `VoxelInstance`.
+    aligned: [u32; LOD_LEVELS - 1],
+
+    /// The voxels drawn using a matrix.
+    matrix: [u32; LOD_LEVELS - 1],
+
+    /// The voxels drawn as quads which face the camera.
+    billboards: u32
+}
+
+/// Records a voxel render pass into the encoder, the axis aligned voxels
+/// are drawn first, then the rest and finally the billboards. Each level of
+/// detail uses its own geometry and a range of the instance buffers.
+///
+/// The voxels which use a matrix are culled for the camera before the pass
+/// and drawn with `draw_indexed_indirect`.
+///
+/// # Arguments
+///
+/// `encoder` - The encoder where the pass is recorded.
+/// `voxel_pipeline` - The pipeline which contains the buffers.
+/// `locals_group` - The bind group of the camera locals.
+/// `color` - The view where the pass renders.
+/// `depth` - The depth view used by the pass.
+/// `color_load` - Defines if the color is cleared or the content is kept.
+/// `counts` - The number of instances to be rendered by each path.
+/// `stats` - The stats where the draw calls are counted.
+#[allow(clippy::too_many_arguments)]
+fn record_voxel_pass<'a>(
+    encoder: &'a mut CommandEncoder,
+    voxel_pipeline: &'a VoxelRenderPipeline,
+    locals_group: &'a BindGroup,
+    color: &'a TextureView,
+    depth: &'a TextureView,
+    color_load: LoadOp<wgpu::Color>,
+    counts: VoxelCounts,
+    stats: &RenderStats) {
+    // The culling writes the visible voxels of this camera, the buffers are
+    // reused by the next camera once this pass is recorded.
+    let matrix_total: u32 = counts.matrix.iter().sum();
+    if matrix_total > 0 {
+        voxel_pipeline.culling.record(encoder, locals_group, matrix_total);
+    }
+
+    let rp_descriptor = RenderPassDescriptor {
+        label: Some("Voxel render pass"),
+        color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+            attachment: color,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: color_load,
+                store: true,
+            },
+        }],
+        depth_stencil_attachment: Some(
+            RenderPassDepthStencilAttachmentDescriptor {
+                attachment: depth,
+                depth_ops: Some(
+                    Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: true
+                    }
+                ),
+                stencil_ops: None
+            }
+        ),
+    };
+
+    // Create the render pass.
+    let mut rpass = encoder.begin_render_pass(&rp_descriptor);
+    // Bind the locals bind group to the group 0. 
+    rpass.set_bind_group(0, locals_group, &[]);
+
+    // The first instance of each level in the instance buffers.
+    let mut first_instance: u32 = 0;
+    let mut first_matrix: u32 = 0;
+
+    rpass.set_pipeline(&voxel_pipeline.instance_pipeline);
+    rpass.set_vertex_buffer(1, voxel_pipeline.instances_buffer.slice(..));
+    for (mesh, count) in voxel_pipeline.lods.iter().zip(counts.aligned.iter()) {
+        if *count > 0 {
+            set_mesh(&mut rpass, mesh);
+            rpass.draw_indexed(0..mesh.index_len, 0, first_instance..first_instance + count);
+            stats.record(mesh.triangles(), *count);
+        }
+        first_instance += count;
+    }
+
+    let culling = &voxel_pipeline.culling;
+    let matrix_size = std::mem::size_of::<[[f32; 4]; 4]>() as BufferAddress;
+    let color_size = Voxel::size() as BufferAddress;
+    rpass.set_pipeline(&voxel_pipeline.pipeline);
+    for (level, (mesh, count)) in voxel_pipeline.lods.iter().zip(counts.matrix.iter()).enumerate() {
+        if *count > 0 {
+            set_mesh(&mut rpass, mesh);
+            // The visible voxels are compacted at the start of the range of
+            // the level, so the first instance of the arguments is 0.
+            let first = first_matrix as BufferAddress;
+            rpass.set_vertex_buffer(1, culling.culled_voxels_buffer.slice(first * color_size..));
+            rpass.set_vertex_buffer(2, culling.culled_transformations_buffer.slice(first * matrix_size..));
+            rpass.draw_indexed_indirect(&culling.draw_args_buffer, VoxelCulling::draw_args_offset(level));
+            // The number of visible voxels is only known by the GPU, the
+            // voxels before the culling are counted.
+            stats.record(mesh.triangles(), *count);
+        }
+        first_matrix += count;
+    }
+
+    if counts.billboards > 0 {
+        let mesh = &voxel_pipeline.lods[LOD_LEVELS - 1];
+        rpass.set_pipeline(&voxel_pipeline.billboard_pipeline);
+        rpass.set_vertex_buffer(1, voxel_pipeline.instances_buffer.slice(..));
+        set_mesh(&mut rpass, mesh);
+        rpass.draw_indexed(0..mesh.index_len, 0, first_instance..first_instance + counts.billboards);
+        stats.record(mesh.triangles(), counts.billboards);
+    }
+}
+
+/// Sets the vertices and the indices of a level of detail.
+///
+/// # Arguments
+///
+/// `rpass` - The render pass where the buffers are set.
+/// `mesh` - The geometry of the level.
+fn set_mesh<'a>(rpass: &mut RenderPass<'a>, mesh: &'a VoxelMesh) {
+    rpass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+    rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+}
+
+/// Grows the voxel instance buffers when the renderer requested it, it must
+/// be executed when the frame ends due the buffers are replaced.
+pub fn resize_voxel_buffer_system(
+    gpu: UniqueRead<Gpu>,
+    voxel_pipeline: UniqueWrite<VoxelRenderPipeline>,
+    resize_voxel_buffer: UniqueWrite<ResizeVoxelBuffer>) {
+    let new_capacity = match resize_voxel_buffer.write().0.take() {
+        Some(c) => c,
+        None => return
+    };
+
+    info(&format!("{{VoxelRenderer}} Growing the instance buffers to {}", new_capacity));
+    voxel_pipeline.write().resize_instances(&gpu.read(), new_capacity);
+}
@@ -0,0 +1,37 @@
+[[location(0)]]
+var<in> in_position: vec3<f32>;
+[[location(1)]]
+var<in> in_color: vec4<f32>;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+[[location(0)]]
+var<out> out_line_color: vec4<f32>;
+
+[[block]]
+struct Locals {
+    transform: mat4x4<f32>;
+    ambient_light: vec4<f32>;
+    directional_light_dir: vec4<f32>;
+    directional_light_color: vec4<f32>;
+};
+[[group(0), binding(0)]]
+var r_locals: Locals;
+
+[[stage(vertex)]]
+fn vs_main() {
+    // The lines are already in world space.
+    out_pos = r_locals.transform * vec4<f32>(in_position, 1.0);
+    out_line_color = in_color;
+}
+
+[[location(0)]]
+var<in> in_line_color: vec4<f32>;
+
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    out_color = in_line_color;
+}
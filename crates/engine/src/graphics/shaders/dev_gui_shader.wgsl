@@ -0,0 +1,57 @@
+[[location(0)]]
+var<in> in_corner: vec2<f32>;
+
+// The per quad data.
+[[location(1)]]
+var<in> in_position: vec2<f32>;
+[[location(2)]]
+var<in> in_size: vec2<f32>;
+[[location(3)]]
+var<in> in_rect: vec4<f32>;
+[[location(4)]]
+var<in> in_color: vec4<f32>;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+[[location(0)]]
+var<out> out_uv: vec2<f32>;
+[[location(1)]]
+var<out> out_color: vec4<f32>;
+
+[[block]]
+struct Locals {
+    projection: mat4x4<f32>;
+};
+[[group(0), binding(0)]]
+var r_locals: Locals;
+
+[[stage(vertex)]]
+fn vs_main() {
+	// The screen and the atlas rows both go from the top to the bottom.
+	const screen: vec2<f32> = in_position + in_corner * in_size;
+
+	out_uv = in_rect.xy + in_corner * in_rect.zw;
+	out_color = in_color;
+
+	// The z is ignored, the quads are drawn in submission order.
+	const clip: vec4<f32> = r_locals.projection * vec4<f32>(screen, 0.0, 1.0);
+	out_pos = vec4<f32>(clip.xy, 0.0, 1.0);
+}
+
+[[group(0), binding(1)]]
+var t_atlas: texture_2d<f32>;
+[[group(0), binding(2)]]
+var s_atlas: sampler;
+
+[[location(0)]]
+var<in> in_uv_fs: vec2<f32>;
+[[location(1)]]
+var<in> in_color_fs: vec4<f32>;
+[[location(0)]]
+var<out> out_target: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    const coverage: f32 = textureSample(t_atlas, s_atlas, in_uv_fs).r;
+    out_target = vec4<f32>(in_color_fs.rgb, in_color_fs.a * coverage);
+}
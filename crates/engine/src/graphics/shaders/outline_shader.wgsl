@@ -0,0 +1,66 @@
+[[location(0)]]
+var<in> in_position: vec4<f32>;
+
+// Get the transformation matrix using 4 4D vectors.
+[[location(2)]]
+var<in> in_transform_0: vec4<f32>;
+[[location(3)]]
+var<in> in_transform_1: vec4<f32>;
+[[location(4)]]
+var<in> in_transform_2: vec4<f32>;
+[[location(5)]]
+var<in> in_transform_3: vec4<f32>;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+
+[[block]]
+struct Locals {
+    transform: mat4x4<f32>;
+    ambient_light: vec4<f32>;
+    directional_light_dir: vec4<f32>;
+    directional_light_color: vec4<f32>;
+};
+[[group(0), binding(0)]]
+var r_locals: Locals;
+
+[[block]]
+struct Settings {
+    color: vec4<f32>;
+};
+[[group(1), binding(0)]]
+var r_settings: Settings;
+
+// Defines how much the voxels are enlarged to draw the outline.
+const OUTLINE_SCALE: f32 = 1.08;
+
+// Returns the clip position of the vertex scaled around the voxel center.
+fn transform_vertex(scale: f32) -> vec4<f32> {
+	const entity_transform: mat4x4<f32> = mat4x4<f32>(
+		in_transform_0.x, in_transform_0.y, in_transform_0.z, in_transform_0.w,
+		in_transform_1.x, in_transform_1.y, in_transform_1.z, in_transform_1.w,
+		in_transform_2.x, in_transform_2.y, in_transform_2.z, in_transform_2.w,
+		in_transform_3.x, in_transform_3.y, in_transform_3.z, in_transform_3.w
+	);
+
+	const position: vec4<f32> = vec4<f32>(in_position.xyz * scale, 1.0);
+	return r_locals.transform * entity_transform * position;
+}
+
+[[stage(vertex)]]
+fn vs_mask() {
+	out_pos = transform_vertex(1.0);
+}
+
+[[stage(vertex)]]
+fn vs_outline() {
+	out_pos = transform_vertex(OUTLINE_SCALE);
+}
+
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    out_color = r_settings.color;
+}
@@ -0,0 +1,118 @@
+[[builtin(vertex_index)]]
+var<in> in_vertex_index: u32;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+[[location(0)]]
+var<out> out_ndc: vec2<f32>;
+
+[[stage(vertex)]]
+fn vs_main() {
+	// Generate a triangle which covers the entire screen:
+	// (-1, -1), (3, -1), (-1, 3).
+	const x: f32 = f32(i32(in_vertex_index & 1u)) * 4.0 - 1.0;
+	const y: f32 = f32(i32(in_vertex_index >> 1u)) * 4.0 - 1.0;
+
+	out_ndc = vec2<f32>(x, y);
+	out_pos = vec4<f32>(x, y, 0.0, 1.0);
+}
+
+[[block]]
+struct Sky {
+    inv_view_proj: mat4x4<f32>;
+    camera_position: vec4<f32>;
+    sun_direction: vec3<f32>;
+    turbidity: f32;
+    sun_color: vec3<f32>;
+    sun_intensity: f32;
+};
+[[group(0), binding(0)]]
+var r_sky: Sky;
+
+[[location(0)]]
+var<in> in_ndc_fs: vec2<f32>;
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+// Perez distribution function, it describes how the luminance changes with
+// the view angle (theta) and the angle to the sun (gamma).
+fn perez(cos_theta: f32, gamma: f32, cos_gamma: f32,
+         a: f32, b: f32, c: f32, d: f32, e: f32) -> f32 {
+    return (1.0 + a * exp(b / cos_theta))
+        * (1.0 + c * exp(d * gamma) + e * cos_gamma * cos_gamma);
+}
+
+[[stage(fragment)]]
+fn fs_main() {
+    // Calculate the view ray using a point in the far plane.
+    const far: vec4<f32> = r_sky.inv_view_proj * vec4<f32>(in_ndc_fs, 1.0, 1.0);
+    const view_dir: vec3<f32> = normalize(far.xyz / far.w - r_sky.camera_position.xyz);
+    const sun_dir: vec3<f32> = normalize(r_sky.sun_direction);
+    const t: f32 = r_sky.turbidity;
+
+    // Below the horizon the horizon color is used.
+    const cos_theta: f32 = max(view_dir.y, 0.001);
+    const cos_gamma: f32 = clamp(dot(view_dir, sun_dir), -1.0, 1.0);
+    const gamma: f32 = acos(cos_gamma);
+    const theta_s: f32 = acos(clamp(sun_dir.y, 0.0, 1.0));
+    const cos_theta_s: f32 = max(cos(theta_s), 0.001);
+
+    // Zenith luminance and chromaticity (Preetham et al. 1999).
+    const chi: f32 = (4.0 / 9.0 - t / 120.0) * (3.14159265 - 2.0 * theta_s);
+    const zenith_y: f32 = (4.0453 * t - 4.9710) * tan(chi) - 0.2155 * t + 2.4192;
+
+    const ts: f32 = theta_s;
+    const ts2: f32 = ts * ts;
+    const ts3: f32 = ts2 * ts;
+    const zenith_x: f32 =
+        t * t * (0.00166 * ts3 - 0.00375 * ts2 + 0.00209 * ts)
+        + t * (-0.02903 * ts3 + 0.06377 * ts2 - 0.03202 * ts + 0.00394)
+        + (0.11693 * ts3 - 0.21196 * ts2 + 0.06052 * ts + 0.25886);
+    const zenith_yc: f32 =
+        t * t * (0.00275 * ts3 - 0.00610 * ts2 + 0.00317 * ts)
+        + t * (-0.04214 * ts3 + 0.08970 * ts2 - 0.04153 * ts + 0.00516)
+        + (0.15346 * ts3 - 0.26756 * ts2 + 0.06670 * ts + 0.26688);
+
+    // Perez coefficients for the luminance and both chromaticities.
+    const lum: f32 = zenith_y
+        * perez(cos_theta, gamma, cos_gamma,
+                0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771, -0.0670 * t + 0.3703)
+        / perez(1.0, theta_s, cos_theta_s,
+                0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771, -0.0670 * t + 0.3703);
+    const cx: f32 = zenith_x
+        * perez(cos_theta, gamma, cos_gamma,
+                -0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989, -0.0033 * t + 0.0452)
+        / perez(1.0, theta_s, cos_theta_s,
+                -0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989, -0.0033 * t + 0.0452);
+    const cy: f32 = zenith_yc
+        * perez(cos_theta, gamma, cos_gamma,
+                -0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537, -0.0109 * t + 0.0529)
+        / perez(1.0, theta_s, cos_theta_s,
+                -0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537, -0.0109 * t + 0.0529);
+
+    // The luminance is in kcd/m2, scale it to keep the zenith in the
+    // displayable range.
+    const y: f32 = lum / max(zenith_y, 0.001) * 0.4 * r_sky.sun_intensity;
+
+    // Yxy to XYZ to linear sRGB.
+    const big_x: f32 = cx / cy * y;
+    const big_z: f32 = (1.0 - cx - cy) / cy * y;
+    var rgb: vec3<f32> = vec3<f32>(
+        3.2406 * big_x - 1.5372 * y - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * y + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * y + 1.0570 * big_z
+    );
+
+    // Add the sun disc.
+    if (cos_gamma > 0.9998) {
+        rgb = rgb + r_sky.sun_color * r_sky.sun_intensity * 4.0;
+    }
+
+    out_color = vec4<f32>(max(rgb, vec3<f32>(0.0, 0.0, 0.0)), 1.0);
+}
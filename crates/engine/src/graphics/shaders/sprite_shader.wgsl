@@ -0,0 +1,65 @@
+[[location(0)]]
+var<in> in_corner: vec2<f32>;
+
+// The per sprite data.
+[[location(1)]]
+var<in> in_position: vec2<f32>;
+[[location(2)]]
+var<in> in_size: vec2<f32>;
+[[location(3)]]
+var<in> in_src_rect: vec4<f32>;
+[[location(4)]]
+var<in> in_color: vec4<f32>;
+[[location(5)]]
+var<in> in_rotation: f32;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+[[location(0)]]
+var<out> out_uv: vec2<f32>;
+[[location(1)]]
+var<out> out_color: vec4<f32>;
+
+[[block]]
+struct Locals {
+    projection: mat4x4<f32>;
+};
+[[group(0), binding(0)]]
+var r_locals: Locals;
+
+[[stage(vertex)]]
+fn vs_main() {
+	// The corner is placed around the center of the sprite and rotated.
+	const local: vec2<f32> = (in_corner - vec2<f32>(0.5, 0.5)) * in_size;
+	const c: f32 = cos(in_rotation);
+	const s: f32 = sin(in_rotation);
+	const rotated: vec2<f32> = vec2<f32>(
+		local.x * c - local.y * s,
+		local.x * s + local.y * c
+	);
+	const screen: vec2<f32> = in_position + rotated;
+
+	out_uv = in_src_rect.xy + in_corner * in_src_rect.zw;
+	out_color = in_color;
+
+	// The z is ignored, the sprites are drawn in submission order.
+	const clip: vec4<f32> = r_locals.projection * vec4<f32>(screen, 0.0, 1.0);
+	out_pos = vec4<f32>(clip.xy, 0.0, 1.0);
+}
+
+[[group(1), binding(0)]]
+var t_sprite: texture_2d<f32>;
+[[group(1), binding(1)]]
+var s_sprite: sampler;
+
+[[location(0)]]
+var<in> in_uv_fs: vec2<f32>;
+[[location(1)]]
+var<in> in_color_fs: vec4<f32>;
+[[location(0)]]
+var<out> out_target: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    out_target = textureSample(t_sprite, s_sprite, in_uv_fs) * in_color_fs;
+}
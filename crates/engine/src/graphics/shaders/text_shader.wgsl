@@ -0,0 +1,68 @@
+[[location(0)]]
+var<in> in_corner: vec2<f32>;
+
+// The per glyph data.
+[[location(1)]]
+var<in> in_anchor: vec3<f32>;
+[[location(2)]]
+var<in> in_offset: vec2<f32>;
+[[location(3)]]
+var<in> in_rect: vec4<f32>;
+[[location(4)]]
+var<in> in_color: vec4<f32>;
+[[location(5)]]
+var<in> in_scale: f32;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+[[location(0)]]
+var<out> out_uv: vec2<f32>;
+[[location(1)]]
+var<out> out_color: vec4<f32>;
+
+[[block]]
+struct Locals {
+    view_projection: mat4x4<f32>;
+    right: vec4<f32>;
+    up: vec4<f32>;
+};
+[[group(0), binding(0)]]
+var r_locals: Locals;
+
+// The size of a glyph in font pixels.
+const GLYPH_SIZE: vec2<f32> = vec2<f32>(5.0, 7.0);
+
+[[stage(vertex)]]
+fn vs_main() {
+	// The quad is placed in the camera plane, so it always faces it.
+	const local: vec2<f32> = (in_offset + in_corner * GLYPH_SIZE) * in_scale;
+	const world: vec3<f32> = in_anchor
+		+ r_locals.right.xyz * local.x
+		+ r_locals.up.xyz * local.y;
+
+	// The atlas rows go from the top to the bottom.
+	out_uv = in_rect.xy + vec2<f32>(in_corner.x, 1.0 - in_corner.y) * in_rect.zw;
+	out_color = in_color;
+	out_pos = r_locals.view_projection * vec4<f32>(world, 1.0);
+}
+
+[[group(0), binding(1)]]
+var t_atlas: texture_2d<f32>;
+[[group(0), binding(2)]]
+var s_atlas: sampler;
+
+[[location(0)]]
+var<in> in_uv_fs: vec2<f32>;
+[[location(1)]]
+var<in> in_color_fs: vec4<f32>;
+[[location(0)]]
+var<out> out_target: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    const coverage: f32 = textureSample(t_atlas, s_atlas, in_uv_fs).r;
+    if (coverage < 0.5) {
+        discard;
+    }
+    out_target = in_color_fs;
+}
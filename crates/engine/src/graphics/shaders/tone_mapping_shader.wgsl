@@ -0,0 +1,61 @@
+[[builtin(vertex_index)]]
+var<in> in_vertex_index: u32;
+
+[[builtin(position)]]
+var<out> out_pos: vec4<f32>;
+[[location(0)]]
+var<out> out_uv: vec2<f32>;
+
+[[stage(vertex)]]
+fn vs_main() {
+	// Generate a triangle which covers the entire screen:
+	// (-1, -1), (3, -1), (-1, 3).
+	const x: f32 = f32(i32(in_vertex_index & 1u)) * 4.0 - 1.0;
+	const y: f32 = f32(i32(in_vertex_index >> 1u)) * 4.0 - 1.0;
+
+	out_uv = vec2<f32>((x + 1.0) * 0.5, (1.0 - y) * 0.5);
+	out_pos = vec4<f32>(x, y, 0.0, 1.0);
+}
+
+[[group(0), binding(0)]]
+var t_hdr: texture_2d<f32>;
+[[group(0), binding(1)]]
+var s_hdr: sampler;
+
+[[block]]
+struct Settings {
+    operator: u32;
+};
+[[group(0), binding(2)]]
+var r_settings: Settings;
+
+[[location(0)]]
+var<in> in_uv_fs: vec2<f32>;
+[[location(0)]]
+var<out> out_color: vec4<f32>;
+
+[[stage(fragment)]]
+fn fs_main() {
+    const hdr: vec3<f32> = textureSample(t_hdr, s_hdr, in_uv_fs).xyz;
+
+    // The swap chain is sRGB so the gamma correction is done by the GPU.
+    var mapped: vec3<f32>;
+    if (r_settings.operator == 0u) {
+        // Reinhard.
+        mapped = hdr / (hdr + vec3<f32>(1.0, 1.0, 1.0));
+    } else {
+        // ACES filmic curve approximation (Krzysztof Narkowicz).
+        const a: f32 = 2.51;
+        const b: f32 = 0.03;
+        const c: f32 = 2.43;
+        const d: f32 = 0.59;
+        const e: f32 = 0.14;
+        mapped = clamp(
+            (hdr * (hdr * a + b)) / (hdr * (hdr * c + d) + e),
+            vec3<f32>(0.0, 0.0, 0.0),
+            vec3<f32>(1.0, 1.0, 1.0)
+        );
+    }
+
+    out_color = vec4<f32>(mapped, 1.0);
+}
@@ -0,0 +1,104 @@
+// Culls the voxels drawn with a matrix against the camera frustum, the
+// visible ones are compacted at the start of the range of their level and
+// counted in the indirect draw arguments.
+
+[[block]]
+struct Locals {
+    transform: mat4x4<f32>;
+    ambient_light: vec4<f32>;
+    directional_light_dir: vec4<f32>;
+    directional_light_color: vec4<f32>;
+};
+[[group(0), binding(0)]]
+var r_locals: Locals;
+
+[[block]]
+struct CullingParams {
+    // The number of voxels of each level, only x and y are used.
+    counts: vec4<u32>;
+};
+[[group(1), binding(0)]]
+var r_params: CullingParams;
+
+[[block]]
+struct Matrices {
+    data: [[stride(64)]] array<mat4x4<f32>>;
+};
+
+// The colors are packed as 3 floats per voxel, as the vertex buffers.
+[[block]]
+struct Colors {
+    data: [[stride(4)]] array<f32>;
+};
+
+// The `DrawIndexedIndirectArgs` of each level, 5 values per level where the
+// second one is the instance count.
+[[block]]
+struct DrawArgs {
+    data: [[stride(4)]] array<atomic<u32>>;
+};
+
+[[group(1), binding(1)]]
+var<storage> r_transforms: [[access(read)]] Matrices;
+[[group(1), binding(2)]]
+var<storage> r_colors: [[access(read)]] Colors;
+[[group(1), binding(3)]]
+var<storage> r_culled_transforms: [[access(read_write)]] Matrices;
+[[group(1), binding(4)]]
+var<storage> r_culled_colors: [[access(read_write)]] Colors;
+[[group(1), binding(5)]]
+var<storage> r_draw_args: [[access(read_write)]] DrawArgs;
+
+[[builtin(global_invocation_id)]]
+var global_id: vec3<u32>;
+
+// Returns the signed distance from the plane to the point, the normal of
+// the plane is not normalized.
+fn plane_distance(plane: vec4<f32>, point: vec3<f32>) -> f32 {
+    return (dot(plane.xyz, point) + plane.w) / length(plane.xyz);
+}
+
+[[stage(compute), workgroup_size(64)]]
+fn cs_main() {
+    const index: u32 = global_id.x;
+    if (index >= r_params.counts.x + r_params.counts.y) {
+        return;
+    }
+
+    // The levels are stored one after the other.
+    var level: u32 = 0u;
+    var first: u32 = 0u;
+    if (index >= r_params.counts.x) {
+        level = 1u;
+        first = r_params.counts.x;
+    }
+
+    // The voxel is a cube from -1 to 1, the corners are inside the sum of
+    // the axes.
+    const transform: mat4x4<f32> = r_transforms.data[index];
+    const center: vec3<f32> = transform[3].xyz;
+    const radius: f32 = length(transform[0].xyz) + length(transform[1].xyz)
+        + length(transform[2].xyz);
+
+    // The planes of the frustum, the normals point inside and the depth
+    // goes from 0 to 1.
+    const m: mat4x4<f32> = r_locals.transform;
+    const row0: vec4<f32> = vec4<f32>(m[0].x, m[1].x, m[2].x, m[3].x);
+    const row1: vec4<f32> = vec4<f32>(m[0].y, m[1].y, m[2].y, m[3].y);
+    const row2: vec4<f32> = vec4<f32>(m[0].z, m[1].z, m[2].z, m[3].z);
+    const row3: vec4<f32> = vec4<f32>(m[0].w, m[1].w, m[2].w, m[3].w);
+    if (plane_distance(row3 + row0, center) < -radius
+        || plane_distance(row3 - row0, center) < -radius
+        || plane_distance(row3 + row1, center) < -radius
+        || plane_distance(row3 - row1, center) < -radius
+        || plane_distance(row2, center) < -radius
+        || plane_distance(row3 - row2, center) < -radius) {
+        return;
+    }
+
+    const slot: u32 = first + atomicAdd(&r_draw_args.data[level * 5u + 1u], 1u);
+    r_culled_transforms.data[slot] = transform;
+    r_culled_colors.data[slot * 3u] = r_colors.data[index * 3u];
+    r_culled_colors.data[slot * 3u + 1u] = r_colors.data[index * 3u + 1u];
+    r_culled_colors.data[slot * 3u + 2u] = r_colors.data[index * 3u + 2u];
+}
@@ -12,20 +12,51 @@ var<in> in_transform_1: vec4<f32>;
 var<in> in_transform_2: vec4<f32>;
 [[location(5)]]
 var<in> in_transform_3: vec4<f32>;
+[[location(6)]]
+var<in> in_normal: vec4<f32>;
+
+// The compact instance of the axis aligned voxels, the position is stored
+// in xyz and the uniform scale in w.
+[[location(7)]]
+var<in> in_instance_position_scale: vec4<f32>;
+[[location(8)]]
+var<in> in_instance_color: vec4<f32>;
 
 
 [[builtin(position)]]
 var<out> out_pos: vec4<f32>;
 [[location(0)]]
 var<out> out_color: vec3<f32>;
+[[location(1)]]
+var<out> out_normal: vec3<f32>;
+[[location(2)]]
+var<out> out_world_position: vec3<f32>;
 
 [[block]]
 struct Locals {
     transform: mat4x4<f32>;
+    ambient_light: vec4<f32>;
+    directional_light_dir: vec4<f32>;
+    directional_light_color: vec4<f32>;
 };
 [[group(0), binding(0)]]
 var r_locals: Locals;
 
+struct PointLight {
+    // The radius is stored in w.
+    position_radius: vec4<f32>;
+    // The intensity is stored in w.
+    color_intensity: vec4<f32>;
+};
+
+[[block]]
+struct PointLights {
+    lights: [[stride(32)]] array<PointLight, 32>;
+    count: u32;
+};
+[[group(0), binding(2)]]
+var r_point_lights: PointLights;
+
 [[stage(vertex)]]
 fn vs_main() {
 	// Try to find a better way to create this matrix.
@@ -38,16 +69,90 @@ fn vs_main() {
 
 	out_color = vec3<f32>(in_color.x, in_color.y, in_color.z);//in_color;
 
+	// The normal is a direction so the translation is not applied (w = 0).
+	const world_normal: vec4<f32> = entity_transform * in_normal;
+	out_normal = world_normal.xyz;
+
+	const world_position: vec4<f32> = entity_transform * in_position;
+	out_world_position = world_position.xyz;
+
 	// Recreate the transformation matrix.
-	out_pos = r_locals.transform * entity_transform * in_position;
+	out_pos = r_locals.transform * world_position;
+}
+
+// Draws the axis aligned voxels, the transformation is reconstructed from
+// the position and the uniform scale of the instance.
+[[stage(vertex)]]
+fn vs_instance() {
+	out_color = in_instance_color.xyz;
+
+	// Without rotation the normal does not change.
+	out_normal = in_normal.xyz;
+
+	const world_position: vec3<f32> = in_instance_position_scale.xyz
+		+ in_position.xyz * in_instance_position_scale.w;
+	out_world_position = world_position;
+
+	out_pos = r_locals.transform * vec4<f32>(world_position, 1.0);
+}
+
+// Draws the far voxels as quads which face the camera, the camera axes are
+// the first rows of the view projection matrix.
+[[stage(vertex)]]
+fn vs_billboard() {
+	out_color = in_instance_color.xyz;
+
+	const right: vec3<f32> = normalize(vec3<f32>(
+		r_locals.transform[0].x, r_locals.transform[1].x, r_locals.transform[2].x
+	));
+	const up: vec3<f32> = normalize(vec3<f32>(
+		r_locals.transform[0].y, r_locals.transform[1].y, r_locals.transform[2].y
+	));
+	out_normal = cross(right, up);
+
+	const world_position: vec3<f32> = in_instance_position_scale.xyz
+		+ (right * in_position.x + up * in_position.y) * in_instance_position_scale.w;
+	out_world_position = world_position;
+
+	out_pos = r_locals.transform * vec4<f32>(world_position, 1.0);
 }
 
 [[location(0)]]
 var<in> in_color_fs: vec3<f32>;
+[[location(1)]]
+var<in> in_normal_fs: vec3<f32>;
+[[location(2)]]
+var<in> in_world_position_fs: vec3<f32>;
 [[location(0)]]
 var<out> out_color: vec4<f32>;
 
 [[stage(fragment)]]
 fn fs_main() {
-    out_color = vec4<f32>(in_color_fs.x, in_color_fs.y, in_color_fs.z, 1.0);
+    // Lambertian shading, the light direction is already normalized.
+    const normal: vec3<f32> = normalize(in_normal_fs);
+    const diffuse_factor: f32 = max(dot(normal, -r_locals.directional_light_dir.xyz), 0.0);
+    var light: vec3<f32> = r_locals.ambient_light.xyz
+        + r_locals.directional_light_color.xyz * diffuse_factor;
+
+    // The point lights fade quadratically to zero at their radius.
+    var i: u32 = 0u;
+    loop {
+        if (i >= r_point_lights.count) {
+            break;
+        }
+
+        const point: PointLight = r_point_lights.lights[i];
+        const to_light: vec3<f32> = point.position_radius.xyz - in_world_position_fs;
+        const light_distance: f32 = length(to_light);
+        const falloff: f32 = clamp(1.0 - light_distance / point.position_radius.w, 0.0, 1.0);
+        const point_diffuse: f32 = max(dot(normal, to_light / max(light_distance, 0.0001)), 0.0);
+        light = light + point.color_intensity.xyz * point.color_intensity.w
+            * point_diffuse * falloff * falloff;
+
+        continuing {
+            i = i + 1u;
+        }
+    }
+
+    out_color = vec4<f32>(in_color_fs * light, 1.0);
 }
@@ -1,5 +1,16 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    path::Path
+};
+
+use image::ImageFormat;
 use wgpu::{TextureView, Sampler, TextureFormat};
 
+use types::Size;
+use log::warning;
+
+use crate::scene::assets::Asset;
+
 /// Represents an engine texture, it contains the reference to the view, the 
 /// texture id in gpu and the sampler.
 pub struct Texture {
@@ -12,19 +23,196 @@ pub struct Texture {
 
     /// Contains the information that the pipeline needs to pick information 
     /// from the `TextureView`, this defines wrapping mode and other stuff.
-    pub sampler: Sampler
+    pub sampler: Sampler,
+
+    /// The size of the texture in pixels.
+    pub size: Size<u32>
 }
 
 /// Provides the needed symbols used to generate textures. 
 pub trait TextureGenerator {
     /// Should generate a new depth texture.
     fn create_depth_texture(&self) -> Texture;
+
+    /// Should generate a new depth texture using the provided size.
+    fn create_depth_texture_with_size(&self, width: u32, height: u32) -> Texture;
+
+    /// Should generate a new texture which can be used as render target and
+    /// sampled later.
+    fn create_render_target_texture(&self, width: u32, height: u32) -> Texture;
+
+    /// Should generate a new HDR texture of the size of the screen.
+    fn create_hdr_texture(&self) -> Texture;
+
+    /// Should generate a new texture which contains the provided pixels.
+    fn create_texture_with_data(&self,
+                                label: &str,
+                                size: Size<u32>,
+                                format: TextureFormat,
+                                bytes_per_pixel: u32,
+                                data: &[u8]) -> Texture;
+
+    /// Should load the image file into a new RGBA texture.
+    fn load_texture_from_file(&self, path: &Path) -> Result<Texture, TextureError>;
+
+    /// Should generate a new RGBA texture which contains the decoded image.
+    fn create_texture_from_image(&self, label: &str, image: &Image) -> Texture;
 }
 
-/// Defines the depth format.
-pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+/// Defines the default depth format, it contains a stencil used by the
+/// outline of the selected voxels. The format used at runtime is
+/// `Gpu::depth_format`.
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth24PlusStencil8;
+
+/// Returns true if the depth format contains a stencil.
+///
+/// # Arguments
+///
+/// `format` - The depth format.
+pub fn has_stencil(format: TextureFormat) -> bool {
+    matches!(format, TextureFormat::Depth24PlusStencil8)
+}
+
+/// Returns the depth format to be used, the preferred one if it is a depth
+/// format otherwise `DEPTH_FORMAT`.
+///
+/// Every adapter supports all the depth formats, so only the kind of the
+/// format is checked.
+///
+/// # Arguments
+///
+/// `preferred` - The format requested in `GpuOptions`.
+pub fn select_depth_format(preferred: Option<TextureFormat>) -> TextureFormat {
+    let format = match preferred {
+        Some(f) => f,
+        None => return DEPTH_FORMAT
+    };
+
+    match format {
+        TextureFormat::Depth32Float |
+        TextureFormat::Depth24Plus |
+        TextureFormat::Depth24PlusStencil8 => {
+            if !has_stencil(format) {
+                warning(&format!(
+                    "{{Gpu}} {:?} does not contain a stencil, the outline is drawn without mask",
+                    format
+                ));
+            }
+            format
+        },
+        f => {
+            warning(&format!("{{Gpu}} {:?} is not a depth format, using {:?}", f, DEPTH_FORMAT));
+            DEPTH_FORMAT
+        }
+    }
+}
+
+/// Defines the format used to render the scene when HDR is enabled.
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
 
 /// Represents an aftraction of a depth texture.
 /// This is wrapping a simple texture due Shipyard dinstinguish the components
 /// by the type.
 pub struct DepthTexture(pub Texture);
+
+/// Represents the texture where the main camera renders when HDR is enabled,
+/// it is tone mapped into the swap chain at the end of the frame.
+pub struct HdrTexture(pub Option<Texture>);
+
+/// Represents the errors that can happen when an image is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureError {
+    /// The file could not be read.
+    IoError(String),
+
+    /// The content of the file is not a valid image.
+    DecodeError(String),
+
+    /// The image format is not PNG, JPEG or BMP.
+    FormatUnsupported(String)
+}
+
+impl Display for TextureError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            TextureError::IoError(e) => write!(formatter, "Image file error: {}", e),
+            TextureError::DecodeError(e) => write!(formatter, "Invalid image: {}", e),
+            TextureError::FormatUnsupported(e) =>
+                write!(formatter, "Unsupported image format: {}", e)
+        }
+    }
+}
+
+/// Represents a decoded image which is not uploaded to the GPU yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    /// The size of the image in pixels.
+    pub size: Size<u32>,
+
+    /// The RGBA8 pixels, row by row from the top.
+    pub pixels: Vec<u8>
+}
+
+/// Decodes a PNG, JPEG or BMP image and returns its pixels as RGBA8, the
+/// format is detected from the content.
+///
+/// # Arguments
+///
+/// `bytes` - The content of the image file.
+pub fn decode_image(bytes: &[u8]) -> Result<Image, TextureError> {
+    let format = image::guess_format(bytes)
+        .map_err(|e| TextureError::FormatUnsupported(e.to_string()))?;
+
+    match format {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Bmp => {},
+        f => return Err(TextureError::FormatUnsupported(format!("{:?}", f)))
+    }
+
+    let rgba = image::load_from_memory_with_format(bytes, format)
+        .map_err(|e| TextureError::DecodeError(e.to_string()))?
+        .to_rgba8();
+
+    Ok(Image {
+        size: Size::new(rgba.width(), rgba.height()),
+        pixels: rgba.into_raw()
+    })
+}
+
+/// Allows the `AssetServer` to decode the images in its background threads,
+/// only the upload is left for the caller.
+impl Asset for Image {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        decode_image(&bytes).map_err(|e| e.to_string())
+    }
+}
+
+#[test]
+fn png_is_decoded_as_rgba() {
+    let mut source = image::RgbImage::new(2, 1);
+    source.put_pixel(1, 0, image::Rgb([255, 0, 0]));
+
+    let mut bytes: Vec<u8> = Vec::new();
+    image::DynamicImage::ImageRgb8(source)
+        .write_to(&mut bytes, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let decoded = decode_image(&bytes).unwrap();
+    assert_eq!(decoded.size, Size::new(2, 1));
+    assert_eq!(decoded.pixels, vec![0, 0, 0, 255, 255, 0, 0, 255]);
+
+    assert!(matches!(
+        decode_image(b"not an image"),
+        Err(TextureError::FormatUnsupported(_))
+    ));
+}
+
+#[test]
+fn depth_format_negotiation() {
+    assert_eq!(select_depth_format(None), DEPTH_FORMAT);
+    assert_eq!(select_depth_format(Some(TextureFormat::Depth32Float)), TextureFormat::Depth32Float);
+    assert!(!has_stencil(TextureFormat::Depth32Float));
+
+    // A color format can not be used as depth.
+    assert_eq!(select_depth_format(Some(TextureFormat::Rgba8Unorm)), DEPTH_FORMAT);
+    assert!(has_stencil(select_depth_format(Some(TextureFormat::Rgba8Unorm))));
+}
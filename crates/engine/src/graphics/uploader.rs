@@ -0,0 +1,186 @@
+use std::{
+    future::Future,
+    num::NonZeroU64,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::Context
+};
+
+use futures::task::noop_waker_ref;
+
+use wgpu::{
+    Buffer,
+    BufferAddress,
+    CommandBuffer,
+    CommandEncoderDescriptor,
+    Device,
+    Maintain,
+    util::StagingBelt
+};
+
+use ecs::UniqueRead;
+use log::warning;
+
+use crate::graphics::{
+    CommandBufferQueue,
+    OrderedCommandBuffer,
+    gpu::Gpu,
+    renderers::RenderOrder
+};
+
+/// The size of each chunk of the staging belt, the uploads bigger than this
+/// get their own chunk.
+const STAGING_CHUNK_SIZE: BufferAddress = 64 * 1024;
+
+/// Defines a future returned by `StagingBelt::recall`.
+type RecallFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Represents a copy recorded during the frame which is not encoded yet.
+struct PendingWrite {
+    /// The destination buffer, it is kept alive until the copy is encoded.
+    buffer: Arc<Buffer>,
+
+    /// The position in the buffer where the data is copied.
+    offset: BufferAddress,
+
+    /// The data to be copied.
+    data: Vec<u8>
+}
+
+/// Contains the state of the uploader protected by its lock.
+///
+/// The command encoders are not `Send`, so the copies are kept as pending
+/// writes and encoded when the uploads are flushed.
+struct UploaderState {
+    /// The staging memory reused between frames.
+    belt: StagingBelt,
+
+    /// The copies recorded during the current frame.
+    pending: Vec<PendingWrite>,
+
+    /// The chunks which are being recalled, they can be reused once the
+    /// futures finish.
+    recalls: Vec<RecallFuture>
+}
+
+/// Batches all the buffer uploads of a frame into a single command encoder,
+/// the data is copied into a staging belt instead of a new staging
+/// allocation per `queue.write_buffer` call.
+///
+/// The uploads are recorded during the frame, `flush_uploads_system`
+/// submits them before any render pass and `recall_uploads_system` makes
+/// the staging memory available again.
+pub struct GpuUploader {
+    state: Mutex<UploaderState>
+}
+
+impl Default for GpuUploader {
+    /// Creates and returns a new `GpuUploader` without uploads.
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(UploaderState {
+                belt: StagingBelt::new(STAGING_CHUNK_SIZE),
+                pending: Vec::new(),
+                recalls: Vec::new()
+            })
+        }
+    }
+}
+
+impl GpuUploader {
+    /// Records a copy of the data into the buffer, the copy happens when the
+    /// uploads are flushed.
+    ///
+    /// # Arguments
+    ///
+    /// `buffer` - The destination buffer, it must allow `COPY_DST`.
+    /// `offset` - The position in the buffer where the data is copied.
+    /// `data` - The data to be copied.
+    pub fn write(&self, buffer: &Arc<Buffer>, offset: BufferAddress, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        self.state.lock().unwrap().pending.push(PendingWrite {
+            buffer: Arc::clone(buffer),
+            offset,
+            data: data.to_vec()
+        });
+    }
+
+    /// Encodes all the pending writes into a single command encoder, closes
+    /// the staging memory used in this frame and returns the command buffer
+    /// which contains all the copies, None if there are not uploads.
+    ///
+    /// # Arguments
+    ///
+    /// `device` - The device used to allocate the staging memory.
+    pub fn flush(&self, device: &Device) -> Option<CommandBuffer> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.is_empty() {
+            return None;
+        }
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Upload encoder")
+        });
+
+        let UploaderState { belt, pending, .. } = &mut *state;
+        for write in pending.drain(..) {
+            // Empty writes are discarded when they are recorded.
+            let size = NonZeroU64::new(write.data.len() as u64).unwrap();
+            belt.write_buffer(&mut encoder, &write.buffer, write.offset, size, device)
+                .copy_from_slice(&write.data);
+        }
+        belt.finish();
+
+        Some(encoder.finish())
+    }
+
+    /// Starts recalling the staging memory of the submitted uploads, it must
+    /// be called after the commands are submitted.
+    ///
+    /// # Arguments
+    ///
+    /// `device` - The device polled to finish the recalls.
+    pub fn recall(&self, device: &Device) {
+        let mut state = self.state.lock().unwrap();
+        let recall: RecallFuture = Box::pin(state.belt.recall());
+        state.recalls.push(recall);
+
+        // The chunks are mapped while the device is polled, the recalls
+        // which are not ready yet are polled again in the next frame.
+        device.poll(Maintain::Poll);
+        let mut context = Context::from_waker(noop_waker_ref());
+        state.recalls.retain_mut(|recall| recall.as_mut().poll(&mut context).is_pending());
+    }
+}
+
+/// Submits the uploads recorded during the frame, it must run before
+/// `submit_commnads_system` and the uploads are submitted before any pass.
+pub fn flush_uploads_system(
+    gpu: UniqueRead<Gpu>,
+    command_buffer: UniqueRead<CommandBufferQueue>) {
+    let gpu_read = gpu.read();
+    let command = match gpu_read.uploader.flush(&gpu_read.device) {
+        Some(c) => c,
+        None => return
+    };
+
+    if command_buffer.read().push(
+        OrderedCommandBuffer {
+            label: Some("Upload_System".to_string()),
+            order: RenderOrder::UPLOAD.as_index(),
+            command,
+            timestamps: None
+        }
+    ).is_err() {
+        warning("{GpuUploader} Uploads could not be queued");
+    }
+}
+
+/// Makes the staging memory of the submitted uploads available again.
+pub fn recall_uploads_system(gpu: UniqueRead<Gpu>) {
+    let gpu_read = gpu.read();
+    gpu_read.uploader.recall(&gpu_read.device);
+}
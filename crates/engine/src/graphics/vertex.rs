@@ -9,6 +9,9 @@ pub struct Vertex {
     /// Position of the Vertex in the 3D space.
     pub pos: Vector4<f32>,
 
+    /// Normal of the face which contains the Vertex, w is always 0.
+    pub normal: Vector4<f32>,
+
     // /// Position of the UV coordinate in 2D space.
     // pub uv: [f32; 2]
 }
@@ -16,13 +19,19 @@ pub struct Vertex {
 /// Contains all the basic functions available for the Vertex.
 impl Vertex {
     /// Creates a new Vertex.
-    pub fn new(pos: Vector3<f32>, uv: [f32; 2]) -> Vertex {
+    pub fn new(pos: Vector3<f32>, normal: Vector3<f32>, uv: [f32; 2]) -> Vertex {
         Vertex {
             pos: Vector4 {
                 x: pos.x,
                 y: pos.y,
                 z: pos.z,
                 w: 1.0
+            },
+            normal: Vector4 {
+                x: normal.x,
+                y: normal.y,
+                z: normal.z,
+                w: 0.0
             }
         }
     }
@@ -1,13 +1,14 @@
 pub enum InitError {
     Window,
-    Gpu
+    /// The GPU could not be created, it contains the reason.
+    Gpu(String)
 }
 
 impl ToString for InitError {
     fn to_string(&self) -> String {
         match self {
         InitError::Window => return "Error trying to create the Window".to_string(),
-        InitError::Gpu => return "Error trying to generate the GPU aftraction".to_string()
+        InitError::Gpu(reason) => return format!("Error trying to generate the GPU aftraction: {}", reason)
         }
     }
 }
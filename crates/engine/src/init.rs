@@ -4,24 +4,64 @@ use winit::{
 };
 
 use types::Size;
-use ecs::{DefaultWorld, ComponentHandler};
+use ecs::{DefaultWorld, ComponentHandler, WorkersDescriptor};
 use log::info;
 
 use crate::{
-    basics::window::{Window, CustomEvent},
+    basics::window::{
+        Window,
+        CustomEvent,
+        FullscreenMode,
+        CursorLocked,
+        WindowState,
+        PendingResize,
+        GuiScreen
+    },
+    basics::{
+        exit::ExitRequested,
+        profiler::{Profiler, PROFILER_PANEL},
+        frame_history::{FrameHistory, FRAME_HISTORY_PANEL},
+        dev_gui::DevGui
+    },
     helpers::errors::InitError,
-    scene::components::{Voxel, Transform},
+    workloads::Schedule,
+    scene::components::{Voxel, LocalTransform},
     graphics::{
         gpu::Gpu,
         texture::{Texture, DepthTexture, TextureGenerator},
         pipelines::{
             initialize_pipelines,
-            bind_groups::locals_bind_group::initialize_locals
+            dev_gui_pipeline::DevGuiPipeline,
+            bind_groups::{
+                locals_bind_group::initialize_locals,
+                layout_cache::BindGroupLayoutCache
+            }
         },
+        render_stats::RenderStats,
+        gpu_info::GPU_INFO_PANEL,
         CommandBufferQueue,
         MAX_NUMBER_OF_COMMANDS_PER_CALL
     },
-    scene::camera::Camera
+    scene::{
+        camera::Camera,
+        hierarchy::WorldTransforms,
+        lod::{LodDistances, VoxelLods},
+        input::{Input, MousePosition, GuiInputCapture},
+        input_map::InputMap,
+        time::Time,
+        file_drop::FileDropEvents,
+        light::{DirectionalLight, SunPosition, SkySettings},
+        picking::PickResult,
+        inspector::{SelectedEntity, EntityInspector, INSPECTOR_PANEL},
+        prefab::PrefabRegistry,
+        snapshot::{
+            SaveRegistry,
+            register_saveable_component,
+            register_saveable_resource
+        },
+        console_log::{ConsoleLog, CONSOLE_PANEL},
+        assets::AssetServer
+    }
 };
 
 /// Only local just to not write long lines.
@@ -33,9 +73,16 @@ type CBQ = CommandBufferQueue;
 ///
 /// `name` - The window name.
 /// `size` - The window initial size.
-pub fn initialize_window(name: &str, size: Size<u32>)
+/// `full_screen` - Determines if the window starts in full screen.
+pub fn initialize_window(name: &str, size: Size<u32>, full_screen: bool)
     -> Result<(Window, EventLoop<CustomEvent>), InitError> {
-    Window::new(name, size)
+    let (window, event_loop) = Window::new(name, size)?;
+
+    if full_screen {
+        window.set_fullscreen(FullscreenMode::Borderless);
+    }
+
+    Ok((window, event_loop))
 }
 
 
@@ -49,36 +96,85 @@ pub fn initialize_window(name: &str, size: Size<u32>)
 ///
 /// `gpu` - The gpu to be setted as a resource in the world.
 /// `window` - The main window used which contains the attached surface. 
+/// `worker_threads` - The number of workers, if it is not present the
+/// default amount is used.
+/// `enable_dev_gui` - Defines if the debug GUI resources are registered.
 pub fn initialize_world(
     gpu: Gpu,
     window: Window,
-    e_loop_proxy: EventLoopProxy<CustomEvent>) -> DefaultWorld {
+    e_loop_proxy: EventLoopProxy<CustomEvent>,
+    worker_threads: Option<usize>,
+    enable_dev_gui: bool) -> DefaultWorld {
     info("Initializing world");
 
     // Creates a mutable wo =rld.
-    let world: DefaultWorld = DefaultWorld::default();
+    let mut descriptor = WorkersDescriptor::default();
+    if let Some(amount) = worker_threads {
+        descriptor.amount = amount;
+    }
+    let world: DefaultWorld = DefaultWorld::with_workers(descriptor);
+
+    // Register the types stored in the scene snapshots.
+    world.register_unique(SaveRegistry::default());
+    register_saveable_component::<Voxel>(&world);
+    register_saveable_component::<LocalTransform>(&world);
+    register_saveable_component::<Camera>(&world);
+    register_saveable_resource::<Camera>(&world);
 
-    // Register default components.
-    world.register::<Voxel>();
-    world.register::<Transform>();
+    // The prefabs are registered later by the application.
+    world.register_unique(PrefabRegistry::default());
+
+    // The bind group layouts are shared by the locals and the pipelines.
+    world.register_unique(BindGroupLayoutCache::default());
+
+    // The gpu is registered before the pipelines, the shaders are compiled
+    // by workers which take it from the world.
+    world.register_unique(gpu.info.clone());
+    let gpu = world.register_unique(gpu);
+    let gpu_read = gpu.read();
 
     // initialize all the locals, this should be performed before the pipelines
     // due the pipelines will need the locals buffer.
-    initialize_locals(&gpu, &world);
+    initialize_locals(&gpu_read, &world);
 
     // Initialize basic pipelines.
-    initialize_pipelines(&gpu, &world);
+    initialize_pipelines(&gpu_read, &world);
 
-    // Initialize egui.
-    //initialize_egui(&gpu, &window, &world, e_loop_proxy);
+    // The debug GUI is opt-in, nothing is registered unless it is enabled
+    // and its systems are skipped when `DevGui` is not present.
+    if enable_dev_gui {
+        let mut dev_gui = DevGui::default();
+        dev_gui.add_panel(CONSOLE_PANEL);
+        dev_gui.add_panel(INSPECTOR_PANEL);
+        dev_gui.add_panel(PROFILER_PANEL);
+        dev_gui.add_panel(GPU_INFO_PANEL);
+        dev_gui.add_panel(FRAME_HISTORY_PANEL);
+        world.register_unique(dev_gui);
+        world.register_unique(DevGuiPipeline::new(&gpu_read, &world));
+    }
 
     // Create and set the depth texture.
-    let depth_texture: Texture = gpu.create_depth_texture();
+    let depth_texture: Texture = gpu_read.create_depth_texture();
     world.register_unique(DepthTexture(depth_texture));
+    drop(gpu_read);
     
+    // The camera uses the aspect of the window.
+    let aspect: f32 = window.aspect();
+    let gui_size: Size<u32> = window.physical_size;
+    let gui_scale_factor: f64 = window.scale_factor;
+
     // Register all the unique resources.
-    world.register_unique(gpu);
     world.register_unique(window);
+    // The cursor starts unlocked.
+    world.register_unique(CursorLocked::default());
+    world.register_unique(WindowState::default());
+    world.register_unique(PendingResize::default());
+    world.register_unique(GuiScreen {
+        physical_size: gui_size,
+        scale_factor: gui_scale_factor
+    });
+    // The application runs until something asks to exit.
+    world.register_unique(ExitRequested::default());
     
     // Register the CommandBufferQueue which is used to send all the commands
     // that are generated from the different renderers.
@@ -92,14 +188,65 @@ pub fn initialize_world(
     world.register_unique((o_swap_chain,));
     
     // Registers the camera.
-    world.register_unique(Camera::default());
+    world.register_unique(Camera {
+        aspect,
+        ..Camera::default()
+    });
     
+    // Registers the light of the scene.
+    world.register_unique(DirectionalLight::default());
+
+    // Registers the sun and the atmosphere used by the sky.
+    world.register_unique(SunPosition::default());
+    world.register_unique(SkySettings::default());
+
     // Create a new default input, this contains the actual input state, which
     // keys are pressed.
-    /*world.add_unique(Input::default()).unwrap();
-    
-    // Create a new MousePosition this contains the actual mouse position.
-    world.add_unique(MousePosition::default()).unwrap();*/
+    world.register_unique(Input::default());
+    world.register_unique(MousePosition::default());
+    // Nothing is claimed until a GUI writes it.
+    world.register_unique(GuiInputCapture::default());
+    // The games bind their actions, it starts empty.
+    world.register_unique(InputMap::default());
+
+    // Contains the world matrices of the entities inside a hierarchy.
+    world.register_unique(WorldTransforms::default());
+
+    // The level of detail of the voxels, updated every frame.
+    world.register_unique(LodDistances::default());
+    world.register_unique(VoxelLods::default());
+
+    // Nothing is under the cursor until the first frame.
+    world.register_unique(PickResult::default());
+
+    // The entity clicked in the 3D view and its components.
+    world.register_unique(SelectedEntity::default());
+    world.register_unique(EntityInspector::default());
+
+    // Contains the frame timing.
+    world.register_unique(Time::default());
+
+    // Contains the files dropped into the window in the current frame.
+    world.register_unique(FileDropEvents::default());
+
+    // Contains the log entries displayed by the in-game console.
+    world.register_unique(ConsoleLog::default());
+
+    // Contains the systems added by the application to the workloads.
+    world.register_unique(Schedule::default());
+
+    // Contains the time taken by the workloads and the profiled scopes.
+    world.register_unique(Profiler::default());
+
+    // Contains the draw calls submitted by the renderers during the frame.
+    world.register_unique(RenderStats::default());
+
+    // Contains the time of the last frames, it is kept when the window is
+    // resized.
+    world.register_unique(FrameHistory::default());
+
+    // Loads the assets in the background.
+    world.register_unique(AssetServer::default());
 
     info("World initialized");
 
@@ -4,9 +4,53 @@ mod graphics;
 mod workloads;
 
 mod init;
+mod app;
 
 pub mod scene;
 
+pub use basics::exit::{
+    ExitRequested,
+    request_exit,
+    request_exit_system
+};
+
+pub use app::{App, Plugin, Stage};
+pub use workloads::{Workloads, Schedule, ScheduleHandler};
+
+pub use basics::profiler::{Profiler, PROFILER_HISTORY, WORKLOAD_SCOPES};
+pub use basics::frame_history::{FrameHistory, FrameSample, FRAME_HISTORY_LENGTH};
+pub use basics::dev_gui::{DevGui, GuiWindow, GuiAnchor, TEXT_COLOR};
+pub use graphics::render_stats::RenderStats;
+pub use graphics::gpu_profiler::ProfiledEncoder;
+pub use graphics::push_constants::{
+    push_constant_range,
+    PushConstantsSetter,
+    MAX_PUSH_CONSTANT_SIZE
+};
+
+pub use graphics::pipelines::tone_mapping_pipeline::ToneMappingOperator;
+pub use graphics::pipelines::outline_pipeline::OutlineColor;
+pub use graphics::pipelines::debug_line_pipeline::DebugDraw;
+
+pub use basics::window::{
+    Window,
+    FullscreenMode,
+    CursorLocked,
+    WindowState,
+    toggle_fullscreen_system,
+    lock_cursor_system,
+    unlock_cursor_system,
+    update_window_with_new_size_system,
+    update_gui_with_new_size_system,
+    PendingResize,
+    GuiScreen
+};
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant}
+};
+
 use futures::executor::block_on;
 
 use winit::{
@@ -18,29 +62,118 @@ use winit::{
 use ecs::{
     ComponentHandler,
     DefaultWorld,
-    UniqueRead
+    SystemHandler,
+    TaskWaitable
 };
 use types::Size;
-use log::{Log, Console, info};
+use log::{Log, LogSeverity, Console, FileSink, Rotation, info, error};
 
 use crate::{
-    basics::window::Window,
-    graphics::gpu::Gpu,
+    graphics::gpu::{Gpu, GpuOptions},
     init::{initialize_window, initialize_world},
-    workloads::{Workloads, run_workload}
+    workloads::run_workload,
+    scene::{
+        input::{
+            Input,
+            InputEvent,
+            update_input_system,
+            update_mouse_position_system,
+            MousePosition,
+            GuiInputCapture
+        },
+        file_drop::{FileDropEvent, FileDropEvents},
+        time::{Time, MAX_FIXED_STEPS_PER_FRAME},
+        input_recorder::{
+            InputRecorder,
+            InputReplay,
+            replay_input_system,
+            DEFAULT_RECORDING_PATH
+        }
+    }
 };
 
+/// Defines what the engine does when the window loses the focus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusPolicy {
+    /// The application keeps running.
+    ContinueOnFocusLoss,
+
+    /// The tick, the input and the render are paused until the window
+    /// gets the focus again.
+    PauseOnFocusLoss
+}
+
 /// Defines the initial configuration for the application.
 pub struct InitialConfig {
     /// The size of the window.
-    window_size: Size<u32>,
+    pub window_size: Size<u32>,
 
     /// Contains a flag defining if the application should run in full screen
     /// or not.
-    full_screen: bool,
+    pub full_screen: bool,
+
+    /// A flag which allows force log into the console, it sets the log
+    /// level to `INFO` no matter the `log_level`.
+    pub force_log: bool,
+
+    /// The minimum severity that will be logged.
+    pub log_level: LogSeverity,
+
+    /// The path of the file where the log is written, if it is not present
+    /// the log is only written into the console.
+    pub log_file: Option<PathBuf>,
+
+    /// Defines when the log file is rotated.
+    pub log_rotation: Rotation,
 
-    /// A flag which allows force log into the console.
-    force_log: bool,
+    /// A flag which enables the input recording, the recording is stored
+    /// when the window is closed.
+    pub recording: bool,
+
+    /// Contains the path of a recording to be played, if it is present the
+    /// input coming from the window is ignored.
+    pub replay_path: Option<PathBuf>,
+
+    /// Defines what happens when the window loses the focus.
+    pub focus_policy: FocusPolicy,
+
+    /// The duration of each fixed tick, the fixed tick runs as many times
+    /// as needed to keep up with the real time.
+    pub fixed_timestep: Duration,
+
+    /// The frame rate used while the window does not have the focus, if it
+    /// is not present the frame rate is not limited.
+    pub background_fps: Option<u32>,
+
+    /// The number of threads used to run the systems, if it is not present
+    /// the amount is calculated based on the number of CPUs.
+    pub worker_threads: Option<usize>,
+
+    /// Contains a flag defining if the scene is rendered in HDR, the result
+    /// is tone mapped using the `ToneMappingOperator` resource.
+    pub hdr: bool,
+
+    /// The depth format preferred by the application, if it is not a depth
+    /// format the default one is used.
+    pub depth_format: Option<wgpu::TextureFormat>,
+
+    /// Forces the graphics API used by the Gpu, if it is not available the
+    /// initialization fails listing the available adapters.
+    pub force_backend: Option<wgpu::Backend>,
+
+    /// Contains a flag defining if the render passes are timed in the GPU,
+    /// the times are published in `RenderStats`. It is ignored if the
+    /// adapter does not support timestamp queries.
+    pub enable_gpu_profiling: bool,
+
+    /// Contains a flag defining if the debug GUI resources are created, it
+    /// is disabled by default so applications which do not use it do not pay
+    /// for it.
+    pub enable_dev_gui: bool,
+
+    /// The callback executed when the application finishes, it is called
+    /// before the world is dropped so the state can be saved.
+    pub on_exit: Option<ExitFn>,
 }
 
 /// Defines the constants values for the window.
@@ -53,7 +186,27 @@ impl Default for InitialConfig {
         Self {
             window_size: Size::new(DEFAULT_WIDTH_SIZE, DEFAULT_HEIGHT_SIZE),
             full_screen: false,
-            force_log: false
+            force_log: false,
+            // Only warnings and errors are logged in release.
+            log_level: if cfg!(debug_assertions) {
+                LogSeverity::INFO
+            } else {
+                LogSeverity::WARNING
+            },
+            log_file: None,
+            log_rotation: Rotation::default(),
+            recording: false,
+            replay_path: None,
+            focus_policy: FocusPolicy::ContinueOnFocusLoss,
+            fixed_timestep: Duration::from_secs_f64(1.0 / 60.0),
+            background_fps: None,
+            worker_threads: None,
+            hdr: false,
+            depth_format: None,
+            force_backend: None,
+            enable_gpu_profiling: false,
+            enable_dev_gui: false,
+            on_exit: None
         }
     }
 }
@@ -64,65 +217,471 @@ pub type ConfigFn = fn(&DefaultWorld);
 /// Defines the callback for the run per frame.
 pub type TickFn = fn(&DefaultWorld);
 
-/// Initializes the log system. 
-fn initializes_log() {
+/// Defines the callback for the run per fixed timestep.
+pub type FixedTickFn = fn(&DefaultWorld);
+
+/// Defines the callback for the application exit.
+pub type ExitFn = fn(&DefaultWorld);
+
+/// Initializes the log system, it returns the file sink if the log file
+/// could be opened.
+///
+/// # Arguments
+///
+/// `level` - The minimum severity to be logged.
+/// `log_file` - The path of the log file.
+/// `rotation` - Defines when the log file is rotated.
+fn initializes_log(level: LogSeverity,
+                   log_file: &Option<PathBuf>,
+                   rotation: Rotation) -> Option<FileSink> {
     Log::init();
+    Log::set_level(level);
     Console::init();
+
+    let path = log_file.as_ref()?;
+    match FileSink::init(path.clone(), rotation) {
+        Ok(sink) => Some(sink),
+        Err(e) => {
+            error(&format!("The log file {} could not be opened: {}", path.display(), e));
+            None
+        }
+    }
+}
+
+/// Returns true if the application asked to finish.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the exit flag.
+fn is_exit_requested(world: &DefaultWorld) -> bool {
+    world.get_unique::<ExitRequested>().read().0
+}
+
+/// Releases the resources which must be finished before the process exits.
+///
+/// The exit callback is executed first, then the world is dropped which
+/// stops all the worker threads, the log is flushed at the end so the
+/// messages of the previous steps are written.
+///
+/// # Arguments
+///
+/// `world` - The world to be dropped.
+/// `on_exit` - The exit callback.
+/// `recording` - Determines if the input recording must be stored.
+/// `file_sink` - The log file sink, the pending lines are written.
+fn shutdown(world: DefaultWorld,
+            on_exit: Option<ExitFn>,
+            recording: bool,
+            file_sink: &Option<FileSink>) {
+    info("Shutting down");
+
+    if let Some(callback) = on_exit {
+        callback(&world);
+    }
+
+    if recording {
+        save_input_recording(&world);
+    }
+
+    drop(world);
+    info("World dropped");
+
+    if let Some(sink) = file_sink {
+        sink.flush();
+    }
+}
+
+/// Registers the input recorder or the input replay based on the config.
+///
+/// # Arguments
+///
+/// `world` - The world where the resources will be registered.
+/// `replay_path` - The path of the recording to be played.
+/// `recording` - Determines if the input should be recorded.
+fn initialize_input_recording(world: &DefaultWorld,
+                              replay_path: &Option<PathBuf>,
+                              recording: bool) -> Result<(), String> {
+    if let Some(path) = replay_path {
+        info("Replaying input recording");
+        let recording = InputRecorder::load(path)?;
+        world.register_unique(InputReplay::new(recording));
+    } else if recording {
+        info("Recording input");
+        world.register_unique(InputRecorder::default());
+    }
+
+    Ok(())
+}
+
+/// Sends the input event to the input resource and records it if needed.
+///
+/// The events claimed by the GUI are discarded.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the input.
+/// `event` - The event to be processed.
+/// `recording` - Determines if the event should be recorded.
+fn dispatch_input_event(world: &DefaultWorld,
+                        event: InputEvent,
+                        recording: bool) {
+    // The dev GUI receives the clicks even when they are claimed by it.
+    if let Some(dev_gui) = world.try_get_unique_mut::<DevGui>() {
+        let cursor = world.get_unique::<MousePosition>().read().physical;
+        dev_gui.write().process_event(&event, cursor);
+    }
+
+    if world.get_unique::<GuiInputCapture>().read().captures(&event) {
+        return;
+    }
+
+    if recording {
+        world.get_unique_mut::<InputRecorder>().write().record(event);
+    }
+
+    update_input_system(event, world.get_unique_mut::<Input>());
+}
+
+/// Stores the input recording in the default path.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the recorder.
+fn save_input_recording(world: &DefaultWorld) {
+    let recorder = world.get_unique::<InputRecorder>();
+    if let Err(e) = recorder.read().save(Path::new(DEFAULT_RECORDING_PATH)) {
+        error(&format!("Error saving the input recording: {}", e));
+    };
+}
+
+/// Returns true if the application must be paused due the window does not
+/// have the focus.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the focus state.
+/// `pause_on_focus_loss` - Determines if the focus loss pauses the
+/// application.
+fn is_paused(world: &DefaultWorld, pause_on_focus_loss: bool) -> bool {
+    pause_on_focus_loss && !world.get_unique::<WindowState>().read().focused
+}
+
+/// Recreates the gpu resources if the window size changed since the last
+/// time it was applied.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the pending resize.
+fn apply_pending_resize(world: &DefaultWorld) {
+    let current: Size<u32> = {
+        let gpu = world.get_unique::<Gpu>();
+        let gpu_read = gpu.read();
+        Size::new(
+            gpu_read.swap_chain_descriptor.width,
+            gpu_read.swap_chain_descriptor.height
+        )
+    };
+
+    let changed = world
+        .get_unique_mut::<PendingResize>()
+        .write()
+        .take_changed(current);
+    if changed.is_some() {
+        (
+            world.run(update_window_with_new_size_system),
+        ).wait();
+
+        // The GUI must use the same size as the swap chain, otherwise its
+        // clip rectangles are wrong until the next input.
+        let window = world.get_unique::<Window>();
+        let window_read = window.read();
+        update_gui_with_new_size_system(
+            window_read.physical_size,
+            window_read.scale_factor,
+            world.get_unique_mut::<GuiScreen>()
+        );
+    }
 }
 
 /// Configures the resources and executes the engine main loop.
 ///
 /// # Arguments
 ///
-/// `config` - The general configuration callback.
-/// `tick` - The tick callback.
-/// `app_config` - The app configuration.
-async fn run(config: ConfigFn,
-             tick: TickFn,
-             app_config: InitialConfig) -> Result<(), String> {
+/// `app` - The application, it contains the configuration, the plugins and
+/// the systems.
+/// `file_sink` - The log file sink, it is flushed when the loop finishes.
+async fn run(mut app: App, file_sink: Option<FileSink>) -> Result<(), String> {
     info("Initialize window and input handlers");
+    let app_config = &app.config;
     
     // Create the window.
     let window_size: Size<u32> = app_config.window_size;
-    let (window, event_loop) = match initialize_window("Shiny", window_size) {
+    let (window, event_loop) = match initialize_window(
+        "Shiny",
+        window_size,
+        app_config.full_screen
+    ) {
         Ok(w) => w,
         Err(e) => return Err(e.to_string())
     };
 
     // Create the Gpu aftraction.
-    let gpu: Gpu = match Gpu::default(&window).await {
+    let gpu_options = GpuOptions {
+        hdr: app_config.hdr,
+        preferred_depth_format: app_config.depth_format,
+        force_backend: app_config.force_backend,
+        enable_gpu_profiling: app_config.enable_gpu_profiling,
+        ..GpuOptions::default()
+    };
+    let gpu: Gpu = match Gpu::with_options(&window, gpu_options).await {
         Ok(g) => g,
         Err(e) => return Err(e.to_string())
     };
 
     // Create a new world an inject the basic resources.
-    let world = initialize_world(gpu, window, event_loop.create_proxy());
+    let world = initialize_world(
+        gpu,
+        window,
+        event_loop.create_proxy(),
+        app_config.worker_threads,
+        app_config.enable_dev_gui
+    );
     
+    // Register the input recording resources if needed.
+    initialize_input_recording(
+        &world,
+        &app_config.replay_path,
+        app_config.recording
+    )?;
+    let replaying: bool = app_config.replay_path.is_some();
+    let recording: bool = app_config.recording && !replaying;
+    let pause_on_focus_loss: bool =
+        app_config.focus_policy == FocusPolicy::PauseOnFocusLoss;
+    let on_exit: Option<ExitFn> = app_config.on_exit;
+    let background_fps: Option<u32> = app_config.background_fps;
+    let fixed_timestep: Duration = app_config.fixed_timestep;
+    let mut last_frame: Instant = Instant::now();
+
     // Configures the user's application.
-    config(&world);
+    app.build(&world);
 
     info("Entering main run loop");
     // Trigger the main run loop.
+    // The world is kept in an option in order to drop it when the loop is
+    // destroyed, winit does not return from `run`.
+    let mut world_holder: Option<DefaultWorld> = Some(world);
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
+        // The process exits after this event.
+        if let Event::LoopDestroyed = event {
+            if let Some(world) = world_holder.take() {
+                shutdown(world, on_exit, recording, &file_sink);
+            }
+            return;
+        }
+
+        let world: &DefaultWorld = match world_holder.as_ref() {
+            Some(w) => w,
+            None => return
+        };
 
         match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => {
+                request_exit(world);
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } => {
+                world.get_unique_mut::<WindowState>().write().focused = focused;
+
+                // The focus loss releases all the keys, the recording
+                // contains its own focus events.
+                if !replaying {
+                    let i_event = match focused {
+                        true => InputEvent::FocusGained,
+                        false => InputEvent::FocusLost
+                    };
+                    dispatch_input_event(world, i_event, recording);
+                }
+
+                if !focused {
+                    // The cursor must be released when the window loses the
+                    // focus.
+                    if world.get_unique::<CursorLocked>().read().0 {
+                        (
+                            world.run(unlock_cursor_system),
+                        ).wait();
+                    }
+                }
+            }
+
+            // The files are not part of the input, so they are received
+            // even when a recording is being played.
+            Event::WindowEvent {
+                event: ref event @ (
+                    WindowEvent::HoveredFile(_) |
+                    WindowEvent::DroppedFile(_) |
+                    WindowEvent::HoveredFileCancelled
+                ),
+                ..
+            } => {
+                if let Some(f_event) = FileDropEvent::from_window_event(event) {
+                    world.get_unique_mut::<FileDropEvents>().write().0.push(f_event);
+                }
+            }
+
+            // When the cursor is locked the motion comes from the device
+            // events, the cursor position is ignored to not process the
+            // motion twice.
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { .. },
+                ..
+            } if world.get_unique::<CursorLocked>().read().0 => {}
+
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                ..
+            } if !replaying && !is_paused(world, pause_on_focus_loss) => {
+                let scale_factor: f64 = world.get_unique::<Window>().read().scale_factor;
+                update_mouse_position_system(
+                    position,
+                    scale_factor,
+                    world.get_unique_mut::<MousePosition>()
+                );
+            }
+
+            // The gpu resources are recreated once per frame, before the
+            // frame starts.
+            Event::WindowEvent {
+                event: WindowEvent::Resized(physical_size),
+                ..
+            } => {
+                let window = world.get_unique_mut::<Window>();
+                let mut window_write = window.write();
+                let scale_factor: f64 = window_write.scale_factor;
+                window_write.update_size(physical_size, scale_factor);
+                world
+                    .get_unique_mut::<PendingResize>()
+                    .write()
+                    .request(window_write.physical_size);
+            }
+
+            // Moving the window to a monitor with a different density
+            // changes the physical size without a resize event.
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size },
+                ..
+            } => {
+                let window = world.get_unique_mut::<Window>();
+                let mut window_write = window.write();
+                window_write.update_size(*new_inner_size, scale_factor);
+                world
+                    .get_unique_mut::<PendingResize>()
+                    .write()
+                    .request(window_write.physical_size);
+            }
+
+            // When a recording is being played the real input is ignored.
+            Event::WindowEvent { ref event, .. }
+                if !replaying && !is_paused(world, pause_on_focus_loss) => {
+                if let Some(i_event) = InputEvent::from_window_event(event) {
+                    dispatch_input_event(world, i_event, recording);
+                }
+            }
+
+            Event::DeviceEvent { ref event, .. }
+                if !replaying && !is_paused(world, pause_on_focus_loss) => {
+                if let Some(i_event) = InputEvent::from_device_event(event) {
+                    dispatch_input_event(world, i_event, recording);
+                }
+            }
+
+            // The exit is only checked between frames, so the frame
+            // workloads are always completed.
+            Event::MainEventsCleared if is_exit_requested(world) => {
+                *control_flow = ControlFlow::Exit;
+            }
+
+            // The surface could be invalid while the application is
+            // suspended.
+            Event::Suspended => {
+                world.get_unique_mut::<WindowState>().write().suspended = true;
+            }
+
+            Event::Resumed => {
+                world.get_unique_mut::<WindowState>().write().suspended = false;
+            }
+
+            // Decide if a new frame is rendered, without the focus the frame
+            // rate could be limited and while suspended nothing is rendered.
             Event::MainEventsCleared => {
-                let window = world.get::<UniqueRead<Window>>();
-                window.read().native_window.request_redraw();
+                let state: WindowState = **world.get_unique::<WindowState>().read();
+                match state.next_frame_time(background_fps, last_frame) {
+                    None => *control_flow = ControlFlow::Wait,
+                    Some(time) if time > Instant::now() => {
+                        *control_flow = ControlFlow::WaitUntil(time);
+                    },
+                    Some(_) => {
+                        *control_flow = ControlFlow::Poll;
+                        let window = world.get_unique::<Window>();
+                        window.read().native_window.request_redraw();
+                    }
+                }
             }
 
+            // The platform could ask for a redraw while suspended.
+            Event::RedrawRequested(_)
+                if world.get_unique::<WindowState>().read().suspended => {}
+
             // Redraw
             Event::RedrawRequested(_) => {
+                last_frame = Instant::now();
+                let paused: bool = is_paused(world, pause_on_focus_loss);
+
+                // Apply the last resize received since the previous frame.
+                apply_pending_resize(world);
+
+                // Inject the recorded input of this frame.
+                if replaying {
+                    (
+                        world.run(replay_input_system),
+                    ).wait();
+                }
+
                 // Run the render workload.
-                run_workload(Workloads::Start, &world);
+                run_workload(Workloads::Start, world);
+
+                // Catch up with the real time using fixed steps, the time
+                // is consumed even when paused to not run all the steps
+                // together when the application continues.
+                let fixed_steps: u32 = world
+                    .get_unique_mut::<Time>()
+                    .write()
+                    .take_fixed_steps(fixed_timestep, MAX_FIXED_STEPS_PER_FRAME);
+
                 // Send the flow to game lands.
-                tick(&world);    
+                if !paused {
+                    for _ in 0..fixed_steps {
+                        app.run_stage(Stage::FixedTick, world);
+                    }
+                    app.run_stage(Stage::Tick, world);
+                }
                 // Render and sync everything else.
-                run_workload(Workloads::Synchronize, &world);
-                run_workload(Workloads::Render, &world);
-                run_workload(Workloads::Commit, &world);
-                run_workload(Workloads::End, &world);
+                run_workload(Workloads::Synchronize, world);
+                if !paused {
+                    run_workload(Workloads::Render, world);
+                }
+                run_workload(Workloads::Commit, world);
+                run_workload(Workloads::End, world);
+
+                // Move the recording to the next frame.
+                if recording {
+                    world.get_unique_mut::<InputRecorder>().write().next_frame();
+                }
             }            
 
             // We do not care about the rest of events.
@@ -131,21 +690,47 @@ async fn run(config: ConfigFn,
     });
 }
 
+/// Runs the application, it is called by `App::run`.
+///
+/// # Arguments
+///
+/// `app` - The application to be executed.
+fn run_app(app: App) -> Result<(), String> {
+    // Initialize the log, the level decides what is logged.
+    let log_level: LogSeverity = match app.config.force_log {
+        true => LogSeverity::INFO,
+        false => app.config.log_level
+    };
+    let file_sink = initializes_log(
+        log_level,
+        &app.config.log_file,
+        app.config.log_rotation
+    );
+
+    // Run the engine and lock the program there.
+    block_on(run(app, file_sink))
+}
+
 /// Runs the given program.
 ///
+/// It is kept for the applications which use function callbacks, `App`
+/// allows systems which capture state and plugins.
+///
 /// # Arguments
 ///
 /// `config` - The function used to configure the world.
-/// `input_event` - The function used to react to events.
 /// `tick` - The funtion executed every frame.
+/// `fixed_tick` - The function executed every fixed timestep, it could run
+/// several times per frame or none.
+/// `app_config` - The app configuration.
 pub fn run_program(config: ConfigFn,
                    tick: TickFn,
+                   fixed_tick: FixedTickFn,
                    app_config: InitialConfig) -> Result<(), String> {
-    // Initialize the log only on debug mode.
-    if cfg!(debug_assertions) || app_config.force_log {
-        initializes_log();
-    }
-
-    // Run the engine and lock the program there.
-    block_on(run(config, tick, app_config))
+    App::new()
+        .with_config(app_config)
+        .add_setup_system(config)
+        .add_system(Stage::FixedTick, fixed_tick)
+        .add_system(Stage::Tick, tick)
+        .run()
 }
\ No newline at end of file
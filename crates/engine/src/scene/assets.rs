@@ -0,0 +1,550 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fs,
+    marker::PhantomData,
+    sync::{Arc, Mutex, RwLock, Weak}
+};
+
+use tasks::{Workers, WorkersDescriptor, Dispatcher, TaskPriority, DEFAULT_WORKER_STACK_SIZE};
+use ecs::UniqueRead;
+use log::warning;
+
+use crate::graphics::{
+    gpu::Gpu,
+    texture::{Texture, TextureGenerator, Image}
+};
+
+/// The number of threads used to load the assets.
+const ASSET_WORKERS: usize = 2;
+
+/// Defines a type which can be loaded from a file by the `AssetServer`.
+pub trait Asset: Sized + Send + Sync + 'static {
+    /// Creates and returns the asset from the content of the file.
+    ///
+    /// # Arguments
+    ///
+    /// `bytes` - The content of the file.
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, String>;
+}
+
+/// Loads the text files as UTF-8 strings.
+impl Asset for String {
+    fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+}
+
+/// Represents the state of an asset.
+#[derive(Debug)]
+pub enum AssetState<T> {
+    /// The file is being read in a background thread.
+    Loading,
+
+    /// The asset is ready to be used.
+    Loaded(Arc<T>),
+
+    /// The asset could not be loaded, it contains the reason.
+    Failed(String)
+}
+
+/// Represents the state of an asset without the asset itself.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(String)
+}
+
+/// Represents the end of a load, the events are available during the frame
+/// which follows the load.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssetEvent {
+    /// The asset with the contained id is ready to be used.
+    Loaded(u64),
+
+    /// The asset with the contained id could not be loaded.
+    Failed(u64, String)
+}
+
+/// Identifies an asset by its path and type, two types loaded from the same
+/// path are different assets.
+type AssetKey = (String, TypeId);
+
+/// The state of an asset shared between the server and the workers.
+type SharedState<T> = Arc<RwLock<AssetState<T>>>;
+
+/// Represents an asset stored in the server.
+struct AssetEntry {
+    /// Contains an `Arc<RwLock<AssetState<T>>>`.
+    state: Arc<dyn Any + Send + Sync>,
+
+    /// Points to the token shared by the handles, when it can not be
+    /// upgraded there are not handles left.
+    token: Weak<HandleToken>,
+
+    key: AssetKey
+}
+
+/// Represents a position in the slot map, the generation is increased each
+/// time the slot is released so the old ids do not match the new asset.
+#[derive(Default)]
+struct Slot {
+    generation: u32,
+    entry: Option<AssetEntry>
+}
+
+/// A generational slot map which contains the assets.
+#[derive(Default)]
+struct AssetSlots {
+    slots: Vec<Slot>,
+
+    /// The slots which can be reused.
+    free: Vec<usize>,
+
+    /// The id of each loaded path.
+    paths: HashMap<AssetKey, u64>
+}
+
+impl AssetSlots {
+    /// Stores the entry and returns its id, the index is in the low 32 bits
+    /// and the generation in the high ones.
+    fn insert(&mut self, entry: AssetEntry) -> u64 {
+        let index = match self.free.pop() {
+            Some(i) => i,
+            None => {
+                self.slots.push(Slot::default());
+                self.slots.len() - 1
+            }
+        };
+
+        let slot = &mut self.slots[index];
+        let id = ((slot.generation as u64) << 32) | index as u64;
+        self.paths.insert(entry.key.clone(), id);
+        slot.entry = Some(entry);
+        id
+    }
+
+    /// Returns the entry, None if the id belongs to a released slot.
+    fn get(&self, id: u64) -> Option<&AssetEntry> {
+        let slot = self.slots.get((id & 0xFFFF_FFFF) as usize)?;
+        match slot.generation == (id >> 32) as u32 {
+            true => slot.entry.as_ref(),
+            false => None
+        }
+    }
+
+    /// Returns the mutable entry, None if the id belongs to a released slot.
+    fn get_mut(&mut self, id: u64) -> Option<&mut AssetEntry> {
+        let slot = self.slots.get_mut((id & 0xFFFF_FFFF) as usize)?;
+        match slot.generation == (id >> 32) as u32 {
+            true => slot.entry.as_mut(),
+            false => None
+        }
+    }
+
+    /// Releases the slot of the asset.
+    fn remove(&mut self, id: u64) {
+        let index = (id & 0xFFFF_FFFF) as usize;
+        if self.get(id).is_none() {
+            return;
+        }
+
+        let slot = &mut self.slots[index];
+        if let Some(entry) = slot.entry.take() {
+            if self.paths.get(&entry.key) == Some(&id) {
+                self.paths.remove(&entry.key);
+            }
+        }
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index);
+    }
+}
+
+/// The token shared by all the handles of the same asset, the asset is
+/// evicted when the token is dropped.
+struct HandleToken {
+    id: u64,
+    slots: Weak<RwLock<AssetSlots>>
+}
+
+impl Drop for HandleToken {
+    /// Evicts the asset, the last handle was dropped.
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots.upgrade() {
+            slots.write().unwrap().remove(self.id);
+        }
+    }
+}
+
+/// Represents a reference to an asset loaded by the `AssetServer`.
+///
+/// The handles are reference counted, when the last handle of an asset is
+/// dropped the asset is evicted from the server.
+pub struct Handle<T> {
+    /// The id of the asset inside the server.
+    pub id: u64,
+
+    token: Arc<HandleToken>,
+    _marker: PhantomData<fn() -> T>
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            token: self.token.clone(),
+            _marker: PhantomData
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "Handle({})", self.id)
+    }
+}
+
+/// Represents an image decoded by the workers which must be uploaded to the
+/// GPU by the main thread.
+struct PendingUpload {
+    id: u64,
+    label: String,
+    image: Image,
+
+    /// The state is not kept alive, if the texture is evicted before the
+    /// upload it is discarded.
+    state: Weak<RwLock<AssetState<Texture>>>
+}
+
+/// Loads the assets in background threads and caches them, loading the
+/// same path twice returns a handle to the same asset.
+pub struct AssetServer {
+    /// Contains the assets which have at least one handle alive.
+    slots: Arc<RwLock<AssetSlots>>,
+
+    /// The images waiting to be uploaded.
+    pending_uploads: Arc<Mutex<Vec<PendingUpload>>>,
+
+    /// The loads finished since the last update.
+    completed: Arc<Mutex<Vec<AssetEvent>>>,
+
+    /// The loads finished before the current frame.
+    events: RwLock<Vec<AssetEvent>>,
+
+    /// The threads which perform the IO.
+    workers: Workers
+}
+
+impl Default for AssetServer {
+    /// Creates and returns a new `AssetServer` with its own workers.
+    fn default() -> Self {
+        let mut workers = Workers::new(WorkersDescriptor {
+            amount: ASSET_WORKERS,
+            name: "Crystal asset workers".to_string(),
+            stack_size: DEFAULT_WORKER_STACK_SIZE
+        });
+        workers.start();
+
+        Self {
+            slots: Arc::new(RwLock::new(AssetSlots::default())),
+            pending_uploads: Arc::new(Mutex::new(Vec::new())),
+            completed: Arc::new(Mutex::new(Vec::new())),
+            events: RwLock::new(Vec::new()),
+            workers
+        }
+    }
+}
+
+impl AssetServer {
+    /// Starts loading the asset and returns a handle to it, if the asset is
+    /// already loaded or loading the existing one is returned.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the file.
+    pub fn load<T: Asset>(&self, path: &str) -> Handle<T> {
+        let (handle, state) = match self.reserve::<T>(path) {
+            (handle, Some(state)) => (handle, state),
+            // Already loaded or loading.
+            (handle, None) => return handle
+        };
+
+        let id = handle.id;
+        let path = path.to_string();
+        let completed = self.completed.clone();
+        self.workers.execute_dyn(Box::new(move || {
+            let loaded = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(T::from_bytes);
+
+            let event = match loaded {
+                Ok(asset) => {
+                    *state.write().unwrap() = AssetState::Loaded(Arc::new(asset));
+                    AssetEvent::Loaded(id)
+                },
+                Err(e) => fail(&state, id, &path, e)
+            };
+            completed.lock().unwrap().push(event);
+        }), TaskPriority::Low, None);
+
+        handle
+    }
+
+    /// Starts loading the image into a texture and returns a handle to it.
+    ///
+    /// The image is decoded by the workers and uploaded by the
+    /// `process_pending_uploads_system`, the GPU is only used from the main
+    /// thread.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the PNG, JPEG or BMP file.
+    pub fn load_texture(&self, path: &str) -> Handle<Texture> {
+        let (handle, state) = match self.reserve::<Texture>(path) {
+            (handle, Some(state)) => (handle, state),
+            // Already loaded or loading.
+            (handle, None) => return handle
+        };
+
+        let id = handle.id;
+        let path = path.to_string();
+        let completed = self.completed.clone();
+        let pending_uploads = self.pending_uploads.clone();
+        self.workers.execute_dyn(Box::new(move || {
+            let decoded = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(Image::from_bytes);
+
+            match decoded {
+                Ok(image) => pending_uploads.lock().unwrap().push(PendingUpload {
+                    id,
+                    label: path,
+                    image,
+                    state: Arc::downgrade(&state)
+                }),
+                Err(e) => completed.lock().unwrap().push(fail(&state, id, &path, e))
+            }
+        }), TaskPriority::Low, None);
+
+        handle
+    }
+
+    /// Returns the handle of the asset if it is already stored, otherwise
+    /// stores a new loading asset and returns its handle along with the
+    /// state which must be filled by the load.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the file.
+    fn reserve<T: Send + Sync + 'static>(
+        &self,
+        path: &str
+    ) -> (Handle<T>, Option<SharedState<T>>) {
+        let key: AssetKey = (path.to_string(), TypeId::of::<T>());
+        let mut slots_write = self.slots.write().unwrap();
+
+        if let Some(id) = slots_write.paths.get(&key).copied() {
+            match slots_write.get(id).and_then(|e| e.token.upgrade()) {
+                Some(token) => return (Handle { id, token, _marker: PhantomData }, None),
+                // The last handle is being dropped, its token can not evict
+                // the new asset due the generation changes.
+                None => slots_write.remove(id)
+            }
+        }
+
+        let state: SharedState<T> = Arc::new(RwLock::new(AssetState::Loading));
+        // The id is only known once the slot is reserved, the token is
+        // attached afterwards.
+        let id = slots_write.insert(AssetEntry {
+            state: state.clone(),
+            token: Weak::new(),
+            key
+        });
+
+        let token = Arc::new(HandleToken {
+            id,
+            slots: Arc::downgrade(&self.slots)
+        });
+        if let Some(entry) = slots_write.get_mut(id) {
+            entry.token = Arc::downgrade(&token);
+        }
+
+        (Handle { id, token, _marker: PhantomData }, Some(state))
+    }
+
+    /// Returns the asset if it is loaded, None while it is loading or if
+    /// the load failed.
+    ///
+    /// # Arguments
+    ///
+    /// `handle` - The handle returned by `load`.
+    pub fn get<T: Send + Sync + 'static>(&self, handle: &Handle<T>) -> Option<Arc<T>> {
+        match &*self.state(handle)?.read().unwrap() {
+            AssetState::Loaded(asset) => Some(asset.clone()),
+            _ => None
+        }
+    }
+
+    /// Returns the state of the load.
+    ///
+    /// # Arguments
+    ///
+    /// `handle` - The handle returned by `load`.
+    pub fn load_state<T: Send + Sync + 'static>(&self, handle: &Handle<T>) -> LoadState {
+        let state = match self.state(handle) {
+            Some(s) => s,
+            None => return LoadState::Failed("Unknown asset".to_string())
+        };
+
+        let state_read = state.read().unwrap();
+        match &*state_read {
+            AssetState::Loading => LoadState::Loading,
+            AssetState::Loaded(_) => LoadState::Loaded,
+            AssetState::Failed(e) => LoadState::Failed(e.clone())
+        }
+    }
+
+    /// Returns the loads which finished before the current frame.
+    pub fn events(&self) -> Vec<AssetEvent> {
+        self.events.read().unwrap().clone()
+    }
+
+    /// Replaces the events with the loads finished since the last update,
+    /// this must only be called when the frame starts.
+    pub fn update_events(&self) {
+        let completed: Vec<AssetEvent> = self.completed.lock().unwrap().drain(..).collect();
+        *self.events.write().unwrap() = completed;
+    }
+
+    /// Uploads the images decoded by the workers.
+    ///
+    /// # Arguments
+    ///
+    /// `gpu` - The gpu used to create the textures.
+    pub fn process_pending_uploads(&self, gpu: &Gpu) {
+        let pending: Vec<PendingUpload> = self.pending_uploads.lock().unwrap().drain(..).collect();
+
+        for upload in pending {
+            // The texture was evicted while it was decoded.
+            let state = match upload.state.upgrade() {
+                Some(s) => s,
+                None => continue
+            };
+
+            let texture = gpu.create_texture_from_image(&upload.label, &upload.image);
+            *state.write().unwrap() = AssetState::Loaded(Arc::new(texture));
+            self.completed.lock().unwrap().push(AssetEvent::Loaded(upload.id));
+        }
+    }
+
+    /// Returns the number of assets stored in the server.
+    pub fn len(&self) -> usize {
+        self.slots.read().unwrap().paths.len()
+    }
+
+    /// Returns true if the server does not contain any asset.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the state of the asset.
+    ///
+    /// # Arguments
+    ///
+    /// `handle` - The handle of the asset.
+    fn state<T: Send + Sync + 'static>(
+        &self,
+        handle: &Handle<T>
+    ) -> Option<SharedState<T>> {
+        let slots_read = self.slots.read().unwrap();
+        let state = slots_read.get(handle.id)?.state.clone();
+        state.downcast::<RwLock<AssetState<T>>>().ok()
+    }
+}
+
+/// Marks the asset as failed and returns the failure event.
+///
+/// # Arguments
+///
+/// `state` - The state of the asset.
+/// `id` - The id of the asset.
+/// `path` - The path of the file.
+/// `error` - The reason of the failure.
+fn fail<T>(state: &RwLock<AssetState<T>>, id: u64, path: &str, error: String) -> AssetEvent {
+    warning(&format!("{{AssetServer}} Could not load {}: {}", path, error));
+    *state.write().unwrap() = AssetState::Failed(error.clone());
+    AssetEvent::Failed(id, error)
+}
+
+/// Uploads the textures decoded by the workers and publishes the events of
+/// the finished loads, it must be executed when the frame starts.
+pub fn process_pending_uploads_system(gpu: UniqueRead<Gpu>, assets: UniqueRead<AssetServer>) {
+    let assets_read = assets.read();
+    assets_read.process_pending_uploads(&gpu.read());
+    assets_read.update_events();
+}
+
+#[test]
+fn assets_are_cached_and_evicted() {
+    let path = std::env::temp_dir().join("crystal_asset_server_test.txt");
+    fs::write(&path, "hello").unwrap();
+    let path = path.to_str().unwrap();
+
+    let server = AssetServer::default();
+    let handle = server.load::<String>(path);
+    let other = server.load::<String>(path);
+    assert_eq!(handle.id, other.id);
+    assert_eq!(server.len(), 1);
+
+    while server.load_state(&handle) == LoadState::Loading {
+        std::thread::yield_now();
+    }
+    assert_eq!(server.get(&other).as_deref().map(String::as_str), Some("hello"));
+
+    let missing = server.load::<String>("this/file/does/not/exist");
+    while server.load_state(&missing) == LoadState::Loading {
+        std::thread::yield_now();
+    }
+    assert!(server.get(&missing).is_none());
+
+    // The asset lives until the last handle is dropped.
+    drop(missing);
+    let old_id = handle.id;
+    drop(handle);
+    assert_eq!(server.len(), 1);
+    drop(other);
+    assert!(server.is_empty());
+
+    // The slot is reused with a new generation, the old id is not valid.
+    let reloaded = server.load::<String>(path);
+    assert_ne!(reloaded.id, old_id);
+    assert_eq!(reloaded.id & 0xFFFF_FFFF, old_id & 0xFFFF_FFFF);
+}
+
+#[test]
+fn concurrent_loads_reach_loaded() {
+    let paths: Vec<String> = (0..8)
+        .map(|i| {
+            let path = std::env::temp_dir().join(format!("crystal_asset_{}.txt", i));
+            fs::write(&path, format!("asset {}", i)).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect();
+
+    let server = AssetServer::default();
+    let handles: Vec<Handle<String>> = paths.iter().map(|p| server.load(p)).collect();
+
+    let mut events: Vec<AssetEvent> = Vec::new();
+    while events.len() < handles.len() {
+        server.update_events();
+        events.extend(server.events());
+        std::thread::yield_now();
+    }
+
+    for (i, handle) in handles.iter().enumerate() {
+        assert_eq!(server.load_state(handle), LoadState::Loaded);
+        assert!(events.contains(&AssetEvent::Loaded(handle.id)));
+        assert_eq!(*server.get(handle).unwrap(), format!("asset {}", i));
+    }
+}
@@ -1,17 +1,44 @@
-use cgmath::{Matrix4, Vector3, Point3, conv::array4x4};
+use std::sync::Arc;
 
-use wgpu::{
-    CommandEncoder,
-    CommandEncoderDescriptor,
-    Buffer,
-    util::{DeviceExt, BufferInitDescriptor}
+use cgmath::{
+    Matrix4,
+    Vector3,
+    Point3,
+    InnerSpace
 };
 
-use ecs::{UniqueRead, UniqueWrite};
+use serde::{Serialize, Deserialize};
 
-use crate::graphics::{ 
-    pipelines::bind_groups::locals_bind_group::LocalsBuffer,
-    gpu::Gpu
+use ecs::{
+    DefaultWorld,
+    Component,
+    ComponentHandler,
+    UniqueRead,
+    UniqueWrite,
+    Read,
+    Searchable
+};
+use types::Size;
+
+use crate::{
+    graphics::{ 
+        pipelines::bind_groups::locals_bind_group::{
+            LocalsBuffer,
+            LocalsLayout,
+            Locals,
+            CameraLocalsPool,
+            CameraPass,
+            PointLightsBuffer,
+            create_locals_bind_group_with_layout
+        },
+        buffer::{BufferCreator, BufferManipulator},
+        texture::{Texture, TextureGenerator},
+        gpu::Gpu
+    },
+    scene::{
+        light::DirectionalLight,
+        snapshot::Saveable
+    }
 };
 
 /// OpenGL matrix 
@@ -22,7 +49,23 @@ const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     0.0, 0.0, 0.5, 1.0,
 );
 
-#[derive(Clone, Copy)]
+/// Defines how the camera projects the world into the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    /// A perspective projection, the objects get smaller with the distance.
+    ///
+    /// `fovy` - The vertical field of view in degrees.
+    Perspective { fovy: f32 },
+
+    /// An orthographic projection, the objects keep the same size no matter
+    /// the distance. Useful for editor views and UI space rendering.
+    ///
+    /// `height` - The number of world units visible vertically, the width is
+    /// calculated using the aspect.
+    Orthographic { height: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component)]
 pub struct Camera {
     /// Contains the position of the camera in the world.
     pub eye: Point3<f32>,
@@ -33,12 +76,20 @@ pub struct Camera {
     /// Contains the up direction of the world.
     pub up: Vector3<f32>,
 
+    /// The relation between the width and the height of the screen.
     pub aspect: f32,
-    pub fovy: f32,
+
+    /// The projection used by the camera.
+    pub projection: Projection,
+
     pub znear: f32,
     pub zfar: f32,
 }
 
+impl Saveable for Camera {
+    const NAME: &'static str = "Camera";
+}
+
 impl Default for Camera {
     /// Creates and returns a new default camera.
     fn default() -> Self {
@@ -47,7 +98,7 @@ impl Default for Camera {
             target: (1.0, 0.0, 0.0).into(),
             up: cgmath::Vector3::unit_y(),
             aspect: 1.0,
-            fovy: 45.0,
+            projection: Projection::Perspective { fovy: 45.0 },
             znear: 0.1,
             zfar: 100.0,
         }
@@ -83,38 +134,274 @@ impl Camera {
         self.target.z += direction.z * amount;
     }
 
+    /// Places the camera at `eye` looking at `target`.
+    ///
+    /// # Arguments
+    ///
+    /// `eye` - The new position of the camera.
+    /// `target` - The point where the camera will look at.
+    pub fn look_at(&mut self, eye: Point3<f32>, target: Point3<f32>) {
+        self.eye = eye;
+        self.target = target;
+    }
+
+    /// Returns the normalized direction where the camera is looking at.
+    pub fn forward(&self) -> Vector3<f32> {
+        (self.target - self.eye).normalize()
+    }
+
+    /// Returns the normalized right direction of the camera.
+    pub fn right(&self) -> Vector3<f32> {
+        self.forward().cross(self.up).normalize()
+    }
+
+    /// Returns the normalized up direction of the camera, this is not the
+    /// world up, it is perpendicular to the forward and right directions.
+    pub fn up_vector(&self) -> Vector3<f32> {
+        self.right().cross(self.forward()).normalize()
+    }
+
+    /// Returns the projection matrix of the camera.
+    pub fn projection_matrix(&self) -> Matrix4<f32> {
+        match self.projection {
+            Projection::Perspective { fovy } => cgmath::perspective(
+                cgmath::Deg(fovy),
+                self.aspect,
+                self.znear,
+                self.zfar
+            ),
+            Projection::Orthographic { height } => {
+                // The width depends on the aspect in order to avoid
+                // stretching the image.
+                let half_height = height / 2.0;
+                let half_width = half_height * self.aspect;
+                cgmath::ortho(
+                    -half_width, half_width,
+                    -half_height, half_height,
+                    self.znear, self.zfar
+                )
+            }
+        }
+    }
+
     /// Returns the view projection of the camera. 
     pub fn view_projection(&self) -> Matrix4<f32> {
         let view = Matrix4::look_at(self.eye, self.target, self.up);
-        let projection = cgmath::perspective(
-            cgmath::Deg(self.fovy),
-            self.aspect, self.znear,
-            self.zfar);
-        OPENGL_TO_WGPU_MATRIX * projection * view
+        OPENGL_TO_WGPU_MATRIX * self.projection_matrix() * view
     }
 }
 
-/// Mantains the locals buffer with respect to the camera.
+/// Represents an offscreen target where a camera entity renders.
 ///
-/// If the camera changes that should be reflected into the locals. 
-pub fn mantain_camera_buffer_system(
+/// A camera entity is only rendered if it contains a `RenderTarget`, the
+/// main camera (the unique `Camera`) always renders into the swap chain.
+#[derive(Component)]
+pub struct RenderTarget {
+    /// The size of the target in pixels.
+    pub size: Size<u32>,
+
+    /// The texture where the camera renders, it could be sampled to display
+    /// the content.
+    pub color: Arc<Texture>,
+
+    /// The depth texture used when the camera renders.
+    pub depth: Arc<Texture>
+}
+
+impl RenderTarget {
+    /// Creates and returns a new `RenderTarget`.
+    ///
+    /// # Arguments
+    ///
+    /// `generator` - The generator used to create the textures.
+    /// `size` - The size of the target.
+    pub fn new<G: TextureGenerator>(generator: &G, size: Size<u32>) -> Self {
+        let color = generator.create_render_target_texture(size.width, size.height);
+        let depth = generator.create_depth_texture_with_size(size.width, size.height);
+
+        Self {
+            size,
+            color: Arc::new(color),
+            depth: Arc::new(depth)
+        }
+    }
+
+    /// Returns the aspect of the target.
+    pub fn aspect(&self) -> f32 {
+        self.size.width as f32 / self.size.height as f32
+    }
+}
+
+/// Creates and returns a new `RenderTarget` using the gpu in the world.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the gpu.
+/// `size` - The size of the target.
+pub fn create_render_target(world: &DefaultWorld, size: Size<u32>) -> RenderTarget {
+    let gpu = world.get_unique::<Gpu>();
+    let gpu_read = gpu.read();
+    RenderTarget::new::<Gpu>(&gpu_read, size)
+}
+
+/// Mantains the locals buffer with respect to the camera and the light.
+///
+/// The new locals are compared against the last written ones, so the buffer
+/// is only updated when the camera or the light change.
+pub fn mantain_locals_system(
     gpu: UniqueRead<Gpu>,
     camera: UniqueRead<Camera>,
+    light: UniqueRead<DirectionalLight>,
+    last_locals: UniqueWrite<Locals>,
     locals_buffer: UniqueRead<LocalsBuffer>) {
-    // Create a new enconder.
-    let view_projection: [[f32; 4]; 4] = array4x4(camera.read().view_projection());
-    let view_projection_bytes: &[u8] = bytemuck::cast_slice(&view_projection);
+    let locals = Locals::new(&camera.read(), &light.read());
+
+    let mut last_write = last_locals.write();
+    if **last_write == locals {
+        return;
+    }
+    **last_write = locals;
+
+    gpu.read().copy_to_buffer(&locals_buffer.read().0, bytemuck::bytes_of(&locals));
+}
+
+/// Mantains one locals buffer per camera entity which contains a render
+/// target.
+///
+/// The buffers and bind groups are created the first time the camera is
+/// found and deleted when the camera is not present anymore.
+pub fn mantain_camera_passes_system(
+    gpu: UniqueRead<Gpu>,
+    locals_layout: UniqueRead<LocalsLayout>,
+    pool: UniqueWrite<CameraLocalsPool>,
+    light: UniqueRead<DirectionalLight>,
+    point_lights: UniqueRead<PointLightsBuffer>,
+    cameras: Read<Camera>,
+    targets: Read<RenderTarget>) {
+    let gpu_read = gpu.read();
+    let light_read = light.read();
+    let point_lights_read = point_lights.read();
+    let layout_read = locals_layout.read();
+    let mut pool_write = pool.write();
+
+    // Remove the passes of the cameras that do not exist anymore.
+    let entities = cameras.entities();
+    pool_write.passes.retain(|entity, _| entities.contains(entity));
+
+    let query = (cameras.iter(), targets.iter()).query();
+    for (entity, (camera, target)) in entities.iter().zip(query) {
+        let target_read = target.read();
+
+        // Use the aspect of the target to avoid stretching the image.
+        let mut target_camera: Camera = **camera.read();
+        target_camera.aspect = target_read.aspect();
+        let locals = Locals::new(&target_camera, &light_read);
 
-    gpu
-        .read()
-        .queue
-        .write_buffer(&locals_buffer.read().0, 0, view_projection_bytes);
+        let pass = pool_write.passes.entry(*entity).or_insert_with(|| {
+            let buffer = Arc::new(gpu_read.create_uniform(locals));
+            let group = create_locals_bind_group_with_layout(
+                &gpu_read,
+                &layout_read.layout,
+                &buffer,
+                &point_lights_read.0
+            );
+
+            CameraPass {
+                buffer,
+                group,
+                color: target_read.color.clone(),
+                depth: target_read.depth.clone()
+            }
+        });
+
+        // The target could be replaced at any moment.
+        pass.color = target_read.color.clone();
+        pass.depth = target_read.depth.clone();
+
+        gpu_read.copy_to_buffer(&pass.buffer, bytemuck::bytes_of(&locals));
+    }
 }
 
 /// Updates the camera aspect.
+///
+/// Both projections depend on the aspect, the perspective uses it directly
+/// and the orthographic one uses it to calculate the visible width.
 pub fn update_camera_resize_system(
     new_aspect: f32,
-    mut camera: UniqueWrite<Camera>) {
+    camera: UniqueWrite<Camera>) {
     // Access to the camera resource and updates the aspect.
     camera.write().aspect = new_aspect;
-}
\ No newline at end of file
+}
+
+/// Projects the point using the camera and returns the normalized device
+/// coordinates.
+#[cfg(test)]
+fn project(camera: &Camera, point: Point3<f32>) -> Vector3<f32> {
+    let clip = camera.view_projection() * point.to_homogeneous();
+    Vector3::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w)
+}
+
+#[cfg(test)]
+fn assert_near(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+}
+
+#[test]
+fn perspective_centers_target() {
+    let mut camera = Camera::default();
+    camera.look_at((0.0, 0.0, 0.0).into(), (0.0, 0.0, -1.0).into());
+
+    let ndc = project(&camera, (0.0, 0.0, -10.0).into());
+    assert_near(ndc.x, 0.0);
+    assert_near(ndc.y, 0.0);
+
+    // The near plane maps to 0 and the far plane to 1.
+    let near = project(&camera, (0.0, 0.0, -camera.znear).into());
+    let far = project(&camera, (0.0, 0.0, -camera.zfar).into());
+    assert_near(near.z, 0.0);
+    assert_near(far.z, 1.0);
+}
+
+#[test]
+fn perspective_shrinks_with_distance() {
+    let mut camera = Camera::default();
+    camera.look_at((0.0, 0.0, 0.0).into(), (0.0, 0.0, -1.0).into());
+
+    let close = project(&camera, (1.0, 1.0, -5.0).into());
+    let far = project(&camera, (1.0, 1.0, -50.0).into());
+    assert!(close.x > far.x);
+    assert!(close.y > far.y);
+}
+
+#[test]
+fn orthographic_keeps_size() {
+    let mut camera = Camera {
+        projection: Projection::Orthographic { height: 2.0 },
+        aspect: 2.0,
+        ..Camera::default()
+    };
+    camera.look_at((0.0, 0.0, 0.0).into(), (0.0, 0.0, -1.0).into());
+
+    // The visible area is 4x2 so the corner maps to (1, 1) no matter the
+    // distance.
+    let close = project(&camera, (2.0, 1.0, -5.0).into());
+    let far = project(&camera, (2.0, 1.0, -50.0).into());
+    assert_near(close.x, 1.0);
+    assert_near(close.y, 1.0);
+    assert_near(far.x, 1.0);
+    assert_near(far.y, 1.0);
+}
+
+#[test]
+fn camera_directions() {
+    let mut camera = Camera::default();
+    camera.look_at((0.0, 0.0, 0.0).into(), (0.0, 0.0, -5.0).into());
+
+    let forward = camera.forward();
+    let right = camera.right();
+    let up = camera.up_vector();
+
+    assert_near(forward.z, -1.0);
+    assert_near(right.x, 1.0);
+    assert_near(up.y, 1.0);
+}
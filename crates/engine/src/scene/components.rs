@@ -1,20 +1,32 @@
 use rand::Rng;
+use serde::{Serialize, Deserialize};
 
 use cgmath::{
     Vector3,
     Matrix4,
     Quaternion,
+    Euler,
     Deg,
-    Rad,
+    InnerSpace,
+    One,
     conv::array4x4,
     conv::array3
 };
 
-#[derive(Clone, Copy)]
+use ecs::Component;
+use types::Color;
+
+use crate::scene::snapshot::Saveable;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component)]
 pub struct Voxel {
     pub color: Vector3<f32>
 }
 
+impl Saveable for Voxel {
+    const NAME: &'static str = "Voxel";
+}
+
 impl Default for Voxel {
     /// Creates and returns a new instance of `Voxel`.
     fn default() -> Self {
@@ -43,6 +55,18 @@ impl Voxel {
         }
     }
 
+    /// Creates and returns a new instance of `Voxel` using a color in sRGB
+    /// space, it is converted to the linear space used by the shaders.
+    ///
+    /// # Arguments
+    ///
+    /// `color` - The color for the Voxel.
+    pub fn from_color(color: Color<f32>) -> Self {
+        Self {
+            color: color.to_linear().into()
+        }
+    }
+
     /// Creates and returns a new instance of 'Voxel' using a random
     /// color.
     pub fn rand_color() -> Self {
@@ -64,34 +88,168 @@ impl Voxel {
     }
 }
 
+/// Marks a voxel as selected, the selected voxels are drawn with an outline
+/// using the `OutlineColor` resource.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Component)]
+pub struct Selected;
 
 /// Represents a trasnformation component.
 ///
-/// This is used to transform one specif entity in the `World`.
-pub struct Transform {
+/// This is used to transform one specif entity in the `World`, relative to
+/// its `Parent` if it has one.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component)]
+pub struct LocalTransform {
     pub position: Vector3<f32>,
     pub scale: Vector3<f32>,
     pub rotation: Quaternion<f32>
 }
 
-impl Transform {
-    /// Returns the size of `Transform` in number of bytes.
+impl Saveable for LocalTransform {
+    const NAME: &'static str = "LocalTransform";
+}
+
+impl Default for LocalTransform {
+    /// Creates and returns a new `LocalTransform` at the origin without rotation
+    /// and with an unit scale.
+    fn default() -> Self {
+        Self {
+            position: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+            scale: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+            rotation: Quaternion::one()
+        }
+    }
+}
+
+impl LocalTransform {
+    /// Returns the size of `LocalTransform` in number of bytes.
     pub fn size() -> u32 {
         std::mem::size_of::<Self>() as u32
     }
 }
 
-impl Transform {
+impl LocalTransform {
+    /// Creates and returns a new `LocalTransform` placed in the provided position.
+    ///
+    /// # Arguments
+    ///
+    /// `position` - The position of the transform.
+    pub fn from_position(position: Vector3<f32>) -> Self {
+        Self {
+            position,
+            ..Self::default()
+        }
+    }
+
+    /// Creates and returns a new `LocalTransform` rotated using euler angles.
+    ///
+    /// # Arguments
+    ///
+    /// `yaw` - The rotation around the y axis in degrees.
+    /// `pitch` - The rotation around the x axis in degrees.
+    /// `roll` - The rotation around the z axis in degrees.
+    pub fn with_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Self {
+            rotation: Quaternion::from(Euler {
+                x: Deg(pitch),
+                y: Deg(yaw),
+                z: Deg(roll)
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Rotates the transform so its forward direction (-z) points to the
+    /// target and returns it.
+    ///
+    /// # Arguments
+    ///
+    /// `target` - The position to look at.
+    pub fn look_at(mut self, target: Vector3<f32>) -> Self {
+        let direction = target - self.position;
+        // If the target is the same position there is nothing to look at.
+        if direction.magnitude2() > 0.0 {
+            self.rotation = Quaternion::from_arc(
+                -Vector3::unit_z(),
+                direction.normalize(),
+                Some(Vector3::unit_y())
+            );
+        }
+        self
+    }
+}
+
+impl LocalTransform {
     /// Creates and returns a new 4x4 matrix which contains the position, 
     /// rotation and scale.
     pub fn as_matrix(&self) -> Matrix4<f32> {
+        // Only unit quaternions represent a rotation, an empty one is
+        // treated as no rotation.
+        let rotation = if self.rotation.magnitude2() > 0.0 {
+            self.rotation.normalize()
+        } else {
+            Quaternion::one()
+        };
+
         Matrix4::from_translation(self.position) *
-        Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z) *
-        Matrix4::from_axis_angle(self.rotation.v, Rad::from(Deg(self.rotation.s)))
+        Matrix4::from(rotation) *
+        Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
     }
 
     /// Creates and returns a new 4x4 matrix and returns it in an array form.
     pub fn as_matrix_array(&self) -> [[f32; 4]; 4] {
         array4x4(self.as_matrix())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+fn assert_rotates(transform: LocalTransform, from: Vector3<f32>, to: Vector3<f32>) {
+    let rotated = transform.as_matrix() * from.extend(0.0);
+    assert!((rotated.truncate() - to).magnitude() < 1e-5);
+}
+
+#[test]
+fn rotation_around_x() {
+    // Pitch.
+    assert_rotates(
+        LocalTransform::with_euler(0.0, 90.0, 0.0),
+        Vector3::unit_y(),
+        Vector3::unit_z()
+    );
+}
+
+#[test]
+fn rotation_around_y() {
+    // Yaw.
+    assert_rotates(
+        LocalTransform::with_euler(90.0, 0.0, 0.0),
+        Vector3::unit_z(),
+        Vector3::unit_x()
+    );
+}
+
+#[test]
+fn rotation_around_z() {
+    // Roll.
+    assert_rotates(
+        LocalTransform::with_euler(0.0, 0.0, 90.0),
+        Vector3::unit_x(),
+        Vector3::unit_y()
+    );
+}
+
+#[test]
+fn look_at_target() {
+    let transform = LocalTransform::default().look_at(Vector3::unit_x());
+    assert_rotates(transform, -Vector3::unit_z(), Vector3::unit_x());
+}
+
+#[test]
+fn voxel_from_color() {
+    // The white and the black are the same in both spaces.
+    assert_eq!(Voxel::from_color(Color::from_hex(0xFFFFFF)), Voxel::color(1.0, 1.0, 1.0));
+    assert_eq!(Voxel::from_color(Color::from_hex(0x000000)), Voxel::color(0.0, 0.0, 0.0));
+
+    // The voxels store the color in linear space.
+    let voxel = Voxel::from_color(Color::new(0.5, 0.5, 0.5, 1.0));
+    assert!((voxel.color.x - 0.21404).abs() < 1e-4);
+}
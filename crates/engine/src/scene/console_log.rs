@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+
+use ecs::UniqueWrite;
+use log::{Log, LogEntry, LogSeverity};
+
+use crate::basics::dev_gui::{DevGui, GuiAnchor};
+
+/// The name of the console panel in the dev GUI.
+pub const CONSOLE_PANEL: &str = "Console";
+
+/// The number of entries kept by the console.
+const CONSOLE_CAPACITY: usize = 512;
+
+/// The maximum number of entries moved from the log each frame.
+const MAX_ENTRIES_PER_FRAME: usize = 128;
+
+/// The number of entries displayed by the console window, the newest ones.
+const CONSOLE_WINDOW_LINES: usize = 16;
+
+/// Contains the entries displayed by the in-game console, from the oldest
+/// to the newest.
+pub struct ConsoleLog {
+    /// Contains the last entries received from the log.
+    entries: VecDeque<LogEntry>
+}
+
+impl Default for ConsoleLog {
+    /// Creates and returns a new empty `ConsoleLog`.
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(CONSOLE_CAPACITY)
+        }
+    }
+}
+
+impl ConsoleLog {
+    /// Adds the entries to the console, the oldest entries are discarded
+    /// when the console is full.
+    ///
+    /// # Arguments
+    ///
+    /// `entries` - The new entries, from the oldest to the newest.
+    pub fn extend(&mut self, entries: Vec<LogEntry>) {
+        for entry in entries {
+            if self.entries.len() == CONSOLE_CAPACITY {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(entry);
+        }
+    }
+
+    /// Returns the entries along with the color used to display them.
+    pub fn lines(&self) -> impl Iterator<Item = (&LogEntry, [f32; 3])> {
+        self.entries
+            .iter()
+            .map(|entry| (entry, severity_color(entry.severity())))
+    }
+
+    /// Removes all the entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Returns the RGB color used to display the severity.
+///
+/// # Arguments
+///
+/// `severity` - The severity of the entry.
+pub fn severity_color(severity: LogSeverity) -> [f32; 3] {
+    match severity {
+        LogSeverity::DEBUG => [0.5, 0.5, 0.5],
+        LogSeverity::INFO => [0.4, 0.6, 1.0],
+        LogSeverity::WARNING => [1.0, 0.8, 0.0],
+        LogSeverity::ERROR => [1.0, 0.2, 0.2]
+    }
+}
+
+/// Moves the new log entries into the console.
+///
+/// The entries are drained from the log, so the log ring buffer does not
+/// lose them when it is full.
+pub fn update_console_log_system(console: UniqueWrite<ConsoleLog>) {
+    let entries = Log::drain(MAX_ENTRIES_PER_FRAME);
+    if entries.is_empty() {
+        return;
+    }
+
+    console.write().extend(entries);
+}
+
+/// Draws the newest entries of the console colored by their severity, the
+/// window is only drawn when the console panel is visible.
+pub fn console_log_window_system(
+    dev_gui: UniqueWrite<DevGui>,
+    console: UniqueWrite<ConsoleLog>) {
+    let mut dev_gui_write = dev_gui.write();
+    if !dev_gui_write.is_visible(CONSOLE_PANEL) {
+        return;
+    }
+
+    let mut window = dev_gui_write.window(CONSOLE_PANEL, GuiAnchor::Bottom);
+    let mut console_write = console.write();
+    if window.button("Clear") {
+        console_write.clear();
+    }
+
+    let skip = console_write.entries.len().saturating_sub(CONSOLE_WINDOW_LINES);
+    for (entry, color) in console_write.lines().skip(skip) {
+        window.text(
+            &format!("{} {}", entry.timestamp().format("%H:%M:%S"), entry.message()),
+            [color[0], color[1], color[2], 1.0]
+        );
+    }
+
+    dev_gui_write.show(window);
+}
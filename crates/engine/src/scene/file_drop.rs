@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use winit::event::WindowEvent;
+
+use ecs::UniqueWrite;
+
+/// Represents the file drag and drop events over the window.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileDropEvent {
+    /// A file is being dragged over the window.
+    Hovered(PathBuf),
+
+    /// A file was dropped into the window.
+    Dropped(PathBuf),
+
+    /// The file was dragged out of the window or the drop was cancelled.
+    HoverCancelled
+}
+
+impl FileDropEvent {
+    /// Creates and returns a new `FileDropEvent` from a winit window event,
+    /// if the event is not related with the files it returns None.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The winit window event.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::HoveredFile(path) => Some(FileDropEvent::Hovered(path.clone())),
+            WindowEvent::DroppedFile(path) => Some(FileDropEvent::Dropped(path.clone())),
+            WindowEvent::HoveredFileCancelled => Some(FileDropEvent::HoverCancelled),
+            _ => None
+        }
+    }
+}
+
+/// Contains all the file drop events received in the current frame, the
+/// events are cleared when the frame ends.
+#[derive(Default)]
+pub struct FileDropEvents(pub Vec<FileDropEvent>);
+
+impl FileDropEvents {
+    /// Returns the paths of the files dropped in the current frame.
+    pub fn dropped(&self) -> impl Iterator<Item = &Path> {
+        self.0.iter().filter_map(|event| match event {
+            FileDropEvent::Dropped(path) => Some(path.as_path()),
+            _ => None
+        })
+    }
+}
+
+/// Clears the file drop events, this must only be called when the frame
+/// ends.
+pub fn clear_file_drop_events_system(events: UniqueWrite<FileDropEvents>) {
+    events.write().0.clear();
+}
+
+#[test]
+fn dropped_paths() {
+    let events = FileDropEvents(vec![
+        FileDropEvent::Hovered(PathBuf::from("model.obj")),
+        FileDropEvent::Dropped(PathBuf::from("model.obj")),
+        FileDropEvent::HoverCancelled,
+        FileDropEvent::Dropped(PathBuf::from("texture.png"))
+    ]);
+
+    let paths: Vec<&Path> = events.dropped().collect();
+    assert_eq!(paths, vec![Path::new("model.obj"), Path::new("texture.png")]);
+}
@@ -0,0 +1,270 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    path::Path
+};
+
+use cgmath::{Vector3, Vector4, Quaternion};
+
+use ecs::{DefaultWorld, ComponentHandler, EntityHandler, Entity};
+use log::warning;
+
+use crate::scene::{
+    assets::AssetServer,
+    components::LocalTransform,
+    hierarchy::Parent,
+    mesh::{Mesh, MeshHandle, Material, register_mesh}
+};
+
+/// Represents the errors that can happen when a GLTF file is loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GltfError {
+    /// The file or one of its buffers could not be read.
+    IoError(String),
+
+    /// The content of the file is not a valid GLTF document.
+    ParseError(String),
+
+    /// The document does not contain any scene.
+    MissingScene
+}
+
+impl Display for GltfError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            GltfError::IoError(e) => write!(formatter, "GLTF file error: {}", e),
+            GltfError::ParseError(e) => write!(formatter, "Invalid GLTF: {}", e),
+            GltfError::MissingScene => write!(formatter, "The GLTF does not contain scenes")
+        }
+    }
+}
+
+impl From<::gltf::Error> for GltfError {
+    fn from(error: ::gltf::Error) -> Self {
+        match error {
+            ::gltf::Error::Io(e) => GltfError::IoError(e.to_string()),
+            e => GltfError::ParseError(e.to_string())
+        }
+    }
+}
+
+/// Loads GLTF 2.0 scenes into the world, only the static content is loaded,
+/// the animations and skins are ignored.
+pub struct GltfLoader;
+
+impl GltfLoader {
+    /// Creates an entity for each node of the default scene and returns all
+    /// of them, the parents are always before their children.
+    ///
+    /// GLTF uses Y as the up axis, the same as the engine, so the
+    /// transformations are used as they are.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the .gltf or .glb file.
+    /// `world` - The world where the entities are created.
+    pub fn load(path: &Path, world: &DefaultWorld) -> Result<Vec<Entity>, GltfError> {
+        // The images are decoded by the import too but they are not used,
+        // the textures are loaded from the URIs by the asset server.
+        let (document, buffers, _) = ::gltf::import(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let scene = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or(GltfError::MissingScene)?;
+
+        let mut context = LoadContext {
+            world,
+            base,
+            buffers,
+            meshes: HashMap::new(),
+            entities: Vec::new()
+        };
+        for node in scene.nodes() {
+            context.spawn_node(&node, None);
+        }
+
+        Ok(context.entities)
+    }
+}
+
+/// Contains the state shared by all the nodes of a load.
+struct LoadContext<'a> {
+    world: &'a DefaultWorld,
+
+    /// The directory of the file, the URIs are relative to it.
+    base: &'a Path,
+
+    buffers: Vec<::gltf::buffer::Data>,
+
+    /// The registered mesh of each primitive, keyed by mesh and primitive
+    /// index, so the meshes used by several nodes are uploaded once.
+    meshes: HashMap<(usize, usize), Option<MeshHandle>>,
+
+    /// The created entities.
+    entities: Vec<Entity>
+}
+
+impl<'a> LoadContext<'a> {
+    /// Creates the entity of the node and its children.
+    ///
+    /// # Arguments
+    ///
+    /// `node` - The GLTF node.
+    /// `parent` - The entity of the parent node.
+    fn spawn_node(&mut self, node: &::gltf::Node, parent: Option<Entity>) {
+        let entity = self.world.add_entity((local_transform(node.transform().decomposed()),));
+        if let Some(p) = parent {
+            self.world.add_component(entity, Parent(p));
+        }
+        self.entities.push(entity);
+
+        if let Some(mesh) = node.mesh() {
+            // An entity draws a single mesh, the first primitive is drawn by
+            // the node entity and the rest by children of it.
+            let mut target = Some(entity);
+            for primitive in mesh.primitives() {
+                let handle = match self.register_primitive(&mesh, &primitive) {
+                    Some(h) => h,
+                    None => continue
+                };
+
+                let drawer = match target.take() {
+                    Some(e) => e,
+                    None => {
+                        let child = self.world.add_entity((LocalTransform::default(), Parent(entity)));
+                        self.entities.push(child);
+                        child
+                    }
+                };
+                self.world.add_component(drawer, handle);
+                self.world.add_component(drawer, self.material(&primitive.material()));
+            }
+        }
+
+        for child in node.children() {
+            self.spawn_node(&child, Some(entity));
+        }
+    }
+
+    /// Registers the primitive if it was not registered yet and returns its
+    /// handle, None if the primitive can not be drawn.
+    ///
+    /// # Arguments
+    ///
+    /// `mesh` - The mesh which contains the primitive.
+    /// `primitive` - The primitive to be registered.
+    fn register_primitive(
+        &mut self,
+        mesh: &::gltf::Mesh,
+        primitive: &::gltf::Primitive
+    ) -> Option<MeshHandle> {
+        let key = (mesh.index(), primitive.index());
+        if let Some(handle) = self.meshes.get(&key) {
+            return *handle;
+        }
+
+        let handle = read_primitive(primitive, &self.buffers)
+            .map(|m| register_mesh(self.world, m));
+        self.meshes.insert(key, handle);
+        handle
+    }
+
+    /// Creates and returns the material component of a GLTF material.
+    ///
+    /// # Arguments
+    ///
+    /// `material` - The GLTF material.
+    fn material(&self, material: &::gltf::Material) -> Material {
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, a] = pbr.base_color_factor();
+
+        let texture_handle = pbr.base_color_texture().and_then(|info| {
+            match info.texture().source().source() {
+                ::gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+                    let path = self.base.join(uri);
+                    let assets = self.world.get_unique::<AssetServer>();
+                    let handle = assets.read().load_texture(&path.to_string_lossy());
+                    Some(handle)
+                },
+                _ => {
+                    warning("{GltfLoader} Embedded textures are not supported");
+                    None
+                }
+            }
+        });
+
+        Material {
+            base_color: Vector4 { x: r, y: g, z: b, w: a },
+            texture_handle
+        }
+    }
+}
+
+/// Reads the triangles of the primitive, None if it does not contain
+/// triangles.
+///
+/// # Arguments
+///
+/// `primitive` - The GLTF primitive.
+/// `buffers` - The buffers of the document.
+fn read_primitive(primitive: &::gltf::Primitive, buffers: &[::gltf::buffer::Data]) -> Option<Mesh> {
+    if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+        warning("{GltfLoader} Only triangle primitives are supported");
+        return None;
+    }
+
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|d| d.0.as_slice()));
+
+    let positions: Vec<f32> = reader.read_positions()?.flat_map(|p| p.to_vec()).collect();
+    let normals: Vec<f32> = reader
+        .read_normals()
+        .map(|n| n.flat_map(|n| n.to_vec()).collect())
+        .unwrap_or_default();
+    // Without indices every three vertices are a triangle.
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(i) => i.into_u32().collect(),
+        None => (0..(positions.len() / 3) as u32).collect()
+    };
+
+    let mut mesh = Mesh::default();
+    mesh.append(&positions, &normals, &indices);
+    if mesh.indices.is_empty() {
+        return None;
+    }
+
+    // The mesh pipeline uses a color per mesh, the base color is the
+    // closest one.
+    let [r, g, b, _] = primitive.material().pbr_metallic_roughness().base_color_factor();
+    mesh.color = Vector3 { x: r, y: g, z: b };
+
+    Some(mesh)
+}
+
+/// Creates and returns the transformation of a GLTF node.
+///
+/// # Arguments
+///
+/// `decomposed` - The translation, rotation and scale of the node.
+fn local_transform(decomposed: ([f32; 3], [f32; 4], [f32; 3])) -> LocalTransform {
+    let (translation, rotation, scale) = decomposed;
+    LocalTransform {
+        position: translation.into(),
+        scale: scale.into(),
+        // GLTF stores the quaternions as [x, y, z, w].
+        rotation: Quaternion::new(rotation[3], rotation[0], rotation[1], rotation[2])
+    }
+}
+
+#[test]
+fn gltf_rotation_is_xyzw() {
+    // 90 degrees around the y axis.
+    let half = std::f32::consts::FRAC_1_SQRT_2;
+    let transform = local_transform(([1.0, 2.0, 3.0], [0.0, half, 0.0, half], [2.0, 2.0, 2.0]));
+
+    assert_eq!(transform.position, Vector3 { x: 1.0, y: 2.0, z: 3.0 });
+    let rotated = transform.rotation * Vector3::unit_x();
+    assert!(rotated.x.abs() < 1e-5);
+    assert!((rotated.z + 1.0).abs() < 1e-5);
+}
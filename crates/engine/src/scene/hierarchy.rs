@@ -0,0 +1,378 @@
+use std::collections::{HashMap, HashSet};
+
+use cgmath::{Matrix4, SquareMatrix};
+
+use ecs::{
+    DefaultWorld,
+    Component,
+    ComponentHandler,
+    Entity,
+    Read,
+    Write
+};
+use log::error;
+
+use crate::scene::components::LocalTransform;
+
+/// Represents the parent of an entity, the entity transformation becomes
+/// relative to the parent transformation.
+#[derive(Clone, Copy, Component)]
+pub struct Parent(pub Entity);
+
+/// Contains the transformation of the entity in world space, it is updated by
+/// the `propagate_transforms_system` for all the entities which contain it.
+#[derive(Clone, Copy, Component)]
+pub struct WorldTransform(pub Matrix4<f32>);
+
+impl Default for WorldTransform {
+    /// Creates and returns a new `WorldTransform` using the identity.
+    fn default() -> Self {
+        Self(Matrix4::identity())
+    }
+}
+
+/// Contains the world matrices of all the entities with a `LocalTransform`.
+///
+/// The local transformations and parents used to calculate the matrices are
+/// cached, so only the entities which changed, or whose ancestors changed,
+/// are calculated again.
+#[derive(Default)]
+pub struct WorldTransforms {
+    /// The world matrix of each entity, used by the renderers.
+    pub matrices: HashMap<Entity, Matrix4<f32>>,
+
+    /// The local transformations used in the last update.
+    locals: HashMap<Entity, LocalTransform>,
+
+    /// The parents used in the last update, only parents with a
+    /// transformation are stored.
+    parents: HashMap<Entity, Entity>,
+
+    /// The entities whose `WorldTransform` component is up to date.
+    synced: HashSet<Entity>
+}
+
+impl WorldTransforms {
+    /// Updates the world matrices and returns the entities whose matrix
+    /// changed.
+    ///
+    /// # Arguments
+    ///
+    /// `locals` - The local transformation of each entity.
+    /// `parents` - The parent of each entity.
+    pub fn update(&mut self,
+                  locals: HashMap<Entity, LocalTransform>,
+                  parents: HashMap<Entity, Entity>) -> HashSet<Entity> {
+        // A parent without transformation is ignored, the child becomes a
+        // root.
+        let parents: HashMap<Entity, Entity> = parents
+            .into_iter()
+            .filter(|(_, parent)| locals.contains_key(parent))
+            .collect();
+
+        let changed: HashSet<Entity> = locals
+            .iter()
+            .filter(|(entity, local)| {
+                self.locals.get(entity) != Some(local) ||
+                self.parents.get(entity) != parents.get(entity)
+            })
+            .map(|(entity, _)| *entity)
+            .collect();
+
+        let dirty = find_dirty_entities(&changed, &locals, &parents);
+
+        // Reuse the matrices of the clean entities.
+        let clean: HashMap<Entity, Matrix4<f32>> = self.matrices
+            .iter()
+            .filter(|(entity, _)| locals.contains_key(entity) && !dirty.contains(entity))
+            .map(|(entity, matrix)| (*entity, *matrix))
+            .collect();
+
+        let local_matrices: HashMap<Entity, Matrix4<f32>> = locals
+            .iter()
+            .filter(|(entity, _)| dirty.contains(entity))
+            .map(|(entity, local)| (*entity, local.as_matrix()))
+            .collect();
+
+        self.matrices = resolve_world_matrices(&local_matrices, &parents, clean);
+        self.synced.retain(|entity| !dirty.contains(entity) && locals.contains_key(entity));
+        self.locals = locals;
+        self.parents = parents;
+
+        dirty
+    }
+}
+
+/// Returns the entities which changed or have an ancestor which changed.
+///
+/// Entities inside a cycle are always dirty.
+///
+/// # Arguments
+///
+/// `changed` - The entities whose local transformation or parent changed.
+/// `locals` - The local transformation of each entity.
+/// `parents` - The parent of each entity.
+fn find_dirty_entities(
+    changed: &HashSet<Entity>,
+    locals: &HashMap<Entity, LocalTransform>,
+    parents: &HashMap<Entity, Entity>) -> HashSet<Entity> {
+    let mut visited: HashMap<Entity, bool> = HashMap::new();
+
+    for entity in locals.keys() {
+        let mut chain: Vec<Entity> = Vec::new();
+        let mut current: Entity = *entity;
+
+        // Walk up the hierarchy until the answer is known.
+        let dirty = loop {
+            if let Some(dirty) = visited.get(&current) {
+                break *dirty;
+            }
+
+            // An entity already in the chain means there is a cycle.
+            if chain.contains(&current) {
+                break true;
+            }
+
+            chain.push(current);
+            if changed.contains(&current) {
+                break true;
+            }
+
+            match parents.get(&current) {
+                Some(parent) => current = *parent,
+                None => break false
+            }
+        };
+
+        for e in chain {
+            visited.insert(e, dirty);
+        }
+    }
+
+    visited
+        .into_iter()
+        .filter(|(_, dirty)| *dirty)
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+/// Calculates the world transformation of all the entities in the hierarchy.
+///
+/// The parents are always calculated before their children, if a parent
+/// does not exist anymore the child is treated as a root. Cycles are logged
+/// and the entities involved are treated as roots.
+///
+/// # Arguments
+///
+/// `locals` - The local transformation of each entity.
+/// `parents` - The parent of each entity.
+pub fn compute_world_matrices(
+    locals: &HashMap<Entity, Matrix4<f32>>,
+    parents: &HashMap<Entity, Entity>) -> HashMap<Entity, Matrix4<f32>> {
+    resolve_world_matrices(locals, parents, HashMap::new())
+}
+
+/// Calculates the world transformation of the entities which are not already
+/// resolved.
+///
+/// # Arguments
+///
+/// `locals` - The local transformation of each entity to calculate.
+/// `parents` - The parent of each entity.
+/// `resolved` - The world transformations already known.
+fn resolve_world_matrices(
+    locals: &HashMap<Entity, Matrix4<f32>>,
+    parents: &HashMap<Entity, Entity>,
+    mut resolved: HashMap<Entity, Matrix4<f32>>) -> HashMap<Entity, Matrix4<f32>> {
+    'entities: for entity in locals.keys() {
+        // Contains the entities from the child to the first entity already
+        // resolved or the root.
+        let mut chain: Vec<Entity> = Vec::new();
+        let mut current: Entity = *entity;
+        let mut base: Matrix4<f32> = Matrix4::identity();
+
+        // Walk up the hierarchy.
+        loop {
+            if let Some(matrix) = resolved.get(&current) {
+                base = *matrix;
+                break;
+            }
+
+            // If the entity was already visited there is a cycle, break it
+            // treating all the entities as roots.
+            if chain.contains(&current) {
+                error(&format!(
+                    "Transform hierarchy cycle detected at entity {:?}",
+                    current
+                ));
+                for e in chain.iter() {
+                    resolved.insert(*e, locals[e]);
+                }
+                continue 'entities;
+            }
+
+            chain.push(current);
+
+            match parents.get(&current) {
+                // Only follow the parent if it still has a transformation,
+                // otherwise the entity becomes a root.
+                Some(parent) if locals.contains_key(parent) || resolved.contains_key(parent) => {
+                    current = *parent
+                },
+                _ => break
+            }
+        }
+
+        // Walk down the hierarchy accumulating the transformations.
+        for e in chain.iter().rev() {
+            base = base * locals[e];
+            resolved.insert(*e, base);
+        }
+    }
+
+    resolved
+}
+
+/// Propagates the transformations from the parents to the children.
+///
+/// Only the entities whose `LocalTransform` or `Parent` changed, and their
+/// descendants, are calculated again and get their `WorldTransform`
+/// written.
+///
+/// This runs over the world instead of a system due it needs to access
+/// entities which do not share the same components.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the entities.
+pub fn propagate_transforms_system(world: &DefaultWorld) {
+    // Collect all the parents.
+    let parents_access = world.get::<Read<Parent>>();
+    let parents: HashMap<Entity, Entity> = parents_access
+        .entities()
+        .iter()
+        .zip(parents_access.iter())
+        .map(|(entity, parent)| (*entity, parent.read().0))
+        .collect();
+
+    // Collect all the local transformations.
+    let locals_access = world.get::<Read<LocalTransform>>();
+    let locals: HashMap<Entity, LocalTransform> = locals_access
+        .entities()
+        .iter()
+        .zip(locals_access.iter())
+        .map(|(entity, transform)| (*entity, **transform.read()))
+        .collect();
+
+    let world_transforms = world.get_unique_mut::<WorldTransforms>();
+    let mut world_transforms_write = world_transforms.write();
+    world_transforms_write.update(locals, parents);
+
+    // Update the world transformation of the entities which are not synced.
+    let components_access = world.get::<Write<WorldTransform>>();
+    for (entity, component) in components_access.entities().iter().zip(components_access.iter()) {
+        if world_transforms_write.synced.contains(entity) {
+            continue;
+        }
+
+        if let Some(matrix) = world_transforms_write.matrices.get(entity).copied() {
+            component.write().0 = matrix;
+            world_transforms_write.synced.insert(*entity);
+        }
+    }
+}
+
+#[cfg(test)]
+fn translation(x: f32, y: f32, z: f32) -> Matrix4<f32> {
+    Matrix4::from_translation(cgmath::Vector3::new(x, y, z))
+}
+
+#[test]
+fn three_level_hierarchy() {
+    let root = Entity::new(0);
+    let child = Entity::new(1);
+    let grandchild = Entity::new(2);
+
+    let mut locals = HashMap::new();
+    locals.insert(root, translation(1.0, 0.0, 0.0));
+    locals.insert(child, translation(0.0, 2.0, 0.0));
+    locals.insert(grandchild, translation(0.0, 0.0, 3.0));
+
+    let mut parents = HashMap::new();
+    parents.insert(child, root);
+    parents.insert(grandchild, child);
+
+    let world_matrices = compute_world_matrices(&locals, &parents);
+
+    let position = world_matrices[&grandchild].w;
+    assert_eq!((position.x, position.y, position.z), (1.0, 2.0, 3.0));
+
+    let position = world_matrices[&child].w;
+    assert_eq!((position.x, position.y, position.z), (1.0, 2.0, 0.0));
+}
+
+#[test]
+fn orphan_becomes_root() {
+    let child = Entity::new(1);
+
+    let mut locals = HashMap::new();
+    locals.insert(child, translation(0.0, 2.0, 0.0));
+
+    // The parent does not exist anymore.
+    let mut parents = HashMap::new();
+    parents.insert(child, Entity::new(0));
+
+    let world_matrices = compute_world_matrices(&locals, &parents);
+    let position = world_matrices[&child].w;
+    assert_eq!((position.x, position.y, position.z), (0.0, 2.0, 0.0));
+}
+
+#[test]
+fn cycle_does_not_hang() {
+    let a = Entity::new(0);
+    let b = Entity::new(1);
+
+    let mut locals = HashMap::new();
+    locals.insert(a, translation(1.0, 0.0, 0.0));
+    locals.insert(b, translation(0.0, 1.0, 0.0));
+
+    let mut parents = HashMap::new();
+    parents.insert(a, b);
+    parents.insert(b, a);
+
+    let world_matrices = compute_world_matrices(&locals, &parents);
+    assert_eq!(world_matrices.len(), 2);
+}
+
+#[test]
+fn only_changed_branches_are_dirty() {
+    let root = Entity::new(0);
+    let child = Entity::new(1);
+    let other = Entity::new(2);
+
+    let mut locals = HashMap::new();
+    locals.insert(root, LocalTransform::from_position(cgmath::Vector3::new(1.0, 0.0, 0.0)));
+    locals.insert(child, LocalTransform::from_position(cgmath::Vector3::new(0.0, 2.0, 0.0)));
+    locals.insert(other, LocalTransform::default());
+
+    let mut parents = HashMap::new();
+    parents.insert(child, root);
+
+    let mut world_transforms = WorldTransforms::default();
+    assert_eq!(world_transforms.update(locals.clone(), parents.clone()).len(), 3);
+    assert!(world_transforms.update(locals.clone(), parents.clone()).is_empty());
+
+    // Moving the root moves the child too.
+    locals.insert(root, LocalTransform::from_position(cgmath::Vector3::new(5.0, 0.0, 0.0)));
+    let dirty = world_transforms.update(locals.clone(), parents.clone());
+    assert!(dirty.contains(&root) && dirty.contains(&child) && !dirty.contains(&other));
+
+    let position = world_transforms.matrices[&child].w;
+    assert_eq!((position.x, position.y, position.z), (5.0, 2.0, 0.0));
+
+    // Removing the parent link turns the child into a root.
+    let dirty = world_transforms.update(locals, HashMap::new());
+    assert_eq!(dirty.len(), 1);
+    let position = world_transforms.matrices[&child].w;
+    assert_eq!((position.x, position.y, position.z), (0.0, 2.0, 0.0));
+}
@@ -0,0 +1,418 @@
+use std::collections::HashSet;
+
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+use winit::dpi::{PhysicalPosition, LogicalPosition};
+use winit::event::{
+    DeviceEvent,
+    ElementState,
+    KeyboardInput,
+    MouseButton,
+    VirtualKeyCode,
+    WindowEvent
+};
+
+use ecs::UniqueWrite;
+
+/// Represents all the input events that the engine understands.
+///
+/// The events are generated from the winit events, in this way the rest of
+/// the engine (and the recorder) does not depend on winit internals.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    /// A key was pressed.
+    KeyDown(VirtualKeyCode),
+
+    /// A key was released.
+    KeyUp(VirtualKeyCode),
+
+    /// The mouse moved, it contains the delta in x and y.
+    ///
+    /// The values are stored using the raw bits in order to keep the exact
+    /// value when the event is recorded and replayed.
+    Motion(
+        #[serde(with = "f64_bits")] f64,
+        #[serde(with = "f64_bits")] f64
+    ),
+
+    /// A mouse button was pressed.
+    MouseDown(MouseButton),
+
+    /// A mouse button was released.
+    MouseUp(MouseButton),
+
+    /// The window got the focus.
+    FocusGained,
+
+    /// The window lost the focus, all the pressed keys are released due
+    /// the keys released outside the window are never received.
+    FocusLost,
+}
+
+impl InputEvent {
+    /// Creates and returns a new `InputEvent` from a winit window event, if
+    /// the event is not related with the input it returns None.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The winit window event.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput {
+                    state,
+                    virtual_keycode: Some(key),
+                    ..
+                },
+                ..
+            } => match state {
+                ElementState::Pressed => Some(InputEvent::KeyDown(*key)),
+                ElementState::Released => Some(InputEvent::KeyUp(*key)),
+            },
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => Some(InputEvent::MouseDown(*button)),
+                ElementState::Released => Some(InputEvent::MouseUp(*button)),
+            },
+            WindowEvent::Focused(true) => Some(InputEvent::FocusGained),
+            WindowEvent::Focused(false) => Some(InputEvent::FocusLost),
+            _ => None
+        }
+    }
+
+    /// Creates and returns a new `InputEvent` from a winit device event, if
+    /// the event is not related with the input it returns None.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The winit device event.
+    pub fn from_device_event(event: &DeviceEvent) -> Option<Self> {
+        match event {
+            DeviceEvent::MouseMotion { delta } =>
+                Some(InputEvent::Motion(delta.0, delta.1)),
+            _ => None
+        }
+    }
+}
+
+/// Contains the current state of the input.
+#[derive(Default)]
+pub struct Input {
+    /// Contains all the keys that are pressed.
+    pub keys_down: HashSet<VirtualKeyCode>,
+
+    /// Contains the mouse motion accumulated in the current frame.
+    pub mouse_delta: (f64, f64),
+
+    /// Contains all the mouse buttons that are pressed.
+    pub buttons_down: HashSet<MouseButton>,
+
+    /// Contains the mouse buttons pressed in the current frame.
+    pub buttons_clicked: HashSet<MouseButton>,
+
+    /// Contains the keys pressed in the current frame, even if they were
+    /// already released. The key repeats are not included.
+    pub keys_just_pressed: HashSet<VirtualKeyCode>,
+
+    /// Contains the keys released in the current frame.
+    pub keys_just_released: HashSet<VirtualKeyCode>,
+
+    /// Contains the keys which were down when the current frame started.
+    pub frame_keys: HashSet<VirtualKeyCode>,
+
+    /// Contains the keys which were down when the previous frame started.
+    pub previous_frame_keys: HashSet<VirtualKeyCode>,
+
+    /// Contains the buttons which were down when the current frame started.
+    pub frame_buttons: HashSet<MouseButton>,
+
+    /// Contains the buttons which were down when the previous frame started.
+    pub previous_frame_buttons: HashSet<MouseButton>,
+}
+
+impl Input {
+    /// Updates the input state using the provided event.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The event to be processed.
+    pub fn process_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::KeyDown(key) => {
+                // The key repeats send KeyDown while the key is down.
+                if self.keys_down.insert(*key) {
+                    self.keys_just_pressed.insert(*key);
+                }
+            }
+            InputEvent::KeyUp(key) => {
+                if self.keys_down.remove(key) {
+                    self.keys_just_released.insert(*key);
+                }
+            }
+            InputEvent::Motion(x, y) => {
+                self.mouse_delta.0 += x;
+                self.mouse_delta.1 += y;
+            }
+            InputEvent::MouseDown(button) => {
+                self.buttons_down.insert(*button);
+                self.buttons_clicked.insert(*button);
+            }
+            InputEvent::MouseUp(button) => {
+                self.buttons_down.remove(button);
+            }
+            InputEvent::FocusGained => (),
+            InputEvent::FocusLost => self.release_all()
+        }
+    }
+
+    /// Returns true if the key is pressed.
+    ///
+    /// # Arguments
+    ///
+    /// `key` - The key to check.
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Returns true if the key was pressed in the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// `key` - The key to check.
+    pub fn is_key_just_pressed(&self, key: VirtualKeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+
+    /// Returns true if the key was released in the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// `key` - The key to check.
+    pub fn is_key_just_released(&self, key: VirtualKeyCode) -> bool {
+        self.keys_just_released.contains(&key)
+    }
+
+    /// Returns true if the mouse button was pressed in the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// `button` - The button to check.
+    pub fn was_clicked(&self, button: MouseButton) -> bool {
+        self.buttons_clicked.contains(&button)
+    }
+
+    /// Stores the state of the keys and buttons for the frame which starts,
+    /// the state of the last frame is kept to detect the changes.
+    ///
+    /// The keys and buttons pressed and released before the frame started
+    /// are considered down during the frame.
+    pub fn begin_frame(&mut self) {
+        let keys: HashSet<VirtualKeyCode> = self.keys_down
+            .union(&self.keys_just_pressed)
+            .copied()
+            .collect();
+        self.previous_frame_keys = std::mem::replace(&mut self.frame_keys, keys);
+
+        let buttons: HashSet<MouseButton> = self.buttons_down
+            .union(&self.buttons_clicked)
+            .copied()
+            .collect();
+        self.previous_frame_buttons = std::mem::replace(&mut self.frame_buttons, buttons);
+    }
+
+    /// Clears the state which only lasts one frame.
+    pub fn end_frame(&mut self) {
+        self.mouse_delta = (0.0, 0.0);
+        self.buttons_clicked.clear();
+        self.keys_just_pressed.clear();
+        self.keys_just_released.clear();
+    }
+
+    /// Releases all the pressed keys and buttons, it is used when the window
+    /// loses the focus in order to not keep keys pressed forever.
+    pub fn release_all(&mut self) {
+        self.keys_just_released.extend(self.keys_down.drain());
+        self.buttons_down.clear();
+    }
+}
+
+/// Defines the input claimed by the GUI, the claimed events are not sent to
+/// the game.
+///
+/// It must be written every frame by the GUI, for example when a text field
+/// has the focus the keyboard is claimed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GuiInputCapture {
+    /// The GUI wants the keyboard input.
+    pub keyboard: bool,
+
+    /// The GUI wants the mouse input.
+    pub pointer: bool,
+}
+
+impl GuiInputCapture {
+    /// Returns true if the event is claimed by the GUI.
+    ///
+    /// The released keys are never claimed, in this way the keys pressed
+    /// before the GUI got the input are not kept pressed forever.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The event to check.
+    pub fn captures(&self, event: &InputEvent) -> bool {
+        match event {
+            InputEvent::KeyDown(_) => self.keyboard,
+            InputEvent::Motion(..) |
+            InputEvent::MouseDown(_) => self.pointer,
+            InputEvent::KeyUp(_) |
+            InputEvent::MouseUp(_) |
+            InputEvent::FocusGained |
+            InputEvent::FocusLost => false
+        }
+    }
+}
+
+/// Contains the position of the mouse inside the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MousePosition {
+    /// The position in logical pixels, it is used by the UI layout.
+    pub logical: (f64, f64),
+
+    /// The position in physical pixels, it matches with the pixels of the
+    /// render targets.
+    pub physical: (f64, f64),
+}
+
+impl MousePosition {
+    /// Creates and returns a new `MousePosition` from the position in
+    /// physical pixels.
+    ///
+    /// # Arguments
+    ///
+    /// `position` - The position in physical pixels.
+    /// `scale_factor` - The number of physical pixels per logical pixel.
+    pub fn from_physical(position: PhysicalPosition<f64>, scale_factor: f64) -> Self {
+        let logical: LogicalPosition<f64> = position.to_logical(scale_factor);
+        Self {
+            logical: (logical.x, logical.y),
+            physical: (position.x, position.y)
+        }
+    }
+}
+
+/// Updates the mouse position resource.
+///
+/// # Arguments
+///
+/// `position` - The cursor position in physical pixels.
+/// `scale_factor` - The scale factor of the window.
+pub fn update_mouse_position_system(
+    position: PhysicalPosition<f64>,
+    scale_factor: f64,
+    mouse: UniqueWrite<MousePosition>) {
+    mouse.set(MousePosition::from_physical(position, scale_factor));
+}
+
+/// Updates the input resource with the provided event.
+pub fn update_input_system(
+    event: InputEvent,
+    input: UniqueWrite<Input>) {
+    input.write().process_event(&event);
+}
+
+/// Stores the state of the input for the frame which starts, this must only
+/// be called when the frame starts.
+pub fn begin_input_frame_system(input: UniqueWrite<Input>) {
+    input.write().begin_frame();
+}
+
+/// Clears the per frame input state, this must only be called when the
+/// frame ends.
+pub fn finalize_input_system(input: UniqueWrite<Input>) {
+    input.write().end_frame();
+}
+
+/// Serializes and deserializes `f64` values using the raw bits, this avoids
+/// losing precision in the text representation.
+mod f64_bits {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S)
+        -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(value.to_bits())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D)
+        -> Result<f64, D::Error> {
+        Ok(f64::from_bits(u64::deserialize(deserializer)?))
+    }
+}
+
+#[test]
+fn mouse_position_scale() {
+    let mouse = MousePosition::from_physical(PhysicalPosition::new(200.0, 100.0), 2.0);
+    assert_eq!(mouse.physical, (200.0, 100.0));
+    assert_eq!(mouse.logical, (100.0, 50.0));
+}
+
+#[test]
+fn focus_lost_releases_keys() {
+    let mut input = Input::default();
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::W));
+    input.process_event(&InputEvent::FocusGained);
+    assert!(input.is_key_down(VirtualKeyCode::W));
+
+    let event = InputEvent::from_window_event(&WindowEvent::Focused(false)).unwrap();
+    input.process_event(&event);
+    assert!(!input.is_key_down(VirtualKeyCode::W));
+}
+
+#[test]
+fn gui_capture_keeps_key_releases() {
+    let capture = GuiInputCapture { keyboard: true, pointer: false };
+    assert!(capture.captures(&InputEvent::KeyDown(VirtualKeyCode::W)));
+    assert!(!capture.captures(&InputEvent::KeyUp(VirtualKeyCode::W)));
+    assert!(!capture.captures(&InputEvent::Motion(1.0, 1.0)));
+    assert!(!capture.captures(&InputEvent::FocusLost));
+}
+
+#[test]
+fn mouse_click_lasts_one_frame() {
+    let mut input = Input::default();
+    input.process_event(&InputEvent::MouseDown(MouseButton::Left));
+    input.process_event(&InputEvent::MouseUp(MouseButton::Left));
+    assert!(input.was_clicked(MouseButton::Left));
+    assert!(input.buttons_down.is_empty());
+
+    input.buttons_clicked.clear();
+    assert!(!input.was_clicked(MouseButton::Left));
+}
+
+#[test]
+fn key_edges_across_frames() {
+    let mut input = Input::default();
+    let set = |keys: &[VirtualKeyCode]| keys.iter().copied().collect::<HashSet<_>>();
+
+    // Frame 1, the repeat does not press the key again.
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::W));
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::W));
+    assert_eq!(input.keys_down, set(&[VirtualKeyCode::W]));
+    assert_eq!(input.keys_just_pressed, set(&[VirtualKeyCode::W]));
+    assert!(input.keys_just_released.is_empty());
+    input.end_frame();
+
+    // Frame 2, W is held and A is tapped.
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::W));
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::A));
+    input.process_event(&InputEvent::KeyUp(VirtualKeyCode::A));
+    assert_eq!(input.keys_down, set(&[VirtualKeyCode::W]));
+    assert_eq!(input.keys_just_pressed, set(&[VirtualKeyCode::A]));
+    assert_eq!(input.keys_just_released, set(&[VirtualKeyCode::A]));
+    input.end_frame();
+
+    // Frame 3, W is released.
+    input.process_event(&InputEvent::KeyUp(VirtualKeyCode::W));
+    assert!(input.keys_down.is_empty());
+    assert!(input.keys_just_pressed.is_empty());
+    assert!(input.is_key_just_released(VirtualKeyCode::W));
+    assert_eq!(input.keys_just_released, set(&[VirtualKeyCode::W]));
+}
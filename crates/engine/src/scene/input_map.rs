@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use crate::scene::input::Input;
+
+/// Represents a key or a mouse button bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton)
+}
+
+impl Binding {
+    /// Returns true if the binding is down during the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// `input` - The state of the input.
+    fn is_down(&self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.frame_keys.contains(key),
+            Binding::Mouse(button) => input.frame_buttons.contains(button)
+        }
+    }
+
+    /// Returns true if the binding was down during the previous frame.
+    ///
+    /// # Arguments
+    ///
+    /// `input` - The state of the input.
+    fn was_down(&self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.previous_frame_keys.contains(key),
+            Binding::Mouse(button) => input.previous_frame_buttons.contains(button)
+        }
+    }
+}
+
+/// Represents an axis built from two bindings, the negative one moves the
+/// axis to -1 and the positive one to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub negative: Binding,
+    pub positive: Binding
+}
+
+/// Binds the keys and buttons to named actions, in this way the game code
+/// does not check the keys directly and the controls can be rebound.
+///
+/// The queries use the state of the input when the frame started, so all
+/// the systems of a frame see the same values.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    /// The bindings of each action.
+    actions: HashMap<String, Vec<Binding>>,
+
+    /// The bindings of each axis.
+    axes: HashMap<String, Vec<AxisBinding>>
+}
+
+impl InputMap {
+    /// Adds a binding to the action, an action can contain several bindings
+    /// and it is pressed when any of them is down.
+    ///
+    /// # Arguments
+    ///
+    /// `action` - The name of the action.
+    /// `binding` - The key or button to be bound.
+    pub fn bind_action(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Replaces all the bindings of the action with the provided one.
+    ///
+    /// # Arguments
+    ///
+    /// `action` - The name of the action.
+    /// `binding` - The new key or button of the action.
+    pub fn rebind_action(&mut self, action: &str, binding: Binding) {
+        self.actions.insert(action.to_string(), vec![binding]);
+    }
+
+    /// Adds an axis built from two keys.
+    ///
+    /// # Arguments
+    ///
+    /// `axis` - The name of the axis.
+    /// `negative` - The key which moves the axis to -1.
+    /// `positive` - The key which moves the axis to 1.
+    pub fn bind_axis(&mut self, axis: &str, negative: VirtualKeyCode, positive: VirtualKeyCode) {
+        self.bind_axis_bindings(axis, AxisBinding {
+            negative: Binding::Key(negative),
+            positive: Binding::Key(positive)
+        });
+    }
+
+    /// Adds an axis built from any pair of bindings.
+    ///
+    /// # Arguments
+    ///
+    /// `axis` - The name of the axis.
+    /// `binding` - The negative and positive bindings.
+    pub fn bind_axis_bindings(&mut self, axis: &str, binding: AxisBinding) {
+        let bindings = self.axes.entry(axis.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes all the bindings of the action or axis.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the action or axis.
+    pub fn unbind(&mut self, name: &str) {
+        self.actions.remove(name);
+        self.axes.remove(name);
+    }
+
+    /// Returns the bindings of the action.
+    ///
+    /// # Arguments
+    ///
+    /// `action` - The name of the action.
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], |b| b.as_slice())
+    }
+
+    /// Returns true if any binding of the action is down.
+    ///
+    /// # Arguments
+    ///
+    /// `input` - The state of the input.
+    /// `action` - The name of the action.
+    pub fn action_pressed(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action).iter().any(|b| b.is_down(input))
+    }
+
+    /// Returns true if the action is pressed in this frame and it was not
+    /// pressed in the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// `input` - The state of the input.
+    /// `action` - The name of the action.
+    pub fn action_just_pressed(&self, input: &Input, action: &str) -> bool {
+        let bindings = self.bindings(action);
+        bindings.iter().any(|b| b.is_down(input)) &&
+        !bindings.iter().any(|b| b.was_down(input))
+    }
+
+    /// Returns true if the action was pressed in the previous frame and it
+    /// is not pressed in this one.
+    ///
+    /// # Arguments
+    ///
+    /// `input` - The state of the input.
+    /// `action` - The name of the action.
+    pub fn action_just_released(&self, input: &Input, action: &str) -> bool {
+        let bindings = self.bindings(action);
+        bindings.iter().any(|b| b.was_down(input)) &&
+        !bindings.iter().any(|b| b.is_down(input))
+    }
+
+    /// Returns the value of the axis between -1 and 1, if both sides are
+    /// pressed they cancel each other.
+    ///
+    /// # Arguments
+    ///
+    /// `input` - The state of the input.
+    /// `axis` - The name of the axis.
+    pub fn axis_value(&self, input: &Input, axis: &str) -> f32 {
+        let bindings = match self.axes.get(axis) {
+            Some(b) => b,
+            None => return 0.0
+        };
+
+        let side = |binding: &Binding| if binding.is_down(input) { 1.0 } else { 0.0 };
+        let value: f32 = bindings
+            .iter()
+            .map(|b| side(&b.positive) - side(&b.negative))
+            .sum();
+        value.clamp(-1.0, 1.0)
+    }
+
+    /// Returns the map as JSON, it is used to store the controls chosen by
+    /// the player.
+    pub fn to_json(&self) -> String {
+        // The map only contains strings and enums, it can not fail.
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Creates and returns a map from its JSON representation.
+    ///
+    /// # Arguments
+    ///
+    /// `json` - The content returned by `to_json`.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+use crate::scene::input::InputEvent;
+
+#[test]
+fn actions_detect_edges_across_frames() {
+    let mut map = InputMap::default();
+    map.bind_action("jump", Binding::Key(VirtualKeyCode::Space));
+    map.bind_action("jump", Binding::Mouse(MouseButton::Right));
+
+    let mut input = Input::default();
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::Space));
+    input.begin_frame();
+    assert!(map.action_pressed(&input, "jump"));
+    assert!(map.action_just_pressed(&input, "jump"));
+    input.end_frame();
+
+    // Holding a second binding does not press the action again.
+    input.process_event(&InputEvent::MouseDown(MouseButton::Right));
+    input.begin_frame();
+    assert!(map.action_pressed(&input, "jump"));
+    assert!(!map.action_just_pressed(&input, "jump"));
+    input.end_frame();
+
+    input.process_event(&InputEvent::KeyUp(VirtualKeyCode::Space));
+    input.process_event(&InputEvent::MouseUp(MouseButton::Right));
+    input.begin_frame();
+    assert!(!map.action_pressed(&input, "jump"));
+    assert!(map.action_just_released(&input, "jump"));
+    input.end_frame();
+
+    input.begin_frame();
+    assert!(!map.action_just_released(&input, "jump"));
+}
+
+#[test]
+fn tapped_key_is_pressed_for_one_frame() {
+    let mut map = InputMap::default();
+    map.bind_action("fire", Binding::Key(VirtualKeyCode::F));
+
+    let mut input = Input::default();
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::F));
+    input.process_event(&InputEvent::KeyUp(VirtualKeyCode::F));
+    input.begin_frame();
+    assert!(map.action_just_pressed(&input, "fire"));
+    input.end_frame();
+
+    input.begin_frame();
+    assert!(map.action_just_released(&input, "fire"));
+}
+
+#[test]
+fn axis_and_rebinding_round_trip() {
+    let mut map = InputMap::default();
+    map.bind_axis("move_x", VirtualKeyCode::A, VirtualKeyCode::D);
+    map.bind_action("move_forward", Binding::Key(VirtualKeyCode::W));
+    map.rebind_action("move_forward", Binding::Key(VirtualKeyCode::Up));
+
+    let mut input = Input::default();
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::A));
+    input.process_event(&InputEvent::KeyDown(VirtualKeyCode::W));
+    input.begin_frame();
+    assert_eq!(map.axis_value(&input, "move_x"), -1.0);
+    assert_eq!(map.axis_value(&input, "move_y"), 0.0);
+    assert!(!map.action_pressed(&input, "move_forward"));
+
+    let loaded = InputMap::from_json(&map.to_json()).unwrap();
+    assert_eq!(loaded, map);
+    assert_eq!(loaded.bindings("move_forward"), &[Binding::Key(VirtualKeyCode::Up)]);
+}
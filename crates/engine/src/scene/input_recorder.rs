@@ -0,0 +1,181 @@
+use std::{
+    fs,
+    path::Path
+};
+
+use serde::{Serialize, Deserialize};
+
+use ecs::UniqueWrite;
+
+use crate::scene::input::{Input, InputEvent};
+
+/// Defines the default path used to store the recordings.
+pub const DEFAULT_RECORDING_PATH: &str = "input_recording.json";
+
+/// Records all the input events along with the frame where they happened.
+#[derive(Default, Serialize, Deserialize)]
+pub struct InputRecorder {
+    /// Contains the current frame.
+    frame: u64,
+
+    /// Contains all the recorded events.
+    events: Vec<(u64, InputEvent)>,
+}
+
+impl InputRecorder {
+    /// Records the event in the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// `event` - The event to be recorded.
+    pub fn record(&mut self, event: InputEvent) {
+        self.events.push((self.frame, event));
+    }
+
+    /// Moves the recorder to the next frame.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Returns all the recorded events.
+    pub fn events(&self) -> &Vec<(u64, InputEvent)> {
+        &self.events
+    }
+
+    /// Serializes the recording and returns it as a string.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    /// Creates and returns a new `InputRecorder` from its serialized form.
+    ///
+    /// # Arguments
+    ///
+    /// `data` - The serialized recording.
+    pub fn from_json(data: &str) -> Result<Self, String> {
+        serde_json::from_str(data).map_err(|e| e.to_string())
+    }
+
+    /// Saves the recording into the provided path.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - Where the recording will be stored.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json()?).map_err(|e| e.to_string())
+    }
+
+    /// Loads and returns a recording from the provided path.
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the recording.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&data)
+    }
+}
+
+/// Plays a recording frame by frame.
+pub struct InputReplay {
+    /// Contains the recording to be played.
+    recording: InputRecorder,
+
+    /// Contains the position of the next event to be played.
+    cursor: usize,
+
+    /// Contains the current frame.
+    frame: u64,
+}
+
+impl InputReplay {
+    /// Creates and returns a new `InputReplay`.
+    ///
+    /// # Arguments
+    ///
+    /// `recording` - The recording to be played.
+    pub fn new(recording: InputRecorder) -> Self {
+        Self {
+            recording,
+            cursor: 0,
+            frame: 0
+        }
+    }
+
+    /// Returns all the events for the current frame and moves the replay
+    /// to the next frame.
+    pub fn next_frame_events(&mut self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+
+        while let Some((frame, event)) = self.recording.events.get(self.cursor) {
+            // The events are stored in order so as soon as we find one
+            // from the future we can stop.
+            if *frame > self.frame {
+                break;
+            }
+            events.push(*event);
+            self.cursor += 1;
+        }
+
+        self.frame += 1;
+        events
+    }
+
+    /// Returns true if all the events were played.
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.recording.events.len()
+    }
+}
+
+/// Injects the events of the current frame from the replay into the input,
+/// this replaces the events coming from winit.
+pub fn replay_input_system(
+    replay: UniqueWrite<InputReplay>,
+    input: UniqueWrite<Input>) {
+    let events = replay.write().next_frame_events();
+
+    let mut input_w = input.write();
+    for event in events.iter() {
+        input_w.process_event(event);
+    }
+}
+
+#[test]
+fn recording_round_trip() {
+    let mut recorder = InputRecorder::default();
+    recorder.record(InputEvent::Motion(0.1 + 0.2, -1.0 / 3.0));
+    recorder.next_frame();
+    recorder.record(InputEvent::Motion(f64::MIN_POSITIVE, 1e300));
+
+    let data = recorder.to_json().unwrap();
+    let loaded = InputRecorder::from_json(&data).unwrap();
+
+    // The values must be exactly the same, bit by bit.
+    for ((f_a, e_a), (f_b, e_b)) in recorder.events().iter()
+        .zip(loaded.events().iter()) {
+        assert_eq!(f_a, f_b);
+        match (e_a, e_b) {
+            (InputEvent::Motion(x_a, y_a), InputEvent::Motion(x_b, y_b)) => {
+                assert_eq!(x_a.to_bits(), x_b.to_bits());
+                assert_eq!(y_a.to_bits(), y_b.to_bits());
+            }
+            _ => panic!("Unexpected event")
+        }
+    }
+}
+
+#[test]
+fn replay_by_frame() {
+    let mut recorder = InputRecorder::default();
+    recorder.record(InputEvent::Motion(1.0, 0.0));
+    recorder.record(InputEvent::Motion(2.0, 0.0));
+    recorder.next_frame();
+    recorder.next_frame();
+    recorder.record(InputEvent::Motion(3.0, 0.0));
+
+    let mut replay = InputReplay::new(recorder);
+    assert_eq!(replay.next_frame_events().len(), 2);
+    assert_eq!(replay.next_frame_events().len(), 0);
+    assert_eq!(replay.next_frame_events().len(), 1);
+    assert!(replay.is_finished());
+}
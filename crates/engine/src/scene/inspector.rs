@@ -0,0 +1,93 @@
+use winit::event::MouseButton;
+
+use ecs::{DefaultWorld, ComponentHandler, EntityHandler, Entity, UniqueRead, UniqueWrite};
+
+use crate::{
+    basics::dev_gui::{DevGui, GuiAnchor, TEXT_COLOR},
+    scene::{
+        input::Input,
+        picking::PickResult
+    }
+};
+
+/// The name of the inspector panel in the dev GUI.
+pub const INSPECTOR_PANEL: &str = "Inspector";
+
+/// Contains the entity chosen by clicking it in the 3D view, it is None if
+/// the click did not hit any voxel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SelectedEntity(pub Option<Entity>);
+
+/// Contains the information displayed by the entity inspector.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityInspector {
+    /// The inspected entity.
+    pub entity: Option<Entity>,
+
+    /// The names of the components of the entity, sorted alphabetically.
+    pub components: Vec<&'static str>
+}
+
+/// Stores the voxel under the cursor in `SelectedEntity` when the left
+/// button is clicked, the picking uses the ray from the camera through the
+/// cursor.
+pub fn select_entity_system(
+    input: UniqueRead<Input>,
+    pick_result: UniqueRead<PickResult>,
+    selected: UniqueWrite<SelectedEntity>) {
+    if !input.read().was_clicked(MouseButton::Left) {
+        return;
+    }
+
+    selected.write().0 = pick_result.read().0.map(|(entity, _, _)| entity);
+}
+
+/// Updates the components listed by the inspector with the ones of the
+/// selected entity.
+///
+/// The components are found using the entity bitmask, so it needs the world
+/// instead of the accessors of a system.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the selected entity.
+pub fn entity_inspector_system(world: &DefaultWorld) {
+    let selected = world.get_unique::<SelectedEntity>().read().0;
+    let inspector = world.get_unique_mut::<EntityInspector>();
+    let mut inspector_write = inspector.write();
+
+    // A removed entity is not inspected anymore.
+    inspector_write.entity = selected.filter(|e| world.is_alive(*e));
+    inspector_write.components = match inspector_write.entity {
+        Some(entity) => world.component_names(entity),
+        None => Vec::new()
+    };
+}
+
+/// Draws the inspected entity and the names of its components, the window
+/// is only drawn when the inspector panel is visible.
+pub fn entity_inspector_panel_system(
+    dev_gui: UniqueWrite<DevGui>,
+    inspector: UniqueRead<EntityInspector>) {
+    let mut dev_gui_write = dev_gui.write();
+    if !dev_gui_write.is_visible(INSPECTOR_PANEL) {
+        return;
+    }
+
+    let inspector_read = inspector.read();
+    let mut window = dev_gui_write.window(INSPECTOR_PANEL, GuiAnchor::Right);
+    match inspector_read.entity {
+        Some(entity) => {
+            window.text(
+                &format!("Entity {} (generation {})", entity.id(), entity.generation()),
+                TEXT_COLOR
+            );
+            for name in &inspector_read.components {
+                window.text(&format!("- {}", name), TEXT_COLOR);
+            }
+        },
+        None => window.text("Click a voxel to inspect it", TEXT_COLOR)
+    }
+
+    dev_gui_write.show(window);
+}
@@ -0,0 +1,200 @@
+use cgmath::{Vector3, InnerSpace, Deg, Rad, Rotation};
+
+use ecs::{Component, UniqueRead, Read, Searchable};
+use log::warning;
+use types::Color;
+
+use crate::{
+    graphics::{
+        gpu::Gpu,
+        buffer::BufferManipulator,
+        pipelines::bind_groups::locals_bind_group::{
+            PointLights,
+            PointLightsBuffer,
+            MAX_POINT_LIGHTS
+        }
+    },
+    scene::components::LocalTransform
+};
+
+/// Represents the light of the scene, a single directional light (like the
+/// sun) plus an ambient light which avoids completely dark faces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// The direction where the light travels, it does not need to be
+    /// normalized.
+    pub direction: Vector3<f32>,
+
+    /// The RGB color of the light.
+    pub color: Vector3<f32>,
+
+    /// The RGB color applied to all the faces no matter the direction.
+    pub ambient: Vector3<f32>
+}
+
+impl Default for DirectionalLight {
+    /// Creates and returns a new white light coming from above.
+    fn default() -> Self {
+        Self {
+            direction: Vector3 { x: -0.3, y: -1.0, z: -0.5 },
+            color: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+            ambient: Vector3 { x: 0.2, y: 0.2, z: 0.2 }
+        }
+    }
+}
+
+impl DirectionalLight {
+    /// Returns the normalized direction of the light, if the direction is
+    /// zero the light points down.
+    pub fn normalized_direction(&self) -> Vector3<f32> {
+        if self.direction.magnitude2() == 0.0 {
+            return -Vector3::unit_y();
+        }
+
+        self.direction.normalize()
+    }
+}
+
+/// Represents a light which emits in all the directions from a point, its
+/// contribution fades to zero at the radius.
+#[derive(Clone, Copy, Debug, PartialEq, Component)]
+pub struct PointLight {
+    /// The position of the light relative to the entity transform.
+    pub position: Vector3<f32>,
+
+    /// The RGB color of the light.
+    pub color: Vector3<f32>,
+
+    /// Scales the color of the light.
+    pub intensity: f32,
+
+    /// The distance where the light stops affecting the voxels.
+    pub radius: f32
+}
+
+impl Default for PointLight {
+    /// Creates and returns a new white `PointLight` placed at the entity.
+    fn default() -> Self {
+        Self {
+            position: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+            color: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
+            intensity: 1.0,
+            radius: 10.0
+        }
+    }
+}
+
+/// Collects all the point lights of the scene and writes them into the
+/// point lights buffer.
+///
+/// Only the first `MAX_POINT_LIGHTS` lights are used.
+pub fn collect_point_lights_system(
+    gpu: UniqueRead<Gpu>,
+    buffer: UniqueRead<PointLightsBuffer>,
+    point_lights: Read<PointLight>,
+    transforms: Read<LocalTransform>) {
+    let mut packed = PointLights::default();
+    let mut ignored: usize = 0;
+
+    for (light, transform) in (point_lights.iter(), transforms.iter()).query() {
+        let light_read = light.read();
+        let transform_read = transform.read();
+        let position = transform_read.position
+            + transform_read.rotation.rotate_vector(light_read.position);
+
+        if !packed.push(position, &light_read) {
+            ignored += 1;
+        }
+    }
+
+    if ignored > 0 {
+        warning(&format!(
+            "{{PointLight}} Only {} lights are supported, {} ignored",
+            MAX_POINT_LIGHTS,
+            ignored
+        ));
+    }
+
+    gpu.read().copy_to_buffer(&buffer.read().0, bytemuck::bytes_of(&packed));
+}
+
+/// Represents the position of the sun in the sky, it is used by the sky
+/// renderer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SunPosition {
+    /// The angle between the horizon and the sun.
+    pub elevation: Deg<f32>,
+
+    /// The angle around the vertical axis, 0 points to +z and 90 to +x.
+    pub azimuth: Deg<f32>
+}
+
+impl Default for SunPosition {
+    /// Creates and returns a new `SunPosition` for a morning sun.
+    fn default() -> Self {
+        Self {
+            elevation: Deg(35.0),
+            azimuth: Deg(135.0)
+        }
+    }
+}
+
+impl SunPosition {
+    /// Returns the normalized direction from the ground to the sun.
+    pub fn direction(&self) -> Vector3<f32> {
+        let elevation: Rad<f32> = self.elevation.into();
+        let azimuth: Rad<f32> = self.azimuth.into();
+
+        Vector3 {
+            x: elevation.0.cos() * azimuth.0.sin(),
+            y: elevation.0.sin(),
+            z: elevation.0.cos() * azimuth.0.cos()
+        }
+    }
+}
+
+/// Contains the atmosphere parameters used by the sky renderer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SkySettings {
+    /// The amount of haze in the atmosphere, 2 is a very clear sky and 10
+    /// a hazy one.
+    pub turbidity: f32,
+
+    /// Scales the brightness of the sky and the sun.
+    pub sun_intensity: f32,
+
+    /// The color of the sun disc in sRGB space.
+    pub sun_color: Color<f32>
+}
+
+impl Default for SkySettings {
+    /// Creates and returns new settings for a clear day.
+    fn default() -> Self {
+        Self {
+            turbidity: 2.5,
+            sun_intensity: 1.0,
+            sun_color: Color::from_hex(0xFFF9ED)
+        }
+    }
+}
+
+#[test]
+fn normalized_direction() {
+    let mut light = DirectionalLight {
+        direction: Vector3 { x: 0.0, y: -10.0, z: 0.0 },
+        ..DirectionalLight::default()
+    };
+    assert_eq!(light.normalized_direction(), Vector3 { x: 0.0, y: -1.0, z: 0.0 });
+
+    light.direction = Vector3 { x: 0.0, y: 0.0, z: 0.0 };
+    assert_eq!(light.normalized_direction(), Vector3 { x: 0.0, y: -1.0, z: 0.0 });
+}
+
+#[test]
+fn sun_direction() {
+    let noon = SunPosition { elevation: Deg(90.0), azimuth: Deg(0.0) };
+    assert!((noon.direction() - Vector3::unit_y()).magnitude() < 1e-6);
+
+    let sunset = SunPosition { elevation: Deg(0.0), azimuth: Deg(90.0) };
+    assert!((sunset.direction() - Vector3::unit_x()).magnitude() < 1e-6);
+}
@@ -0,0 +1,129 @@
+use cgmath::{MetricSpace, Point3};
+
+use ecs::{UniqueRead, UniqueWrite, Read, Write, Searchable, Component};
+
+use crate::scene::{
+    camera::Camera,
+    components::LocalTransform,
+    hierarchy::WorldTransforms
+};
+
+/// The number of detail levels of the voxels.
+pub const LOD_LEVELS: usize = 3;
+
+/// Contains the detail used to draw the voxel, 0 is the full cube, 1 the
+/// simplified cube and 2 a quad which faces the camera.
+///
+/// The voxels without this component are always drawn using the full cube.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Component)]
+pub struct LodLevel(pub u8);
+
+/// Defines the distances to the camera where the voxels change their
+/// detail.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LodDistances {
+    /// The voxels closer than this distance use the level 0.
+    pub near: f32,
+
+    /// The voxels further than this distance use the level 2, the rest use
+    /// the level 1.
+    pub far: f32
+}
+
+impl Default for LodDistances {
+    fn default() -> Self {
+        Self {
+            near: 50.0,
+            far: 150.0
+        }
+    }
+}
+
+impl LodDistances {
+    /// Returns the level of detail for the provided distance to the camera.
+    ///
+    /// # Arguments
+    ///
+    /// `distance` - The distance between the voxel and the camera.
+    pub fn level(&self, distance: f32) -> LodLevel {
+        if distance < self.near {
+            LodLevel(0)
+        } else if distance < self.far {
+            LodLevel(1)
+        } else {
+            LodLevel(2)
+        }
+    }
+}
+
+/// Contains the level of detail of each entity id, it is written by
+/// `lod_update_system` and read by the voxel renderer, which can not
+/// require `LodLevel` without skipping the voxels that do not have it.
+#[derive(Debug, Default)]
+pub struct VoxelLods {
+    /// The level of each entity id, the entities without `LodLevel` use 0.
+    levels: Vec<u8>
+}
+
+impl VoxelLods {
+    /// Returns the level of detail of the entity.
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id of the entity.
+    pub fn level(&self, id: usize) -> usize {
+        self.levels.get(id).map_or(0, |l| (*l as usize).min(LOD_LEVELS - 1))
+    }
+}
+
+/// Updates the `LodLevel` of the voxels using their distance to the camera.
+pub fn lod_update_system(
+    camera: UniqueRead<Camera>,
+    distances: UniqueRead<LodDistances>,
+    world_transforms: UniqueRead<WorldTransforms>,
+    voxel_lods: UniqueWrite<VoxelLods>,
+    transforms: Read<LocalTransform>,
+    lods: Write<LodLevel>) {
+    let eye: Point3<f32> = camera.read().eye;
+    let distances_read = distances.read();
+    let world_transforms_read = world_transforms.read();
+
+    // The buffer is reused every frame, it only grows.
+    let mut voxel_lods_write = voxel_lods.write();
+    voxel_lods_write.levels.iter_mut().for_each(|l| *l = 0);
+
+    lods.entities().iter()
+        .zip((transforms.iter(), lods.iter()).query())
+        .for_each(|(entity, (transform, lod))| {
+            // The world position is used by the entities inside a hierarchy.
+            let position = match world_transforms_read.matrices.get(entity) {
+                Some(matrix) => Point3::new(matrix.w.x, matrix.w.y, matrix.w.z),
+                None => {
+                    let position = transform.read().position;
+                    Point3::new(position.x, position.y, position.z)
+                }
+            };
+
+            let level = distances_read.level(eye.distance(position));
+            **lod.write() = level;
+
+            if voxel_lods_write.levels.len() <= entity.id() {
+                voxel_lods_write.levels.resize(entity.id() + 1, 0);
+            }
+            voxel_lods_write.levels[entity.id()] = level.0;
+        });
+}
+
+#[test]
+fn lod_level_by_distance() {
+    let distances = LodDistances { near: 10.0, far: 20.0 };
+    assert_eq!(distances.level(0.0), LodLevel(0));
+    assert_eq!(distances.level(10.0), LodLevel(1));
+    assert_eq!(distances.level(19.9), LodLevel(1));
+    assert_eq!(distances.level(20.0), LodLevel(2));
+
+    let lods = VoxelLods { levels: vec![0, 1, 7] };
+    assert_eq!(lods.level(1), 1);
+    assert_eq!(lods.level(2), LOD_LEVELS - 1);
+    assert_eq!(lods.level(100), 0);
+}
@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use cgmath::Vector4;
+
+use ecs::{DefaultWorld, Component, ComponentHandler};
+
+pub use crate::graphics::mesh::{Mesh, MeshError};
+
+use crate::{
+    graphics::{
+        gpu::Gpu,
+        mesh::MeshLoader,
+        pipelines::mesh_pipeline::MeshRegistry,
+        texture::Texture
+    },
+    scene::assets::Handle
+};
+
+/// Represents an entity drawn using a registered mesh, the entity must also
+/// contain a `LocalTransform`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Component)]
+pub struct MeshHandle(pub usize);
+
+/// Represents the surface of a mesh entity.
+#[derive(Clone, Debug, Component)]
+pub struct Material {
+    /// The color multiplied by the texture, in linear space.
+    pub base_color: Vector4<f32>,
+
+    /// The texture of the surface, loaded by the `AssetServer`.
+    pub texture_handle: Option<Handle<Texture>>
+}
+
+impl Default for Material {
+    /// Creates and returns a new white `Material` without texture.
+    fn default() -> Self {
+        Self {
+            base_color: Vector4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 },
+            texture_handle: None
+        }
+    }
+}
+
+/// Uploads the mesh to the GPU and returns the handle used to draw it.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the mesh registry.
+/// `mesh` - The mesh to be registered.
+pub fn register_mesh(world: &DefaultWorld, mesh: Mesh) -> MeshHandle {
+    let gpu = world.get_unique::<Gpu>();
+    let gpu_read = gpu.read();
+    let registry = world.get_unique_mut::<MeshRegistry>();
+    let mut registry_write = registry.write();
+    MeshHandle(registry_write.register(&gpu_read, mesh))
+}
+
+/// Loads the OBJ file and registers it, returns the handle used to draw it.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the mesh registry.
+/// `path` - The path of the OBJ file.
+pub fn load_mesh(world: &DefaultWorld, path: &Path) -> Result<MeshHandle, MeshError> {
+    let mesh = world.get_unique::<Gpu>().read().load_obj(path)?;
+    Ok(register_mesh(world, mesh))
+}
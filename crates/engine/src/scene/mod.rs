@@ -1,2 +1,22 @@
+pub mod assets;
 pub mod camera;
-pub mod components;
\ No newline at end of file
+pub mod components;
+pub mod console_log;
+pub mod file_drop;
+pub mod gltf;
+pub mod hierarchy;
+pub mod input;
+pub mod inspector;
+pub mod input_map;
+pub mod input_recorder;
+pub mod light;
+pub mod lod;
+pub mod mesh;
+pub mod picking;
+pub mod prefab;
+pub mod serde;
+pub mod snapshot;
+pub mod sprite;
+pub mod terrain;
+pub mod text_label;
+pub mod time;
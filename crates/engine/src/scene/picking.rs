@@ -0,0 +1,317 @@
+use cgmath::{
+    Matrix,
+    Matrix4,
+    Point3,
+    Vector3,
+    Vector4,
+    SquareMatrix,
+    InnerSpace,
+    EuclideanSpace
+};
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler,
+    Entity,
+    UniqueRead,
+    UniqueWrite,
+    Read,
+    Searchable
+};
+use types::Size;
+
+use crate::{
+    basics::window::{Window, CursorLocked},
+    scene::{
+        camera::Camera,
+        components::{Voxel, LocalTransform, Selected},
+        hierarchy::WorldTransforms,
+        input::MousePosition
+    }
+};
+
+/// Defines the half size of the voxel cube in local space.
+const VOXEL_HALF_EXTENT: f32 = 1.0;
+
+/// Represents a half line in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    /// The point where the ray starts.
+    pub origin: Point3<f32>,
+
+    /// The normalized direction of the ray.
+    pub direction: Vector3<f32>
+}
+
+impl Ray {
+    /// Returns the point placed at `distance` from the origin.
+    ///
+    /// # Arguments
+    ///
+    /// `distance` - The distance from the origin.
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+}
+
+/// Contains the voxel under the cursor, the hit point in world space and the
+/// normal of the face which was hit.
+///
+/// It is updated by the `picking_system` at the start of each frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PickResult(pub Option<(Entity, Point3<f32>, Vector3<f32>)>);
+
+/// Returns the ray which starts at the camera near plane and goes through
+/// the provided screen position.
+///
+/// It returns None if the camera matrix can not be inverted.
+///
+/// # Arguments
+///
+/// `camera` - The camera used to render the screen.
+/// `position` - The position in physical pixels from the top left corner.
+/// `size` - The size of the screen in physical pixels.
+pub fn screen_ray(camera: &Camera, position: (f64, f64), size: Size<u32>) -> Option<Ray> {
+    let width = size.width.max(1) as f32;
+    let height = size.height.max(1) as f32;
+
+    // Convert the pixel into normalized device coordinates.
+    let x = 2.0 * position.0 as f32 / width - 1.0;
+    let y = 1.0 - 2.0 * position.1 as f32 / height;
+
+    let inverse = camera.view_projection().invert()?;
+    let near = unproject(&inverse, x, y, 0.0);
+    let far = unproject(&inverse, x, y, 1.0);
+
+    Some(Ray {
+        origin: near,
+        direction: (far - near).normalize()
+    })
+}
+
+/// Maps a point from the normalized device coordinates to world space.
+///
+/// # Arguments
+///
+/// `inverse` - The inverse of the view projection.
+/// `x` - The horizontal coordinate.
+/// `y` - The vertical coordinate.
+/// `z` - The depth, 0 is the near plane and 1 the far plane.
+fn unproject(inverse: &Matrix4<f32>, x: f32, y: f32, z: f32) -> Point3<f32> {
+    let point = *inverse * Vector4 { x, y, z, w: 1.0 };
+    Point3::from_vec(point.truncate() / point.w)
+}
+
+/// Returns the distance to the first face of the box hit by the ray and the
+/// normal of that face.
+///
+/// The boxes which contain the ray origin are ignored. The distance is
+/// measured in units of the ray direction, so it does not need to be
+/// normalized.
+///
+/// # Arguments
+///
+/// `origin` - The origin of the ray.
+/// `direction` - The direction of the ray.
+/// `min` - The minimum corner of the box.
+/// `max` - The maximum corner of the box.
+pub fn ray_aabb(origin: Point3<f32>,
+                direction: Vector3<f32>,
+                min: Point3<f32>,
+                max: Point3<f32>) -> Option<(f32, Vector3<f32>)> {
+    let mut t_near = f32::NEG_INFINITY;
+    let mut t_far = f32::INFINITY;
+    let mut normal = Vector3 { x: 0.0, y: 0.0, z: 0.0 };
+
+    for axis in 0..3 {
+        if direction[axis].abs() < f32::EPSILON {
+            // The ray is parallel to the slab, it must start inside it.
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let inverse = 1.0 / direction[axis];
+        let mut t1 = (min[axis] - origin[axis]) * inverse;
+        let mut t2 = (max[axis] - origin[axis]) * inverse;
+        if t1 > t2 {
+            std::mem::swap(&mut t1, &mut t2);
+        }
+
+        if t1 > t_near {
+            t_near = t1;
+            // The face faces the ray.
+            normal = Vector3 { x: 0.0, y: 0.0, z: 0.0 };
+            normal[axis] = -direction[axis].signum();
+        }
+        t_far = t_far.min(t2);
+
+        if t_near > t_far {
+            return None;
+        }
+    }
+
+    if t_near < 0.0 {
+        return None;
+    }
+
+    Some((t_near, normal))
+}
+
+/// Returns the nearest voxel hit by the ray, the hit point and the normal
+/// of the face.
+///
+/// The voxels are sorted by the distance to their bounding sphere, so the
+/// search stops as soon as the rest of the voxels are farther than the
+/// nearest hit.
+///
+/// # Arguments
+///
+/// `ray` - The ray in world space.
+/// `voxels` - The world transformation of each voxel.
+pub fn pick_nearest<I>(ray: &Ray, voxels: I) -> Option<(Entity, Point3<f32>, Vector3<f32>)>
+    where I: Iterator<Item = (Entity, Matrix4<f32>)> {
+    // Keep only the voxels whose bounding sphere is hit by the ray.
+    let mut candidates: Vec<(f32, Entity, Matrix4<f32>)> = voxels
+        .filter_map(|(entity, matrix)| {
+            let center = Point3::from_vec(matrix.w.truncate());
+            let scale = matrix.x.truncate().magnitude()
+                .max(matrix.y.truncate().magnitude())
+                .max(matrix.z.truncate().magnitude());
+            let radius = scale * VOXEL_HALF_EXTENT * 3.0_f32.sqrt();
+
+            let to_center = center - ray.origin;
+            let along = to_center.dot(ray.direction);
+            let distance2 = to_center.magnitude2() - along * along;
+            if distance2 > radius * radius || along + radius < 0.0 {
+                return None;
+            }
+
+            Some((along - radius, entity, matrix))
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let min = Point3 { x: -VOXEL_HALF_EXTENT, y: -VOXEL_HALF_EXTENT, z: -VOXEL_HALF_EXTENT };
+    let max = Point3 { x: VOXEL_HALF_EXTENT, y: VOXEL_HALF_EXTENT, z: VOXEL_HALF_EXTENT };
+    let mut nearest: Option<(f32, Entity, Vector3<f32>)> = None;
+
+    for (lower_bound, entity, matrix) in candidates {
+        if let Some((distance, _, _)) = nearest {
+            if lower_bound > distance {
+                break;
+            }
+        }
+
+        let inverse = match matrix.invert() {
+            Some(i) => i,
+            None => continue
+        };
+
+        // The intersection is done in the voxel space, the distance is the
+        // same in both spaces due the direction is not normalized.
+        let origin = Point3::from_vec((inverse * ray.origin.to_homogeneous()).truncate());
+        let direction = (inverse * ray.direction.extend(0.0)).truncate();
+
+        if let Some((distance, normal)) = ray_aabb(origin, direction, min, max) {
+            if nearest.is_none_or(|(d, _, _)| distance < d) {
+                let normal = (inverse.transpose() * normal.extend(0.0)).truncate().normalize();
+                nearest = Some((distance, entity, normal));
+            }
+        }
+    }
+
+    nearest.map(|(distance, entity, normal)| (entity, ray.at(distance), normal))
+}
+
+/// Finds the voxel under the cursor and stores it in the `PickResult`.
+///
+/// When the cursor is locked the center of the screen is used.
+#[allow(clippy::too_many_arguments)]
+pub fn picking_system(
+    camera: UniqueRead<Camera>,
+    window: UniqueRead<Window>,
+    cursor_locked: UniqueRead<CursorLocked>,
+    mouse: UniqueRead<MousePosition>,
+    world_transforms: UniqueRead<WorldTransforms>,
+    pick_result: UniqueWrite<PickResult>,
+    // Components
+    voxels: Read<Voxel>,
+    transforms: Read<LocalTransform>) {
+    let size = window.read().physical_size;
+    let position = match cursor_locked.read().0 {
+        true => (size.width as f64 / 2.0, size.height as f64 / 2.0),
+        false => mouse.read().physical
+    };
+
+    let ray = match screen_ray(&camera.read(), position, size) {
+        Some(r) => r,
+        None => {
+            pick_result.write().0 = None;
+            return;
+        }
+    };
+
+    let world_transforms_read = world_transforms.read();
+    let matrices = voxels.entities().iter()
+        .zip((voxels.iter(), transforms.iter()).query())
+        .map(|(entity, (_, transform))| {
+            let matrix = match world_transforms_read.matrices.get(entity) {
+                Some(m) => *m,
+                None => transform.read().as_matrix()
+            };
+            (*entity, matrix)
+        });
+
+    pick_result.write().0 = pick_nearest(&ray, matrices);
+}
+
+/// Marks the voxel under the cursor as `Selected` and unselects the rest,
+/// it returns the selected entity.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the voxels.
+pub fn select_under_cursor(world: &DefaultWorld) -> Option<Entity> {
+    let picked = world.get_unique::<PickResult>().read().0.map(|(entity, _, _)| entity);
+
+    let selected: Vec<Entity> = world
+        .get::<Read<Selected>>()
+        .entities()
+        .to_vec();
+
+    for entity in selected.into_iter().filter(|e| Some(*e) != picked) {
+        world.remove_component::<Selected>(entity);
+    }
+
+    if let Some(entity) = picked {
+        world.add_component(entity, Selected);
+    }
+
+    picked
+}
+
+#[test]
+fn nearest_voxel_is_picked() {
+    let ray = Ray {
+        origin: Point3 { x: 0.0, y: 0.0, z: 10.0 },
+        direction: Vector3 { x: 0.0, y: 0.0, z: -1.0 }
+    };
+
+    let near = Entity::new(0);
+    let far = Entity::new(1);
+    let aside = Entity::new(2);
+    let voxels = vec![
+        (far, Matrix4::from_translation(Vector3 { x: 0.0, y: 0.0, z: -5.0 })),
+        (near, Matrix4::from_scale(2.0)),
+        (aside, Matrix4::from_translation(Vector3 { x: 10.0, y: 0.0, z: 5.0 }))
+    ];
+
+    let (entity, point, normal) = pick_nearest(&ray, voxels.into_iter()).unwrap();
+    assert_eq!(entity, near);
+    assert!((point.z - 2.0).abs() < 1e-5);
+    assert!((normal - Vector3 { x: 0.0, y: 0.0, z: 1.0 }).magnitude() < 1e-5);
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    ComponentBundler,
+    EntityHandler,
+    Entity
+};
+use log::error;
+
+use crate::scene::hierarchy::Parent;
+
+/// Represents a reusable entity template.
+///
+/// The components are cloned every time the prefab is instantiated, the
+/// children are other prefabs resolved by name when the prefab is
+/// instantiated.
+pub struct Prefab {
+    /// Creates a new entity with a copy of the components.
+    spawn: Box<dyn Fn(&DefaultWorld) -> Entity + Send + Sync>,
+
+    /// The names of the prefabs instantiated as children.
+    children: Vec<String>
+}
+
+impl Prefab {
+    /// Creates and returns a new `Prefab` from a set of components.
+    ///
+    /// # Arguments
+    ///
+    /// `components` - The components of the template.
+    pub fn new<B>(components: B) -> Self
+        where B: ComponentBundler + Clone + Send + Sync + 'static {
+        Self {
+            spawn: Box::new(move |world| world.add_entity(components.clone())),
+            children: Vec::new()
+        }
+    }
+
+    /// Adds a child prefab, the child entity gets a `Parent` pointing to the
+    /// entity of this prefab.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the child prefab.
+    pub fn with_child(mut self, name: &str) -> Self {
+        self.children.push(name.to_string());
+        self
+    }
+}
+
+/// Contains all the prefabs registered by name.
+#[derive(Default)]
+pub struct PrefabRegistry {
+    prefabs: HashMap<String, Prefab>
+}
+
+impl PrefabRegistry {
+    /// Instantiates the prefab and all its children, it returns None if the
+    /// prefab does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// `world` - The world where the entities are created.
+    /// `name` - The name of the prefab.
+    /// `stack` - The prefabs being instantiated, used to detect cycles.
+    fn instantiate(&self,
+                   world: &DefaultWorld,
+                   name: &str,
+                   stack: &mut Vec<String>) -> Option<Entity> {
+        let prefab = match self.prefabs.get(name) {
+            Some(p) => p,
+            None => {
+                error(&format!("{{Prefab}} Unknown prefab {}", name));
+                return None;
+            }
+        };
+
+        if stack.iter().any(|n| n == name) {
+            error(&format!("{{Prefab}} Prefab {} contains itself", name));
+            return None;
+        }
+
+        let entity = (prefab.spawn)(world);
+
+        stack.push(name.to_string());
+        for child_name in prefab.children.iter() {
+            if let Some(child) = self.instantiate(world, child_name, stack) {
+                world.add_component(child, Parent(entity));
+            }
+        }
+        stack.pop();
+
+        Some(entity)
+    }
+}
+
+/// Registers and instantiates prefabs.
+pub trait PrefabHandler {
+    /// Registers a prefab which only contains the provided components, if
+    /// a prefab with the same name exists it is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the prefab.
+    /// `components` - The components of the template.
+    fn register_prefab<B>(&self, name: &str, components: B)
+        where B: ComponentBundler + Clone + Send + Sync + 'static;
+
+    /// Registers a prefab, if a prefab with the same name exists it is
+    /// replaced.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the prefab.
+    /// `prefab` - The template.
+    fn register_prefab_template(&self, name: &str, prefab: Prefab);
+
+    /// Creates the entities of the prefab and returns the root entity, it
+    /// returns None if the prefab does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name of the prefab.
+    fn instantiate_prefab(&self, name: &str) -> Option<Entity>;
+}
+
+impl PrefabHandler for DefaultWorld {
+    fn register_prefab<B>(&self, name: &str, components: B)
+        where B: ComponentBundler + Clone + Send + Sync + 'static {
+        self.register_prefab_template(name, Prefab::new(components));
+    }
+
+    fn register_prefab_template(&self, name: &str, prefab: Prefab) {
+        self.get_unique_mut::<PrefabRegistry>()
+            .write()
+            .prefabs
+            .insert(name.to_string(), prefab);
+    }
+
+    fn instantiate_prefab(&self, name: &str) -> Option<Entity> {
+        let registry = self.get_unique::<PrefabRegistry>();
+        let registry_read = registry.read();
+        registry_read.instantiate(self, name, &mut Vec::new())
+    }
+}
+
+#[test]
+fn nested_prefabs_are_parented() {
+    use crate::scene::components::{Voxel, LocalTransform};
+
+    let world = DefaultWorld::default();
+    world.register::<Voxel>();
+    world.register::<LocalTransform>();
+    world.register::<Parent>();
+    world.register_unique(PrefabRegistry::default());
+
+    world.register_prefab("leaf", (Voxel::color(1.0, 0.0, 0.0), LocalTransform::default()));
+    world.register_prefab_template(
+        "root",
+        Prefab::new((LocalTransform::default(),)).with_child("leaf").with_child("leaf")
+    );
+    world.register_prefab_template("loop", Prefab::new((Voxel::default(),)).with_child("loop"));
+
+    let root = world.instantiate_prefab("root").unwrap();
+    {
+        let parents = world.get::<ecs::Read<Parent>>();
+        assert_eq!(parents.iter().filter(|p| p.read().0 == root).count(), 2);
+    }
+
+    // The nested prefab is skipped instead of looping forever.
+    assert!(world.instantiate_prefab("loop").is_some());
+    assert!(world.instantiate_prefab("missing").is_none());
+}
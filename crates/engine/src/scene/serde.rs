@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path
+};
+
+use ::serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+use ecs::{DefaultWorld, Entity};
+
+use crate::scene::snapshot::{
+    Scene,
+    ResourcesSnapshot,
+    ResourceSnapshotHandler,
+    register_component_with_name
+};
+
+/// Represents the errors that can happen when a scene file is saved or
+/// loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneError {
+    /// The file could not be read or written.
+    Io(String),
+
+    /// The content of the file is not a valid scene.
+    Format(String)
+}
+
+impl Display for SceneError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(formatter, "Scene file error: {}", e),
+            SceneError::Format(e) => write!(formatter, "Invalid scene: {}", e)
+        }
+    }
+}
+
+/// Represents the content of a scene file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneFile {
+    /// The entities and their components.
+    pub scene: Scene,
+
+    /// The unique resources, like the main camera.
+    pub resources: ResourcesSnapshot
+}
+
+/// Allows the user components to be stored in the scene files.
+pub trait SerializableHandler {
+    /// Adds the component type to the scene files using the provided name,
+    /// the component must be registered in the world.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The name which identifies the component in the file, it
+    ///          must not change between versions.
+    fn register_serializable<
+        T: Serialize + DeserializeOwned + Send + Sync + 'static
+    >(&self, name: &'static str);
+}
+
+impl SerializableHandler for DefaultWorld {
+    fn register_serializable<
+        T: Serialize + DeserializeOwned + Send + Sync + 'static
+    >(&self, name: &'static str) {
+        register_component_with_name::<T>(self, name);
+    }
+}
+
+/// Saves the serializable components and resources of the world into a
+/// RON file.
+///
+/// # Arguments
+///
+/// `world` - The world to be saved.
+/// `path` - The path of the file, it is replaced if it exists.
+pub fn save_scene(world: &DefaultWorld, path: &Path) -> Result<(), SceneError> {
+    let file = SceneFile {
+        scene: Scene::snapshot(world),
+        resources: world.snapshot_resources()
+    };
+
+    let ron = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+        .map_err(|e| SceneError::Format(e.to_string()))?;
+    fs::write(path, ron).map_err(|e| SceneError::Io(e.to_string()))
+}
+
+/// Loads a scene file into the world and returns the new entity of each
+/// saved entity id.
+///
+/// The entities are always created, the saved ids are not reused. The
+/// unknown components are reported as warnings and skipped.
+///
+/// # Arguments
+///
+/// `world` - The world where the entities are created.
+/// `path` - The path of the file.
+pub fn load_scene(world: &DefaultWorld, path: &Path) -> Result<HashMap<usize, Entity>, SceneError> {
+    let ron = fs::read_to_string(path).map_err(|e| SceneError::Io(e.to_string()))?;
+    let file: SceneFile = ron::from_str(&ron)
+        .map_err(|e| SceneError::Format(e.to_string()))?;
+
+    world.restore_resources(file.resources);
+    Ok(Scene::restore(world, file.scene))
+}
+
+#[test]
+fn scene_file_round_trip() {
+    use cgmath::Vector3;
+    use ecs::{ComponentHandler, EntityHandler};
+
+    use crate::scene::{
+        camera::Camera,
+        components::{Voxel, LocalTransform},
+        snapshot::{SaveRegistry, register_saveable_resource}
+    };
+
+    let create_world = || {
+        let world = DefaultWorld::default();
+        world.register::<Voxel>();
+        world.register::<LocalTransform>();
+        world.register_unique(SaveRegistry::default());
+        world.register_unique(Camera::default());
+        world.register_serializable::<Voxel>("Voxel");
+        world.register_serializable::<LocalTransform>("LocalTransform");
+        register_saveable_resource::<Camera>(&world);
+        world
+    };
+
+    let world = create_world();
+    for i in 0..300 {
+        let position = Vector3 { x: i as f32, y: (i % 7) as f32, z: -(i as f32) };
+        world.add_entity((Voxel::rand_color(), LocalTransform::from_position(position)));
+    }
+    world
+        .get_unique_mut::<Camera>()
+        .write()
+        .look_at((1.0, 2.0, 3.0).into(), (4.0, 5.0, 6.0).into());
+
+    let path = std::env::temp_dir()
+        .join(format!("crystal_scene_round_trip_{}.ron", std::process::id()));
+    save_scene(&world, &path).unwrap();
+
+    let loaded = create_world();
+    let restored = load_scene(&loaded, &path).unwrap();
+    assert_eq!(restored.len(), 300);
+    assert_eq!(Scene::snapshot(&loaded), Scene::snapshot(&world));
+    assert_eq!(**loaded.get_unique::<Camera>().read(), **world.get_unique::<Camera>().read());
+
+    // Loading into the same world creates new entities.
+    let again = load_scene(&world, &path).unwrap();
+    assert!(again.iter().all(|(id, entity)| entity.id() != *id));
+    assert_eq!(Scene::snapshot(&world).entities.len(), 600);
+
+    let _ = fs::remove_file(path);
+}
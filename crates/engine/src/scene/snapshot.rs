@@ -0,0 +1,356 @@
+use std::{
+    any::type_name,
+    collections::{BTreeMap, HashMap}
+};
+
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use ecs::{
+    DefaultWorld,
+    ComponentHandler,
+    EntityHandler,
+    Entity,
+    Read
+};
+use log::{error, warning};
+
+/// Marks a component or resource which can be stored in a `Scene`.
+///
+/// The type must also be added to the `SaveRegistry` using
+/// `register_saveable_component` or `register_saveable_resource`.
+pub trait Saveable: Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// The name which identifies the type in the snapshot, it must be unique
+    /// and must not change between versions.
+    const NAME: &'static str;
+}
+
+/// Represents a component or resource encoded as JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SerializedComponent {
+    /// The `Saveable::NAME` of the type.
+    pub name: String,
+
+    /// The encoded value.
+    pub data: Value
+}
+
+/// Represents an entity and all its saveable components.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    /// The id of the entity when the snapshot was taken, the restored
+    /// entities could get a different id.
+    pub id: usize,
+
+    /// The saveable components of the entity.
+    pub components: Vec<SerializedComponent>
+}
+
+/// Contains all the entities of a world which have saveable components.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub entities: Vec<EntitySnapshot>
+}
+
+/// Contains the saveable unique resources of a world.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourcesSnapshot {
+    pub resources: Vec<SerializedComponent>
+}
+
+/// Knows how to save and restore one component type.
+struct ComponentEntry {
+    name: &'static str,
+    collect: fn(&DefaultWorld) -> Vec<(Entity, Value)>,
+    spawn: fn(&DefaultWorld, Value) -> serde_json::Result<Entity>,
+    attach: fn(&DefaultWorld, Entity, Value) -> serde_json::Result<()>
+}
+
+/// Knows how to save and restore one resource type.
+struct ResourceEntry {
+    name: &'static str,
+    save: fn(&DefaultWorld) -> Option<Value>,
+    load: fn(&DefaultWorld, Value) -> serde_json::Result<()>
+}
+
+/// Contains all the types which are stored in the snapshots.
+#[derive(Default)]
+pub struct SaveRegistry {
+    components: Vec<ComponentEntry>,
+    resources: Vec<ResourceEntry>
+}
+
+impl SaveRegistry {
+    /// Returns the component entry with the provided name.
+    ///
+    /// # Arguments
+    ///
+    /// `name` - The `Saveable::NAME` of the component.
+    fn component(&self, name: &str) -> Option<&ComponentEntry> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+/// Adds a component type to the snapshots, the component must be registered
+/// in the world.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the `SaveRegistry`.
+pub fn register_saveable_component<T: Saveable>(world: &DefaultWorld) {
+    register_component_with_name::<T>(world, T::NAME);
+}
+
+/// Adds a component type to the snapshots using the provided name, if the
+/// name is already used the previous type is replaced.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the `SaveRegistry`.
+/// `name` - The name which identifies the type in the snapshot.
+pub(crate) fn register_component_with_name<
+    T: Serialize + DeserializeOwned + Send + Sync + 'static
+>(world: &DefaultWorld, name: &'static str) {
+    let registry = world.get_unique_mut::<SaveRegistry>();
+    let mut registry_write = registry.write();
+    registry_write.components.retain(|c| c.name != name);
+    registry_write.components.push(ComponentEntry {
+        name,
+        collect: collect_components::<T>,
+        spawn: spawn_component::<T>,
+        attach: attach_component::<T>
+    });
+}
+
+/// Adds a unique resource type to the resource snapshots.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the `SaveRegistry`.
+pub fn register_saveable_resource<T: Saveable>(world: &DefaultWorld) {
+    world.get_unique_mut::<SaveRegistry>().write().resources.push(ResourceEntry {
+        name: T::NAME,
+        save: save_resource::<T>,
+        load: load_resource::<T>
+    });
+}
+
+/// Returns all the components of type `T` encoded as JSON.
+fn collect_components<
+    T: Serialize + Send + Sync + 'static
+>(world: &DefaultWorld) -> Vec<(Entity, Value)> {
+    let access = world.get::<Read<T>>();
+    access
+        .entities()
+        .iter()
+        .zip(access.iter())
+        .filter_map(|(entity, component)| match serde_json::to_value(&**component.read()) {
+            Ok(value) => Some((*entity, value)),
+            Err(e) => {
+                error(&format!("{{Scene}} Error saving {}: {}", type_name::<T>(), e));
+                None
+            }
+        })
+        .collect()
+}
+
+/// Creates a new entity which only contains the decoded component.
+fn spawn_component<
+    T: DeserializeOwned + Send + Sync + 'static
+>(world: &DefaultWorld, value: Value) -> serde_json::Result<Entity> {
+    let component: T = serde_json::from_value(value)?;
+    Ok(world.add_entity((component,)))
+}
+
+/// Adds the decoded component to an existing entity.
+fn attach_component<
+    T: DeserializeOwned + Send + Sync + 'static
+>(world: &DefaultWorld, entity: Entity, value: Value) -> serde_json::Result<()> {
+    let component: T = serde_json::from_value(value)?;
+    world.add_component(entity, component);
+    Ok(())
+}
+
+/// Returns the resource of type `T` encoded as JSON, if it exists.
+fn save_resource<T: Saveable>(world: &DefaultWorld) -> Option<Value> {
+    let resource = world.try_get_unique::<T>()?;
+    let value = serde_json::to_value(&**resource.read());
+    match value {
+        Ok(v) => Some(v),
+        Err(e) => {
+            error(&format!("{{Scene}} Error saving {}: {}", T::NAME, e));
+            None
+        }
+    }
+}
+
+/// Replaces the resource of type `T` with the decoded one.
+fn load_resource<T: Saveable>(world: &DefaultWorld, value: Value) -> serde_json::Result<()> {
+    let resource: T = serde_json::from_value(value)?;
+    match world.try_get_unique_mut::<T>() {
+        Some(unique) => unique.set(resource),
+        None => {
+            world.register_unique(resource);
+        }
+    }
+    Ok(())
+}
+
+impl Scene {
+    /// Creates and returns a snapshot of all the entities which contain
+    /// saveable components.
+    ///
+    /// # Arguments
+    ///
+    /// `world` - The world to be saved.
+    pub fn snapshot(world: &DefaultWorld) -> Self {
+        let registry = world.get_unique::<SaveRegistry>();
+        let registry_read = registry.read();
+
+        // Sorted by id in order to generate the same output for the same
+        // world.
+        let mut entities: BTreeMap<usize, Vec<SerializedComponent>> = BTreeMap::new();
+        for entry in registry_read.components.iter() {
+            for (entity, data) in (entry.collect)(world) {
+                entities.entry(entity.id()).or_default().push(SerializedComponent {
+                    name: entry.name.to_string(),
+                    data
+                });
+            }
+        }
+
+        Self {
+            entities: entities
+                .into_iter()
+                .map(|(id, components)| EntitySnapshot { id, components })
+                .collect()
+        }
+    }
+
+    /// Creates the entities of the scene in the world and returns the new
+    /// entity of each snapshot id.
+    ///
+    /// The components which are not registered or can not be decoded are
+    /// skipped.
+    ///
+    /// # Arguments
+    ///
+    /// `world` - The world where the entities are created.
+    /// `scene` - The scene to be restored.
+    pub fn restore(world: &DefaultWorld, scene: Scene) -> HashMap<usize, Entity> {
+        let registry = world.get_unique::<SaveRegistry>();
+        let registry_read = registry.read();
+        let mut restored: HashMap<usize, Entity> = HashMap::new();
+
+        for snapshot in scene.entities {
+            let mut entity: Option<Entity> = None;
+
+            for component in snapshot.components {
+                let entry = match registry_read.component(&component.name) {
+                    Some(e) => e,
+                    None => {
+                        warning(&format!("{{Scene}} Unknown component {}", component.name));
+                        continue;
+                    }
+                };
+
+                // The entity is created with its first component.
+                let result = match entity {
+                    Some(e) => (entry.attach)(world, e, component.data),
+                    None => (entry.spawn)(world, component.data).map(|e| {
+                        entity = Some(e);
+                    })
+                };
+
+                if let Err(e) = result {
+                    error(&format!("{{Scene}} Error loading {}: {}", component.name, e));
+                }
+            }
+
+            if let Some(e) = entity {
+                restored.insert(snapshot.id, e);
+            }
+        }
+
+        restored
+    }
+
+    /// Returns the scene encoded as JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Creates and returns a scene from its JSON representation.
+    ///
+    /// # Arguments
+    ///
+    /// `json` - The encoded scene.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Saves and restores the unique resources of a world.
+pub trait ResourceSnapshotHandler {
+    /// Creates and returns a snapshot of all the saveable resources.
+    fn snapshot_resources(&self) -> ResourcesSnapshot;
+
+    /// Replaces the resources with the ones in the snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// `snapshot` - The resources to be restored.
+    fn restore_resources(&self, snapshot: ResourcesSnapshot);
+}
+
+impl ResourceSnapshotHandler for DefaultWorld {
+    fn snapshot_resources(&self) -> ResourcesSnapshot {
+        let registry = self.get_unique::<SaveRegistry>();
+        let registry_read = registry.read();
+
+        ResourcesSnapshot {
+            resources: registry_read.resources
+                .iter()
+                .filter_map(|entry| (entry.save)(self).map(|data| SerializedComponent {
+                    name: entry.name.to_string(),
+                    data
+                }))
+                .collect()
+        }
+    }
+
+    fn restore_resources(&self, snapshot: ResourcesSnapshot) {
+        let registry = self.get_unique::<SaveRegistry>();
+        let registry_read = registry.read();
+
+        for resource in snapshot.resources {
+            let entry = match registry_read.resources.iter().find(|r| r.name == resource.name) {
+                Some(e) => e,
+                None => {
+                    warning(&format!("{{Scene}} Unknown resource {}", resource.name));
+                    continue;
+                }
+            };
+
+            if let Err(e) = (entry.load)(self, resource.data) {
+                error(&format!("{{Scene}} Error loading {}: {}", resource.name, e));
+            }
+        }
+    }
+}
+
+#[test]
+fn scene_json_round_trip() {
+    let scene = Scene {
+        entities: vec![EntitySnapshot {
+            id: 3,
+            components: vec![SerializedComponent {
+                name: "Voxel".to_string(),
+                data: serde_json::json!({ "color": { "x": 1.0, "y": 0.5, "z": 0.0 } })
+            }]
+        }]
+    };
+
+    let json = scene.to_json().unwrap();
+    assert_eq!(Scene::from_json(&json).unwrap(), scene);
+}
@@ -0,0 +1,107 @@
+use cgmath::Matrix4;
+
+use wgpu::TextureView;
+
+use ecs::{DefaultWorld, Component, ComponentHandler};
+use types::Size;
+
+use crate::graphics::{
+    gpu::Gpu,
+    pipelines::sprite_pipeline::{SpriteRenderer, TextureHandle}
+};
+
+/// Represents a 2D image drawn on top of the scene.
+#[derive(Clone, Copy, Debug, PartialEq, Component)]
+pub struct Sprite {
+    /// The handle returned by `SpriteRenderer::register_texture`.
+    pub texture_handle: usize,
+
+    /// The area of the texture used by the sprite, x, y, width and height in
+    /// normalized coordinates.
+    pub src_rect: [f32; 4],
+
+    /// The RGBA color multiplied by the texture color.
+    pub color: [f32; 4]
+}
+
+impl Sprite {
+    /// Creates and returns a new `Sprite` which uses the entire texture.
+    ///
+    /// # Arguments
+    ///
+    /// `handle` - The texture of the sprite.
+    pub fn new(handle: TextureHandle) -> Self {
+        Self {
+            texture_handle: handle.0,
+            src_rect: [0.0, 0.0, 1.0, 1.0],
+            color: [1.0, 1.0, 1.0, 1.0]
+        }
+    }
+}
+
+/// Represents the placement of a sprite in the screen, in physical pixels
+/// from the top left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Component)]
+pub struct SpriteTransform {
+    /// The position of the center of the sprite.
+    pub position: [f32; 2],
+
+    /// The width and height of the sprite.
+    pub size: [f32; 2],
+
+    /// The rotation around the center, in radians.
+    pub rotation: f32
+}
+
+impl Default for SpriteTransform {
+    /// Creates and returns a new `SpriteTransform` of one pixel placed at
+    /// the top left corner.
+    fn default() -> Self {
+        Self {
+            position: [0.0, 0.0],
+            size: [1.0, 1.0],
+            rotation: 0.0
+        }
+    }
+}
+
+/// Returns the projection which maps the screen pixels to the clip space,
+/// the origin is the top left corner and the y axis points down.
+///
+/// # Arguments
+///
+/// `size` - The size of the screen in physical pixels.
+pub fn screen_projection(size: Size<u32>) -> Matrix4<f32> {
+    let width = size.width.max(1) as f32;
+    let height = size.height.max(1) as f32;
+
+    // The z is ignored by the sprites, so the depth range does not matter.
+    cgmath::ortho(0.0, width, height, 0.0, -1.0, 1.0)
+}
+
+/// Registers a texture to be used by the sprites and returns its handle.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the sprite renderer.
+/// `view` - The view of the texture.
+pub fn register_sprite_texture(world: &DefaultWorld, view: &TextureView) -> TextureHandle {
+    let gpu = world.get_unique::<Gpu>();
+    let gpu_read = gpu.read();
+    let renderer = world.get_unique_mut::<SpriteRenderer>();
+    let mut renderer_write = renderer.write();
+    renderer_write.register_texture(&gpu_read, view)
+}
+
+#[test]
+fn screen_projection_corners() {
+    use cgmath::Vector4;
+
+    let projection = screen_projection(Size::new(800, 600));
+
+    let top_left = projection * Vector4 { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
+    assert!((top_left.x + 1.0).abs() < 1e-6 && (top_left.y - 1.0).abs() < 1e-6);
+
+    let bottom_right = projection * Vector4 { x: 800.0, y: 600.0, z: 0.0, w: 1.0 };
+    assert!((bottom_right.x - 1.0).abs() < 1e-6 && (bottom_right.y + 1.0).abs() < 1e-6);
+}
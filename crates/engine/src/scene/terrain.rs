@@ -0,0 +1,311 @@
+use cgmath::Vector3;
+
+use ecs::{DefaultWorld, EntityHandler};
+
+use crate::scene::components::{Voxel, LocalTransform};
+
+/// The distance between the centers of two neighbour voxels.
+const VOXEL_SIZE: f32 = 2.0;
+
+/// Defines the colors of the terrain by height, the levels are fractions of
+/// the amplitude.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainColorRamp {
+    pub water: Vector3<f32>,
+    pub sand: Vector3<f32>,
+    pub grass: Vector3<f32>,
+    pub rock: Vector3<f32>,
+
+    /// The columns below this level are flooded.
+    pub water_level: f32,
+
+    /// The columns below this level are sand.
+    pub sand_level: f32,
+
+    /// The columns above this level are rock.
+    pub rock_level: f32
+}
+
+impl Default for TerrainColorRamp {
+    fn default() -> Self {
+        Self {
+            water: Vector3 { x: 0.1, y: 0.3, z: 0.8 },
+            sand: Vector3 { x: 0.9, y: 0.8, z: 0.5 },
+            grass: Vector3 { x: 0.2, y: 0.6, z: 0.2 },
+            rock: Vector3 { x: 0.5, y: 0.5, z: 0.5 },
+            water_level: 0.2,
+            sand_level: 0.25,
+            rock_level: 0.55
+        }
+    }
+}
+
+impl TerrainColorRamp {
+    /// Returns the color of a column.
+    ///
+    /// # Arguments
+    ///
+    /// `level` - The height of the column as a fraction of the amplitude.
+    fn color(&self, level: f32) -> Vector3<f32> {
+        if level < self.water_level {
+            self.water
+        } else if level < self.sand_level {
+            self.sand
+        } else if level < self.rock_level {
+            self.grass
+        } else {
+            self.rock
+        }
+    }
+}
+
+/// Defines the characteristics of a heightmap terrain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainDescriptor {
+    /// The number of voxels along the x axis.
+    pub width: u32,
+
+    /// The number of voxels along the z axis.
+    pub depth: u32,
+
+    /// The seed of the noise, the same seed generates the same terrain.
+    pub seed: u32,
+
+    /// The maximum height in voxels.
+    pub amplitude: f32,
+
+    /// The size in voxels of the biggest hills.
+    pub scale: f32,
+
+    /// The number of noise layers, each one adds smaller details.
+    pub octaves: u32,
+
+    /// The number of voxels placed beneath the surface of each column.
+    pub layers: u32,
+
+    /// Lowers the terrain towards the edges in order to create an island.
+    pub island: bool,
+
+    pub colors: TerrainColorRamp
+}
+
+impl Default for TerrainDescriptor {
+    fn default() -> Self {
+        Self {
+            width: 64,
+            depth: 64,
+            seed: 0,
+            amplitude: 16.0,
+            scale: 48.0,
+            octaves: 4,
+            layers: 1,
+            island: false,
+            colors: TerrainColorRamp::default()
+        }
+    }
+}
+
+/// A small 2D Perlin noise, the permutation table is shuffled using the
+/// seed so the result does not depend on external crates.
+struct Perlin {
+    permutation: [u8; 512]
+}
+
+impl Perlin {
+    /// Creates and returns a new noise generator.
+    ///
+    /// # Arguments
+    ///
+    /// `seed` - The seed used to shuffle the permutation table.
+    fn new(seed: u32) -> Self {
+        let mut table: [u8; 256] = [0; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+
+        // Xorshift, the state must never be zero.
+        let mut state: u64 = (seed as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        for i in (1..256).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            table.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+
+        let mut permutation: [u8; 512] = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i & 255];
+        }
+
+        Self { permutation }
+    }
+
+    /// Returns the noise at the provided point, roughly between -1 and 1.
+    fn noise(&self, x: f32, y: f32) -> f32 {
+        let xi = (x.floor() as i32 & 255) as usize;
+        let yi = (y.floor() as i32 & 255) as usize;
+        let xf = x - x.floor();
+        let yf = y - y.floor();
+        let u = fade(xf);
+        let v = fade(yf);
+
+        let p = &self.permutation;
+        let aa = p[p[xi] as usize + yi];
+        let ab = p[p[xi] as usize + yi + 1];
+        let ba = p[p[xi + 1] as usize + yi];
+        let bb = p[p[xi + 1] as usize + yi + 1];
+
+        let bottom = lerp(gradient(aa, xf, yf), gradient(ba, xf - 1.0, yf), u);
+        let top = lerp(gradient(ab, xf, yf - 1.0), gradient(bb, xf - 1.0, yf - 1.0), u);
+        // The raw 2D noise stays between -0.7 and 0.7.
+        lerp(bottom, top, v) * std::f32::consts::SQRT_2
+    }
+
+    /// Returns the sum of several octaves of noise, between 0 and 1.
+    ///
+    /// # Arguments
+    ///
+    /// `x` - The x coordinate.
+    /// `y` - The y coordinate.
+    /// `octaves` - The number of octaves.
+    fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+
+        for _ in 0..octaves.max(1) {
+            total += self.noise(x * frequency, y * frequency) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+
+        (total / max * 0.5 + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+/// Smooths the interpolation factor.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Returns the dot product between a pseudo random gradient and the offset.
+fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y
+    }
+}
+
+/// Returns the height of each column as a fraction of the amplitude, row by
+/// row along the x axis.
+///
+/// # Arguments
+///
+/// `descriptor` - The characteristics of the terrain.
+pub fn terrain_levels(descriptor: &TerrainDescriptor) -> Vec<f32> {
+    let perlin = Perlin::new(descriptor.seed);
+    let scale = descriptor.scale.max(1.0);
+    let half_width = descriptor.width as f32 / 2.0;
+    let half_depth = descriptor.depth as f32 / 2.0;
+
+    let mut levels = Vec::with_capacity((descriptor.width * descriptor.depth) as usize);
+    for j in 0..descriptor.depth {
+        for i in 0..descriptor.width {
+            let mut level = perlin.fractal(i as f32 / scale, j as f32 / scale, descriptor.octaves);
+
+            if descriptor.island {
+                let dx = (i as f32 - half_width) / half_width;
+                let dz = (j as f32 - half_depth) / half_depth;
+                let squared_distance = (dx * dx + dz * dz).min(1.0);
+                level *= 1.0 - squared_distance * squared_distance;
+            }
+
+            levels.push(level);
+        }
+    }
+
+    levels
+}
+
+/// Returns the height in voxels of a column, the flooded columns are raised
+/// to the water level.
+fn column_height(level: f32, descriptor: &TerrainDescriptor) -> i32 {
+    let level = level.max(descriptor.colors.water_level);
+    (level * descriptor.amplitude).round() as i32
+}
+
+/// Creates the voxels of a heightmap terrain and returns the number of
+/// voxels created.
+///
+/// Only the surface and `layers` voxels beneath it are created, the columns
+/// next to a lower column are extended down in order to not leave holes in
+/// the slopes. The terrain is centered in the origin.
+///
+/// # Arguments
+///
+/// `world` - The world where the voxels are created.
+/// `descriptor` - The characteristics of the terrain.
+pub fn generate_heightmap_terrain(world: &DefaultWorld, descriptor: TerrainDescriptor) -> usize {
+    let levels = terrain_levels(&descriptor);
+    let heights: Vec<i32> = levels.iter().map(|l| column_height(*l, &descriptor)).collect();
+
+    let width = descriptor.width as i32;
+    let depth = descriptor.depth as i32;
+    let height_at = |i: i32, j: i32| -> Option<i32> {
+        if i < 0 || j < 0 || i >= width || j >= depth {
+            return None;
+        }
+        Some(heights[(j * width + i) as usize])
+    };
+
+    let mut count: usize = 0;
+    for j in 0..depth {
+        for i in 0..width {
+            let index = (j * width + i) as usize;
+            let top = heights[index];
+            let color = descriptor.colors.color(levels[index]);
+
+            let lowest_neighbour = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                .iter()
+                .filter_map(|(di, dj)| height_at(i + di, j + dj))
+                .min()
+                .unwrap_or(top);
+            let bottom = (top - descriptor.layers as i32).min(lowest_neighbour + 1);
+
+            let x = (i - width / 2) as f32 * VOXEL_SIZE;
+            let z = (j - depth / 2) as f32 * VOXEL_SIZE;
+            for y in bottom..=top {
+                world.add_entity((
+                    Voxel { color },
+                    LocalTransform::from_position(Vector3 { x, y: y as f32 * VOXEL_SIZE, z })
+                ));
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+#[test]
+fn terrain_is_deterministic() {
+    let descriptor = TerrainDescriptor { seed: 7, island: true, ..TerrainDescriptor::default() };
+    let levels = terrain_levels(&descriptor);
+
+    assert_eq!(levels.len(), 64 * 64);
+    assert!(levels.iter().all(|l| (0.0..=1.0).contains(l)));
+    assert_eq!(levels, terrain_levels(&descriptor));
+    assert_ne!(levels, terrain_levels(&TerrainDescriptor { seed: 8, ..descriptor }));
+}
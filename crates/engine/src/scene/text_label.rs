@@ -0,0 +1,39 @@
+use cgmath::Vector3;
+
+use ecs::Component;
+
+/// Represents a text drawn above an entity, the text always faces the main
+/// camera.
+///
+/// The entity must also contain a `LocalTransform`, the label is placed at
+/// the world position of the entity plus the offset.
+#[derive(Clone, Debug, PartialEq, Component)]
+pub struct TextLabel {
+    /// The text, the lowercase letters are drawn as uppercase.
+    pub text: String,
+
+    /// The RGBA color of the text.
+    pub color: [f32; 4],
+
+    /// The size of a font pixel in world units.
+    pub scale: f32,
+
+    /// The offset from the entity position in world units.
+    pub offset: Vector3<f32>
+}
+
+impl TextLabel {
+    /// Creates and returns a new white `TextLabel` placed above the entity.
+    ///
+    /// # Arguments
+    ///
+    /// `text` - The text of the label.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            color: [1.0, 1.0, 1.0, 1.0],
+            scale: 0.1,
+            offset: Vector3 { x: 0.0, y: 1.5, z: 0.0 }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    thread,
+    time::{Duration, Instant}
+};
+
+use ecs::UniqueWrite;
+
+use crate::graphics::FPS_LIMIT;
+
+/// The maximum number of fixed steps run in a single frame, if the frame
+/// takes longer the rest of the time is discarded. It avoids the spiral of
+/// death where each frame needs more steps than the previous one.
+pub const MAX_FIXED_STEPS_PER_FRAME: u32 = 5;
+
+/// Contains the time information of the current frame.
+pub struct Time {
+    /// The time in seconds that the last frame took.
+    pub delta_seconds: f32,
+
+    /// The time in seconds since the application started.
+    pub elapsed_seconds: f64,
+
+    /// The number of frames since the application started.
+    pub frame_count: u64,
+
+    /// The fixed timestep in seconds used by the fixed tick.
+    pub fixed_delta_seconds: f32,
+
+    /// How far the frame is between the last fixed step and the next one,
+    /// in the range [0, 1). It is used to blend the fixed step results.
+    pub fixed_alpha: f32,
+
+    /// The time which was not consumed by the fixed steps yet.
+    fixed_accumulator: Duration,
+
+    /// The instant when the application started.
+    start: Instant,
+
+    /// The instant when the last frame started.
+    last_frame: Instant
+}
+
+impl Default for Time {
+    /// Creates and returns a new `Time` starting now.
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl Time {
+    /// Creates and returns a new `Time`.
+    ///
+    /// # Arguments
+    ///
+    /// `start` - The instant when the application started.
+    pub fn new(start: Instant) -> Self {
+        Self {
+            delta_seconds: 0.0,
+            elapsed_seconds: 0.0,
+            frame_count: 0,
+            fixed_delta_seconds: 0.0,
+            fixed_alpha: 0.0,
+            fixed_accumulator: Duration::from_secs(0),
+            start,
+            last_frame: start
+        }
+    }
+
+    /// Moves the time to a new frame.
+    ///
+    /// # Arguments
+    ///
+    /// `now` - The instant when the new frame starts.
+    pub fn advance(&mut self, now: Instant) {
+        let delta = now - self.last_frame;
+        self.fixed_accumulator += delta;
+        self.delta_seconds = delta.as_secs_f32();
+        self.elapsed_seconds = (now - self.start).as_secs_f64();
+        self.frame_count += 1;
+        self.last_frame = now;
+    }
+
+    /// Consumes the accumulated time and returns the number of fixed steps
+    /// to run in this frame, it updates the interpolation alpha.
+    ///
+    /// # Arguments
+    ///
+    /// `timestep` - The duration of each fixed step.
+    /// `max_steps` - The maximum number of steps, the time left is
+    /// discarded.
+    pub fn take_fixed_steps(&mut self, timestep: Duration, max_steps: u32) -> u32 {
+        self.fixed_delta_seconds = timestep.as_secs_f32();
+        if timestep.is_zero() {
+            self.fixed_alpha = 0.0;
+            return 0;
+        }
+
+        let mut steps: u32 = 0;
+        while self.fixed_accumulator >= timestep && steps < max_steps {
+            self.fixed_accumulator -= timestep;
+            steps += 1;
+        }
+
+        // The application can not keep up, drop the time it is behind.
+        if self.fixed_accumulator >= timestep {
+            let remainder = self.fixed_accumulator.as_nanos() % timestep.as_nanos();
+            self.fixed_accumulator = Duration::from_nanos(remainder as u64);
+        }
+
+        self.fixed_alpha = self.fixed_accumulator.as_secs_f32() / timestep.as_secs_f32();
+        steps
+    }
+
+    /// Returns the time left to reach the minimum frame duration, if the
+    /// frame already took longer it returns None.
+    ///
+    /// # Arguments
+    ///
+    /// `now` - The current instant.
+    /// `fps_limit` - The maximum number of frames per second.
+    pub fn remaining_frame_time(&self, now: Instant, fps_limit: f64) -> Option<Duration> {
+        let frame_duration = Duration::from_secs_f64(1.0 / fps_limit);
+        frame_duration.checked_sub(now - self.last_frame)
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// Updates the time resource, if the last frame was faster than the fps
+/// limit it sleeps the remaining time.
+pub fn update_time_system(time: UniqueWrite<Time>) {
+    let mut time = time.write();
+
+    if let Some(remaining) = time.remaining_frame_time(Instant::now(), FPS_LIMIT) {
+        thread::sleep(remaining);
+    }
+
+    time.advance(Instant::now());
+}
+
+#[test]
+fn advance_frames() {
+    let start = Instant::now();
+    let mut time = Time::new(start);
+
+    time.advance(start + Duration::from_millis(16));
+    time.advance(start + Duration::from_millis(48));
+
+    assert_eq!(time.frame_count, 2);
+    assert!((time.delta_seconds - 0.032).abs() < 1e-6);
+    assert!((time.elapsed_seconds - 0.048).abs() < 1e-9);
+}
+
+#[test]
+fn remaining_frame_time() {
+    let start = Instant::now();
+    let time = Time::new(start);
+
+    let remaining = time.remaining_frame_time(start + Duration::from_millis(10), 50.0);
+    assert_eq!(remaining, Some(Duration::from_millis(10)));
+
+    // The frame took longer than the limit.
+    assert_eq!(time.remaining_frame_time(start + Duration::from_millis(30), 50.0), None);
+}
+
+#[test]
+fn fixed_steps() {
+    let start = Instant::now();
+    let mut time = Time::new(start);
+    let timestep = Duration::from_millis(10);
+
+    // Count the calls as the main loop would do.
+    let mut calls: u32 = 0;
+    let mut frame = |time: &mut Time, now: Instant| {
+        time.advance(now);
+        for _ in 0..time.take_fixed_steps(timestep, MAX_FIXED_STEPS_PER_FRAME) {
+            calls += 1;
+        }
+    };
+
+    frame(&mut time, start + Duration::from_millis(25));
+    assert!((time.fixed_alpha - 0.5).abs() < 1e-4);
+
+    // The 5ms left plus this frame complete one step.
+    frame(&mut time, start + Duration::from_millis(30));
+    assert!(time.fixed_alpha.abs() < 1e-4);
+
+    // A very long frame is capped.
+    frame(&mut time, start + Duration::from_millis(135));
+    assert!((time.fixed_alpha - 0.5).abs() < 1e-4);
+
+    assert_eq!(calls, 2 + 1 + MAX_FIXED_STEPS_PER_FRAME);
+}
@@ -1,19 +1,69 @@
-use ecs::{DefaultWorld, TaskWaitable, SystemHandler};
+use std::{sync::Arc, time::Duration};
+
+use ecs::{
+    DefaultWorld,
+    TaskWaitable,
+    TaskSync,
+    SystemHandler,
+    System,
+    ComponentBundler,
+    TaskPriority,
+    EventHandler,
+    ComponentHandler
+};
 
 use crate::{
+    profile_scope,
+    basics::{
+        profiler::{Profiler, profiler_panel_system},
+        frame_history::{update_frame_history_system, frame_history_overlay_system},
+        dev_gui::{DevGui, top_bar_renderer_system, layout_dev_gui_system}
+    },
     graphics::{
         renderers::{
-            voxel_renderer::voxel_renderer_system,
+            voxel_renderer::{voxel_renderer_system, resize_voxel_buffer_system},
+            sky_renderer::{sky_renderer_system, mantain_sky_system},
+            outline_renderer::outline_renderer_system,
+            mesh_renderer::mesh_renderer_system,
+            debug_draw_renderer::{debug_draw_renderer_system, clear_debug_draw_system},
+            sprite_renderer::sprite_renderer_system,
+            dev_gui_renderer::dev_gui_renderer_system,
+            text_renderer::text_renderer_system,
+            tone_mapping_renderer::tone_mapping_system,
             maintain_swap_chain_output_system,
             submit_commnads_system,
             clean_and_drop_system
-        }
+        },
+        uploader::{flush_uploads_system, recall_uploads_system},
+        gpu_info::gpu_info_panel_system,
+        render_stats::reset_render_stats_system
     },
-    scene::camera::mantain_camera_buffer_system
+    scene::{
+        camera::{mantain_locals_system, mantain_camera_passes_system},
+        hierarchy::propagate_transforms_system,
+        lod::lod_update_system,
+        light::collect_point_lights_system,
+        picking::picking_system,
+        inspector::{
+            select_entity_system,
+            entity_inspector_system,
+            entity_inspector_panel_system
+        },
+        input::{begin_input_frame_system, finalize_input_system},
+        time::update_time_system,
+        file_drop::clear_file_drop_events_system,
+        assets::process_pending_uploads_system,
+        console_log::{update_console_log_system, console_log_window_system}
+    }
 };
 
+/// Defines the time a workload can take before its systems are reported as
+/// stuck, it is only used in debug builds.
+const WORKLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Represents all the available workloads in the engine.
 /// Find a better name for this.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Workloads {
     Start,
     Synchronize,
@@ -22,6 +72,71 @@ pub enum Workloads {
     End
 }
 
+/// Defines a function which dispatches a system to the workers.
+type SystemRunner = Box<dyn Fn(&DefaultWorld) -> Arc<TaskSync> + Send + Sync>;
+
+/// Contains the systems added by the application to the workloads.
+#[derive(Default)]
+pub struct Schedule {
+    /// Contains the systems and the workload where they run, in the order
+    /// they were added.
+    systems: Vec<(Workloads, SystemRunner)>
+}
+
+impl Schedule {
+    /// Adds a system which runs every frame in the provided workload, it
+    /// runs in parallel with the systems of the engine.
+    ///
+    /// # Arguments
+    ///
+    /// `workload` - The workload where the system runs.
+    /// `system` - The system to be executed.
+    pub fn add_system<
+        B: ComponentBundler,
+        S: System<B> + Clone + 'static + Send + Sync
+    >(&mut self, workload: Workloads, system: S) {
+        self.systems.push((workload, Box::new(move |world| world.run(system.clone()))));
+    }
+
+    /// Dispatches the systems of the workload and returns their syncs.
+    ///
+    /// # Arguments
+    ///
+    /// `workload` - The workload being executed.
+    /// `world` - The world where the systems are executed.
+    fn dispatch(&self, workload: Workloads, world: &DefaultWorld) -> Vec<Arc<TaskSync>> {
+        self.systems
+            .iter()
+            .filter(|(w, _)| *w == workload)
+            .map(|(_, run)| run(world))
+            .collect()
+    }
+}
+
+/// Adds the systems of the application to the workloads.
+pub trait ScheduleHandler {
+    /// Adds a system which runs every frame in the provided workload, it
+    /// runs in parallel with the systems of the engine.
+    ///
+    /// # Arguments
+    ///
+    /// `workload` - The workload where the system runs.
+    /// `system` - The system to be executed.
+    fn add_system<
+        B: ComponentBundler,
+        S: System<B> + Clone + 'static + Send + Sync
+    >(&self, workload: Workloads, system: S);
+}
+
+impl ScheduleHandler for DefaultWorld {
+    fn add_system<
+        B: ComponentBundler,
+        S: System<B> + Clone + 'static + Send + Sync
+    >(&self, workload: Workloads, system: S) {
+        self.get_unique_mut::<Schedule>().write().add_system(workload, system);
+    }
+}
+
 /// Runs the provided workload in the provided world.
 ///
 /// # Arguments
@@ -29,13 +144,27 @@ pub enum Workloads {
 /// `workload` - The workload to be executed.
 /// `world` - The world where the workload will be executed.
 pub fn run_workload(workload: Workloads, world: &DefaultWorld) {
+    // The time of each workload is recorded using its name, see
+    // `WORKLOAD_SCOPES`.
+    let profiler = world.get_unique::<Profiler>();
+
     // Match the workload with the actual work to do.
     match workload {
-        Workloads::Start => run_start_workload(world),
-        Workloads::Synchronize => run_synchronize_workload(world),
-        Workloads::Render => run_render_workload(world),
-        Workloads::Commit => run_commit_workload(world),
-        Workloads::End => run_end_workload(world)
+        Workloads::Start => profile_scope!(profiler.read(), "Start", {
+            run_start_workload(world)
+        }),
+        Workloads::Synchronize => profile_scope!(profiler.read(), "Synchronize", {
+            run_synchronize_workload(world)
+        }),
+        Workloads::Render => profile_scope!(profiler.read(), "Render", {
+            run_render_workload(world)
+        }),
+        Workloads::Commit => profile_scope!(profiler.read(), "Commit", {
+            run_commit_workload(world)
+        }),
+        Workloads::End => profile_scope!(profiler.read(), "End", {
+            run_end_workload(world)
+        })
     }
 }
 
@@ -48,9 +177,21 @@ pub fn run_workload(workload: Workloads, world: &DefaultWorld) {
 ///
 /// `world` - The world which contains all the resources.
 fn run_start_workload(world: &DefaultWorld) {
-    (
-        world.run(maintain_swap_chain_output_system),
-    ).wait();
+    // The events sent during the last frame become visible to the readers.
+    update_events_system(world);
+
+    // The frame can not start until the swap chain output is ready.
+    let tasks = (
+        world.run_priority(update_time_system, TaskPriority::High),
+        world.run_priority(begin_input_frame_system, TaskPriority::High),
+        world.run_priority(maintain_swap_chain_output_system, TaskPriority::High),
+        world.run_priority(reset_render_stats_system, TaskPriority::High),
+        world.run(picking_system),
+        world.run(process_pending_uploads_system),
+    );
+    let scheduled = dispatch_scheduled(Workloads::Start, world);
+    wait_workload(tasks);
+    wait_workload(scheduled);
 }
 
 /// Generates and executes the synchronize workload.
@@ -62,9 +203,20 @@ fn run_start_workload(world: &DefaultWorld) {
 ///
 /// `world` - The world which contains all the resources.
 fn run_synchronize_workload(world: &DefaultWorld) {
-    (
-        world.run(mantain_camera_buffer_system),
-    ).wait();
+    // The transformations must be propagated before any renderer uses them.
+    propagate_transforms_system(world);
+
+    let tasks = (
+        world.run(mantain_locals_system),
+        world.run(mantain_camera_passes_system),
+        world.run(mantain_sky_system),
+        world.run(collect_point_lights_system),
+        world.run(select_entity_system),
+        world.run(lod_update_system)
+    );
+    let scheduled = dispatch_scheduled(Workloads::Synchronize, world);
+    wait_workload(tasks);
+    wait_workload(scheduled);
 }
 
 /// Generates and executes the render workload.
@@ -76,9 +228,25 @@ fn run_synchronize_workload(world: &DefaultWorld) {
 ///
 /// `world` - The world which contains all the resources.
 fn run_render_workload(world: &DefaultWorld) {
-    (
+    let tasks = (
+        world.run(sky_renderer_system),
         world.run(voxel_renderer_system),
-    ).wait();
+        world.run(mesh_renderer_system),
+        world.run(outline_renderer_system),
+        world.run(debug_draw_renderer_system),
+        world.run(text_renderer_system),
+        world.run(tone_mapping_system),
+        world.run(sprite_renderer_system)
+    );
+    // The dev GUI is only drawn when it is enabled.
+    let mut optional: Vec<Arc<TaskSync>> = Vec::new();
+    if world.has_unique::<DevGui>() {
+        optional.push(world.run(dev_gui_renderer_system));
+    }
+    let scheduled = dispatch_scheduled(Workloads::Render, world);
+    wait_workload(tasks);
+    wait_workload(optional);
+    wait_workload(scheduled);
 }
 
 /// Generates and executes the commit workload.
@@ -90,9 +258,15 @@ fn run_render_workload(world: &DefaultWorld) {
 ///
 /// `world` - The world which contains all the resources.
 fn run_commit_workload(world: &DefaultWorld) {
-    (
+    // The uploads must be queued before the commands are submitted.
+    wait_workload((world.run(flush_uploads_system),));
+
+    let tasks = (
         world.run(submit_commnads_system),
-    ).wait();
+    );
+    let scheduled = dispatch_scheduled(Workloads::Commit, world);
+    wait_workload(tasks);
+    wait_workload(scheduled);
 }
 
 /// Generates and executes the end workload.
@@ -104,7 +278,106 @@ fn run_commit_workload(world: &DefaultWorld) {
 ///
 /// `world` - The world which contains all the resources.
 fn run_end_workload(world: &DefaultWorld) {
-    (
+    // The inspector uses the selection made during the frame.
+    entity_inspector_system(world);
+
+    let tasks = (
         world.run(clean_and_drop_system),
-    ).wait();
-}
\ No newline at end of file
+        world.run(recall_uploads_system),
+        world.run(finalize_input_system),
+        world.run(clear_file_drop_events_system),
+        world.run(update_console_log_system),
+        world.run(resize_voxel_buffer_system),
+        world.run(clear_debug_draw_system),
+        world.run(update_frame_history_system)
+    );
+    let scheduled = dispatch_scheduled(Workloads::End, world);
+    wait_workload(tasks);
+    wait_workload(scheduled);
+
+    // The windows are placed once all the systems of the frame added them.
+    run_dev_gui_systems(world);
+}
+
+/// Runs the systems which add the windows of the dev GUI and places them,
+/// nothing runs when the dev GUI is not enabled.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the dev GUI.
+fn run_dev_gui_systems(world: &DefaultWorld) {
+    if !world.has_unique::<DevGui>() {
+        return;
+    }
+
+    let panels = vec![
+        world.run(top_bar_renderer_system),
+        world.run(console_log_window_system),
+        world.run(entity_inspector_panel_system),
+        world.run(profiler_panel_system),
+        world.run(gpu_info_panel_system),
+        world.run(frame_history_overlay_system)
+    ];
+    wait_workload(panels);
+    wait_workload((world.run(layout_dev_gui_system),));
+}
+
+/// Dispatches the systems added by the application to the workload, the
+/// schedule is read while they are dispatched so they can not add systems.
+///
+/// # Arguments
+///
+/// `workload` - The workload being executed.
+/// `world` - The world which contains the schedule.
+fn dispatch_scheduled(workload: Workloads, world: &DefaultWorld) -> Vec<Arc<TaskSync>> {
+    match world.try_get_unique::<Schedule>() {
+        Some(schedule) => schedule.read().dispatch(workload, world),
+        None => Vec::new()
+    }
+}
+
+/// Swaps the buffers of all the events registered with
+/// `world.register_event`, it must run before any system of the frame.
+///
+/// # Arguments
+///
+/// `world` - The world which contains the events.
+fn update_events_system(world: &DefaultWorld) {
+    world.update_events();
+}
+
+/// Waits until all the systems of the workload finish.
+///
+/// In debug builds the systems which take too long are reported, in this
+/// way a deadlocked system is visible instead of only freezing the window.
+///
+/// # Arguments
+///
+/// `tasks` - The systems of the workload.
+fn wait_workload<T: TaskWaitable>(tasks: T) {
+    if cfg!(debug_assertions) {
+        // The stuck systems are logged every time the timeout is reached.
+        while !tasks.wait_timeout(WORKLOAD_TIMEOUT).finished {}
+    } else {
+        tasks.wait();
+    }
+}
+
+#[test]
+fn scheduled_systems_run_in_their_workload() {
+    use ecs::UniqueWrite;
+
+    let world = DefaultWorld::default();
+    world.register_unique(Schedule::default());
+    world.register_unique(0u32);
+
+    let increment = |value: UniqueWrite<u32>| **value.write() += 1;
+    world.add_system(Workloads::Synchronize, increment);
+
+    wait_workload(dispatch_scheduled(Workloads::Start, &world));
+    assert_eq!(**world.get_unique::<u32>().read(), 0);
+
+    wait_workload(dispatch_scheduled(Workloads::Synchronize, &world));
+    wait_workload(dispatch_scheduled(Workloads::Synchronize, &world));
+    assert_eq!(**world.get_unique::<u32>().read(), 2);
+}
@@ -1,23 +1,42 @@
 use termion::color;
 
-use crate::{LogEntry, LogSeverity, log_hook};
+use crate::{LogEntry, LogSeverity, log_hook, level_from_env};
 
 pub struct Console;
 
 impl Console {
+	/// Registers the console as a log listener, the entries below the
+	/// `CRYSTAL_LOG` level are not printed.
 	pub fn init() {
-		log_hook(|entry: &LogEntry| {
+		let level: Option<LogSeverity> = level_from_env();
+		log_hook(move |entry: &LogEntry| {
+			if level.is_some_and(|l| entry.severity < l) {
+				return;
+			}
 			println!(
-				"{}[{}][{}] {}",
-				color_for_entry(&entry),
+				"{}[{}][{}] {}{}",
+				color_for_entry(entry),
 				entry.date.to_rfc3339(),
-				entry.severity.to_string(),
-				entry.buffer
+				entry.severity,
+				entry.buffer,
+				format_fields(entry)
 			);
 		});
 	}
 }
 
+/// Formats the structured fields of the entry as `key=value` pairs.
+///
+/// # Arguments
+///
+/// `entry` - The entry to extract the fields.
+pub(crate) fn format_fields(entry: &LogEntry) -> String {
+	entry.fields
+		.iter()
+		.map(|(key, value)| format!(" {}={}", key, value))
+		.collect()
+}
+
 /// Figures out and returns the correct color for the entry.
 ///
 /// # Arguments
@@ -25,6 +44,7 @@ impl Console {
 /// `entry` - The entry to extract the color.
 fn color_for_entry(entry: &LogEntry) -> String {
 	match entry.severity {
+		LogSeverity::DEBUG => format!("{}", color::Fg(color::LightBlack)),
 		LogSeverity::INFO => format!("{}", color::Fg(color::Blue)),
 		LogSeverity::WARNING => format!("{}", color::Fg(color::Yellow)),
 		LogSeverity::ERROR => format!("{}", color::Fg(color::Red))
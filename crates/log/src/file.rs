@@ -0,0 +1,379 @@
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, BufWriter, Write},
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+		Mutex
+	},
+	thread::{self, JoinHandle},
+	time::Duration
+};
+
+use crossbeam_queue::SegQueue;
+
+use crate::{
+	LogEntry,
+	LogSeverity,
+	ListenerHandle,
+	log_hook,
+	remove_log_hook,
+	level_from_env,
+	console::format_fields
+};
+
+/// Defines how often the pending lines are written into the file.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Defines when the log file is rotated and how many files are kept.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation {
+	/// The number of files kept, including the one being written.
+	pub max_files: usize,
+
+	/// The size in bytes a file can reach before being rotated.
+	pub max_bytes: u64
+}
+
+impl Default for Rotation {
+	/// Creates and returns a new `Rotation` which keeps 5 files of 10MB.
+	fn default() -> Self {
+		Self {
+			max_files: 5,
+			max_bytes: 10 * 1024 * 1024
+		}
+	}
+}
+
+/// A sink which writes all the log entries into a file, one line per entry
+/// using the `[ISO8601][SEVERITY] message` format.
+///
+/// When the file reaches the rotation size it is renamed to `path.1`, the
+/// previous `path.1` to `path.2` and so on, the oldest file is removed.
+///
+/// The writes are performed by a background thread in order to avoid
+/// blocking the caller, the sink must be kept alive while it is needed,
+/// when it is dropped all the pending lines are flushed and the file is
+/// closed.
+pub struct FileSink {
+	/// Contains all the lines waiting to be written.
+	queue: Arc<SegQueue<String>>,
+
+	/// The writer shared with the background thread.
+	writer: Arc<Mutex<RotatingWriter>>,
+
+	/// A flag used to tell the background thread when to stop.
+	running: Arc<AtomicBool>,
+
+	/// The background thread which writes into the file.
+	handle: Option<JoinHandle<()>>,
+
+	/// The handle of the registered log listener.
+	listener: ListenerHandle
+}
+
+impl FileSink {
+	/// Opens (or creates) the file and registers the sink as a log
+	/// listener, the entries below the `CRYSTAL_LOG` level are ignored.
+	///
+	/// # Arguments
+	///
+	/// `path` - The path of the file where the entries will be written.
+	/// `rotation` - Defines when the file is rotated.
+	pub fn init(path: PathBuf, rotation: Rotation) -> io::Result<Self> {
+		let writer = Arc::new(Mutex::new(RotatingWriter::open(path, rotation)?));
+		let queue: Arc<SegQueue<String>> = Arc::new(SegQueue::new());
+		let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(true));
+
+		let handle = flush_loop(writer.clone(), queue.clone(), running.clone())?;
+
+		// Only format the entry in the caller thread, the IO is done by the
+		// background thread.
+		let level: Option<LogSeverity> = level_from_env();
+		let listener_queue = queue.clone();
+		let listener = log_hook(move |entry: &LogEntry| {
+			if level.is_some_and(|l| entry.severity < l) {
+				return;
+			}
+			listener_queue.push(format_entry(entry));
+		});
+
+		Ok(Self {
+			queue,
+			writer,
+			running,
+			handle: Some(handle),
+			listener
+		})
+	}
+
+	/// Returns the number of lines waiting to be written.
+	pub fn pending(&self) -> usize {
+		self.queue.len()
+	}
+
+	/// Writes all the pending lines into the file, it blocks until they are
+	/// written.
+	pub fn flush(&self) {
+		write_pending(&self.writer, &self.queue);
+	}
+}
+
+impl Drop for FileSink {
+	/// Removes the listener and stops the background thread, it writes all
+	/// the pending lines before closing the file.
+	fn drop(&mut self) {
+		remove_log_hook(self.listener);
+		self.running.store(false, Ordering::SeqCst);
+		if let Some(handle) = self.handle.take() {
+			let _ = handle.join();
+		}
+	}
+}
+
+/// Writes the lines into the file rotating it when it is too big.
+struct RotatingWriter {
+	/// The path of the file being written.
+	path: PathBuf,
+
+	/// Defines when the file is rotated.
+	rotation: Rotation,
+
+	/// The current file, it is not present if it could not be opened.
+	writer: Option<BufWriter<File>>,
+
+	/// The number of bytes in the current file.
+	written: u64,
+
+	/// A flag used to only report the first error, otherwise each message
+	/// would report the same error.
+	error_reported: bool
+}
+
+impl RotatingWriter {
+	/// Opens (or creates) the file and returns a new `RotatingWriter`.
+	///
+	/// # Arguments
+	///
+	/// `path` - The path of the file.
+	/// `rotation` - Defines when the file is rotated.
+	fn open(path: PathBuf, rotation: Rotation) -> io::Result<Self> {
+		let file: File = open_file(&path)?;
+		let written: u64 = file.metadata()?.len();
+
+		Ok(Self {
+			path,
+			rotation,
+			writer: Some(BufWriter::new(file)),
+			written,
+			error_reported: false
+		})
+	}
+
+	/// Writes the line rotating the file if it does not fit.
+	///
+	/// # Arguments
+	///
+	/// `line` - The line to write without the new line.
+	fn write_line(&mut self, line: &str) {
+		let size: u64 = line.len() as u64 + 1;
+		if self.written > 0 && self.written + size > self.rotation.max_bytes {
+			if let Err(e) = self.rotate() {
+				self.report(e);
+			}
+		}
+
+		let result = match self.writer.as_mut() {
+			Some(writer) => writeln!(writer, "{}", line),
+			None => return
+		};
+
+		match result {
+			Ok(_) => self.written += size,
+			Err(e) => self.report(e)
+		}
+	}
+
+	/// Flushes the buffered lines into the file.
+	fn flush(&mut self) {
+		let result = match self.writer.as_mut() {
+			Some(writer) => writer.flush(),
+			None => return
+		};
+
+		if let Err(e) = result {
+			self.report(e);
+		}
+	}
+
+	/// Shifts the old files and opens a new empty file.
+	fn rotate(&mut self) -> io::Result<()> {
+		// Close the current file before renaming it.
+		self.flush();
+		self.writer = None;
+		self.written = 0;
+
+		// The oldest file is overwritten by the next one.
+		for index in (1..self.rotation.max_files).rev() {
+			let source: PathBuf = match index {
+				1 => self.path.clone(),
+				_ => rotated_path(&self.path, index - 1)
+			};
+			if source.exists() {
+				let destination = rotated_path(&self.path, index);
+				if destination.exists() {
+					fs::remove_file(&destination)?;
+				}
+				fs::rename(&source, &destination)?;
+			}
+		}
+
+		// Only one file is kept, so it is truncated.
+		if self.rotation.max_files <= 1 {
+			File::create(&self.path)?;
+		}
+
+		self.writer = Some(BufWriter::new(open_file(&self.path)?));
+		Ok(())
+	}
+
+	/// Reports the error in the standard error, only the first error is
+	/// reported.
+	///
+	/// The log can not be used due the writer is called from a log
+	/// listener.
+	///
+	/// # Arguments
+	///
+	/// `error` - The error to report.
+	fn report(&mut self, error: io::Error) {
+		if self.error_reported {
+			return;
+		}
+		self.error_reported = true;
+		eprintln!("[ERROR] Log file {} failed: {}", self.path.display(), error);
+	}
+}
+
+/// Opens (or creates) the file in append mode.
+///
+/// # Arguments
+///
+/// `path` - The path of the file.
+fn open_file(path: &Path) -> io::Result<File> {
+	OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)
+}
+
+/// Returns the path of the rotated file, `path.index`.
+///
+/// # Arguments
+///
+/// `path` - The path of the current file.
+/// `index` - The index of the rotated file, 1 is the newest.
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+	let mut name = path.as_os_str().to_os_string();
+	name.push(format!(".{}", index));
+	PathBuf::from(name)
+}
+
+/// Writes all the pending lines and flushes the file.
+///
+/// # Arguments
+///
+/// `writer` - The destination writer.
+/// `queue` - The queue which contains the pending lines.
+fn write_pending(writer: &Mutex<RotatingWriter>, queue: &SegQueue<String>) {
+	// The lines are taken while holding the writer, so the order is kept
+	// when the background thread and `flush` run at the same time.
+	let mut writer_lock = writer.lock().unwrap();
+	while let Some(line) = queue.pop() {
+		writer_lock.write_line(&line);
+	}
+	writer_lock.flush();
+}
+
+/// Spawns and returns the thread which writes the lines into the file.
+///
+/// # Arguments
+///
+/// `writer` - The destination writer.
+/// `queue` - The queue which contains the pending lines.
+/// `running` - The flag used to stop the thread.
+fn flush_loop(
+	writer: Arc<Mutex<RotatingWriter>>,
+	queue: Arc<SegQueue<String>>,
+	running: Arc<AtomicBool>) -> io::Result<JoinHandle<()>> {
+	thread::Builder::new()
+		.name("Log file sink".to_string())
+		.spawn(move || {
+			loop {
+				// Read the flag before draining so the lines pushed before
+				// the stop request are always written.
+				let keep_running = running.load(Ordering::SeqCst);
+
+				write_pending(&writer, &queue);
+
+				if !keep_running {
+					return;
+				}
+
+				thread::sleep(FLUSH_INTERVAL);
+			}
+		})
+}
+
+/// Formats the entry as `[ISO8601][SEVERITY] message key=value`.
+///
+/// # Arguments
+///
+/// `entry` - The entry to be formatted.
+fn format_entry(entry: &LogEntry) -> String {
+	// The message could contain new lines, keep one entry per line.
+	let message = entry.buffer.replace('\n', "\\n");
+
+	format!(
+		"[{}][{}] {}{}",
+		entry.date.to_rfc3339(),
+		entry.severity,
+		message,
+		format_fields(entry)
+	)
+}
+
+#[test]
+fn rotation_keeps_max_files() {
+	use crate::{Log, info};
+
+	let directory = std::env::temp_dir()
+		.join(format!("crystal_log_rotation_{}", std::process::id()));
+	let _ = fs::remove_dir_all(&directory);
+	fs::create_dir_all(&directory).unwrap();
+
+	Log::init();
+	let rotation = Rotation { max_files: 3, max_bytes: 4096 };
+	let sink = FileSink::init(directory.join("crystal.log"), rotation).unwrap();
+	for i in 0..10000 {
+		info(&format!("rotation {}", i));
+	}
+	sink.flush();
+	drop(sink);
+
+	let files: Vec<PathBuf> = fs::read_dir(&directory)
+		.unwrap()
+		.map(|e| e.unwrap().path())
+		.collect();
+	assert_eq!(files.len(), 3);
+	for file in files.iter() {
+		assert!(fs::metadata(file).unwrap().len() <= rotation.max_bytes);
+	}
+
+	// The newest entry is always in the current file.
+	let current = fs::read_to_string(directory.join("crystal.log")).unwrap();
+	assert!(current.contains("][INFO] rotation 9999"));
+
+	fs::remove_dir_all(&directory).unwrap();
+}
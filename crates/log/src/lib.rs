@@ -3,138 +3,487 @@ extern crate crossbeam_queue;
 mod console;
 pub use console::Console;
 
+mod file;
+pub use file::{FileSink, Rotation};
+
+// The macros are exported at the root of the crate, `log::log_info!`.
+#[cfg(feature = "std")]
+#[macro_use]
+mod macros;
+
 use chrono::{DateTime, Utc};
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::VecDeque,
+    env,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+        OnceLock,
+        RwLock
+    }
+};
 
-use crossbeam_queue::SegQueue;
+/// Creates a global LOG holder, it is set only once by `Log::init`.
+static LOG: OnceLock<Log> = OnceLock::new();
 
-/// Creates a global LOG holder.
-static mut LOG: Option<Arc<Log>> = None;
+/// The number of entries kept by the log, the oldest entries are discarded
+/// when the limit is reached.
+pub const DEFAULT_LOG_CAPACITY: usize = 1024;
 
+/// The environment variable which defines the minimum severity written by
+/// the sinks, e.g. `CRYSTAL_LOG=warn`.
+pub const LOG_LEVEL_ENV: &str = "CRYSTAL_LOG";
+
+/// Defines the severity of the entries, the order of the variants is used
+/// to filter the entries by level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogSeverity {
-    INFO,
-    WARNING,
-    ERROR
+    DEBUG = 0,
+    INFO = 1,
+    WARNING = 2,
+    ERROR = 3
+}
+
+impl fmt::Display for LogSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogSeverity::DEBUG => "DEBUG",
+            LogSeverity::INFO => "INFO",
+            LogSeverity::WARNING => "WARNING",
+            LogSeverity::ERROR => "ERROR",
+        };
+        write!(f, "{}", name)
+    }
 }
 
-impl ToString for LogSeverity {
-    fn to_string(&self) -> String {
-        match self {
-            LogSeverity::INFO => "INFO".to_string(),
-            LogSeverity::WARNING => "WARNING".to_string(),
-            LogSeverity::ERROR => "ERROR".to_string(),
+impl FromStr for LogSeverity {
+    type Err = String;
+
+    /// Parses the severity ignoring the case, `warn` is accepted as
+    /// `WARNING`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "debug" => Ok(LogSeverity::DEBUG),
+            "info" => Ok(LogSeverity::INFO),
+            "warn" | "warning" => Ok(LogSeverity::WARNING),
+            "error" => Ok(LogSeverity::ERROR),
+            _ => Err(format!("Unknown log severity: {}", s))
         }
     }
 }
 
+/// Returns the minimum severity defined by the `CRYSTAL_LOG` environment
+/// variable, None if it is not defined or it is not valid.
+pub fn level_from_env() -> Option<LogSeverity> {
+    env::var(LOG_LEVEL_ENV).ok()?.parse().ok()
+}
+
+#[derive(Clone, Debug)]
 pub struct LogEntry {
     date: DateTime<Utc>,
     buffer: String,
-    severity: LogSeverity
+    severity: LogSeverity,
+    /// Contains the structured fields attached to the entry as key value
+    /// pairs.
+    fields: Vec<(String, String)>
 }
 
 impl LogEntry {
-    fn info(str: &str) -> Self {
-        Self::new_instance(str, LogSeverity::INFO)
+    fn new_instance(
+        str: &str,
+        severity: LogSeverity,
+        fields: &[(&str, &str)]) -> Self {
+        Self {
+            date: Utc::now(),
+            buffer: String::from(str),
+            severity,
+            fields: fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        }
     }
 
-    fn warning(str: &str) -> Self {
-        Self::new_instance(str, LogSeverity::WARNING)
+    /// Returns the moment when the entry was created.
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.date
     }
 
-    fn error(str: &str) -> Self {
-        Self::new_instance(str, LogSeverity::ERROR)
+    /// Returns the message of the entry.
+    pub fn message(&self) -> &str {
+        &self.buffer
     }
 
-    fn new_instance(str: &str, severity: LogSeverity) -> Self {
-        Self {
-            date: Utc::now(),
-            buffer: String::from(str),
-            severity: severity
-        }
+    /// Returns the severity of the entry.
+    pub fn severity(&self) -> LogSeverity {
+        self.severity
+    }
+
+    /// Returns the structured fields attached to the entry.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
     }
 }
 
+/// Identifies a registered listener, it is used to remove the listener.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ListenerHandle(pub usize);
+
+/// Defines the listeners, they are called from any thread which logs.
+///
+/// They are shared so the dispatch can call them without holding the lock,
+/// in this way a listener can log or remove listeners.
+type Listener = Arc<dyn Fn(&LogEntry) + Send + Sync>;
+
 /// Defines a log entry 
 pub struct Log {
-    /// Contains all the entries in the log.
-    entries: SegQueue<LogEntry>,
-    listeners: Mutex<Vec<Box<dyn Fn(&LogEntry)>>>
+    /// Contains the last entries in the log, from the oldest to the newest.
+    entries: Mutex<VecDeque<LogEntry>>,
+    /// Contains the maximum number of entries kept.
+    capacity: usize,
+    /// Contains all the listeners along with their id.
+    listeners: RwLock<Vec<(usize, Listener)>>,
+    /// Contains the id used by the next registered listener.
+    next_listener_id: AtomicUsize,
+    /// Contains the minimum severity allowed, the entries below it are
+    /// ignored.
+    level: AtomicU8
+}
+
+impl Default for Log {
+    /// Creates and returns a new Log.
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Log {
     /// Creates and returns a new Log.
     pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_LOG_CAPACITY)
+    }
+
+    /// Creates and returns a new Log which keeps at most `capacity`
+    /// entries.
+    ///
+    /// # Arguments
+    ///
+    /// `capacity` - The maximum number of entries kept.
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
-             entries: SegQueue::new(),
-             listeners: Mutex::new(Vec::new())
+             entries: Mutex::new(VecDeque::with_capacity(capacity)),
+             capacity,
+             listeners: RwLock::new(Vec::new()),
+             next_listener_id: AtomicUsize::new(0),
+             level: AtomicU8::new(LogSeverity::INFO as u8)
         }
     }
 
-    /// Inits the log, this is mandatory in order to be able to use it.
+    /// Inits the log, the entries are only stored and sent to the
+    /// listeners after calling it. Calling it again does not have effect.
     pub fn init() {
-        unsafe {
-            LOG = Some(Arc::new(Log::new()))
+        Self::global();
+    }
+
+    /// Returns the global log, it is initialized if needed.
+    fn global() -> &'static Log {
+        LOG.get_or_init(Log::new)
+    }
+
+    /// Sets the minimum severity allowed, all the entries below it will be
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// `level` - The minimum severity.
+    pub fn set_level(level: LogSeverity) {
+        Self::global().level.store(level as u8, Ordering::Relaxed);
+    }
+
+    /// Returns true if the severity is allowed by the current level.
+    ///
+    /// # Arguments
+    ///
+    /// `severity` - The severity to check.
+    fn is_enabled(&self, severity: LogSeverity) -> bool {
+        severity as u8 >= self.level.load(Ordering::Relaxed)
+    }
+
+    /// Removes and returns up to `max` entries of the global log, from the
+    /// oldest to the newest.
+    ///
+    /// # Arguments
+    ///
+    /// `max` - The maximum number of entries to remove.
+    pub fn drain(max: usize) -> Vec<LogEntry> {
+        match LOG.get() {
+            Some(log) => log.drain_entries(max),
+            None => Vec::new()
+        }
+    }
+
+    /// Returns a copy of the last `n` entries of the global log, from the
+    /// oldest to the newest.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The maximum number of entries to return.
+    pub fn recent(n: usize) -> Vec<LogEntry> {
+        match LOG.get() {
+            Some(log) => log.recent_entries(n),
+            None => Vec::new()
         }
     }
+
+    /// Stores the entry, the oldest entry is discarded if the log is full.
+    ///
+    /// # Arguments
+    ///
+    /// `entry` - The entry to store.
+    fn push_entry(&self, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries_lock = self.entries.lock().unwrap();
+        if entries_lock.len() == self.capacity {
+            entries_lock.pop_front();
+        }
+        entries_lock.push_back(entry);
+    }
+
+    /// Removes and returns up to `max` of the oldest entries.
+    fn drain_entries(&self, max: usize) -> Vec<LogEntry> {
+        let mut entries_lock = self.entries.lock().unwrap();
+        let amount = max.min(entries_lock.len());
+        entries_lock.drain(..amount).collect()
+    }
+
+    /// Returns a copy of the last `n` entries.
+    fn recent_entries(&self, n: usize) -> Vec<LogEntry> {
+        let entries_lock = self.entries.lock().unwrap();
+        let skip = entries_lock.len().saturating_sub(n);
+        entries_lock.iter().skip(skip).cloned().collect()
+    }
 }
 
-pub fn log_hook(hook: fn(&LogEntry)) {
-    unsafe {
-        if let Some(log) = &LOG {
-            let mut listeners_lock = log.listeners.lock().unwrap();
-            listeners_lock.push(Box::new(hook));
-            return
-        }    
+/// Registers a new listener which will be called for each new entry, it
+/// returns the handle used to remove it.
+///
+/// # Arguments
+///
+/// `hook` - The listener to be registered.
+pub fn log_hook<F: 'static + Fn(&LogEntry) + Send + Sync>(hook: F) -> ListenerHandle {
+    let log = Log::global();
+    let id = log.next_listener_id.fetch_add(1, Ordering::Relaxed);
+    let mut listeners_lock = log.listeners.write().unwrap();
+    listeners_lock.push((id, Arc::new(hook)));
+    ListenerHandle(id)
+}
+
+/// Removes a listener, after calling this function the listener will not
+/// be called anymore.
+///
+/// # Arguments
+///
+/// `handle` - The handle returned when the listener was registered.
+pub fn remove_log_hook(handle: ListenerHandle) {
+    if let Some(log) = LOG.get() {
+        let mut listeners_lock = log.listeners.write().unwrap();
+        listeners_lock.retain(|(id, _)| *id != handle.0);
     }
-    panic!("Log is not initializated");
+}
+
+/// Logs a debug log message.
+pub fn debug(str: &str) {
+    debug_with(str, &[]);
+}
+
+/// Logs a debug log message with structured fields.
+///
+/// # Arguments
+///
+/// `str` - The message.
+/// `fields` - The key value pairs attached to the message.
+pub fn debug_with(str: &str, fields: &[(&str, &str)]) {
+    dispatch(str, LogSeverity::DEBUG, fields);
 }
 
 /// Logs an info log message.
 pub fn info(str: &str) {
-    unsafe {
-        if let Some(log) = &LOG {
-            let entry = LogEntry::info(str);
-            let listeners_lock = log.listeners.lock().unwrap();
-            for listener in listeners_lock.iter() {
-                listener(&entry);
-            }
-            log.entries.push(entry);
-            return   
-        }    
-    }
-    panic!("Log is not initializated");
+    info_with(str, &[]);
 }
 
+/// Logs an info log message with structured fields.
+///
+/// # Arguments
+///
+/// `str` - The message.
+/// `fields` - The key value pairs attached to the message.
+pub fn info_with(str: &str, fields: &[(&str, &str)]) {
+    dispatch(str, LogSeverity::INFO, fields);
+}
 
 /// Logs an warning log message.
 pub fn warning(str: &str) {
-    unsafe {
-        if let Some(log) = &LOG {
-            let entry = LogEntry::warning(str);
-            let listeners_lock = log.listeners.lock().unwrap();
-            for listener in listeners_lock.iter() {
-                listener(&entry);
-            }
-            log.entries.push(entry);
-            return
-        }    
-    }
-    panic!("Log is not initializated");
+    warning_with(str, &[]);
+}
+
+/// Logs an warning log message with structured fields.
+///
+/// # Arguments
+///
+/// `str` - The message.
+/// `fields` - The key value pairs attached to the message.
+pub fn warning_with(str: &str, fields: &[(&str, &str)]) {
+    dispatch(str, LogSeverity::WARNING, fields);
 }
 
 /// Logs an error log message.
 pub fn error(str: &str) {
-    unsafe {
-        if let Some(log) = &LOG {
-            let entry = LogEntry::error(str);
-            let listeners_lock = log.listeners.lock().unwrap();
-            for listener in listeners_lock.iter() {
-                listener(&entry);
+    error_with(str, &[]);
+}
+
+/// Logs an error log message with structured fields.
+///
+/// # Arguments
+///
+/// `str` - The message.
+/// `fields` - The key value pairs attached to the message.
+pub fn error_with(str: &str, fields: &[(&str, &str)]) {
+    dispatch(str, LogSeverity::ERROR, fields);
+}
+
+/// Creates a new entry and sends it to all the listeners.
+///
+/// If the log is not initialized the warnings and errors are written into
+/// the standard error and the rest is ignored.
+///
+/// # Arguments
+///
+/// `str` - The message.
+/// `severity` - The severity of the entry.
+/// `fields` - The key value pairs attached to the message.
+fn dispatch(str: &str, severity: LogSeverity, fields: &[(&str, &str)]) {
+    with_log(severity, str, |log| {
+        // Avoid creating the entry and taking the lock if the entry
+        // is going to be ignored.
+        if !log.is_enabled(severity) {
+            return
+        }
+        let entry = LogEntry::new_instance(str, severity, fields);
+        // The listeners are copied and the lock released before calling
+        // them, a listener could log or remove a listener.
+        let listeners: Vec<Listener> = log.listeners
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect();
+        for listener in listeners {
+            listener(&entry);
+        }
+        log.push_entry(entry);
+    });
+}
+
+/// Executes the closure with the global log, if the log is not initialized
+/// the important messages fall back to the standard error.
+///
+/// # Arguments
+///
+/// `severity` - The severity of the message.
+/// `str` - The message.
+/// `f` - The closure executed with the log.
+fn with_log<F: FnOnce(&Log)>(severity: LogSeverity, str: &str, f: F) {
+    match LOG.get() {
+        Some(log) => f(log),
+        None if severity >= LogSeverity::WARNING => {
+            eprintln!("[{}] {}", severity, str);
+        },
+        None => ()
+    }
+}
+
+#[test]
+fn concurrent_logging() {
+    use std::thread;
+
+    Log::init();
+    Log::set_level(LogSeverity::DEBUG);
+
+    // Only count the entries of this test, other tests could log too.
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter_copy = counter.clone();
+    let handle = log_hook(move |entry: &LogEntry| {
+        if entry.buffer.starts_with("concurrent") {
+            counter_copy.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    let threads: Vec<_> = (0..8)
+        .map(|i| thread::spawn(move || {
+            for j in 0..100 {
+                info_with("concurrent", &[("thread", &i.to_string()), ("j", &j.to_string())]);
+            }
+        }))
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    remove_log_hook(handle);
+    assert_eq!(counter.load(Ordering::SeqCst), 800);
+}
+
+#[test]
+fn listener_can_log_and_remove_itself() {
+    Log::init();
+    Log::set_level(LogSeverity::DEBUG);
+
+    // The listener logs and removes itself, it must not deadlock.
+    let handle: Arc<Mutex<Option<ListenerHandle>>> = Arc::new(Mutex::new(None));
+    let handle_copy = handle.clone();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_copy = calls.clone();
+    let id = log_hook(move |entry: &LogEntry| {
+        if entry.buffer == "reentrant" {
+            calls_copy.fetch_add(1, Ordering::SeqCst);
+            info("reentrant listener");
+            if let Some(h) = handle_copy.lock().unwrap().take() {
+                remove_log_hook(h);
             }
-            log.entries.push(entry);
-            return 
-        }    
+        }
+    });
+    *handle.lock().unwrap() = Some(id);
+
+    info("reentrant");
+    info("reentrant");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn ring_buffer_caps_entries() {
+    let log = Log::with_capacity(4);
+    for i in 0..10 {
+        log.push_entry(LogEntry::new_instance(&i.to_string(), LogSeverity::INFO, &[]));
     }
-    panic!("Log is not initializated");
+
+    // Only the newest entries are kept, in the order they were logged.
+    let recent: Vec<String> = log.recent_entries(10)
+        .iter()
+        .map(|e| e.message().to_string())
+        .collect();
+    assert_eq!(recent, vec!["6", "7", "8", "9"]);
+    assert_eq!(log.recent_entries(2)[0].message(), "8");
+
+    // Draining removes the oldest entries first.
+    let drained = log.drain_entries(3);
+    assert_eq!(drained.len(), 3);
+    assert_eq!(drained[0].message(), "6");
+    assert_eq!(drained[2].message(), "8");
+    assert_eq!(log.drain_entries(10).len(), 1);
+    assert!(log.recent_entries(10).is_empty());
 }
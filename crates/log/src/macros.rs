@@ -0,0 +1,41 @@
+/// Logs a debug message using a format string, it accepts the same
+/// arguments as `println!`.
+#[macro_export]
+macro_rules! log_debug {
+	($($arg:tt)*) => {
+		$crate::debug(&format!($($arg)*))
+	};
+}
+
+/// Logs an info message using a format string, it accepts the same
+/// arguments as `println!`.
+///
+/// # Example
+///
+/// ```ignore
+/// log_info!("Number of entities: {}", count);
+/// ```
+#[macro_export]
+macro_rules! log_info {
+	($($arg:tt)*) => {
+		$crate::info(&format!($($arg)*))
+	};
+}
+
+/// Logs a warning message using a format string, it accepts the same
+/// arguments as `println!`.
+#[macro_export]
+macro_rules! log_warn {
+	($($arg:tt)*) => {
+		$crate::warning(&format!($($arg)*))
+	};
+}
+
+/// Logs an error message using a format string, it accepts the same
+/// arguments as `println!`.
+#[macro_export]
+macro_rules! log_error {
+	($($arg:tt)*) => {
+		$crate::error(&format!($($arg)*))
+	};
+}
@@ -0,0 +1,50 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    },
+    thread::yield_now,
+    time::Instant
+};
+
+use tasks::{Workers, Dispatcher, TaskPriority};
+
+/// Defines the number of tasks sent per frame.
+const TASKS_PER_FRAME: usize = 1000;
+
+/// Defines the number of frames executed.
+const FRAMES: u32 = 200;
+
+fn main() {
+    let mut workers: Workers = Workers::default();
+    workers.start();
+
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let start = Instant::now();
+    for frame in 1..=FRAMES {
+        for _ in 0..TASKS_PER_FRAME {
+            let counter_copy = counter.clone();
+            workers.execute_dyn(Box::new(move || {
+                // Simulate a small system.
+                let mut value: u64 = 0;
+                for i in 0..1000 {
+                    value = value.wrapping_add(i * i);
+                }
+                std::hint::black_box(value);
+                counter_copy.fetch_add(1, Ordering::SeqCst);
+            }), TaskPriority::Normal, None);
+        }
+
+        // Wait until all the tasks of the frame finish.
+        while counter.load(Ordering::SeqCst) < frame as usize * TASKS_PER_FRAME {
+            yield_now();
+        }
+    }
+
+    println!(
+        "{} tasks per frame: {:?} per frame",
+        TASKS_PER_FRAME,
+        start.elapsed() / FRAMES
+    );
+}
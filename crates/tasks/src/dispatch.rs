@@ -1,4 +1,4 @@
-use crate::task::Task;
+use crate::task::{Task, TaskPriority, CancellationToken};
 
 /// Defines how a dispatcher should behave.
 /// 
@@ -7,10 +7,27 @@ use crate::task::Task;
 pub trait Dispatcher {
     /// Defines a entry point for the Dispatcher.
     fn start(&mut self);
+
+    /// Defines a exit point for the Dispatcher, it must wait until all
+    /// the work in progress finishes. The queued work must not be lost,
+    /// somebody could be waiting for it.
+    fn stop(&mut self);
  
-    /// Defines a task exection by dynamic dispatching.
-    fn execute_dyn(&self, task: Task);
+    /// Defines a task exection by dynamic dispatching using the provided
+    /// priority, if the token is cancelled before the task starts the task
+    /// is discarded.
+    fn execute_dyn(
+        &self,
+        task: Task,
+        priority: TaskPriority,
+        token: Option<CancellationToken>);
 
-    /// Defines a tasks execution by dynamic dispatching. 
+    /// Defines a tasks execution by dynamic dispatching using the normal
+    /// priority.
     fn execute_batch(&self, tasks: Vec<Task>);
+
+    /// Defines a task execution which runs in the calling thread when
+    /// there is not any worker available, in this way the caller never
+    /// waits for a saturated pool.
+    fn execute_blocking(&self, task: Task);
 }
\ No newline at end of file
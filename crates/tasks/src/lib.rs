@@ -2,9 +2,9 @@ mod dispatch;
 pub use dispatch::Dispatcher;
 
 mod task;
-pub use task::{Executable, Task};
+pub use task::{Executable, Task, TaskPriority, CancellationToken};
 
 mod workers;
-pub use workers::{Workers, WorkersDescriptor};
+pub use workers::{Workers, WorkersDescriptor, DEFAULT_WORKER_STACK_SIZE};
 
 extern crate num_cpus;
\ No newline at end of file
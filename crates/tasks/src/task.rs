@@ -1,5 +1,8 @@
 use std::{
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc
+    },
     fmt::{Debug, Result, Formatter}
 };
 
@@ -19,6 +22,43 @@ pub trait Executable {
 }
 
 pub type Task = Box<dyn FnOnce() + Send + 'static>;
+
+/// A token used to cancel tasks.
+///
+/// If the token is cancelled before the task starts the task is discarded,
+/// long running tasks can keep a clone of the token and check it
+/// periodically in order to stop early.
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken {
+    /// Contains a flag which determines if the token was cancelled.
+    cancelled: Arc<AtomicBool>
+}
+
+impl CancellationToken {
+    /// Creates and returns a new `CancellationToken`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all the tasks which use the token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Defines the priority of a task, the tasks with higher priority are
+/// executed first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskPriority {
+    High,
+    Normal,
+    Low
+}
 /*
 /// Defines a wrapper which contains the callback and some useful 
 /// debug information. 
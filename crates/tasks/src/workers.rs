@@ -1,27 +1,217 @@
 use std::{
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+        RwLock,
+        Mutex,
+        Condvar
+    },
     thread::{JoinHandle, Builder, yield_now},
+    time::Duration,
     fmt::{Debug, Result, Formatter},
+    iter::repeat_with
 };
 
-use crossbeam_queue::SegQueue;
+use crossbeam_deque::{Injector, Stealer, Steal, Worker as LocalQueue};
 
 use crate::{
     dispatch::Dispatcher,
-    task::{Task}
+    task::{Task, TaskPriority, CancellationToken}
 };
 
+/// Contains a task waiting to be executed.
+struct QueuedTask {
+    /// The work to get done.
+    task: Task,
+
+    /// The token used to discard the task.
+    token: Option<CancellationToken>
+}
+
+impl QueuedTask {
+    /// Returns true if the task was cancelled.
+    fn is_cancelled(&self) -> bool {
+        self.token.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+
+    /// Executes the task, the cancelled tasks are discarded without
+    /// executing them.
+    ///
+    /// # Arguments
+    ///
+    /// `busy` - The counter of workers executing a task.
+    fn run(self, busy: &AtomicUsize) {
+        if !self.is_cancelled() {
+            busy.fetch_add(1, Ordering::SeqCst);
+            (self.task)();
+            busy.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Defines the maximum time an idle worker sleeps before checking the
+/// local queues of the other workers.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Defines the number of times an idle worker looks for tasks before going
+/// to sleep.
+const IDLE_ROUNDS_BEFORE_SLEEP: usize = 64;
+
+/// Contains one global queue per priority.
+///
+/// The workers move the tasks from these queues into their local queues in
+/// batches, the local queues are only pushed by its owner thread.
+#[derive(Default)]
+struct TaskQueues {
+    high: Injector<QueuedTask>,
+    normal: Injector<QueuedTask>,
+    low: Injector<QueuedTask>,
+
+    /// The lock used together with `wakeup` to put the idle workers to
+    /// sleep.
+    sleep_lock: Mutex<()>,
+
+    /// Used to wake up the idle workers when a new task arrives.
+    wakeup: Condvar,
+
+    /// Contains the number of workers sleeping, it allows to skip the
+    /// notifications when all the workers are busy.
+    sleeping: AtomicUsize,
+
+    /// Contains the number of workers executing a task.
+    busy: AtomicUsize
+}
+
+impl TaskQueues {
+    /// Pushes the task into the queue of its priority, the workers are not
+    /// notified.
+    ///
+    /// # Arguments
+    ///
+    /// `task` - The task to be pushed.
+    /// `priority` - The priority of the task.
+    fn push(&self, task: QueuedTask, priority: TaskPriority) {
+        match priority {
+            TaskPriority::High => self.high.push(task),
+            TaskPriority::Normal => self.normal.push(task),
+            TaskPriority::Low => self.low.push(task)
+        }
+    }
+
+    /// Wakes up one sleeping worker per new task.
+    ///
+    /// # Arguments
+    ///
+    /// `tasks` - The number of new tasks.
+    fn notify(&self, tasks: usize) {
+        if self.sleeping.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        // Taking the lock ensures the notification is not lost if a worker
+        // is about to sleep.
+        let _lock = self.sleep_lock.lock().unwrap();
+        let sleeping: usize = self.sleeping.load(Ordering::SeqCst);
+        for _ in 0..tasks.min(sleeping) {
+            self.wakeup.notify_one();
+        }
+    }
+
+    /// Pops and returns the next task of the global queues, from the
+    /// highest priority to the lowest.
+    fn pop_global(&self) -> Option<QueuedTask> {
+        steal_task(|| self.high.steal())
+            .or_else(|| steal_task(|| self.normal.steal()))
+            .or_else(|| steal_task(|| self.low.steal()))
+    }
+
+    /// Returns true if there are not tasks in the global queues.
+    fn is_empty(&self) -> bool {
+        self.high.is_empty() && self.normal.is_empty() && self.low.is_empty()
+    }
+
+    /// Puts the worker to sleep until a new task arrives or the worker is
+    /// stopped.
+    ///
+    /// # Arguments
+    ///
+    /// `stop_flag` - The flag used to stop the worker.
+    fn wait_for_tasks(&self, stop_flag: &AtomicBool) {
+        let lock = self.sleep_lock.lock().unwrap();
+        // The worker is counted as sleeping before checking the queues, so
+        // a task pushed after the check always notifies it.
+        self.sleeping.fetch_add(1, Ordering::SeqCst);
+        if self.is_empty() && !stop_flag.load(Ordering::SeqCst) {
+            let _ = self.wakeup.wait_timeout(lock, IDLE_TIMEOUT);
+        }
+        self.sleeping.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Wakes up all the sleeping workers.
+    fn wake_all(&self) {
+        let _lock = self.sleep_lock.lock().unwrap();
+        self.wakeup.notify_all();
+    }
+
+    /// Pops and returns the next task for the worker.
+    ///
+    /// The high priority tasks are always returned first, then the tasks in
+    /// the local queue, then the normal and low priority tasks and finally
+    /// the tasks stolen from other workers.
+    ///
+    /// # Arguments
+    ///
+    /// `local` - The local queue of the worker.
+    /// `stealers` - The stealers of the other workers.
+    fn pop(
+        &self,
+        local: &LocalQueue<QueuedTask>,
+        stealers: &[Stealer<QueuedTask>]) -> Option<QueuedTask> {
+        steal_task(|| self.high.steal())
+            .or_else(|| local.pop())
+            .or_else(|| steal_task(|| self.normal.steal_batch_and_pop(local)))
+            .or_else(|| steal_task(|| self.low.steal_batch_and_pop(local)))
+            .or_else(|| stealers
+                .iter()
+                .find_map(|s| steal_task(|| s.steal_batch_and_pop(local))))
+    }
+}
+
+/// Executes the steal operation until it does not need to be retried and
+/// returns the stolen task if any.
+///
+/// # Arguments
+///
+/// `steal` - The steal operation.
+fn steal_task<F: Fn() -> Steal<QueuedTask>>(steal: F) -> Option<QueuedTask> {
+    repeat_with(steal)
+        .find(|s| !s.is_retry())
+        .and_then(|s| s.success())
+}
+
 /// Just a handy wrapper of the task queue so we do not deal with 
 /// large data types.
-type TaskQueue = Arc<SegQueue<Task>>;
+type TaskQueue = Arc<TaskQueues>;
+
+/// Contains the stealers of the workers local queues, it changes when the
+/// number of workers is adjusted.
+type SharedStealers = Arc<RwLock<Vec<Stealer<QueuedTask>>>>;
+
+/// Defines the default stack size of each worker, it is the same one used
+/// by the standard library.
+pub const DEFAULT_WORKER_STACK_SIZE: usize = 2 * 1024 * 1024;
 
 /// Defines a worker.
 struct Worker {
-    /// A variable used to control the worker (Thread).
-    handle: JoinHandle<()>,
+    /// A variable used to control the worker (Thread), it is taken when
+    /// the worker is stopped.
+    handle: Option<JoinHandle<()>>,
 
     /// An id that identify the worker.
-    id: usize
+    id: usize,
+
+    /// A flag used to tell the worker when to stop.
+    stop_flag: Arc<AtomicBool>
 }
 
 /// Defines the characteristics of the `ThreadPool`.
@@ -29,7 +219,25 @@ pub struct WorkersDescriptor {
     /// Contains the number of workers needed.
     pub amount: usize,
     /// A name used to identify the `ThreadPool`
-    pub name: String
+    pub name: String,
+    /// The stack size in bytes of each worker.
+    pub stack_size: usize
+}
+
+/// Provides a default descriptor.
+///
+/// The amount of workers will be calculated based on the number
+/// of CPU that the host provides (number of cores * 2).
+impl Default for WorkersDescriptor {
+    fn default() -> Self {
+        Self {
+            // Get the number of CPUs and calculate the amount of 
+            // workers needed.
+            amount: num_cpus::get() * 2,
+            name: "Crystal workers".to_string(),
+            stack_size: DEFAULT_WORKER_STACK_SIZE
+        }
+    }
 }
 
 /// Defines a `ThreadPool`.
@@ -41,14 +249,25 @@ pub struct Workers {
 
     /// Contains all the spawned threads. We do not need any return
     /// from the execution so avoiding that.
-    workers: Vec<Worker>,
+    ///
+    /// It is behind a lock in order to adjust the number of workers
+    /// at runtime.
+    workers: RwLock<Vec<Worker>>,
 
     /// The task queue shared across threads.
     /// 
     /// TODO(Angel): We could use a Box insted of a Rc and drop it 
     /// when the Workers is destroyed because the threads should be
     /// stoped before the Workers deletion.
-    queue: TaskQueue
+    queue: TaskQueue,
+
+    /// Contains the stealers of the workers local queues, the idle workers
+    /// use them to take tasks from the busy ones.
+    stealers: SharedStealers,
+
+    /// True once the workers are stopped, the tasks submitted after it are
+    /// executed in the calling thread.
+    stopped: AtomicBool
 }
 
 /// Provides defaults constructors for `Workers`.
@@ -58,8 +277,10 @@ impl Workers {
     pub fn new(descriptor: WorkersDescriptor) -> Self {
         Self {
             descriptor,
-            workers: Vec::new(),
-            queue: Arc::new(SegQueue::new())
+            workers: RwLock::new(Vec::new()),
+            queue: Arc::new(TaskQueues::default()),
+            stealers: Arc::new(RwLock::new(Vec::new())),
+            stopped: AtomicBool::new(false)
         }
     }
 }
@@ -72,41 +293,101 @@ impl Default for Workers {
     /// Creates and returns a new `Worker` based on the default
     /// configuration.
     fn default() -> Self {
-        // Create the `Workers`.
-        Self {
-            descriptor: WorkersDescriptor {
-                // Get the number of CPUs and calculate the amount of 
-                // workers needed.
-                amount: num_cpus::get() * 2,
-                name: "Crystal workers".to_string()
-            },
-            workers: Vec::new(),
-            queue: Arc::new(SegQueue::new())
-        }
+        Self::new(WorkersDescriptor::default())
     }
 }
 
 /// Useful functions.
 impl Workers {
-    fn spawn_workers(&mut self) {
-        // Copy the number of workers needed. 
-        let number_of_workers = self.descriptor.amount;
-        // Spawn all the workers.
-        for i in 0..number_of_workers {
-            // Get a clone of the reference to the queue to move that
-            // into the thread. 
-            let queue_ref: TaskQueue = self.queue.clone();
-            // Create a new worker.
-            let new_worker: Worker = Worker {
-                handle: worker_loop(
-                    format!("[{}]{:?}", i, self.descriptor.name),
-                    queue_ref
-                ), 
-                id: i
-            };
-            // Send the worker to the pool.
-            self.workers.push(new_worker);
+    /// Spawns a new worker and adds it into the pool.
+    ///
+    /// # Arguments
+    ///
+    /// `workers` - The pool where the worker is added.
+    fn spawn_worker(&self, workers: &mut Vec<Worker>) {
+        let id: usize = workers.len();
+        // The stealer is published before the thread starts, in this way
+        // the rest of the workers can take its tasks.
+        let local: LocalQueue<QueuedTask> = LocalQueue::new_fifo();
+        self.stealers.write().unwrap().push(local.stealer());
+
+        let stop_flag: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        // Create a new worker.
+        let new_worker: Worker = Worker {
+            handle: Some(worker_loop(
+                format!("[{}]{:?}", id, self.descriptor.name),
+                self.descriptor.stack_size,
+                self.queue.clone(),
+                local,
+                self.stealers.clone(),
+                stop_flag.clone()
+            )),
+            id,
+            stop_flag
+        };
+        // Send the worker to the pool.
+        workers.push(new_worker);
+    }
+
+    /// Adjusts the number of workers, it spawns the missing ones or
+    /// retires the excess.
+    ///
+    /// The retired workers finish the tasks they already took before
+    /// stopping, this function waits until they finish.
+    ///
+    /// # Arguments
+    ///
+    /// `n` - The new number of workers.
+    pub fn set_thread_count(&self, n: usize) {
+        let mut workers = self.workers.write().unwrap();
+
+        while workers.len() < n {
+            self.spawn_worker(&mut workers);
+        }
+
+        // The workers are retired from the last one, so the stealers keep
+        // the same order than the workers.
+        let mut retired: Vec<Worker> = Vec::new();
+        while workers.len() > n {
+            if let Some(worker) = workers.pop() {
+                worker.stop_flag.store(true, Ordering::SeqCst);
+                self.stealers.write().unwrap().pop();
+                retired.push(worker);
+            }
         }
+        self.queue.wake_all();
+
+        for mut worker in retired {
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    /// Returns the number of running workers.
+    pub fn thread_count(&self) -> usize {
+        self.workers.read().unwrap().len()
+    }
+
+    /// Executes the task in the calling thread if the workers are stopped
+    /// and returns None, otherwise the task is returned to be queued.
+    ///
+    /// # Arguments
+    ///
+    /// `task` - The task to be executed.
+    fn run_if_stopped(&self, task: QueuedTask) -> Option<QueuedTask> {
+        if self.stopped.load(Ordering::SeqCst) {
+            task.run(&self.queue.busy);
+            return None;
+        }
+        Some(task)
+    }
+}
+
+impl Drop for Workers {
+    /// Stops the workers in order to not leave threads running.
+    fn drop(&mut self) {
+        self.stop();
     }
 }
 
@@ -114,7 +395,22 @@ impl Workers {
 impl Dispatcher for Workers {
     /// Create and deploy all the workers needed.
     fn start(&mut self) {
-        self.spawn_workers();
+        self.stopped.store(false, Ordering::SeqCst);
+        self.set_thread_count(self.descriptor.amount);
+    }
+
+    /// Stops all the workers and waits until they finish.
+    ///
+    /// The tasks which are still in the global queues are executed in the
+    /// calling thread, so nobody waits forever for them. The tasks
+    /// submitted after stopping are executed in the calling thread too.
+    fn stop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.set_thread_count(0);
+
+        while let Some(queued) = self.queue.pop_global() {
+            queued.run(&self.queue.busy);
+        }
     }
 
     /// Executes the provided task by dynamic dispatching as soon as
@@ -123,8 +419,17 @@ impl Dispatcher for Workers {
     /// # Arguments
     /// 
     /// `task` -The task to be executed.
-    fn execute_dyn(&self, task: Task) {
-        self.queue.push(task);
+    /// `priority` - The priority of the task.
+    /// `token` - The token used to cancel the task.
+    fn execute_dyn(
+        &self,
+        task: Task,
+        priority: TaskPriority,
+        token: Option<CancellationToken>) {
+        if let Some(queued) = self.run_if_stopped(QueuedTask { task, token }) {
+            self.queue.push(queued, priority);
+            self.queue.notify(1);
+        }
     }
 
     /// Executes the provided tasks by dynamic dispatching as soon as
@@ -136,9 +441,30 @@ impl Dispatcher for Workers {
     fn execute_batch(
         &self,
         tasks: Vec<Task>) {
+        let number_of_tasks: usize = tasks.len();
         for task in tasks {
-            self.queue.push(task);
+            if let Some(queued) = self.run_if_stopped(QueuedTask { task, token: None }) {
+                self.queue.push(queued, TaskPriority::Normal);
+            }
         }
+        self.queue.notify(number_of_tasks);
+    }
+
+    /// Executes the provided task in a worker, if all the workers are busy
+    /// the task is executed in the calling thread.
+    ///
+    /// # Arguments
+    ///
+    /// `task` - The task to be executed.
+    fn execute_blocking(&self, task: Task) {
+        let busy: usize = self.queue.busy.load(Ordering::SeqCst);
+        if busy >= self.thread_count() {
+            task();
+            return;
+        }
+
+        self.queue.push(QueuedTask { task, token: None }, TaskPriority::Normal);
+        self.queue.notify(1);
     }
 }
 
@@ -146,27 +472,53 @@ impl Dispatcher for Workers {
 /// 
 /// # Arguments
 /// 
+/// `name` - The name of the thread.
+/// `stack_size` - The stack size of the thread.
 /// `task_queue` - The task queue referece to be moved into the loop.
+/// `local` - The local queue of the worker.
+/// `stealers` - The stealers of all the workers.
+/// `stop_flag` - The flag used to stop the loop.
 fn worker_loop(
     name: String,
-    task_queue: TaskQueue) -> JoinHandle<()> {
+    stack_size: usize,
+    task_queue: TaskQueue,
+    local: LocalQueue<QueuedTask>,
+    stealers: SharedStealers,
+    stop_flag: Arc<AtomicBool>) -> JoinHandle<()> {
 
     // Create a new thread builder.
-    // TODO(Angel): Define stack size.
     let thread_builder: Builder = Builder::new()
-                                          .name(name);
+                                          .name(name)
+                                          .stack_size(stack_size);
     match thread_builder.spawn(move || {
         // Force move ownership.
         let t_queue = task_queue;
+        // Contains the number of times in a row the worker did not find
+        // any task.
+        let mut idle_rounds: usize = 0;
 
-        loop {
+        while !stop_flag.load(Ordering::SeqCst) {
             // Get a task from the queue, if there are not tasks to
             // do go to sleep.
-            if let Some(task) = t_queue.pop() {
-                task();
-            } else {
+            let next = t_queue.pop(&local, &stealers.read().unwrap());
+            if let Some(queued) = next {
+                idle_rounds = 0;
+                queued.run(&t_queue.busy);
+            } else if idle_rounds < IDLE_ROUNDS_BEFORE_SLEEP {
+                // The tasks usually arrive in bursts, yielding a few times
+                // avoids sleeping between them.
+                idle_rounds += 1;
                 yield_now();
-            } 
+            } else {
+                // Sleep until there is something to do.
+                t_queue.wait_for_tasks(&stop_flag);
+            }
+        }
+
+        // Drain the tasks already taken by the worker, nobody else can
+        // take them once the stealer is removed.
+        while let Some(queued) = local.pop() {
+            queued.run(&t_queue.busy);
         }
     }) {
         Ok(handle) => handle,
@@ -183,7 +535,7 @@ impl Debug for Workers {
     [*] number of workers: {}
         "#,
         self.descriptor.name,
-        self.descriptor.amount)
+        self.thread_count())
     }
 }
 
@@ -196,4 +548,233 @@ impl Debug for Worker {
         "#,
         self.id)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+use std::{thread, sync::mpsc, time::Instant};
+
+#[cfg(test)]
+fn queued<F: FnOnce() + Send + 'static>(
+    task: F,
+    token: Option<CancellationToken>) -> QueuedTask {
+    QueuedTask { task: Box::new(task), token }
+}
+
+#[test]
+fn high_priority_first() {
+    let queues = TaskQueues::default();
+    let local = LocalQueue::new_fifo();
+    let order = Arc::new(Injector::new());
+
+    for (priority, id) in [
+        (TaskPriority::Low, 0),
+        (TaskPriority::Normal, 1),
+        (TaskPriority::High, 2)
+    ].iter() {
+        let order_copy = order.clone();
+        let id = *id;
+        queues.push(queued(move || order_copy.push(id), None), *priority);
+    }
+
+    while let Some(task) = queues.pop(&local, &[]) {
+        (task.task)();
+    }
+
+    assert_eq!(order.steal().success(), Some(2));
+    assert_eq!(order.steal().success(), Some(1));
+    assert_eq!(order.steal().success(), Some(0));
+}
+
+#[test]
+fn cancelled_task_is_discarded() {
+    let token = CancellationToken::new();
+    let task = queued(|| panic!("The task must not run"), Some(token.clone()));
+    assert!(!task.is_cancelled());
+
+    token.cancel();
+    assert!(task.is_cancelled());
+}
+
+#[test]
+fn stop_joins_workers() {
+    let mut workers = Workers::new(WorkersDescriptor {
+        amount: 2,
+        name: "Test workers".to_string(),
+        stack_size: DEFAULT_WORKER_STACK_SIZE
+    });
+    workers.start();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let done_copy = done.clone();
+    workers.execute_dyn(
+        Box::new(move || done_copy.store(true, Ordering::SeqCst)),
+        TaskPriority::Normal,
+        None
+    );
+
+    while !done.load(Ordering::SeqCst) {
+        yield_now();
+    }
+
+    workers.stop();
+    assert_eq!(workers.thread_count(), 0);
+}
+
+#[test]
+fn stop_runs_pending_tasks() {
+    let mut workers = Workers::new(WorkersDescriptor {
+        amount: 1,
+        name: "Test workers".to_string(),
+        stack_size: DEFAULT_WORKER_STACK_SIZE
+    });
+
+    // The tasks are queued before the workers start, so they are still in
+    // the global queue when the workers stop.
+    let counter = Arc::new(AtomicUsize::new(0));
+    for _ in 0..16 {
+        let counter_copy = counter.clone();
+        workers.execute_dyn(
+            Box::new(move || { counter_copy.fetch_add(1, Ordering::SeqCst); }),
+            TaskPriority::Low,
+            None
+        );
+    }
+    workers.stop();
+    assert_eq!(counter.load(Ordering::SeqCst), 16);
+
+    // The tasks submitted after stopping run in the calling thread.
+    let counter_copy = counter.clone();
+    workers.execute_dyn(
+        Box::new(move || { counter_copy.fetch_add(1, Ordering::SeqCst); }),
+        TaskPriority::Normal,
+        None
+    );
+    let counter_copy = counter.clone();
+    workers.execute_batch(vec![Box::new(move || { counter_copy.fetch_add(1, Ordering::SeqCst); })]);
+    assert_eq!(counter.load(Ordering::SeqCst), 18);
+}
+
+#[test]
+fn set_thread_count_resizes() {
+    let mut workers = Workers::new(WorkersDescriptor {
+        amount: 2,
+        name: "Test workers".to_string(),
+        stack_size: DEFAULT_WORKER_STACK_SIZE
+    });
+    workers.start();
+    assert_eq!(workers.thread_count(), 2);
+
+    workers.set_thread_count(4);
+    assert_eq!(workers.thread_count(), 4);
+
+    workers.set_thread_count(1);
+    assert_eq!(workers.thread_count(), 1);
+
+    // The remaining worker keeps executing tasks.
+    let done = Arc::new(AtomicBool::new(false));
+    let done_copy = done.clone();
+    workers.execute_dyn(
+        Box::new(move || done_copy.store(true, Ordering::SeqCst)),
+        TaskPriority::Normal,
+        None
+    );
+
+    while !done.load(Ordering::SeqCst) {
+        yield_now();
+    }
+}
+
+#[test]
+fn idle_worker_steals() {
+    let queues = TaskQueues::default();
+    let busy = LocalQueue::new_fifo();
+    let idle = LocalQueue::new_fifo();
+
+    // The busy worker has pending tasks in its local queue.
+    for _ in 0..4 {
+        busy.push(queued(|| {}, None));
+    }
+
+    let stealers = vec![busy.stealer()];
+    assert!(queues.pop(&idle, &stealers).is_some());
+    assert!(busy.len() < 4);
+}
+
+#[test]
+fn drop_joins_workers() {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut workers = Workers::new(WorkersDescriptor {
+            amount: 4,
+            name: "Test workers".to_string(),
+            stack_size: DEFAULT_WORKER_STACK_SIZE
+        });
+        workers.start();
+        drop(workers);
+        let _ = sender.send(());
+    });
+
+    // The idle workers are sleeping, they must wake up and join.
+    assert!(receiver.recv_timeout(Duration::from_secs(1)).is_ok());
+}
+
+#[test]
+fn idle_workers_sleep() {
+    let mut workers = Workers::new(WorkersDescriptor {
+        amount: 2,
+        name: "Test workers".to_string(),
+        stack_size: DEFAULT_WORKER_STACK_SIZE
+    });
+    workers.start();
+
+    // Nothing is scheduled, all the workers end up sleeping.
+    let start = Instant::now();
+    while workers.queue.sleeping.load(Ordering::SeqCst) != 2 {
+        assert!(start.elapsed() < Duration::from_millis(100));
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    // The sleeping workers wake up as soon as the tasks arrive.
+    let (sender, receiver) = mpsc::channel();
+    let tasks: Vec<Task> = (0..4)
+        .map(|_| {
+            let sender_copy = sender.clone();
+            Box::new(move || { let _ = sender_copy.send(()); }) as Task
+        })
+        .collect();
+    workers.execute_batch(tasks);
+
+    for _ in 0..4 {
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_ok());
+    }
+}
+
+#[test]
+fn saturated_pool_runs_in_caller() {
+    let mut workers = Workers::new(WorkersDescriptor {
+        amount: 1,
+        name: "Test workers".to_string(),
+        stack_size: DEFAULT_WORKER_STACK_SIZE
+    });
+    workers.start();
+
+    // Keep the only worker busy until the second task runs.
+    let (sender, receiver) = mpsc::channel::<()>();
+    workers.execute_dyn(Box::new(move || {
+        let _ = receiver.recv_timeout(Duration::from_secs(1));
+    }), TaskPriority::Normal, None);
+    while workers.queue.busy.load(Ordering::SeqCst) == 0 {
+        yield_now();
+    }
+
+    let caller = thread::current().id();
+    let ran_in_caller = Arc::new(AtomicBool::new(false));
+    let ran_copy = ran_in_caller.clone();
+    workers.execute_blocking(Box::new(move || {
+        ran_copy.store(thread::current().id() == caller, Ordering::SeqCst);
+    }));
+    let _ = sender.send(());
+
+    assert!(ran_in_caller.load(Ordering::SeqCst));
+}
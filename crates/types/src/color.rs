@@ -0,0 +1,188 @@
+/// Defines a RGBA color, the components go from 0 to 1.
+///
+/// The components are stored as they are authored, hex codes and color
+/// pickers use sRGB. The shaders work in linear space and the swap chain
+/// (`Bgra8UnormSrgb`) encodes the output back to sRGB, so the colors must
+/// be converted using `to_linear` before they are sent to the GPU.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color<T> {
+	pub r: T,
+	pub g: T,
+	pub b: T,
+	pub a: T
+}
+
+impl<T> Color<T> {
+	/// Creates and returns a new `Color` which contains the provided
+	/// components.
+	pub fn new(r: T, g: T, b: T, a: T) -> Self {
+		Self {
+			r,
+			g,
+			b,
+			a
+		}
+	}
+}
+
+impl<T: Copy> Color<T> {
+	/// Returns the components in RGBA order.
+	pub fn to_array(&self) -> [T; 4] {
+		[self.r, self.g, self.b, self.a]
+	}
+}
+
+impl Color<f32> {
+	/// Creates and returns an opaque `Color` from a 0xRRGGBB value.
+	///
+	/// # Arguments
+	///
+	/// `hex` - The color, the highest byte is ignored.
+	pub fn from_hex(hex: u32) -> Self {
+		let channel = |shift: u32| ((hex >> shift) & 0xFF) as f32 / 255.0;
+		Self::new(channel(16), channel(8), channel(0), 1.0)
+	}
+
+	/// Creates and returns an opaque `Color` from its hue, saturation and
+	/// value.
+	///
+	/// # Arguments
+	///
+	/// `h` - The hue in degrees, it wraps around 360.
+	/// `s` - The saturation, from 0 to 1.
+	/// `v` - The value, from 0 to 1.
+	pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+		let h = h.rem_euclid(360.0) / 60.0;
+		let chroma = v * s;
+		let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+		let (r, g, b) = match h as u32 {
+			0 => (chroma, x, 0.0),
+			1 => (x, chroma, 0.0),
+			2 => (0.0, chroma, x),
+			3 => (0.0, x, chroma),
+			4 => (x, 0.0, chroma),
+			_ => (chroma, 0.0, x)
+		};
+		let m = v - chroma;
+		Self::new(r + m, g + m, b + m, 1.0)
+	}
+
+	/// Returns the hue in degrees, the saturation and the value of the
+	/// color, the alpha is ignored.
+	pub fn to_hsv(&self) -> (f32, f32, f32) {
+		let max = self.r.max(self.g).max(self.b);
+		let min = self.r.min(self.g).min(self.b);
+		let delta = max - min;
+
+		let h = if delta == 0.0 {
+			0.0
+		} else if max == self.r {
+			60.0 * ((self.g - self.b) / delta).rem_euclid(6.0)
+		} else if max == self.g {
+			60.0 * ((self.b - self.r) / delta + 2.0)
+		} else {
+			60.0 * ((self.r - self.g) / delta + 4.0)
+		};
+		let s = if max == 0.0 { 0.0 } else { delta / max };
+
+		(h, s, max)
+	}
+
+	/// Returns the color between this one and the other one, the
+	/// components are interpolated independently.
+	///
+	/// # Arguments
+	///
+	/// `other` - The color returned when t is 1.
+	/// `t` - The position between the colors, from 0 to 1.
+	pub fn lerp(&self, other: Color<f32>, t: f32) -> Self {
+		let mix = |a: f32, b: f32| a + (b - a) * t;
+		Self::new(
+			mix(self.r, other.r),
+			mix(self.g, other.g),
+			mix(self.b, other.b),
+			mix(self.a, other.a)
+		)
+	}
+
+	/// Returns the color converted from sRGB to linear space, the alpha is
+	/// already linear.
+	pub fn to_linear(&self) -> Self {
+		let linear = |c: f32| if c <= 0.04045 {
+			c / 12.92
+		} else {
+			((c + 0.055) / 1.055).powf(2.4)
+		};
+		Self::new(linear(self.r), linear(self.g), linear(self.b), self.a)
+	}
+
+	/// Returns the color converted from linear to sRGB space, the alpha is
+	/// kept linear.
+	pub fn to_srgb(&self) -> Self {
+		let srgb = |c: f32| if c <= 0.0031308 {
+			c * 12.92
+		} else {
+			1.055 * c.powf(1.0 / 2.4) - 0.055
+		};
+		Self::new(srgb(self.r), srgb(self.g), srgb(self.b), self.a)
+	}
+}
+
+/// Copies the RGB components without any conversion, the alpha is dropped.
+#[cfg(feature = "cgmath")]
+impl From<Color<f32>> for cgmath::Vector3<f32> {
+	fn from(color: Color<f32>) -> Self {
+		cgmath::Vector3::new(color.r, color.g, color.b)
+	}
+}
+
+#[cfg(test)]
+fn assert_close(a: Color<f32>, b: Color<f32>) {
+	for (x, y) in a.to_array().iter().zip(b.to_array().iter()) {
+		assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+	}
+}
+
+#[test]
+fn hex_colors() {
+	assert_eq!(Color::from_hex(0xFF8000), Color::new(1.0, 128.0 / 255.0, 0.0, 1.0));
+	assert_eq!(Color::from_hex(0x000000), Color::new(0.0, 0.0, 0.0, 1.0));
+	// The highest byte is ignored.
+	assert_eq!(Color::from_hex(0xAA00FF00), Color::new(0.0, 1.0, 0.0, 1.0));
+}
+
+#[test]
+fn hsv_round_trip() {
+	assert_close(Color::from_hsv(0.0, 1.0, 1.0), Color::new(1.0, 0.0, 0.0, 1.0));
+	assert_close(Color::from_hsv(120.0, 1.0, 1.0), Color::new(0.0, 1.0, 0.0, 1.0));
+	assert_close(Color::from_hsv(-120.0, 1.0, 0.5), Color::new(0.0, 0.0, 0.5, 1.0));
+
+	for hex in [0x336699, 0xFF8000, 0x7F7F7F, 0x00FFAA, 0xC0FFEE] {
+		let color = Color::from_hex(hex);
+		let (h, s, v) = color.to_hsv();
+		assert_close(Color::from_hsv(h, s, v), color);
+	}
+}
+
+#[test]
+fn srgb_linear_conversion() {
+	// 0.5 in sRGB is about 0.214 in linear space.
+	assert_close(
+		Color::new(0.5, 0.0, 1.0, 0.5).to_linear(),
+		Color::new(0.21404, 0.0, 1.0, 0.5)
+	);
+	// The low values use the linear segment.
+	assert_close(Color::new(0.04, 0.0, 0.0, 1.0).to_linear(), Color::new(0.04 / 12.92, 0.0, 0.0, 1.0));
+	assert_close(Color::new(0.21404, 0.5, 0.0, 1.0).to_srgb(), Color::new(0.5, 0.73536, 0.0, 1.0));
+
+	let color = Color::from_hex(0x336699);
+	assert_close(color.to_linear().to_srgb(), color);
+}
+
+#[test]
+fn lerp_colors() {
+	let black = Color::new(0.0, 0.0, 0.0, 0.0);
+	let white = Color::new(1.0, 1.0, 1.0, 1.0);
+	assert_eq!(black.lerp(white, 0.25), Color::new(0.25, 0.25, 0.25, 0.25));
+	assert_eq!(black.lerp(white, 1.0), white);
+}
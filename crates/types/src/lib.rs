@@ -2,4 +2,7 @@ mod size;
 pub use size::Size;
 
 mod byte;
-pub use byte::{Bytes, Byte};
\ No newline at end of file
+pub use byte::{Bytes, Byte};
+
+mod color;
+pub use color::Color;
\ No newline at end of file
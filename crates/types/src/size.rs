@@ -1,4 +1,5 @@
 /// Defines a simple `Size` data structure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Size<T> {
 	pub width: T,
 	pub height: T
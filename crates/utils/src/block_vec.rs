@@ -1,7 +1,4 @@
-use std::{
-    sync::{Arc, RwLock, RwLockWriteGuard},
-    iter
-};
+use std::{iter, slice, sync::Arc};
 
 /// A list of vectors which allow access any index (except negatives).
 /// 
@@ -13,8 +10,9 @@ pub struct BlockVec<T, const N: usize> {
     /// The current number of items in the Vec.
     number_of_items: usize,
 
-    /// A queue of pointers to blocks.
-    blocks: Vec<Vec<Option<T>>>
+    /// A queue of pointers to blocks, the blocks are shared with the
+    /// snapshots.
+    blocks: Vec<Arc<Vec<Option<T>>>>
 }
 
 /// Provides default constructors for `BlockVec`.
@@ -33,32 +31,153 @@ impl<T, const N: usize> BlockVec<T, N> {
     }
 }
 
+impl<T, const N: usize> Default for BlockVec<T, N> {
+    /// Creates and returns a new empty `BlockVec`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Provides item handling.
 impl<T, const N: usize> BlockVec<T, N> {
-    /// Pushes a new item into the data structure.
+    /// Pushes a new item into the data structure, it returns true if the
+    /// slot was empty.
     ///
     /// # Arguments
     ///
     /// `item` - The item to be inserted.
     /// `index` - The position of the element. 
     pub fn set(&mut self, item: T, index: usize) -> bool {
-        let mut was_expanded: bool = false;
-
         // Get the block based on the index.
         let block_index = Self::block_for_index(index);
 
         // Check if the index is out of range, if it is it has to 
         // expand to be able to contain the index.
-        if block_index > self.blocks_len() - 1 {
-            self.append_empty_blocks(block_index);
-            was_expanded = true;
+        if block_index >= self.blocks_len() {
+            self.append_empty_blocks(block_index - self.blocks_len() + 1);
         }
     
         // Set the value in the correct place.
         let corrected_index = Self::corrected_index(index); 
-        self.blocks[block_index][corrected_index] = Some(item);
+        let slot = &mut self.block_mut(block_index)[corrected_index];
+
+        // Overriding an item does not change the number of items.
+        let was_empty: bool = slot.is_none();
+        *slot = Some(item);
+        if was_empty {
+            self.number_of_items += 1;
+        }
+
+        was_empty
+    }
+
+    /// Removes and returns the item in the provided index.
+    ///
+    /// # Arguments
+    ///
+    /// `index` - The index of the element to be removed.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let block_index = Self::block_for_index(index);
+        let corrected_index = Self::corrected_index(index);
+
+        // If the index is out of the blocks there is nothing to remove.
+        if block_index >= self.blocks_len() {
+            return None;
+        }
+
+        let item = self.block_mut(block_index)[corrected_index].take();
+        if item.is_some() {
+            self.number_of_items -= 1;
+        }
+
+        item
+    }
+
+    /// Moves the last items into the empty slots, in this way all the items
+    /// are at the beginning of the vector. The unused trailing blocks are
+    /// released, at least one block is kept.
+    ///
+    /// It returns the old and the new index of each moved item, the callers
+    /// use it to update the references to the items.
+    pub fn compact(&mut self) -> Vec<(usize, usize)> {
+        let mut moved: Vec<(usize, usize)> = Vec::new();
+        let mut low: usize = 0;
+        let mut high: usize = self.actual_len();
+
+        loop {
+            // The first hole from the beginning.
+            while low < high && self.get(low).is_some() {
+                low += 1;
+            }
+            // The last item from the end.
+            while high > low && self.get(high - 1).is_none() {
+                high -= 1;
+            }
+            if high <= low + 1 {
+                break;
+            }
+
+            high -= 1;
+            let item = self.block_mut(Self::block_for_index(high))[Self::corrected_index(high)].take();
+            self.block_mut(Self::block_for_index(low))[Self::corrected_index(low)] = item;
+            moved.push((high, low));
+        }
+
+        let used_blocks = self.number_of_items.div_ceil(N);
+        self.blocks.truncate(used_blocks.max(1));
+
+        moved
+    }
 
-        was_expanded
+    /// Returns the blocks, only the pointers are cloned.
+    ///
+    /// The snapshot allows to read the slots without keeping the vector
+    /// borrowed, the blocks appended later are not part of it. The blocks
+    /// can not be modified while a snapshot of them is alive, so it is
+    /// meant for vectors whose slots contain their own locks.
+    pub fn snapshot_blocks(&self) -> Vec<Arc<Vec<Option<T>>>> {
+        self.blocks.clone()
+    }
+
+    /// Sets an item in every empty slot of the allocated blocks, the blocks
+    /// without empty slots are not modified.
+    ///
+    /// # Arguments
+    ///
+    /// `fill` - Creates the item of each empty slot.
+    pub fn fill_empty_slots<F: FnMut() -> T>(&mut self, mut fill: F) {
+        for block_index in 0..self.blocks_len() {
+            if self.blocks[block_index].iter().all(|slot| slot.is_some()) {
+                continue;
+            }
+
+            let mut filled: usize = 0;
+            for slot in self.block_mut(block_index).iter_mut().filter(|s| s.is_none()) {
+                *slot = Some(fill());
+                filled += 1;
+            }
+            self.number_of_items += filled;
+        }
+    }
+
+    /// Returns an iterator over the occupied slots, it yields the index
+    /// and the item.
+    pub fn iter(&self) -> BlockVecIter<'_, T> {
+        BlockVecIter {
+            blocks: self.blocks.iter(),
+            slots: [].iter(),
+            index: 0
+        }
+    }
+
+    /// Returns an iterator over the occupied slots, it yields the index
+    /// and a mutable reference to the item.
+    pub fn iter_mut(&mut self) -> BlockVecIterMut<'_, T> {
+        BlockVecIterMut {
+            blocks: self.blocks.iter_mut(),
+            slots: [].iter_mut(),
+            index: 0
+        }
     }
 
     /// Returns the element associated with the key.
@@ -72,7 +191,7 @@ impl<T, const N: usize> BlockVec<T, N> {
         let corrected_index = Self::corrected_index(index);
 
         // If the index is out of the blocks return none.
-        if block_index > self.blocks_len() {
+        if block_index >= self.blocks_len() {
             return &None;
         }
 
@@ -83,15 +202,21 @@ impl<T, const N: usize> BlockVec<T, N> {
     /// Returns the element if it is inside the bounds if not returns
     /// an error.
     ///
+    /// The bounds are defined by the allocated blocks, so any index below
+    /// `actual_len` is valid even if it was never set.
+    ///
+    /// # Arguments
+    ///
     /// `index` - The index of the element to be returned.
+    #[allow(clippy::result_unit_err)]
     pub fn get_inbouds(&self, index: usize) -> Result<&Option<T>, ()> {
         // Get the bloc based on the index.
         let block_index = Self::block_for_index(index);
         let corrected_index = Self::corrected_index(index);
 
         // If the index is out of the blocks return none.
-        if block_index > self.blocks_len() - 1 {
-            // Return out of bounds.:
+        if block_index >= self.blocks_len() {
+            // Return out of bounds.
             return Err(());
         }
 
@@ -100,6 +225,101 @@ impl<T, const N: usize> BlockVec<T, N> {
     }
 }
 
+/// Returns the mutable content of the block, it panics if a snapshot of the
+/// block is alive.
+///
+/// # Arguments
+///
+/// `block` - The shared block.
+fn unique_block<T>(block: &mut Arc<Vec<Option<T>>>) -> &mut Vec<Option<T>> {
+    match Arc::get_mut(block) {
+        Some(b) => b,
+        None => panic!("A block can not be modified while a snapshot of it is alive")
+    }
+}
+
+/// Iterates over the occupied slots of a `BlockVec`, the empty slots are
+/// skipped.
+pub struct BlockVecIter<'a, T> {
+    /// The blocks which were not visited yet.
+    blocks: slice::Iter<'a, Arc<Vec<Option<T>>>>,
+
+    /// The remaining slots of the current block.
+    slots: slice::Iter<'a, Option<T>>,
+
+    /// The index of the next slot.
+    index: usize
+}
+
+impl<'a, T> Iterator for BlockVecIter<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.slots.next() {
+                Some(slot) => {
+                    self.index += 1;
+                    if let Some(item) = slot {
+                        return Some((self.index - 1, item));
+                    }
+                },
+                None => self.slots = self.blocks.next()?.iter()
+            }
+        }
+    }
+}
+
+/// Iterates over the occupied slots of a `BlockVec` allowing to modify the
+/// items, the empty slots are skipped.
+///
+/// It panics if a snapshot of the blocks is alive.
+pub struct BlockVecIterMut<'a, T> {
+    /// The blocks which were not visited yet.
+    blocks: slice::IterMut<'a, Arc<Vec<Option<T>>>>,
+
+    /// The remaining slots of the current block.
+    slots: slice::IterMut<'a, Option<T>>,
+
+    /// The index of the next slot.
+    index: usize
+}
+
+impl<'a, T> Iterator for BlockVecIterMut<'a, T> {
+    type Item = (usize, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.slots.next() {
+                Some(slot) => {
+                    self.index += 1;
+                    if let Some(item) = slot {
+                        return Some((self.index - 1, item));
+                    }
+                },
+                None => self.slots = unique_block(self.blocks.next()?).iter_mut()
+            }
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a BlockVec<T, N> {
+    type Item = (usize, &'a T);
+    type IntoIter = BlockVecIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut BlockVec<T, N> {
+    type Item = (usize, &'a mut T);
+    type IntoIter = BlockVecIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 /// Provides implementations used to handle the memory.
 impl<T, const N: usize> BlockVec<T, N> { 
     /// Forces the vector to creare N number of new blocks.
@@ -110,11 +330,11 @@ impl<T, const N: usize> BlockVec<T, N> {
     pub fn append_empty_blocks(&mut self, num_of_blocks: usize) {
         // Create all the new blocks.
         for _ in 0..num_of_blocks {
-            self.blocks.push(
+            self.blocks.push(Arc::new(
                 iter::repeat_with(|| None)
                     .take(N)
                     .collect()
-            );
+            ));
         } 
     }
 }
@@ -135,6 +355,16 @@ impl<T, const N: usize> BlockVec<T, N> {
         (float_index / float_n).floor() as usize
     }
 
+    /// Returns the mutable content of the block, it panics if a snapshot
+    /// of the block is alive.
+    ///
+    /// # Arguments
+    ///
+    /// `block_index` - The index of the block.
+    fn block_mut(&mut self, block_index: usize) -> &mut Vec<Option<T>> {
+        unique_block(&mut self.blocks[block_index])
+    }
+
     /// Returns the relative index.
     ///
     /// # Arguments
@@ -150,6 +380,11 @@ impl<T, const N: usize> BlockVec<T, N> {
         self.number_of_items
     }
 
+    /// Returns true if the vector does not contain elements.
+    pub fn is_empty(&self) -> bool {
+        self.number_of_items == 0
+    }
+
     /// Returns the number of blocks.
     pub fn blocks_len(&self) -> usize {
         self.blocks.len()
@@ -158,7 +393,8 @@ impl<T, const N: usize> BlockVec<T, N> {
     /// Returns the actual len of the block vec.
     /// 
     /// As the item could be setted in any part of the block vec we
-    /// do not know the last index 
+    /// do not know the last index, it returns the number of slots
+    /// allocated, all the indices below it can be read.
     pub fn actual_len(&self) -> usize {
         self.blocks.len() * N
     }
@@ -254,3 +490,148 @@ fn sync_mem() {
     assert_eq!(vec1.blocks_len(), vec1.blocks_len());
     assert_eq!(vec1.blocks_len(), vec3.blocks_len());
 }
+
+#[test]
+fn boundary_indices() {
+    let mut vec = BlockVec::<i32, 10>::new();
+
+    // N - 1 is the last slot of the first block.
+    assert!(vec.set(1, 9));
+    assert_eq!(vec.get(9), &Some(1));
+
+    // N and N + 1 are out of the blocks until they are set.
+    assert_eq!(vec.get(10), &None);
+    assert_eq!(vec.get(11), &None);
+    assert!(vec.get_inbouds(10).is_err());
+    assert_eq!(vec.get_inbouds(9), Ok(&Some(1)));
+
+    assert!(vec.set(2, 10));
+    assert_eq!(vec.blocks_len(), 2);
+    assert_eq!(vec.actual_len(), 20);
+    assert_eq!(vec.get(10), &Some(2));
+    assert_eq!(vec.get_inbouds(11), Ok(&None));
+    assert!(vec.get_inbouds(20).is_err());
+}
+
+#[test]
+fn large_jumps() {
+    let mut vec = BlockVec::<i32, 10>::new();
+    vec.set(1, 15);
+
+    // Jumping several blocks only allocates the needed ones.
+    vec.set(2, 95);
+    assert_eq!(vec.blocks_len(), 10);
+    assert_eq!(vec.actual_len(), 100);
+    assert_eq!(vec.get(95), &Some(2));
+    assert_eq!(vec.get(15), &Some(1));
+    assert_eq!(vec.get(100), &None);
+    assert_eq!(vec.get(1000), &None);
+}
+
+#[test]
+fn count_items() {
+    let mut vec = BlockVec::<i32, 10>::new();
+    assert!(vec.is_empty());
+
+    assert!(vec.set(1, 0));
+    assert!(vec.set(2, 9));
+    assert!(vec.set(3, 42));
+    assert_eq!(vec.len(), 3);
+
+    // Overriding does not count twice.
+    assert!(!vec.set(4, 42));
+    assert_eq!(vec.len(), 3);
+
+    assert_eq!(vec.remove(9), Some(2));
+    assert_eq!(vec.remove(9), None);
+    assert_eq!(vec.remove(1000), None);
+    assert_eq!(vec.len(), 2);
+
+    let items: Vec<(usize, &i32)> = vec.iter().collect();
+    assert_eq!(items, vec![(0, &1), (42, &4)]);
+}
+
+#[test]
+fn iterate_across_blocks() {
+    let mut vec = BlockVec::<i32, 4>::new();
+    vec.set(1, 3);
+    vec.set(2, 4);
+    vec.set(3, 17);
+
+    // Doubles the items, the indices keep the position across blocks.
+    for (_, item) in vec.iter_mut() {
+        *item *= 2;
+    }
+
+    let items: Vec<(usize, i32)> = (&vec).into_iter().map(|(i, v)| (i, *v)).collect();
+    assert_eq!(items, vec![(3, 2), (4, 4), (17, 6)]);
+}
+
+#[test]
+fn compact_fills_holes() {
+    let mut vec = BlockVec::<i32, 4>::new();
+    for index in 0..10 {
+        vec.set(index as i32, index);
+    }
+    vec.remove(1);
+    vec.remove(2);
+    vec.remove(8);
+
+    let moved = vec.compact();
+    assert_eq!(moved, vec![(9, 1), (7, 2)]);
+    assert_eq!(vec.len(), 7);
+    assert_eq!(vec.blocks_len(), 2);
+
+    let items: Vec<(usize, i32)> = vec.iter().map(|(i, v)| (i, *v)).collect();
+    assert_eq!(items, vec![(0, 0), (1, 9), (2, 7), (3, 3), (4, 4), (5, 5), (6, 6)]);
+
+    // Nothing to move when there are not holes.
+    assert!(vec.compact().is_empty());
+}
+
+#[test]
+fn len_and_capacity_after_mixed_operations() {
+    let mut vec = BlockVec::<i32, 8>::new();
+    vec.set(1, 2);
+    vec.set(2, 20);
+    vec.set(3, 2);
+    vec.remove(20);
+    vec.set(4, 7);
+    vec.remove(3);
+
+    // The count only includes the occupied slots, the capacity includes
+    // every allocated block.
+    assert_eq!(vec.len(), 2);
+    assert_eq!(vec.actual_len(), 24);
+
+    vec.remove(2);
+    vec.remove(7);
+    assert!(vec.is_empty());
+    assert_eq!(vec.actual_len(), 24);
+}
+
+#[test]
+fn snapshots_share_the_blocks() {
+    let mut vec = BlockVec::<i32, 4>::new();
+    vec.set(1, 2);
+    vec.fill_empty_slots(|| 0);
+    assert_eq!(vec.len(), 4);
+
+    let snapshot = vec.snapshot_blocks();
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot[0][2], Some(1));
+
+    // The new blocks are not part of the snapshot and the full blocks are
+    // not modified.
+    vec.append_empty_blocks(1);
+    vec.fill_empty_slots(|| 7);
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(vec.len(), 8);
+    assert_eq!(vec.get(6), &Some(7));
+    drop(snapshot);
+
+    // Without snapshots the blocks can be modified.
+    vec.set(3, 0);
+    assert_eq!(vec.len(), 8);
+    assert_eq!(vec.get(0), &Some(3));
+}
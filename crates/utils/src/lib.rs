@@ -1,5 +1,7 @@
 mod block_vec;
 pub use block_vec::{
     BlockVec,
+    BlockVecIter,
+    BlockVecIterMut,
     sync_mem_to_biggest
 };
\ No newline at end of file